@@ -0,0 +1,620 @@
+//! A [Canvas] trait over the drawing surface of [DisplayListBuilder].
+//!
+//! Drawing code that is generic over [Canvas] can target impeller through a
+//! [DisplayListBuilder], or draw straight into a [RecordedDisplayList] using the pure rust
+//! `Recorded*` objects. A recording can then be checked, [exported](RecordedDisplayList::to_svg)
+//! or [rasterized](RecordedDisplayList::rasterize) without linking impeller.
+use std::sync::Arc;
+
+use crate::{
+    ClipOperation, Color, DisplayList, DisplayListBuilder, DisplayOp, ImageFilter, Matrix, Paint,
+    Paragraph, Path, Point, RecordedDisplayList, RecordedImageFilter, RecordedPaint,
+    RecordedParagraph, RecordedPath, RecordedTexture, Rect, RoundingRadii, Texture,
+    TextureSampling,
+};
+
+/// Something that can be drawn on like a [DisplayListBuilder].
+///
+/// The objects used by draw calls are associated types, so that implementations
+/// don't need impeller objects. Every method behaves like the [DisplayListBuilder]
+/// method with the same name.
+///
+/// ```rust
+/// # use impellers::*;
+/// fn draw_badge<C: Canvas>(canvas: &mut C, paint: &C::Paint) {
+///     canvas
+///         .save()
+///         .translate(10.0, 10.0)
+///         .draw_oval(&Rect::from_size(Size::new(20.0, 20.0)), paint)
+///         .restore();
+/// }
+/// let mut recording = RecordedDisplayList::new(None);
+/// draw_badge(&mut recording, &RecordedPaint::default());
+/// assert_eq!(recording.ops().len(), 4);
+/// ```
+pub trait Canvas {
+    /// The paint of draw calls. eg: [Paint]
+    type Paint;
+    /// The paths drawn or clipped with. eg: [Path]
+    type Path;
+    /// The backdrop filter of layers. eg: [ImageFilter]
+    type ImageFilter;
+    /// The display lists drawn into this canvas. eg: [DisplayList]
+    type DisplayList;
+    /// The paragraphs drawn into this canvas. eg: [Paragraph]
+    type Paragraph;
+    /// The textures drawn into this canvas. eg: [Texture]
+    type Texture;
+
+    /// @see [DisplayListBuilder::save]
+    fn save(&mut self) -> &mut Self;
+    /// @see [DisplayListBuilder::save_layer]
+    fn save_layer(
+        &mut self,
+        bounds: &Rect,
+        paint: Option<&Self::Paint>,
+        backdrop: Option<&Self::ImageFilter>,
+    ) -> &mut Self;
+    /// @see [DisplayListBuilder::restore]
+    fn restore(&mut self) -> &mut Self;
+    /// @see [DisplayListBuilder::scale]
+    fn scale(&mut self, x_scale: f32, y_scale: f32) -> &mut Self;
+    /// @see [DisplayListBuilder::rotate]
+    fn rotate(&mut self, angle_degrees: f32) -> &mut Self;
+    /// @see [DisplayListBuilder::translate]
+    fn translate(&mut self, x_translation: f32, y_translation: f32) -> &mut Self;
+    /// @see [DisplayListBuilder::transform]
+    fn transform(&mut self, transform: &Matrix) -> &mut Self;
+    /// @see [DisplayListBuilder::set_transform]
+    fn set_transform(&mut self, transform: &Matrix) -> &mut Self;
+    /// @see [DisplayListBuilder::get_transform]
+    fn get_transform(&self) -> Matrix;
+    /// @see [DisplayListBuilder::reset_transform]
+    fn reset_transform(&mut self) -> &mut Self;
+    /// @see [DisplayListBuilder::get_save_count]
+    fn get_save_count(&mut self) -> u32;
+    /// @see [DisplayListBuilder::restore_to_count]
+    fn restore_to_count(&mut self, count: u32) -> &mut Self;
+    /// @see [DisplayListBuilder::set_tag]
+    fn set_tag(&mut self, tag: u64) -> &mut Self;
+    /// @see [DisplayListBuilder::clip_rect]
+    fn clip_rect(&mut self, rect: &Rect, op: ClipOperation) -> &mut Self;
+    /// @see [DisplayListBuilder::clip_oval]
+    fn clip_oval(&mut self, oval_bounds: &Rect, op: ClipOperation) -> &mut Self;
+    /// @see [DisplayListBuilder::clip_rounded_rect]
+    fn clip_rounded_rect(
+        &mut self,
+        rect: &Rect,
+        radii: &RoundingRadii,
+        op: ClipOperation,
+    ) -> &mut Self;
+    /// @see [DisplayListBuilder::clip_path]
+    fn clip_path(&mut self, path: &Self::Path, op: ClipOperation) -> &mut Self;
+    /// @see [DisplayListBuilder::draw_paint]
+    fn draw_paint(&mut self, paint: &Self::Paint) -> &mut Self;
+    /// @see [DisplayListBuilder::draw_line]
+    fn draw_line(&mut self, from: Point, to: Point, paint: &Self::Paint) -> &mut Self;
+    /// @see [DisplayListBuilder::draw_dashed_line]
+    fn draw_dashed_line(
+        &mut self,
+        from: Point,
+        to: Point,
+        on_length: f32,
+        off_length: f32,
+        paint: &Self::Paint,
+    ) -> &mut Self;
+    /// @see [DisplayListBuilder::draw_rect]
+    fn draw_rect(&mut self, rect: &Rect, paint: &Self::Paint) -> &mut Self;
+    /// @see [DisplayListBuilder::draw_oval]
+    fn draw_oval(&mut self, oval_bounds: &Rect, paint: &Self::Paint) -> &mut Self;
+    /// @see [DisplayListBuilder::draw_rounded_rect]
+    fn draw_rounded_rect(
+        &mut self,
+        rect: &Rect,
+        radii: &RoundingRadii,
+        paint: &Self::Paint,
+    ) -> &mut Self;
+    /// @see [DisplayListBuilder::draw_rounded_rect_difference]
+    fn draw_rounded_rect_difference(
+        &mut self,
+        outer_rect: &Rect,
+        outer_radii: &RoundingRadii,
+        inner_rect: &Rect,
+        inner_radii: &RoundingRadii,
+        paint: &Self::Paint,
+    ) -> &mut Self;
+    /// @see [DisplayListBuilder::draw_path]
+    fn draw_path(&mut self, path: &Self::Path, paint: &Self::Paint) -> &mut Self;
+    /// @see [DisplayListBuilder::draw_display_list]
+    fn draw_display_list(&mut self, display_list: &Self::DisplayList, opacity: f32) -> &mut Self;
+    /// @see [DisplayListBuilder::draw_paragraph]
+    fn draw_paragraph(&mut self, paragraph: &Self::Paragraph, point: Point) -> &mut Self;
+    /// @see [DisplayListBuilder::draw_shadow]
+    fn draw_shadow(
+        &mut self,
+        path: &Self::Path,
+        color: &Color,
+        elevation: f32,
+        occluder_is_transparent: bool,
+        device_pixel_ratio: f32,
+    ) -> &mut Self;
+    /// @see [DisplayListBuilder::draw_texture]
+    fn draw_texture(
+        &mut self,
+        texture: &Self::Texture,
+        point: Point,
+        sampling: TextureSampling,
+        paint: &Self::Paint,
+    ) -> &mut Self;
+    /// @see [DisplayListBuilder::draw_texture_rect]
+    fn draw_texture_rect(
+        &mut self,
+        texture: &Self::Texture,
+        src_rect: &Rect,
+        dst_rect: &Rect,
+        sampling: TextureSampling,
+        paint: Option<&Self::Paint>,
+    ) -> &mut Self;
+}
+
+impl Canvas for DisplayListBuilder {
+    type Paint = Paint;
+    type Path = Path;
+    type ImageFilter = ImageFilter;
+    type DisplayList = DisplayList;
+    type Paragraph = Paragraph;
+    type Texture = Texture;
+
+    fn save(&mut self) -> &mut Self {
+        DisplayListBuilder::save(self)
+    }
+    fn save_layer(
+        &mut self,
+        bounds: &Rect,
+        paint: Option<&Paint>,
+        backdrop: Option<&ImageFilter>,
+    ) -> &mut Self {
+        DisplayListBuilder::save_layer(self, bounds, paint, backdrop)
+    }
+    fn restore(&mut self) -> &mut Self {
+        DisplayListBuilder::restore(self)
+    }
+    fn scale(&mut self, x_scale: f32, y_scale: f32) -> &mut Self {
+        DisplayListBuilder::scale(self, x_scale, y_scale)
+    }
+    fn rotate(&mut self, angle_degrees: f32) -> &mut Self {
+        DisplayListBuilder::rotate(self, angle_degrees)
+    }
+    fn translate(&mut self, x_translation: f32, y_translation: f32) -> &mut Self {
+        DisplayListBuilder::translate(self, x_translation, y_translation)
+    }
+    fn transform(&mut self, transform: &Matrix) -> &mut Self {
+        DisplayListBuilder::transform(self, transform)
+    }
+    fn set_transform(&mut self, transform: &Matrix) -> &mut Self {
+        DisplayListBuilder::set_transform(self, transform)
+    }
+    fn get_transform(&self) -> Matrix {
+        DisplayListBuilder::get_transform(self)
+    }
+    fn reset_transform(&mut self) -> &mut Self {
+        DisplayListBuilder::reset_transform(self)
+    }
+    fn get_save_count(&mut self) -> u32 {
+        DisplayListBuilder::get_save_count(self)
+    }
+    fn restore_to_count(&mut self, count: u32) -> &mut Self {
+        DisplayListBuilder::restore_to_count(self, count)
+    }
+    fn set_tag(&mut self, tag: u64) -> &mut Self {
+        DisplayListBuilder::set_tag(self, tag)
+    }
+    fn clip_rect(&mut self, rect: &Rect, op: ClipOperation) -> &mut Self {
+        DisplayListBuilder::clip_rect(self, rect, op)
+    }
+    fn clip_oval(&mut self, oval_bounds: &Rect, op: ClipOperation) -> &mut Self {
+        DisplayListBuilder::clip_oval(self, oval_bounds, op)
+    }
+    fn clip_rounded_rect(
+        &mut self,
+        rect: &Rect,
+        radii: &RoundingRadii,
+        op: ClipOperation,
+    ) -> &mut Self {
+        DisplayListBuilder::clip_rounded_rect(self, rect, radii, op)
+    }
+    fn clip_path(&mut self, path: &Path, op: ClipOperation) -> &mut Self {
+        DisplayListBuilder::clip_path(self, path, op)
+    }
+    fn draw_paint(&mut self, paint: &Paint) -> &mut Self {
+        DisplayListBuilder::draw_paint(self, paint)
+    }
+    fn draw_line(&mut self, from: Point, to: Point, paint: &Paint) -> &mut Self {
+        DisplayListBuilder::draw_line(self, from, to, paint)
+    }
+    fn draw_dashed_line(
+        &mut self,
+        from: Point,
+        to: Point,
+        on_length: f32,
+        off_length: f32,
+        paint: &Paint,
+    ) -> &mut Self {
+        DisplayListBuilder::draw_dashed_line(self, from, to, on_length, off_length, paint)
+    }
+    fn draw_rect(&mut self, rect: &Rect, paint: &Paint) -> &mut Self {
+        DisplayListBuilder::draw_rect(self, rect, paint)
+    }
+    fn draw_oval(&mut self, oval_bounds: &Rect, paint: &Paint) -> &mut Self {
+        DisplayListBuilder::draw_oval(self, oval_bounds, paint)
+    }
+    fn draw_rounded_rect(
+        &mut self,
+        rect: &Rect,
+        radii: &RoundingRadii,
+        paint: &Paint,
+    ) -> &mut Self {
+        DisplayListBuilder::draw_rounded_rect(self, rect, radii, paint)
+    }
+    fn draw_rounded_rect_difference(
+        &mut self,
+        outer_rect: &Rect,
+        outer_radii: &RoundingRadii,
+        inner_rect: &Rect,
+        inner_radii: &RoundingRadii,
+        paint: &Paint,
+    ) -> &mut Self {
+        DisplayListBuilder::draw_rounded_rect_difference(
+            self,
+            outer_rect,
+            outer_radii,
+            inner_rect,
+            inner_radii,
+            paint,
+        )
+    }
+    fn draw_path(&mut self, path: &Path, paint: &Paint) -> &mut Self {
+        DisplayListBuilder::draw_path(self, path, paint)
+    }
+    fn draw_display_list(&mut self, display_list: &DisplayList, opacity: f32) -> &mut Self {
+        DisplayListBuilder::draw_display_list(self, display_list, opacity)
+    }
+    fn draw_paragraph(&mut self, paragraph: &Paragraph, point: Point) -> &mut Self {
+        DisplayListBuilder::draw_paragraph(self, paragraph, point)
+    }
+    fn draw_shadow(
+        &mut self,
+        path: &Path,
+        color: &Color,
+        elevation: f32,
+        occluder_is_transparent: bool,
+        device_pixel_ratio: f32,
+    ) -> &mut Self {
+        DisplayListBuilder::draw_shadow(
+            self,
+            path,
+            color,
+            elevation,
+            occluder_is_transparent,
+            device_pixel_ratio,
+        )
+    }
+    fn draw_texture(
+        &mut self,
+        texture: &Texture,
+        point: Point,
+        sampling: TextureSampling,
+        paint: &Paint,
+    ) -> &mut Self {
+        DisplayListBuilder::draw_texture(self, texture, point, sampling, paint)
+    }
+    fn draw_texture_rect(
+        &mut self,
+        texture: &Texture,
+        src_rect: &Rect,
+        dst_rect: &Rect,
+        sampling: TextureSampling,
+        paint: Option<&Paint>,
+    ) -> &mut Self {
+        DisplayListBuilder::draw_texture_rect(self, texture, src_rect, dst_rect, sampling, paint)
+    }
+}
+
+impl RecordedDisplayList {
+    /// The transforms on the save stack after the recorded ops, starting with the identity.
+    fn transform_stack(&self) -> Vec<Matrix> {
+        let identity = Matrix::identity();
+        let mut stack = vec![identity];
+        for op in self.ops() {
+            let top = *stack.last().unwrap();
+            if let Some(next) = op.next_transform(&top, &identity) {
+                *stack.last_mut().unwrap() = next;
+                continue;
+            }
+            match op {
+                DisplayOp::Save | DisplayOp::SaveLayer { .. } => stack.push(top),
+                DisplayOp::Restore if stack.len() > 1 => {
+                    stack.pop();
+                }
+                DisplayOp::RestoreToCount(count) => stack.truncate((*count as usize).max(1)),
+                _ => {}
+            }
+        }
+        stack
+    }
+}
+/// Appends ops to the recording, using the pure rust mirrors of impeller objects.
+///
+/// [Canvas::get_transform] and [Canvas::get_save_count] walk through all of the
+/// recorded ops, so they are not free.
+impl Canvas for RecordedDisplayList {
+    type Paint = RecordedPaint;
+    type Path = Arc<RecordedPath>;
+    type ImageFilter = Arc<RecordedImageFilter>;
+    type DisplayList = Arc<RecordedDisplayList>;
+    type Paragraph = Arc<RecordedParagraph>;
+    type Texture = Arc<RecordedTexture>;
+
+    fn save(&mut self) -> &mut Self {
+        self.push(DisplayOp::Save)
+    }
+    fn save_layer(
+        &mut self,
+        bounds: &Rect,
+        paint: Option<&RecordedPaint>,
+        backdrop: Option<&Arc<RecordedImageFilter>>,
+    ) -> &mut Self {
+        self.push(DisplayOp::SaveLayer {
+            bounds: *bounds,
+            paint: paint.cloned(),
+            backdrop: backdrop.cloned(),
+        })
+    }
+    fn restore(&mut self) -> &mut Self {
+        self.push(DisplayOp::Restore)
+    }
+    fn scale(&mut self, x_scale: f32, y_scale: f32) -> &mut Self {
+        self.push(DisplayOp::Scale { x_scale, y_scale })
+    }
+    fn rotate(&mut self, angle_degrees: f32) -> &mut Self {
+        self.push(DisplayOp::Rotate { angle_degrees })
+    }
+    fn translate(&mut self, x_translation: f32, y_translation: f32) -> &mut Self {
+        self.push(DisplayOp::Translate {
+            x_translation,
+            y_translation,
+        })
+    }
+    fn transform(&mut self, transform: &Matrix) -> &mut Self {
+        self.push(DisplayOp::Transform(*transform))
+    }
+    fn set_transform(&mut self, transform: &Matrix) -> &mut Self {
+        self.push(DisplayOp::SetTransform(*transform))
+    }
+    fn get_transform(&self) -> Matrix {
+        *self.transform_stack().last().unwrap()
+    }
+    fn reset_transform(&mut self) -> &mut Self {
+        self.push(DisplayOp::ResetTransform)
+    }
+    fn get_save_count(&mut self) -> u32 {
+        self.transform_stack().len() as u32
+    }
+    fn restore_to_count(&mut self, count: u32) -> &mut Self {
+        self.push(DisplayOp::RestoreToCount(count))
+    }
+    fn set_tag(&mut self, tag: u64) -> &mut Self {
+        self.push(DisplayOp::SetTag(tag))
+    }
+    fn clip_rect(&mut self, rect: &Rect, op: ClipOperation) -> &mut Self {
+        self.push(DisplayOp::ClipRect { rect: *rect, op })
+    }
+    fn clip_oval(&mut self, oval_bounds: &Rect, op: ClipOperation) -> &mut Self {
+        self.push(DisplayOp::ClipOval {
+            oval_bounds: *oval_bounds,
+            op,
+        })
+    }
+    fn clip_rounded_rect(
+        &mut self,
+        rect: &Rect,
+        radii: &RoundingRadii,
+        op: ClipOperation,
+    ) -> &mut Self {
+        self.push(DisplayOp::ClipRoundedRect {
+            rect: *rect,
+            radii: *radii,
+            op,
+        })
+    }
+    fn clip_path(&mut self, path: &Arc<RecordedPath>, op: ClipOperation) -> &mut Self {
+        self.push(DisplayOp::ClipPath {
+            path: path.clone(),
+            op,
+        })
+    }
+    fn draw_paint(&mut self, paint: &RecordedPaint) -> &mut Self {
+        self.push(DisplayOp::DrawPaint {
+            paint: paint.clone(),
+        })
+    }
+    fn draw_line(&mut self, from: Point, to: Point, paint: &RecordedPaint) -> &mut Self {
+        self.push(DisplayOp::DrawLine {
+            from,
+            to,
+            paint: paint.clone(),
+        })
+    }
+    fn draw_dashed_line(
+        &mut self,
+        from: Point,
+        to: Point,
+        on_length: f32,
+        off_length: f32,
+        paint: &RecordedPaint,
+    ) -> &mut Self {
+        self.push(DisplayOp::DrawDashedLine {
+            from,
+            to,
+            on_length,
+            off_length,
+            paint: paint.clone(),
+        })
+    }
+    fn draw_rect(&mut self, rect: &Rect, paint: &RecordedPaint) -> &mut Self {
+        self.push(DisplayOp::DrawRect {
+            rect: *rect,
+            paint: paint.clone(),
+        })
+    }
+    fn draw_oval(&mut self, oval_bounds: &Rect, paint: &RecordedPaint) -> &mut Self {
+        self.push(DisplayOp::DrawOval {
+            oval_bounds: *oval_bounds,
+            paint: paint.clone(),
+        })
+    }
+    fn draw_rounded_rect(
+        &mut self,
+        rect: &Rect,
+        radii: &RoundingRadii,
+        paint: &RecordedPaint,
+    ) -> &mut Self {
+        self.push(DisplayOp::DrawRoundedRect {
+            rect: *rect,
+            radii: *radii,
+            paint: paint.clone(),
+        })
+    }
+    fn draw_rounded_rect_difference(
+        &mut self,
+        outer_rect: &Rect,
+        outer_radii: &RoundingRadii,
+        inner_rect: &Rect,
+        inner_radii: &RoundingRadii,
+        paint: &RecordedPaint,
+    ) -> &mut Self {
+        self.push(DisplayOp::DrawRoundedRectDifference {
+            outer_rect: *outer_rect,
+            outer_radii: *outer_radii,
+            inner_rect: *inner_rect,
+            inner_radii: *inner_radii,
+            paint: paint.clone(),
+        })
+    }
+    fn draw_path(&mut self, path: &Arc<RecordedPath>, paint: &RecordedPaint) -> &mut Self {
+        self.push(DisplayOp::DrawPath {
+            path: path.clone(),
+            paint: paint.clone(),
+        })
+    }
+    fn draw_display_list(
+        &mut self,
+        display_list: &Arc<RecordedDisplayList>,
+        opacity: f32,
+    ) -> &mut Self {
+        self.push(DisplayOp::DrawDisplayList {
+            display_list: Some(display_list.clone()),
+            opacity,
+        })
+    }
+    fn draw_paragraph(&mut self, paragraph: &Arc<RecordedParagraph>, point: Point) -> &mut Self {
+        self.push(DisplayOp::DrawParagraph {
+            paragraph: paragraph.clone(),
+            point,
+        })
+    }
+    fn draw_shadow(
+        &mut self,
+        path: &Arc<RecordedPath>,
+        color: &Color,
+        elevation: f32,
+        occluder_is_transparent: bool,
+        device_pixel_ratio: f32,
+    ) -> &mut Self {
+        self.push(DisplayOp::DrawShadow {
+            path: path.clone(),
+            color: *color,
+            elevation,
+            occluder_is_transparent,
+            device_pixel_ratio,
+        })
+    }
+    fn draw_texture(
+        &mut self,
+        texture: &Arc<RecordedTexture>,
+        point: Point,
+        sampling: TextureSampling,
+        paint: &RecordedPaint,
+    ) -> &mut Self {
+        self.push(DisplayOp::DrawTexture {
+            texture: texture.clone(),
+            point,
+            sampling,
+            paint: paint.clone(),
+        })
+    }
+    fn draw_texture_rect(
+        &mut self,
+        texture: &Arc<RecordedTexture>,
+        src_rect: &Rect,
+        dst_rect: &Rect,
+        sampling: TextureSampling,
+        paint: Option<&RecordedPaint>,
+    ) -> &mut Self {
+        self.push(DisplayOp::DrawTextureRect {
+            texture: texture.clone(),
+            src_rect: *src_rect,
+            dst_rect: *dst_rect,
+            sampling,
+            paint: paint.cloned(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Size;
+
+    /// Drawing code that doesn't know which canvas it draws on.
+    fn draw_card<C: Canvas>(canvas: &mut C, paint: &C::Paint) -> u32 {
+        let count = canvas.get_save_count();
+        canvas
+            .save()
+            .translate(10.0, 20.0)
+            .scale(2.0, 2.0)
+            .clip_rect(
+                &Rect::from_size(Size::new(5.0, 5.0)),
+                ClipOperation::Intersect,
+            )
+            .draw_rect(&Rect::from_size(Size::new(5.0, 5.0)), paint);
+        let inside = canvas.get_save_count();
+        canvas.restore_to_count(count);
+        inside
+    }
+
+    #[test]
+    fn recording_canvas() {
+        let mut recording = RecordedDisplayList::new(None);
+        assert_eq!(recording.get_save_count(), 1);
+        recording.translate(1.0, 0.0);
+        let inside = draw_card(&mut recording, &RecordedPaint::default());
+        assert_eq!(inside, 2);
+        assert_eq!(recording.get_save_count(), 1);
+        assert_eq!(
+            recording.get_transform(),
+            Matrix::translation(1.0, 0.0, 0.0)
+        );
+        assert_eq!(recording.ops().len(), 7);
+        assert!(matches!(recording.ops()[5], DisplayOp::DrawRect { .. }));
+
+        recording.save().translate(0.0, 5.0).rotate(90.0);
+        let point = recording
+            .get_transform()
+            .transform_point2d(Point::new(1.0, 0.0))
+            .unwrap();
+        assert!((point - Point::new(1.0, 6.0)).length() < 1e-5);
+        recording.restore().restore();
+        assert_eq!(recording.get_save_count(), 1);
+    }
+}
@@ -1,8 +1,28 @@
 //! parsed from json file at <<https://github.com/jonathantneal/color-names/blob/master/color-names.json>>
 use crate::Color;
-// No need for docs for color constants
-#[allow(missing_docs)]
-impl Color {
+
+mod css;
+mod matrix;
+mod mix;
+mod named;
+mod space;
+
+pub use mix::InterpolationSpace;
+pub use space::{Hsl, Hsv, Lab, LinearSrgb, Oklab, Oklch};
+
+/// Defines the color constants, and [NAMED] with all of them.
+macro_rules! named_colors {
+    ($(pub const $name:ident: Self = Self::new_srgb($red:expr, $green:expr, $blue:expr);)*) => {
+        // No need for docs for color constants
+        #[allow(missing_docs)]
+        impl Color {
+            $(pub const $name: Self = Self::new_srgb($red, $green, $blue);)*
+        }
+        /// The names of the color constants and their colors, in the order of the json file.
+        const NAMED: &[(&str, Color)] = &[$((stringify!($name), Color::$name)),*];
+    };
+}
+named_colors! {
     pub const ABBEY: Self = Self::new_srgb(0.298_039_23, 0.309_803_93, 0.337_254_9);
     pub const ABSOLUTE_ZERO: Self = Self::new_srgb(0.0, 0.282_352_95, 0.729_411_8);
     pub const ACADIA: Self = Self::new_srgb(0.105_882_354, 0.078_431_375, 0.015_686_275);
@@ -0,0 +1,652 @@
+//! Parsing and formatting colors with the syntax of CSS.
+//!
+//! <https://www.w3.org/TR/css-color-4/>
+use std::fmt;
+use std::str::FromStr;
+
+use super::space::{hsl_to_rgb, oklch_to_rgb};
+use crate::{Color, ColorSpace, ParseError};
+
+/// The CSS named colors as `0xRRGGBB`, sorted by name.
+///
+/// <https://www.w3.org/TR/css-color-4/#named-colors>
+const CSS_NAMES: &[(&str, u32)] = &[
+    ("aliceblue", 0xf0f8ff),
+    ("antiquewhite", 0xfaebd7),
+    ("aqua", 0x00ffff),
+    ("aquamarine", 0x7fffd4),
+    ("azure", 0xf0ffff),
+    ("beige", 0xf5f5dc),
+    ("bisque", 0xffe4c4),
+    ("black", 0x000000),
+    ("blanchedalmond", 0xffebcd),
+    ("blue", 0x0000ff),
+    ("blueviolet", 0x8a2be2),
+    ("brown", 0xa52a2a),
+    ("burlywood", 0xdeb887),
+    ("cadetblue", 0x5f9ea0),
+    ("chartreuse", 0x7fff00),
+    ("chocolate", 0xd2691e),
+    ("coral", 0xff7f50),
+    ("cornflowerblue", 0x6495ed),
+    ("cornsilk", 0xfff8dc),
+    ("crimson", 0xdc143c),
+    ("cyan", 0x00ffff),
+    ("darkblue", 0x00008b),
+    ("darkcyan", 0x008b8b),
+    ("darkgoldenrod", 0xb8860b),
+    ("darkgray", 0xa9a9a9),
+    ("darkgreen", 0x006400),
+    ("darkgrey", 0xa9a9a9),
+    ("darkkhaki", 0xbdb76b),
+    ("darkmagenta", 0x8b008b),
+    ("darkolivegreen", 0x556b2f),
+    ("darkorange", 0xff8c00),
+    ("darkorchid", 0x9932cc),
+    ("darkred", 0x8b0000),
+    ("darksalmon", 0xe9967a),
+    ("darkseagreen", 0x8fbc8f),
+    ("darkslateblue", 0x483d8b),
+    ("darkslategray", 0x2f4f4f),
+    ("darkslategrey", 0x2f4f4f),
+    ("darkturquoise", 0x00ced1),
+    ("darkviolet", 0x9400d3),
+    ("deeppink", 0xff1493),
+    ("deepskyblue", 0x00bfff),
+    ("dimgray", 0x696969),
+    ("dimgrey", 0x696969),
+    ("dodgerblue", 0x1e90ff),
+    ("firebrick", 0xb22222),
+    ("floralwhite", 0xfffaf0),
+    ("forestgreen", 0x228b22),
+    ("fuchsia", 0xff00ff),
+    ("gainsboro", 0xdcdcdc),
+    ("ghostwhite", 0xf8f8ff),
+    ("gold", 0xffd700),
+    ("goldenrod", 0xdaa520),
+    ("gray", 0x808080),
+    ("green", 0x008000),
+    ("greenyellow", 0xadff2f),
+    ("grey", 0x808080),
+    ("honeydew", 0xf0fff0),
+    ("hotpink", 0xff69b4),
+    ("indianred", 0xcd5c5c),
+    ("indigo", 0x4b0082),
+    ("ivory", 0xfffff0),
+    ("khaki", 0xf0e68c),
+    ("lavender", 0xe6e6fa),
+    ("lavenderblush", 0xfff0f5),
+    ("lawngreen", 0x7cfc00),
+    ("lemonchiffon", 0xfffacd),
+    ("lightblue", 0xadd8e6),
+    ("lightcoral", 0xf08080),
+    ("lightcyan", 0xe0ffff),
+    ("lightgoldenrodyellow", 0xfafad2),
+    ("lightgray", 0xd3d3d3),
+    ("lightgreen", 0x90ee90),
+    ("lightgrey", 0xd3d3d3),
+    ("lightpink", 0xffb6c1),
+    ("lightsalmon", 0xffa07a),
+    ("lightseagreen", 0x20b2aa),
+    ("lightskyblue", 0x87cefa),
+    ("lightslategray", 0x778899),
+    ("lightslategrey", 0x778899),
+    ("lightsteelblue", 0xb0c4de),
+    ("lightyellow", 0xffffe0),
+    ("lime", 0x00ff00),
+    ("limegreen", 0x32cd32),
+    ("linen", 0xfaf0e6),
+    ("magenta", 0xff00ff),
+    ("maroon", 0x800000),
+    ("mediumaquamarine", 0x66cdaa),
+    ("mediumblue", 0x0000cd),
+    ("mediumorchid", 0xba55d3),
+    ("mediumpurple", 0x9370db),
+    ("mediumseagreen", 0x3cb371),
+    ("mediumslateblue", 0x7b68ee),
+    ("mediumspringgreen", 0x00fa9a),
+    ("mediumturquoise", 0x48d1cc),
+    ("mediumvioletred", 0xc71585),
+    ("midnightblue", 0x191970),
+    ("mintcream", 0xf5fffa),
+    ("mistyrose", 0xffe4e1),
+    ("moccasin", 0xffe4b5),
+    ("navajowhite", 0xffdead),
+    ("navy", 0x000080),
+    ("oldlace", 0xfdf5e6),
+    ("olive", 0x808000),
+    ("olivedrab", 0x6b8e23),
+    ("orange", 0xffa500),
+    ("orangered", 0xff4500),
+    ("orchid", 0xda70d6),
+    ("palegoldenrod", 0xeee8aa),
+    ("palegreen", 0x98fb98),
+    ("paleturquoise", 0xafeeee),
+    ("palevioletred", 0xdb7093),
+    ("papayawhip", 0xffefd5),
+    ("peachpuff", 0xffdab9),
+    ("peru", 0xcd853f),
+    ("pink", 0xffc0cb),
+    ("plum", 0xdda0dd),
+    ("powderblue", 0xb0e0e6),
+    ("purple", 0x800080),
+    ("rebeccapurple", 0x663399),
+    ("red", 0xff0000),
+    ("rosybrown", 0xbc8f8f),
+    ("royalblue", 0x4169e1),
+    ("saddlebrown", 0x8b4513),
+    ("salmon", 0xfa8072),
+    ("sandybrown", 0xf4a460),
+    ("seagreen", 0x2e8b57),
+    ("seashell", 0xfff5ee),
+    ("sienna", 0xa0522d),
+    ("silver", 0xc0c0c0),
+    ("skyblue", 0x87ceeb),
+    ("slateblue", 0x6a5acd),
+    ("slategray", 0x708090),
+    ("slategrey", 0x708090),
+    ("snow", 0xfffafa),
+    ("springgreen", 0x00ff7f),
+    ("steelblue", 0x4682b4),
+    ("tan", 0xd2b48c),
+    ("teal", 0x008080),
+    ("thistle", 0xd8bfd8),
+    ("tomato", 0xff6347),
+    ("turquoise", 0x40e0d0),
+    ("violet", 0xee82ee),
+    ("wheat", 0xf5deb3),
+    ("white", 0xffffff),
+    ("whitesmoke", 0xf5f5f5),
+    ("yellow", 0xffff00),
+    ("yellowgreen", 0x9acd32),
+];
+
+/// A single value inside the parentheses of a color function.
+#[derive(Debug, Copy, Clone)]
+enum Component {
+    /// The `none` keyword, which means zero.
+    None,
+    Number(f32),
+    Percentage(f32),
+    /// An angle in degrees.
+    Angle(f32),
+}
+
+/// A component, and the position where it starts.
+type Positioned = (usize, Component);
+
+struct Parser<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+impl Parser<'_> {
+    fn error(&self, message: &'static str) -> ParseError {
+        ParseError {
+            position: self.pos,
+            message,
+        }
+    }
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.pos).copied()
+    }
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(|b| b.is_ascii_whitespace()) {
+            self.pos += 1;
+        }
+    }
+    /// Skips whitespace, then the byte if it is next.
+    fn eat(&mut self, byte: u8) -> bool {
+        self.skip_whitespace();
+        let found = self.peek() == Some(byte);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+    fn expect(&mut self, byte: u8, message: &'static str) -> Result<(), ParseError> {
+        if self.eat(byte) {
+            Ok(())
+        } else {
+            Err(self.error(message))
+        }
+    }
+    /// Letters, digits and dashes, lowercased.
+    fn ident(&mut self) -> String {
+        let start = self.pos;
+        while self
+            .peek()
+            .is_some_and(|b| b.is_ascii_alphanumeric() || b == b'-')
+        {
+            self.pos += 1;
+        }
+        // only ascii was consumed, so this is always valid utf-8
+        String::from_utf8_lossy(&self.bytes[start..self.pos]).to_ascii_lowercase()
+    }
+    fn component(&mut self) -> Result<Positioned, ParseError> {
+        self.skip_whitespace();
+        let start = self.pos;
+        if self.peek().is_some_and(|b| b.is_ascii_alphabetic()) {
+            return match self.ident().as_str() {
+                "none" => Ok((start, Component::None)),
+                _ => Err(ParseError {
+                    position: start,
+                    message: "expected a number",
+                }),
+            };
+        }
+        if matches!(self.peek(), Some(b'+' | b'-')) {
+            self.pos += 1;
+        }
+        let mut digits = 0;
+        while self.peek().is_some_and(|b| b.is_ascii_digit()) {
+            self.pos += 1;
+            digits += 1;
+        }
+        if self.peek() == Some(b'.') {
+            self.pos += 1;
+            while self.peek().is_some_and(|b| b.is_ascii_digit()) {
+                self.pos += 1;
+                digits += 1;
+            }
+        }
+        if digits == 0 {
+            self.pos = start;
+            return Err(self.error("expected a number"));
+        }
+        if matches!(self.peek(), Some(b'e' | b'E')) {
+            let mantissa_end = self.pos;
+            self.pos += 1;
+            if matches!(self.peek(), Some(b'+' | b'-')) {
+                self.pos += 1;
+            }
+            if self.peek().is_some_and(|b| b.is_ascii_digit()) {
+                while self.peek().is_some_and(|b| b.is_ascii_digit()) {
+                    self.pos += 1;
+                }
+            } else {
+                // not an exponent. eg: the `e` could be the start of a unit
+                self.pos = mantissa_end;
+            }
+        }
+        let value: f32 = std::str::from_utf8(&self.bytes[start..self.pos])
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .ok_or(ParseError {
+                position: start,
+                message: "invalid number",
+            })?;
+        if self.peek() == Some(b'%') {
+            self.pos += 1;
+            return Ok((start, Component::Percentage(value)));
+        }
+        let unit_start = self.pos;
+        let degrees = match self.ident().as_str() {
+            "" => return Ok((start, Component::Number(value))),
+            "deg" => value,
+            "rad" => value.to_degrees(),
+            "grad" => value * 0.9,
+            "turn" => value * 360.0,
+            _ => {
+                return Err(ParseError {
+                    position: unit_start,
+                    message: "unknown unit",
+                })
+            }
+        };
+        Ok((start, Component::Angle(degrees)))
+    }
+    /// The three components and the optional alpha of a color function, up to the
+    /// closing parenthesis.
+    ///
+    /// Both the legacy syntax with commas and the modern one with a slash before the
+    /// alpha are accepted.
+    fn arguments(&mut self) -> Result<([Positioned; 3], Option<Positioned>), ParseError> {
+        let first = self.component()?;
+        let legacy = self.eat(b',');
+        let second = self.component()?;
+        if legacy {
+            self.expect(b',', "expected ','")?;
+        }
+        let third = self.component()?;
+        let alpha_separator = if legacy { b',' } else { b'/' };
+        let alpha = match self.eat(alpha_separator) {
+            true => Some(self.component()?),
+            false => None,
+        };
+        self.expect(b')', "expected ')'")?;
+        Ok(([first, second, third], alpha))
+    }
+}
+
+/// A number, or a percentage of `percent_reference`.
+fn number((position, component): Positioned, percent_reference: f32) -> Result<f32, ParseError> {
+    match component {
+        Component::None => Ok(0.0),
+        Component::Number(value) => Ok(value),
+        Component::Percentage(value) => Ok(value / 100.0 * percent_reference),
+        Component::Angle(_) => Err(ParseError {
+            position,
+            message: "unexpected angle",
+        }),
+    }
+}
+/// An angle in degrees, where plain numbers are degrees too.
+fn hue((position, component): Positioned) -> Result<f32, ParseError> {
+    match component {
+        Component::None => Ok(0.0),
+        Component::Number(degrees) | Component::Angle(degrees) => Ok(degrees),
+        Component::Percentage(_) => Err(ParseError {
+            position,
+            message: "expected a hue",
+        }),
+    }
+}
+fn alpha(alpha: Option<Positioned>) -> Result<f32, ParseError> {
+    Ok(match alpha {
+        Some(alpha) => number(alpha, 1.0)?.clamp(0.0, 1.0),
+        None => 1.0,
+    })
+}
+/// <https://www.w3.org/TR/css-color-4/#hwb-to-rgb>
+fn hwb_to_rgb(hue: f32, whiteness: f32, blackness: f32) -> [f32; 3] {
+    if whiteness + blackness >= 1.0 {
+        let gray = whiteness / (whiteness + blackness);
+        return [gray; 3];
+    }
+    hsl_to_rgb(hue, 1.0, 0.5).map(|c| c * (1.0 - whiteness - blackness) + whiteness)
+}
+impl Color {
+    /// Parse a color written like in CSS.
+    ///
+    /// The supported syntaxes are:
+    ///
+    /// * hex colors: `#rgb`, `#rgba`, `#rrggbb` and `#rrggbbaa`
+    /// * `rgb()` and `rgba()`, with numbers from 0 to 255 or percentages
+    /// * `hsl()` and `hsla()`
+    /// * `hwb()`
+    /// * `oklch()`
+    /// * `color()` with the `srgb` or `display-p3` color spaces
+    /// * the CSS named colors, like `rebeccapurple`, and `transparent`
+    ///
+    /// Functions accept both the legacy syntax with commas, like `rgba(255, 0, 0, 0.5)`,
+    /// and the modern one, like `rgb(255 0 0 / 50%)`. Hues can have a `deg`, `rad`,
+    /// `grad` or `turn` unit, and `none` is zero. Names, functions and units are not
+    /// case sensitive.
+    ///
+    /// Components are clamped to their range. `oklch()` colors outside of the sRGB gamut
+    /// are clipped to it, while `color(srgb ...)` colors outside of it are
+    /// [extended sRGB](ColorSpace::ExtendedSRGB).
+    ///
+    /// <https://www.w3.org/TR/css-color-4/>
+    ///
+    /// ```rust
+    /// # use impellers::*;
+    /// assert_eq!(Color::parse("#ff000080").unwrap(), Color::new_srgba(1.0, 0.0, 0.0, 128.0 / 255.0));
+    /// assert_eq!(Color::parse("rgb(0 0 255 / 50%)").unwrap(), Color::new_srgba(0.0, 0.0, 1.0, 0.5));
+    /// assert_eq!(Color::parse("hsl(120deg, 100%, 50%)").unwrap(), Color::new_srgb(0.0, 1.0, 0.0));
+    /// assert_eq!(Color::parse("White").unwrap(), Color::new_srgb(1.0, 1.0, 1.0));
+    /// assert!(Color::parse("rgb(1, 2)").is_err());
+    /// ```
+    pub fn parse(text: &str) -> Result<Self, ParseError> {
+        let mut parser = Parser {
+            bytes: text.as_bytes(),
+            pos: 0,
+        };
+        let color = parser.color()?;
+        parser.skip_whitespace();
+        if parser.pos != text.len() {
+            return Err(parser.error("unexpected characters after the color"));
+        }
+        Ok(color)
+    }
+    /// The color as `#rrggbb`, or `#rrggbbaa` if it is not opaque.
+    ///
+    /// Components are clamped to 0..1 and rounded to 8 bits. The color space is ignored.
+    pub fn to_hex(&self) -> String {
+        let byte = |v: f32| (v.clamp(0.0, 1.0) * 255.0).round() as u8;
+        let (r, g, b, a) = (
+            byte(self.red),
+            byte(self.green),
+            byte(self.blue),
+            byte(self.alpha),
+        );
+        if a == 255 {
+            format!("#{r:02x}{g:02x}{b:02x}")
+        } else {
+            format!("#{r:02x}{g:02x}{b:02x}{a:02x}")
+        }
+    }
+}
+impl Parser<'_> {
+    fn color(&mut self) -> Result<Color, ParseError> {
+        self.skip_whitespace();
+        let start = self.pos;
+        if self.eat(b'#') {
+            let digits_start = self.pos;
+            while self.peek().is_some_and(|b| b.is_ascii_hexdigit()) {
+                self.pos += 1;
+            }
+            let digits = &self.bytes[digits_start..self.pos];
+            let nibble = |i: usize| (digits[i] as char).to_digit(16).unwrap() as f32;
+            let channels: Vec<f32> = match digits.len() {
+                3 | 4 => (0..digits.len())
+                    .map(|i| nibble(i) * 17.0 / 255.0)
+                    .collect(),
+                6 | 8 => (0..digits.len() / 2)
+                    .map(|i| (nibble(2 * i) * 16.0 + nibble(2 * i + 1)) / 255.0)
+                    .collect(),
+                _ => {
+                    return Err(ParseError {
+                        position: digits_start,
+                        message: "expected 3, 4, 6 or 8 hex digits",
+                    })
+                }
+            };
+            let alpha = channels.get(3).copied().unwrap_or(1.0);
+            return Ok(Color::new_srgba(
+                channels[0],
+                channels[1],
+                channels[2],
+                alpha,
+            ));
+        }
+        let name = self.ident();
+        if name.is_empty() {
+            return Err(self.error("expected a color"));
+        }
+        if !self.eat(b'(') {
+            if name == "transparent" {
+                return Ok(Color::TRANSPARENT);
+            }
+            return match CSS_NAMES.binary_search_by(|(n, _)| n.cmp(&name.as_str())) {
+                Ok(index) => {
+                    let rgb = CSS_NAMES[index].1;
+                    let channel = |shift: u32| ((rgb >> shift) & 0xff) as f32 / 255.0;
+                    Ok(Color::new_srgb(channel(16), channel(8), channel(0)))
+                }
+                Err(_) => Err(ParseError {
+                    position: start,
+                    message: "unknown color name",
+                }),
+            };
+        }
+        let space = if name == "color" {
+            self.skip_whitespace();
+            let space_start = self.pos;
+            match self.ident().as_str() {
+                "srgb" => ColorSpace::ExtendedSRGB,
+                "display-p3" => ColorSpace::DisplayP3,
+                _ => {
+                    return Err(ParseError {
+                        position: space_start,
+                        message: "unsupported color space",
+                    })
+                }
+            }
+        } else {
+            ColorSpace::SRGB
+        };
+        let ([c0, c1, c2], a) = self.arguments()?;
+        let alpha = alpha(a)?;
+        let rgb = match name.as_str() {
+            "rgb" | "rgba" => {
+                [number(c0, 255.0)?, number(c1, 255.0)?, number(c2, 255.0)?].map(|c| c / 255.0)
+            }
+            "hsl" | "hsla" => hsl_to_rgb(
+                hue(c0)?,
+                (number(c1, 100.0)? / 100.0).clamp(0.0, 1.0),
+                (number(c2, 100.0)? / 100.0).clamp(0.0, 1.0),
+            ),
+            "hwb" => hwb_to_rgb(
+                hue(c0)?,
+                (number(c1, 100.0)? / 100.0).clamp(0.0, 1.0),
+                (number(c2, 100.0)? / 100.0).clamp(0.0, 1.0),
+            ),
+            "oklch" => oklch_to_rgb(
+                number(c0, 1.0)?.clamp(0.0, 1.0),
+                number(c1, 0.4)?.max(0.0),
+                hue(c2)?,
+            ),
+            "color" => {
+                let rgb = [number(c0, 1.0)?, number(c1, 1.0)?, number(c2, 1.0)?];
+                if space == ColorSpace::DisplayP3 || rgb.iter().any(|c| !(0.0..=1.0).contains(c)) {
+                    return Ok(Color {
+                        red: rgb[0],
+                        green: rgb[1],
+                        blue: rgb[2],
+                        alpha,
+                        color_space: space,
+                    });
+                }
+                rgb
+            }
+            _ => {
+                return Err(ParseError {
+                    position: start,
+                    message: "unknown color function",
+                })
+            }
+        };
+        let [red, green, blue] = rgb.map(|c| c.clamp(0.0, 1.0));
+        Ok(Color::new_srgba(red, green, blue, alpha))
+    }
+}
+impl FromStr for Color {
+    type Err = ParseError;
+
+    /// @see [Color::parse]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Color::parse(s)
+    }
+}
+/// Formats sRGB colors with [Color::to_hex], and other colors with the CSS `color()`
+/// function, so the result can be read back with [Color::parse].
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let space = match self.color_space {
+            ColorSpace::SRGB => return f.write_str(&self.to_hex()),
+            ColorSpace::ExtendedSRGB => "srgb",
+            ColorSpace::DisplayP3 => "display-p3",
+        };
+        write!(f, "color({space} {} {} {}", self.red, self.green, self.blue)?;
+        if self.alpha != 1.0 {
+            write!(f, " / {}", self.alpha)?;
+        }
+        f.write_str(")")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::fixtures::close;
+
+    fn rgba(r: u8, g: u8, b: u8, a: f32) -> Color {
+        Color::new_srgba(r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0, a)
+    }
+    #[test]
+    fn parsing() {
+        assert!(CSS_NAMES.windows(2).all(|w| w[0].0 < w[1].0));
+        assert_eq!(CSS_NAMES.len(), 148);
+        let parse = |text| Color::parse(text).unwrap();
+        assert_eq!(parse("#f00"), rgba(255, 0, 0, 1.0));
+        assert_eq!(parse("#F008"), rgba(255, 0, 0, 136.0 / 255.0));
+        assert_eq!(parse(" #336699 "), rgba(0x33, 0x66, 0x99, 1.0));
+        assert_eq!(parse("#33669900"), rgba(0x33, 0x66, 0x99, 0.0));
+        assert_eq!(parse("rgb(255, 128, 0)"), rgba(255, 128, 0, 1.0));
+        assert_eq!(
+            parse("RGBA(100%, 0%, 50%, 25%)"),
+            Color::new_srgba(1.0, 0.0, 0.5, 0.25)
+        );
+        assert_eq!(parse("rgb(300 none -5 / 2)"), rgba(255, 0, 0, 1.0));
+        assert_eq!(parse("rgb(1e2 0 0)"), rgba(100, 0, 0, 1.0));
+        assert!(close(
+            parse("hsl(0.5turn 100% 25%)"),
+            Color::new_srgb(0.0, 0.5, 0.5)
+        ));
+        assert!(close(
+            parse("hsla(240, 100%, 50%, .5)"),
+            rgba(0, 0, 255, 0.5)
+        ));
+        assert!(close(parse("hwb(60 20% 20%)"), rgba(204, 204, 51, 1.0)));
+        assert!(close(
+            parse("hwb(0 60% 60%)"),
+            Color::new_srgb(0.5, 0.5, 0.5)
+        ));
+        assert!(close(
+            parse("oklch(62.8% 0.2577 29.23)"),
+            rgba(255, 0, 0, 1.0)
+        ));
+        assert!(close(parse("oklch(1 0 0)"), Color::new_srgb(1.0, 1.0, 1.0)));
+        assert_eq!(parse("rebeccapurple"), rgba(0x66, 0x33, 0x99, 1.0));
+        assert_eq!(parse("transparent"), Color::TRANSPARENT);
+        assert_eq!(
+            parse("color(display-p3 1 0 0 / 0.5)"),
+            Color {
+                color_space: ColorSpace::DisplayP3,
+                ..Color::new_srgba(1.0, 0.0, 0.0, 0.5)
+            }
+        );
+        assert_eq!(parse("color(srgb 50% 0 1)"), Color::new_srgb(0.5, 0.0, 1.0));
+
+        let error = |text| Color::parse(text).unwrap_err();
+        assert_eq!(error("").message, "expected a color");
+        assert_eq!(error("#12345").message, "expected 3, 4, 6 or 8 hex digits");
+        assert_eq!(
+            error("notacolor"),
+            ParseError {
+                position: 0,
+                message: "unknown color name"
+            }
+        );
+        assert_eq!(error("rgb(1 2)").position, 7);
+        assert_eq!(error("rgb(1, 2 3)").message, "expected ','");
+        assert_eq!(error("hsl(10 20deg 30)").message, "unexpected angle");
+        assert_eq!(error("hsl(10px 20 30)").position, 6);
+        assert_eq!(
+            error("red blue").message,
+            "unexpected characters after the color"
+        );
+    }
+
+    #[test]
+    fn formatting() {
+        assert_eq!(Color::new_srgb(1.0, 0.5, 0.0).to_hex(), "#ff8000");
+        assert_eq!(
+            Color::new_srgba(0.0, 0.0, 0.0, 0.5).to_string(),
+            "#00000080"
+        );
+        let p3 = Color {
+            color_space: ColorSpace::DisplayP3,
+            ..Color::new_srgba(1.0, 0.25, 0.0, 0.5)
+        };
+        assert_eq!(p3.to_string(), "color(display-p3 1 0.25 0 / 0.5)");
+        let extended = Color {
+            color_space: ColorSpace::ExtendedSRGB,
+            ..Color::new_srgb(1.5, -0.25, 0.0)
+        };
+        for color in [p3, extended, Color::new_srgb(0.2, 0.4, 0.6)] {
+            assert_eq!(color.to_string().parse::<Color>().unwrap(), color);
+        }
+    }
+}
@@ -0,0 +1,289 @@
+//! Constructors for [ColorMatrix], matching the css filter functions.
+//!
+//! <https://www.w3.org/TR/filter-effects-1/#FilterPrimitiveRepresentation>
+use crate::{Color, ColorMatrix};
+
+/// Luminance weights of red, green and blue used by the css filter functions.
+const LUMINANCE: [f32; 3] = [0.2126, 0.7152, 0.0722];
+
+impl ColorMatrix {
+    /// A matrix from the 3x3 matrix which mixes red, green and blue, keeping alpha.
+    const fn rgb(m: [f32; 9]) -> Self {
+        Self {
+            m: [
+                m[0], m[1], m[2], 0.0, 0.0, //
+                m[3], m[4], m[5], 0.0, 0.0, //
+                m[6], m[7], m[8], 0.0, 0.0, //
+                0.0, 0.0, 0.0, 1.0, 0.0,
+            ],
+        }
+    }
+    /// A matrix scaling red, green and blue by `slope`, and adding `intercept` in 0..1.
+    fn linear(slope: f32, intercept: f32) -> Self {
+        let offset = intercept * 255.0;
+        Self {
+            m: [
+                slope, 0.0, 0.0, 0.0, offset, //
+                0.0, slope, 0.0, 0.0, offset, //
+                0.0, 0.0, slope, 0.0, offset, //
+                0.0, 0.0, 0.0, 1.0, 0.0,
+            ],
+        }
+    }
+    /// The matrix which keeps colors unchanged.
+    pub const fn identity() -> Self {
+        Self::rgb([1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0])
+    }
+    /// Scale the saturation, 0 is grayscale, 1 is unchanged, and more over saturates.
+    /// Negative amounts are treated as 0.
+    ///
+    /// <https://www.w3.org/TR/filter-effects-1/#saturateEquivalent>
+    pub fn saturation(amount: f32) -> Self {
+        let s = amount.max(0.0);
+        Self::rgb([
+            0.213 + 0.787 * s,
+            0.715 - 0.715 * s,
+            0.072 - 0.072 * s,
+            0.213 - 0.213 * s,
+            0.715 + 0.285 * s,
+            0.072 - 0.072 * s,
+            0.213 - 0.213 * s,
+            0.715 - 0.715 * s,
+            0.072 + 0.928 * s,
+        ])
+    }
+    /// Rotate hues by `degrees`, keeping the luminance.
+    ///
+    /// <https://www.w3.org/TR/filter-effects-1/#huerotateEquivalent>
+    pub fn hue_rotate(degrees: f32) -> Self {
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        Self::rgb([
+            0.213 + cos * 0.787 - sin * 0.213,
+            0.715 - cos * 0.715 - sin * 0.715,
+            0.072 - cos * 0.072 + sin * 0.928,
+            0.213 - cos * 0.213 + sin * 0.143,
+            0.715 + cos * 0.285 + sin * 0.140,
+            0.072 - cos * 0.072 - sin * 0.283,
+            0.213 - cos * 0.213 - sin * 0.787,
+            0.715 - cos * 0.715 + sin * 0.715,
+            0.072 + cos * 0.928 + sin * 0.072,
+        ])
+    }
+    /// Convert to grayscale, where `amount` 1 is fully gray and 0 is unchanged.
+    ///
+    /// <https://www.w3.org/TR/filter-effects-1/#grayscaleEquivalent>
+    pub fn grayscale(amount: f32) -> Self {
+        let a = 1.0 - amount.clamp(0.0, 1.0);
+        let [r, g, b] = LUMINANCE;
+        Self::rgb([
+            r + (1.0 - r) * a,
+            g - g * a,
+            b - b * a,
+            r - r * a,
+            g + (1.0 - g) * a,
+            b - b * a,
+            r - r * a,
+            g - g * a,
+            b + (1.0 - b) * a,
+        ])
+    }
+    /// Convert to sepia, where `amount` 1 is fully sepia and 0 is unchanged.
+    ///
+    /// <https://www.w3.org/TR/filter-effects-1/#sepiaEquivalent>
+    pub fn sepia(amount: f32) -> Self {
+        let a = 1.0 - amount.clamp(0.0, 1.0);
+        Self::rgb([
+            0.393 + 0.607 * a,
+            0.769 - 0.769 * a,
+            0.189 - 0.189 * a,
+            0.349 - 0.349 * a,
+            0.686 + 0.314 * a,
+            0.168 - 0.168 * a,
+            0.272 - 0.272 * a,
+            0.534 - 0.534 * a,
+            0.131 + 0.869 * a,
+        ])
+    }
+    /// Multiply red, green and blue by `amount`, 0 is black and 1 is unchanged.
+    ///
+    /// <https://www.w3.org/TR/filter-effects-1/#brightnessEquivalent>
+    pub fn brightness(amount: f32) -> Self {
+        Self::linear(amount.max(0.0), 0.0)
+    }
+    /// Scale the contrast around the middle gray, 0 is gray and 1 is unchanged.
+    ///
+    /// <https://www.w3.org/TR/filter-effects-1/#contrastEquivalent>
+    pub fn contrast(amount: f32) -> Self {
+        let amount = amount.max(0.0);
+        Self::linear(amount, 0.5 - 0.5 * amount)
+    }
+    /// Invert red, green and blue, where `amount` 1 is fully inverted and 0 is unchanged.
+    ///
+    /// <https://www.w3.org/TR/filter-effects-1/#invertEquivalent>
+    pub fn invert(amount: f32) -> Self {
+        let amount = amount.clamp(0.0, 1.0);
+        Self::linear(1.0 - 2.0 * amount, amount)
+    }
+    /// Black with the luminance as alpha, for masks.
+    ///
+    /// <https://www.w3.org/TR/filter-effects-1/#element-attrdef-fecolormatrix-type>
+    pub const fn luminance_to_alpha() -> Self {
+        let mut m = [0.0; 20];
+        m[15] = 0.2125;
+        m[16] = 0.7154;
+        m[17] = 0.0721;
+        Self { m }
+    }
+    /// Multiply each component by the one of `color`, including alpha.
+    /// Combine it with [ColorMatrix::grayscale] to colorize.
+    ///
+    /// The color space of `color` is ignored.
+    pub const fn tint(color: Color) -> Self {
+        let mut m = [0.0; 20];
+        m[0] = color.red;
+        m[6] = color.green;
+        m[12] = color.blue;
+        m[18] = color.alpha;
+        Self { m }
+    }
+    /// The matrix which applies `b` and then `a`, the product `a * b`.
+    ///
+    /// Unlike two color filters in a row, colors are not clamped to 0..1 in between.
+    ///
+    /// ```rust
+    /// # use impellers::*;
+    /// let sepia_tone = ColorMatrix::concat(&ColorMatrix::sepia(1.0), &ColorMatrix::grayscale(1.0));
+    /// let color = Color::new_srgb(0.2, 0.4, 0.6);
+    /// let expected = ColorMatrix::sepia(1.0).apply(ColorMatrix::grayscale(1.0).apply(color));
+    /// let result = sepia_tone.apply(color);
+    /// assert!((result.red - expected.red).abs() < 1e-6);
+    /// ```
+    pub fn concat(a: &ColorMatrix, b: &ColorMatrix) -> ColorMatrix {
+        let (a, b) = (&a.m, &b.m);
+        ColorMatrix {
+            m: std::array::from_fn(|i| {
+                let (row, column) = (i / 5, i % 5);
+                let product: f32 = (0..4).map(|k| a[row * 5 + k] * b[k * 5 + column]).sum();
+                // the implicit fifth row of b is [0, 0, 0, 0, 1]
+                match column {
+                    4 => product + a[row * 5 + 4],
+                    _ => product,
+                }
+            }),
+        }
+    }
+    /// Apply the matrix to a color which is not premultiplied, like
+    /// [ColorFilter::new_matrix](crate::ColorFilter::new_matrix) does on the GPU.
+    ///
+    /// The components of the result are clamped to 0..1, and the color space is kept.
+    ///
+    /// ```rust
+    /// # use impellers::*;
+    /// let color = Color::new_srgba(0.2, 0.4, 0.6, 0.5);
+    /// assert_eq!(ColorMatrix::identity().apply(color), color);
+    /// let magenta = Color::new_srgb(1.0, 0.0, 1.0);
+    /// assert_eq!(ColorMatrix::invert(1.0).apply(magenta), Color::new_srgb(0.0, 1.0, 0.0));
+    /// ```
+    pub fn apply(&self, color: Color) -> Color {
+        let c = [color.red, color.green, color.blue, color.alpha];
+        // impeller takes the offsets in 0..255
+        let [red, green, blue, alpha] = std::array::from_fn(|row| {
+            let m = &self.m[row * 5..row * 5 + 5];
+            (m[0] * c[0] + m[1] * c[1] + m[2] * c[2] + m[3] * c[3] + m[4] / 255.0).clamp(0.0, 1.0)
+        });
+        Color {
+            red,
+            green,
+            blue,
+            alpha,
+            color_space: color.color_space,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::fixtures::close;
+
+    #[test]
+    fn constructors() {
+        let color = Color::new_srgba(0.2, 0.4, 0.6, 0.8);
+        let unchanged = [
+            ColorMatrix::saturation(1.0),
+            ColorMatrix::hue_rotate(0.0),
+            ColorMatrix::hue_rotate(360.0),
+            ColorMatrix::grayscale(0.0),
+            ColorMatrix::sepia(0.0),
+            ColorMatrix::brightness(1.0),
+            ColorMatrix::contrast(1.0),
+            ColorMatrix::invert(0.0),
+            ColorMatrix::tint(Color::new_srgb(1.0, 1.0, 1.0)),
+        ];
+        for matrix in unchanged {
+            assert!(close(matrix.apply(color), color), "{matrix:?}");
+        }
+
+        let gray = ColorMatrix::grayscale(1.0).apply(color);
+        assert!(gray.red == gray.green && gray.green == gray.blue);
+        assert!(close(ColorMatrix::saturation(0.0).apply(color), gray));
+        assert_eq!(ColorMatrix::saturation(-1.0), ColorMatrix::saturation(0.0));
+        assert!(close(
+            ColorMatrix::brightness(0.5).apply(color),
+            Color::new_srgba(0.1, 0.2, 0.3, 0.8)
+        ));
+        assert!(close(
+            ColorMatrix::contrast(0.0).apply(color),
+            Color::new_srgba(0.5, 0.5, 0.5, 0.8)
+        ));
+        assert!(close(
+            ColorMatrix::invert(0.5).apply(color),
+            Color::new_srgba(0.5, 0.5, 0.5, 0.8)
+        ));
+        assert!(close(
+            ColorMatrix::luminance_to_alpha().apply(Color::new_srgb(1.0, 1.0, 1.0)),
+            Color::new_srgba(0.0, 0.0, 0.0, 1.0)
+        ));
+        assert!(close(
+            ColorMatrix::tint(Color::new_srgba(0.5, 1.0, 0.0, 0.5)).apply(color),
+            Color::new_srgba(0.1, 0.4, 0.0, 0.4)
+        ));
+        // hue rotation keeps grays, and turns red around the wheel
+        let white = Color::new_srgb(1.0, 1.0, 1.0);
+        assert!(close(ColorMatrix::hue_rotate(90.0).apply(white), white));
+        let red = Color::new_srgb(1.0, 0.0, 0.0);
+        let rotated = ColorMatrix::hue_rotate(120.0).apply(red);
+        assert!(rotated.green > rotated.red && rotated.green > rotated.blue);
+        // results are clamped
+        let bright = ColorMatrix::brightness(4.0).apply(color);
+        assert_eq!(bright, Color::new_srgba(0.8, 1.0, 1.0, 0.8));
+    }
+
+    #[test]
+    fn concat() {
+        let color = Color::new_srgba(0.2, 0.4, 0.6, 0.8);
+        let matrices = [
+            ColorMatrix::identity(),
+            ColorMatrix::hue_rotate(45.0),
+            ColorMatrix::sepia(0.7),
+            ColorMatrix::contrast(0.5),
+            ColorMatrix::invert(1.0),
+            ColorMatrix::tint(Color::new_srgba(0.5, 1.0, 0.25, 0.5)),
+        ];
+        for a in &matrices {
+            for b in &matrices {
+                let expected = a.apply(b.apply(color));
+                assert!(
+                    close(ColorMatrix::concat(a, b).apply(color), expected),
+                    "{a:?} {b:?}"
+                );
+            }
+        }
+        assert_eq!(
+            ColorMatrix::concat(&ColorMatrix::identity(), &ColorMatrix::invert(1.0)),
+            ColorMatrix::invert(1.0)
+        );
+        let twice = ColorMatrix::concat(&ColorMatrix::invert(1.0), &ColorMatrix::invert(1.0));
+        assert_eq!(twice, ColorMatrix::identity());
+    }
+}
@@ -0,0 +1,259 @@
+//! Interpolating colors, alpha premultiplication and contrast.
+use crate::{Color, Hsl, Hsv, Lab, Oklab, Oklch};
+
+/// The color space in which [Color::lerp] interpolates.
+///
+/// <https://www.w3.org/TR/css-color-4/#interpolation>
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum InterpolationSpace {
+    /// Gamma encoded sRGB, like most renderers blend colors.
+    Srgb,
+    /// Linear sRGB, which mixes light physically.
+    LinearSrgb,
+    /// CIE Lab.
+    Lab,
+    /// OKLab, which keeps the perceived lightness even, and is usually the best choice
+    /// for gradients.
+    Oklab,
+    /// OKLCH, which goes around the hue wheel instead of through gray.
+    Oklch,
+    /// HSL, going around the hue wheel.
+    Hsl,
+    /// HSV, going around the hue wheel.
+    Hsv,
+}
+
+/// Interpolate hues in degrees along the shorter arc.
+fn mix_hue(a: f32, b: f32, t: f32) -> f32 {
+    let delta = (b - a + 180.0).rem_euclid(360.0) - 180.0;
+    (a + delta * t).rem_euclid(360.0)
+}
+
+impl Color {
+    /// Interpolate from `a` at `t = 0` to `b` at `t = 1` in `space`.
+    ///
+    /// Like in css, components are interpolated premultiplied by alpha, so that transparent
+    /// colors do not bleed their color into the result, and hues go along the shorter arc.
+    /// The hue of a gray is ignored, so mixing with gray only changes the chroma.
+    ///
+    /// `t` is not clamped, values outside of 0..1 extrapolate. The result is in
+    /// [ColorSpace::SRGB](crate::ColorSpace::SRGB), or in
+    /// [ColorSpace::ExtendedSRGB](crate::ColorSpace::ExtendedSRGB) if it is out of the sRGB
+    /// gamut, use [Color::to_srgb] to gamut map it.
+    ///
+    /// <https://www.w3.org/TR/css-color-4/#interpolation>
+    ///
+    /// ```rust
+    /// # use impellers::*;
+    /// let (black, white) = (Color::new_srgb(0.0, 0.0, 0.0), Color::new_srgb(1.0, 1.0, 1.0));
+    /// let middle = Color::lerp(black, white, 0.5, InterpolationSpace::Srgb);
+    /// assert_eq!(middle, Color::new_srgb(0.5, 0.5, 0.5));
+    /// // half the light, which looks brighter than half way
+    /// let middle = Color::lerp(black, white, 0.5, InterpolationSpace::LinearSrgb);
+    /// assert!(middle.red > 0.7);
+    /// ```
+    pub fn lerp(a: Color, b: Color, t: f32, space: InterpolationSpace) -> Color {
+        let mix = |x: f32, y: f32| x + (y - x) * t;
+        let alpha = mix(a.alpha, b.alpha);
+        let premixed = |x: f32, y: f32| {
+            if alpha > 0.0 {
+                mix(x * a.alpha, y * b.alpha) / alpha
+            } else {
+                mix(x, y)
+            }
+        };
+        let premixed3 = |x: [f32; 3], y: [f32; 3]| std::array::from_fn(|i| premixed(x[i], y[i]));
+        // the hue of grays is missing, and takes the other hue
+        let hue = |(x, x_gray): (f32, bool), (y, y_gray): (f32, bool)| match (x_gray, y_gray) {
+            (true, false) => y,
+            (false, true) => x,
+            _ => mix_hue(x, y, t),
+        };
+        let alpha = alpha.clamp(0.0, 1.0);
+        match space {
+            InterpolationSpace::Srgb => {
+                let (a, b) = (a.to_extended_srgb(), b.to_extended_srgb());
+                let rgb = premixed3([a.red, a.green, a.blue], [b.red, b.green, b.blue]);
+                Color::from_encoded(rgb, alpha)
+            }
+            InterpolationSpace::LinearSrgb => {
+                Color::from_linear(premixed3(a.linear(), b.linear()), alpha)
+            }
+            InterpolationSpace::Lab => {
+                let (x, y) = (a.to_lab(), b.to_lab());
+                let [lightness, a, b] = premixed3([x.lightness, x.a, x.b], [y.lightness, y.a, y.b]);
+                Color::from_lab(Lab {
+                    lightness,
+                    a,
+                    b,
+                    alpha,
+                })
+            }
+            InterpolationSpace::Oklab => {
+                let (x, y) = (a.to_oklab(), b.to_oklab());
+                let [lightness, a, b] = premixed3([x.lightness, x.a, x.b], [y.lightness, y.a, y.b]);
+                Color::from_oklab(Oklab {
+                    lightness,
+                    a,
+                    b,
+                    alpha,
+                })
+            }
+            InterpolationSpace::Oklch => {
+                let (x, y) = (a.to_oklch(), b.to_oklch());
+                Color::from_oklch(Oklch {
+                    lightness: premixed(x.lightness, y.lightness),
+                    chroma: premixed(x.chroma, y.chroma),
+                    hue: hue((x.hue, x.chroma < 1e-4), (y.hue, y.chroma < 1e-4)),
+                    alpha,
+                })
+            }
+            InterpolationSpace::Hsl => {
+                let (x, y) = (a.to_hsl(), b.to_hsl());
+                Color::from_hsl(Hsl {
+                    hue: hue((x.hue, x.saturation <= 0.0), (y.hue, y.saturation <= 0.0)),
+                    saturation: premixed(x.saturation, y.saturation),
+                    lightness: premixed(x.lightness, y.lightness),
+                    alpha,
+                })
+            }
+            InterpolationSpace::Hsv => {
+                let (x, y) = (a.to_hsv(), b.to_hsv());
+                Color::from_hsv(Hsv {
+                    hue: hue((x.hue, x.saturation <= 0.0), (y.hue, y.saturation <= 0.0)),
+                    saturation: premixed(x.saturation, y.saturation),
+                    value: premixed(x.value, y.value),
+                    alpha,
+                })
+            }
+        }
+    }
+    /// The color with red, green and blue multiplied by alpha, in the same color space.
+    pub fn premultiplied(self) -> Self {
+        Self {
+            red: self.red * self.alpha,
+            green: self.green * self.alpha,
+            blue: self.blue * self.alpha,
+            ..self
+        }
+    }
+    /// The inverse of [Color::premultiplied]. Fully transparent colors become transparent black.
+    pub fn unpremultiplied(self) -> Self {
+        if self.alpha <= 0.0 {
+            return Self {
+                red: 0.0,
+                green: 0.0,
+                blue: 0.0,
+                ..self
+            };
+        }
+        Self {
+            red: self.red / self.alpha,
+            green: self.green / self.alpha,
+            blue: self.blue / self.alpha,
+            ..self
+        }
+    }
+    /// The relative luminance of WCAG, from 0 for black to 1 for white.
+    ///
+    /// Alpha is ignored, blend translucent colors onto their background first, for example
+    /// with [BlendMode::SourceOver](crate::BlendMode::SourceOver).
+    ///
+    /// <https://www.w3.org/TR/WCAG21/#dfn-relative-luminance>
+    pub fn relative_luminance(&self) -> f32 {
+        let [r, g, b] = self.linear();
+        0.2126 * r + 0.7152 * g + 0.0722 * b
+    }
+    /// The WCAG contrast ratio between two colors, from 1 for the same luminance to 21 for
+    /// black and white. The order of the colors does not matter.
+    ///
+    /// WCAG AA asks for at least 4.5 for normal text, and 3 for large text.
+    ///
+    /// <https://www.w3.org/TR/WCAG21/#dfn-contrast-ratio>
+    ///
+    /// ```rust
+    /// # use impellers::*;
+    /// let white = Color::new_srgb(1.0, 1.0, 1.0);
+    /// let black = Color::new_srgb(0.0, 0.0, 0.0);
+    /// assert!((white.contrast_ratio(&black) - 21.0).abs() < 1e-4);
+    /// assert!(white.contrast_ratio(&Color::new_srgb(0.6, 0.6, 0.6)) < 4.5);
+    /// ```
+    pub fn contrast_ratio(&self, other: &Color) -> f32 {
+        let (a, b) = (self.relative_luminance(), other.relative_luminance());
+        (a.max(b) + 0.05) / (a.min(b) + 0.05)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::fixtures::close;
+    use crate::ColorSpace;
+
+    #[test]
+    fn lerp() {
+        let red = Color::new_srgb(1.0, 0.0, 0.0);
+        let blue = Color::new_srgb(0.0, 0.0, 1.0);
+        let spaces = [
+            InterpolationSpace::Srgb,
+            InterpolationSpace::LinearSrgb,
+            InterpolationSpace::Lab,
+            InterpolationSpace::Oklab,
+            InterpolationSpace::Oklch,
+            InterpolationSpace::Hsl,
+            InterpolationSpace::Hsv,
+        ];
+        for space in spaces {
+            assert!(close(Color::lerp(red, blue, 0.0, space), red), "{space:?}");
+            assert!(close(Color::lerp(red, blue, 1.0, space), blue), "{space:?}");
+        }
+        let srgb = Color::lerp(red, blue, 0.5, InterpolationSpace::Srgb);
+        assert_eq!(srgb, Color::new_srgb(0.5, 0.0, 0.5));
+        // red to blue in HSL goes through magenta, the shorter arc
+        let hsl = Color::lerp(red, blue, 0.5, InterpolationSpace::Hsl);
+        assert!(close(hsl, Color::new_srgb(1.0, 0.0, 1.0)));
+        let hue = Color::lerp(red, blue, 0.5, InterpolationSpace::Oklch)
+            .to_oklch()
+            .hue;
+        assert!(!(29.0..=264.0).contains(&hue));
+
+        // transparent colors do not bleed
+        let transparent_blue = blue.with_alpha(0.0);
+        let half = Color::lerp(red, transparent_blue, 0.5, InterpolationSpace::Oklab);
+        assert!(close(half, red.with_alpha(0.5)));
+        // gray takes the other hue
+        let gray = Color::new_srgb(0.5, 0.5, 0.5);
+        let hsl = Color::lerp(gray, red, 0.5, InterpolationSpace::Hsl).to_hsl();
+        assert!(hsl.hue.abs() < 1e-3 && (hsl.saturation - 0.5).abs() < 1e-3);
+
+        let p3 = Color {
+            color_space: ColorSpace::DisplayP3,
+            ..red
+        };
+        let mixed = Color::lerp(p3, p3, 0.5, InterpolationSpace::Oklab);
+        assert_eq!(mixed.color_space, ColorSpace::ExtendedSRGB);
+        assert!(close(mixed.to_display_p3(), p3));
+    }
+
+    #[test]
+    fn premultiplication_and_contrast() {
+        let color = Color::new_srgba(0.5, 1.0, 0.25, 0.5);
+        assert_eq!(
+            color.premultiplied(),
+            Color::new_srgba(0.25, 0.5, 0.125, 0.5)
+        );
+        assert_eq!(color.premultiplied().unpremultiplied(), color);
+        assert_eq!(
+            Color::new_srgba(1.0, 1.0, 1.0, 0.0).unpremultiplied(),
+            Color::TRANSPARENT
+        );
+
+        let white = Color::new_srgb(1.0, 1.0, 1.0);
+        assert!((white.relative_luminance() - 1.0).abs() < 1e-6);
+        assert_eq!(Color::new_srgb(0.0, 0.0, 0.0).relative_luminance(), 0.0);
+        // https://webaim.org/resources/contrastchecker/ gives 4.54 for #767676 on white
+        let gray = Color::parse("#767676").unwrap();
+        assert!((white.contrast_ratio(&gray) - 4.54).abs() < 0.01);
+        assert_eq!(white.contrast_ratio(&gray), gray.contrast_ratio(&white));
+    }
+}
@@ -0,0 +1,105 @@
+//! Looking up the color constants by name.
+use super::NAMED;
+use crate::Color;
+
+/// Lowercase `name`, and map separators to `_`, or drop them if `keep_separators` is false.
+fn normalized(name: &str, keep_separators: bool) -> impl Iterator<Item = char> + '_ {
+    name.chars()
+        .filter_map(move |c| match c {
+            '_' | '-' | ' ' => keep_separators.then_some('_'),
+            c => Some(c),
+        })
+        .flat_map(char::to_lowercase)
+}
+
+impl Color {
+    /// Find a color constant by name.
+    ///
+    /// The lookup ignores case, and `_`, `-` and spaces, so `"alice_blue"`, `"Alice Blue"`
+    /// and `"aliceblue"` are all [Color::ALICE_BLUE]. When two names differ only by their
+    /// separators, like [Color::INCH_WORM] and [Color::INCHWORM], the one with the same
+    /// separators wins.
+    ///
+    /// These are the constants of [Color], not the CSS named colors, use [Color::parse] for those.
+    ///
+    /// ```rust
+    /// # use impellers::*;
+    /// assert_eq!(Color::from_name("alice_blue"), Some(Color::ALICE_BLUE));
+    /// assert_eq!(Color::from_name("Alice-Blue"), Some(Color::ALICE_BLUE));
+    /// assert_eq!(Color::from_name("not a color"), None);
+    /// ```
+    pub fn from_name(name: &str) -> Option<Self> {
+        let find = |keep_separators| {
+            NAMED.iter().find(|(named, _)| {
+                normalized(named, keep_separators).eq(normalized(name, keep_separators))
+            })
+        };
+        find(true).or_else(|| find(false)).map(|&(_, color)| color)
+    }
+    /// All the color constants of [Color], with the names of the constants, like `"ALICE_BLUE"`.
+    pub fn all_named() -> impl Iterator<Item = (&'static str, Color)> {
+        NAMED.iter().copied()
+    }
+    /// The color constant closest to this color, with its name.
+    ///
+    /// The distance is a weighted euclidean distance of the sRGB components, which
+    /// tracks perceived differences better than the plain one. Colors in other spaces are
+    /// converted with [Color::to_srgb] first, and alpha is ignored.
+    ///
+    /// <https://www.compuphase.com/cmetric.htm>
+    ///
+    /// ```rust
+    /// # use impellers::*;
+    /// let (name, color) = Color::new_srgb(0.94, 0.97, 1.0).nearest_named();
+    /// assert_eq!(name, "ALICE_BLUE");
+    /// assert_eq!(color, Color::ALICE_BLUE);
+    /// ```
+    pub fn nearest_named(self) -> (&'static str, Color) {
+        let srgb = self.to_srgb();
+        let distance = |color: &Color| {
+            let mean_red = (srgb.red + color.red) / 2.0;
+            let (r, g, b) = (
+                srgb.red - color.red,
+                srgb.green - color.green,
+                srgb.blue - color.blue,
+            );
+            (2.0 + mean_red) * r * r + 4.0 * g * g + (3.0 - mean_red) * b * b
+        };
+        NAMED
+            .iter()
+            .copied()
+            .min_by(|(_, a), (_, b)| distance(a).total_cmp(&distance(b)))
+            .expect("there are named colors")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn lookup() {
+        assert_eq!(Color::from_name("ALICE_BLUE"), Some(Color::ALICE_BLUE));
+        assert_eq!(Color::from_name("alice blue"), Some(Color::ALICE_BLUE));
+        assert_eq!(Color::from_name("AliceBlue"), Some(Color::ALICE_BLUE));
+        assert_eq!(Color::from_name("halayà úbe"), Some(Color::HALAYÀ_ÚBE));
+        assert_eq!(Color::from_name("inch-worm"), Some(Color::INCH_WORM));
+        assert_eq!(Color::from_name("inchworm"), Some(Color::INCHWORM));
+        assert_eq!(Color::from_name(""), None);
+        assert_eq!(Color::from_name("alice blu"), None);
+
+        assert_eq!(Color::all_named().count(), NAMED.len());
+        for (name, color) in Color::all_named() {
+            assert_eq!(Color::from_name(name), Some(color));
+            assert_eq!(color.nearest_named().1, color);
+        }
+        assert_eq!(
+            Color::new_srgb(0.01, 0.0, 0.0).nearest_named().1,
+            Color::BLACK
+        );
+        assert_eq!(
+            Color::new_srgba(1.0, 1.0, 1.0, 0.0).nearest_named().1,
+            Color::WHITE
+        );
+    }
+}
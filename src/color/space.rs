@@ -0,0 +1,604 @@
+//! Conversions between the color spaces of [Color], and to color models like HSL or OKLab.
+//!
+//! Everything goes through linear extended sRGB, with the matrices and transfer functions
+//! of the css color spec.
+//!
+//! <https://www.w3.org/TR/css-color-4/#color-conversion-code>
+use crate::{Color, ColorSpace};
+
+/// A color as hue, saturation and lightness, like the css `hsl()` function.
+///
+/// <https://en.wikipedia.org/wiki/HSL_and_HSV>
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Hsl {
+    /// The hue in degrees, 0..360. Zero for grays.
+    pub hue: f32,
+    /// 0..1
+    pub saturation: f32,
+    /// 0..1
+    pub lightness: f32,
+    /// 0..1
+    pub alpha: f32,
+}
+/// A color as hue, saturation and value, also known as HSB.
+///
+/// <https://en.wikipedia.org/wiki/HSL_and_HSV>
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Hsv {
+    /// The hue in degrees, 0..360. Zero for grays.
+    pub hue: f32,
+    /// 0..1
+    pub saturation: f32,
+    /// 0..1
+    pub value: f32,
+    /// 0..1
+    pub alpha: f32,
+}
+/// A color in the CIE Lab color space, with the D50 white point like the css `lab()` function.
+///
+/// <https://en.wikipedia.org/wiki/CIELAB_color_space>
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Lab {
+    /// 0..100
+    pub lightness: f32,
+    /// The green (negative) to red (positive) axis, usually within -125..125.
+    pub a: f32,
+    /// The blue (negative) to yellow (positive) axis, usually within -125..125.
+    pub b: f32,
+    /// 0..1
+    pub alpha: f32,
+}
+/// A color in the OKLab perceptual color space.
+///
+/// <https://bottosson.github.io/posts/oklab/>
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Oklab {
+    /// 0..1
+    pub lightness: f32,
+    /// The green (negative) to red (positive) axis, usually within -0.4..0.4.
+    pub a: f32,
+    /// The blue (negative) to yellow (positive) axis, usually within -0.4..0.4.
+    pub b: f32,
+    /// 0..1
+    pub alpha: f32,
+}
+/// A color in the OKLab color space, with polar coordinates.
+///
+/// <https://bottosson.github.io/posts/oklab/>
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Oklch {
+    /// 0..1
+    pub lightness: f32,
+    /// The distance from gray, usually within 0..0.4.
+    pub chroma: f32,
+    /// The hue in degrees, 0..360. Zero for grays.
+    pub hue: f32,
+    /// 0..1
+    pub alpha: f32,
+}
+/// A color in sRGB without the gamma encoding, where components are proportional to light
+/// intensity. This is the space to average or blend colors physically.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct LinearSrgb {
+    /// 0..1, or beyond for colors outside of the sRGB gamut.
+    pub red: f32,
+    /// 0..1, or beyond for colors outside of the sRGB gamut.
+    pub green: f32,
+    /// 0..1, or beyond for colors outside of the sRGB gamut.
+    pub blue: f32,
+    /// 0..1
+    pub alpha: f32,
+}
+
+type Matrix = [[f32; 3]; 3];
+
+const LINEAR_P3_TO_SRGB: Matrix = [
+    [1.224_940_2, -0.224_940_18, 0.0],
+    [-0.042_056_955, 1.042_056_9, 0.0],
+    [-0.019_637_555, -0.078_636_05, 1.098_273_6],
+];
+const LINEAR_SRGB_TO_P3: Matrix = [
+    [0.822_461_97, 0.177_538_03, 0.0],
+    [0.033_194_2, 0.966_805_8, 0.0],
+    [0.017_082_632, 0.072_397_44, 0.910_519_9],
+];
+/// To XYZ, with the Bradford chromatic adaptation from D65 to D50.
+const LINEAR_SRGB_TO_XYZ_D50: Matrix = [
+    [0.436_065_75, 0.385_151_5, 0.143_078_42],
+    [0.222_493_18, 0.716_887, 0.060_619_81],
+    [0.013_923_922, 0.097_081_326, 0.714_099_4],
+];
+const XYZ_D50_TO_LINEAR_SRGB: Matrix = [
+    [3.134_136, -1.617_386, -0.490_662_22],
+    [-0.978_795_47, 1.916_254_4, 0.033_442_873],
+    [0.071_955_39, -0.228_976_76, 1.405_386],
+];
+const D50_WHITE: [f32; 3] = [0.964_295_7, 1.0, 0.825_104_6];
+/// The largest difference in OKLab which is not noticeable, used by the gamut mapping.
+const JUST_NOTICEABLE_DIFFERENCE: f32 = 0.02;
+/// How far out of 0..1 components may be from rounding errors, and still be in gamut.
+const TOLERANCE: f32 = 1e-5;
+
+fn multiply(matrix: &Matrix, [x, y, z]: [f32; 3]) -> [f32; 3] {
+    matrix.map(|row| row[0] * x + row[1] * y + row[2] * z)
+}
+/// The sRGB transfer function, which is also the one of Display P3.
+/// Negative components are mirrored.
+fn decode(rgb: [f32; 3]) -> [f32; 3] {
+    rgb.map(|c| {
+        let linear = if c.abs() <= 0.040_45 {
+            c.abs() / 12.92
+        } else {
+            ((c.abs() + 0.055) / 1.055).powf(2.4)
+        };
+        linear.copysign(c)
+    })
+}
+/// The inverse of [decode].
+fn encode(linear: [f32; 3]) -> [f32; 3] {
+    linear.map(|c| {
+        let encoded = if c.abs() <= 0.003_130_8 {
+            12.92 * c.abs()
+        } else {
+            1.055 * c.abs().powf(1.0 / 2.4) - 0.055
+        };
+        encoded.copysign(c)
+    })
+}
+fn in_gamut(rgb: [f32; 3]) -> bool {
+    rgb.iter()
+        .all(|c| (-TOLERANCE..=1.0 + TOLERANCE).contains(c))
+}
+fn linear_srgb_to_oklab([r, g, b]: [f32; 3]) -> [f32; 3] {
+    let l = (0.412_221_46 * r + 0.536_332_55 * g + 0.051_445_995 * b).cbrt();
+    let m = (0.211_903_5 * r + 0.680_699_5 * g + 0.107_396_96 * b).cbrt();
+    let s = (0.088_302_46 * r + 0.281_718_85 * g + 0.629_978_7 * b).cbrt();
+    [
+        0.210_454_26 * l + 0.793_617_8 * m - 0.004_072_047 * s,
+        1.977_998_5 * l - 2.428_592_2 * m + 0.450_593_7 * s,
+        0.025_904_037 * l + 0.782_771_77 * m - 0.808_675_77 * s,
+    ]
+}
+fn oklab_to_linear_srgb([lightness, a, b]: [f32; 3]) -> [f32; 3] {
+    let l = (lightness + 0.396_337_78 * a + 0.215_803_76 * b).powi(3);
+    let m = (lightness - 0.105_561_346 * a - 0.063_854_17 * b).powi(3);
+    let s = (lightness - 0.089_484_18 * a - 1.291_485_5 * b).powi(3);
+    [
+        4.076_741_7 * l - 3.307_711_6 * m + 0.230_969_94 * s,
+        -1.268_438 * l + 2.609_757_4 * m - 0.341_319_38 * s,
+        -0.004_196_086_3 * l - 0.703_418_6 * m + 1.707_614_7 * s,
+    ]
+}
+fn lab_to_lch([lightness, a, b]: [f32; 3]) -> [f32; 3] {
+    let chroma = a.hypot(b);
+    // the hue of grays is noise from rounding errors
+    let hue = if chroma < TOLERANCE {
+        0.0
+    } else {
+        b.atan2(a).to_degrees().rem_euclid(360.0)
+    };
+    [lightness, chroma, hue]
+}
+fn lch_to_lab([lightness, chroma, hue]: [f32; 3]) -> [f32; 3] {
+    let (sin, cos) = hue.to_radians().sin_cos();
+    [lightness, chroma * cos, chroma * sin]
+}
+/// <https://www.w3.org/TR/css-color-4/#hsl-to-rgb>
+pub(super) fn hsl_to_rgb(hue: f32, saturation: f32, lightness: f32) -> [f32; 3] {
+    let hue = hue.rem_euclid(360.0);
+    let f = |n: f32| {
+        let k = (n + hue / 30.0) % 12.0;
+        let a = saturation * lightness.min(1.0 - lightness);
+        lightness - a * (k - 3.0).min(9.0 - k).clamp(-1.0, 1.0)
+    };
+    [f(0.0), f(8.0), f(4.0)]
+}
+/// OKLCH to gamma encoded sRGB, which may be out of the 0..1 range.
+pub(super) fn oklch_to_rgb(lightness: f32, chroma: f32, hue: f32) -> [f32; 3] {
+    encode(oklab_to_linear_srgb(lch_to_lab([lightness, chroma, hue])))
+}
+/// The hue in degrees of gamma encoded sRGB, and its min and max components.
+fn hue_min_max([r, g, b]: [f32; 3]) -> (f32, f32, f32) {
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let delta = max - min;
+    let hue = if delta <= 0.0 {
+        0.0
+    } else if max == r {
+        60.0 * ((g - b) / delta)
+    } else if max == g {
+        60.0 * ((b - r) / delta + 2.0)
+    } else {
+        60.0 * ((r - g) / delta + 4.0)
+    };
+    (hue.rem_euclid(360.0), min, max)
+}
+/// Map linear sRGB into the gamut of `target`, by reducing the chroma in OKLCH until the
+/// clipped color is not noticeably different, and clip.
+///
+/// `target` converts linear sRGB to the gamma encoded components of the target space.
+///
+/// <https://www.w3.org/TR/css-color-4/#gamut-mapping>
+fn gamut_map(linear: [f32; 3], target: fn([f32; 3]) -> [f32; 3]) -> [f32; 3] {
+    let clip = |rgb: [f32; 3]| rgb.map(|c| c.clamp(0.0, 1.0));
+    let encoded = target(linear);
+    if in_gamut(encoded) {
+        return clip(encoded);
+    }
+    let [lightness, chroma, hue] = lab_to_lch(linear_srgb_to_oklab(linear));
+    if lightness >= 1.0 {
+        return [1.0; 3];
+    }
+    if lightness <= 0.0 {
+        return [0.0; 3];
+    }
+    // the difference between an OKLCH color and its clipped version in the target space
+    let clipped = |chroma: f32| {
+        let current = lch_to_lab([lightness, chroma, hue]);
+        let encoded = target(oklab_to_linear_srgb(current));
+        let clipped = clip(encoded);
+        let [l, a, b] = linear_srgb_to_oklab(decode(clipped));
+        let difference = (current[0] - l).hypot(current[1] - a).hypot(current[2] - b);
+        (encoded, clipped, difference)
+    };
+    let (_, mut result, difference) = clipped(chroma);
+    if difference < JUST_NOTICEABLE_DIFFERENCE {
+        return result;
+    }
+    let (mut min, mut max) = (0.0, chroma);
+    let mut min_in_gamut = true;
+    while max - min > 1e-4 {
+        let chroma = (min + max) / 2.0;
+        let (encoded, clipped, difference) = clipped(chroma);
+        if min_in_gamut && in_gamut(encoded) {
+            min = chroma;
+            continue;
+        }
+        result = clipped;
+        if difference < JUST_NOTICEABLE_DIFFERENCE {
+            if JUST_NOTICEABLE_DIFFERENCE - difference < 1e-4 {
+                break;
+            }
+            min_in_gamut = false;
+            min = chroma;
+        } else {
+            max = chroma;
+        }
+    }
+    result
+}
+
+impl Color {
+    /// The color in linear extended sRGB.
+    pub(super) fn linear(&self) -> [f32; 3] {
+        let rgb = decode([self.red, self.green, self.blue]);
+        match self.color_space {
+            ColorSpace::SRGB | ColorSpace::ExtendedSRGB => rgb,
+            ColorSpace::DisplayP3 => multiply(&LINEAR_P3_TO_SRGB, rgb),
+        }
+    }
+    /// Gamma encode linear sRGB, see [Color::from_encoded].
+    pub(super) fn from_linear(linear: [f32; 3], alpha: f32) -> Self {
+        Self::from_encoded(encode(linear), alpha)
+    }
+    /// Gamma encoded sRGB, as [ColorSpace::SRGB] if it is in gamut, and as
+    /// [ColorSpace::ExtendedSRGB] otherwise.
+    pub(super) fn from_encoded(rgb: [f32; 3], alpha: f32) -> Self {
+        let (rgb, color_space) = match in_gamut(rgb) {
+            true => (rgb.map(|c| c.clamp(0.0, 1.0)), ColorSpace::SRGB),
+            false => (rgb, ColorSpace::ExtendedSRGB),
+        };
+        Self {
+            red: rgb[0],
+            green: rgb[1],
+            blue: rgb[2],
+            alpha,
+            color_space,
+        }
+    }
+    /// Convert to [ColorSpace::SRGB].
+    ///
+    /// Colors outside of the sRGB gamut, from [ColorSpace::DisplayP3] or
+    /// [ColorSpace::ExtendedSRGB], are gamut mapped: their chroma is reduced while keeping
+    /// the lightness and hue, which looks much closer to the original than clipping the
+    /// components. sRGB colors are only clamped to 0..1.
+    ///
+    /// <https://www.w3.org/TR/css-color-4/#gamut-mapping>
+    ///
+    /// ```rust
+    /// # use impellers::*;
+    /// let p3 = Color { color_space: ColorSpace::DisplayP3, ..Color::new_srgb(1.0, 0.0, 0.0) };
+    /// let srgb = p3.to_srgb();
+    /// assert_eq!(srgb.color_space, ColorSpace::SRGB);
+    /// assert!(srgb.red > 0.99 && srgb.green < 0.1 && srgb.blue < 0.1);
+    /// ```
+    pub fn to_srgb(self) -> Self {
+        let [red, green, blue] = match self.color_space {
+            ColorSpace::SRGB => [self.red, self.green, self.blue].map(|c| c.clamp(0.0, 1.0)),
+            _ => gamut_map(self.linear(), encode),
+        };
+        Self::new_srgba(red, green, blue, self.alpha)
+    }
+    /// Convert to [ColorSpace::ExtendedSRGB], where colors outside of the sRGB gamut have
+    /// components outside of 0..1. No gamut mapping is done, so this conversion is lossless.
+    pub fn to_extended_srgb(self) -> Self {
+        let [red, green, blue] = match self.color_space {
+            ColorSpace::SRGB | ColorSpace::ExtendedSRGB => [self.red, self.green, self.blue],
+            ColorSpace::DisplayP3 => encode(self.linear()),
+        };
+        Self {
+            red,
+            green,
+            blue,
+            alpha: self.alpha,
+            color_space: ColorSpace::ExtendedSRGB,
+        }
+    }
+    /// Convert to [ColorSpace::DisplayP3].
+    ///
+    /// sRGB colors always fit, while extended sRGB colors outside of the Display P3 gamut are
+    /// gamut mapped like in [Color::to_srgb].
+    pub fn to_display_p3(self) -> Self {
+        let [red, green, blue] = match self.color_space {
+            ColorSpace::DisplayP3 => [self.red, self.green, self.blue],
+            _ => gamut_map(self.linear(), |linear| {
+                encode(multiply(&LINEAR_SRGB_TO_P3, linear))
+            }),
+        };
+        Self {
+            red,
+            green,
+            blue,
+            alpha: self.alpha,
+            color_space: ColorSpace::DisplayP3,
+        }
+    }
+    /// Convert to linear sRGB, without gamut mapping.
+    pub fn to_linear_srgb(self) -> LinearSrgb {
+        let [red, green, blue] = self.linear();
+        LinearSrgb {
+            red,
+            green,
+            blue,
+            alpha: self.alpha,
+        }
+    }
+    /// The color of linear sRGB components, in [ColorSpace::SRGB], or in
+    /// [ColorSpace::ExtendedSRGB] if they are outside of 0..1.
+    pub fn from_linear_srgb(linear: LinearSrgb) -> Self {
+        Self::from_linear([linear.red, linear.green, linear.blue], linear.alpha)
+    }
+    /// Convert to HSL.
+    ///
+    /// HSL is defined on gamma encoded sRGB, so colors in other spaces are converted with
+    /// [Color::to_extended_srgb] first, and the saturation of colors outside of the sRGB
+    /// gamut may be out of 0..1.
+    pub fn to_hsl(self) -> Hsl {
+        let srgb = self.to_extended_srgb();
+        let (hue, min, max) = hue_min_max([srgb.red, srgb.green, srgb.blue]);
+        let lightness = (min + max) / 2.0;
+        let saturation = if lightness <= 0.0 || lightness >= 1.0 {
+            0.0
+        } else {
+            (max - lightness) / lightness.min(1.0 - lightness)
+        };
+        Hsl {
+            hue,
+            saturation,
+            lightness,
+            alpha: self.alpha,
+        }
+    }
+    /// The color of HSL components, in [ColorSpace::SRGB] unless they are out of range.
+    ///
+    /// ```rust
+    /// # use impellers::*;
+    /// let green = Color::from_hsl(Hsl { hue: 120.0, saturation: 1.0, lightness: 0.5, alpha: 1.0 });
+    /// assert_eq!(green, Color::new_srgb(0.0, 1.0, 0.0));
+    /// assert_eq!(green.to_hsl().hue, 120.0);
+    /// ```
+    pub fn from_hsl(hsl: Hsl) -> Self {
+        let rgb = hsl_to_rgb(hsl.hue, hsl.saturation, hsl.lightness);
+        Self::from_encoded(rgb, hsl.alpha)
+    }
+    /// Convert to HSV, with the same caveats as [Color::to_hsl].
+    pub fn to_hsv(self) -> Hsv {
+        let srgb = self.to_extended_srgb();
+        let (hue, min, max) = hue_min_max([srgb.red, srgb.green, srgb.blue]);
+        Hsv {
+            hue,
+            saturation: if max <= 0.0 { 0.0 } else { (max - min) / max },
+            value: max,
+            alpha: self.alpha,
+        }
+    }
+    /// The color of HSV components, in [ColorSpace::SRGB] unless they are out of range.
+    ///
+    /// <https://en.wikipedia.org/wiki/HSL_and_HSV#HSV_to_RGB_alternative>
+    pub fn from_hsv(hsv: Hsv) -> Self {
+        let hue = hsv.hue.rem_euclid(360.0);
+        let f = |n: f32| {
+            let k = (n + hue / 60.0) % 6.0;
+            hsv.value - hsv.value * hsv.saturation * k.min(4.0 - k).clamp(0.0, 1.0)
+        };
+        Self::from_encoded([f(5.0), f(3.0), f(1.0)], hsv.alpha)
+    }
+    /// Convert to CIE Lab.
+    pub fn to_lab(self) -> Lab {
+        let xyz = multiply(&LINEAR_SRGB_TO_XYZ_D50, self.linear());
+        let f = |i: usize| {
+            let t = xyz[i] / D50_WHITE[i];
+            if t > 216.0 / 24389.0 {
+                t.cbrt()
+            } else {
+                (24389.0 / 27.0 * t + 16.0) / 116.0
+            }
+        };
+        let (x, y, z) = (f(0), f(1), f(2));
+        Lab {
+            lightness: 116.0 * y - 16.0,
+            a: 500.0 * (x - y),
+            b: 200.0 * (y - z),
+            alpha: self.alpha,
+        }
+    }
+    /// The color of CIE Lab components, in [ColorSpace::SRGB], or in
+    /// [ColorSpace::ExtendedSRGB] if they are outside of the sRGB gamut.
+    pub fn from_lab(lab: Lab) -> Self {
+        let y = (lab.lightness + 16.0) / 116.0;
+        let f = [y + lab.a / 500.0, y, y - lab.b / 200.0];
+        let xyz = [0, 1, 2].map(|i| {
+            let t = if f[i].powi(3) > 216.0 / 24389.0 {
+                f[i].powi(3)
+            } else {
+                (116.0 * f[i] - 16.0) / (24389.0 / 27.0)
+            };
+            t * D50_WHITE[i]
+        });
+        Self::from_linear(multiply(&XYZ_D50_TO_LINEAR_SRGB, xyz), lab.alpha)
+    }
+    /// Convert to OKLab.
+    pub fn to_oklab(self) -> Oklab {
+        let [lightness, a, b] = linear_srgb_to_oklab(self.linear());
+        Oklab {
+            lightness,
+            a,
+            b,
+            alpha: self.alpha,
+        }
+    }
+    /// The color of OKLab components, in [ColorSpace::SRGB], or in
+    /// [ColorSpace::ExtendedSRGB] if they are outside of the sRGB gamut.
+    /// Use [Color::to_srgb] to gamut map it.
+    pub fn from_oklab(oklab: Oklab) -> Self {
+        let linear = oklab_to_linear_srgb([oklab.lightness, oklab.a, oklab.b]);
+        Self::from_linear(linear, oklab.alpha)
+    }
+    /// Convert to OKLCH.
+    pub fn to_oklch(self) -> Oklch {
+        let [lightness, chroma, hue] = lab_to_lch(linear_srgb_to_oklab(self.linear()));
+        Oklch {
+            lightness,
+            chroma,
+            hue,
+            alpha: self.alpha,
+        }
+    }
+    /// The color of OKLCH components, like [Color::from_oklab].
+    ///
+    /// ```rust
+    /// # use impellers::*;
+    /// // a palette with the same perceived lightness and chroma
+    /// let palette: Vec<Color> = (0..6)
+    ///     .map(|i| Color::from_oklch(Oklch { lightness: 0.7, chroma: 0.1, hue: i as f32 * 60.0, alpha: 1.0 }))
+    ///     .collect();
+    /// assert!(palette.iter().all(|color| color.color_space == ColorSpace::SRGB));
+    /// ```
+    pub fn from_oklch(oklch: Oklch) -> Self {
+        let lab = lch_to_lab([oklch.lightness, oklch.chroma, oklch.hue]);
+        Self::from_linear(oklab_to_linear_srgb(lab), oklch.alpha)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::fixtures::close;
+
+    fn p3(red: f32, green: f32, blue: f32) -> Color {
+        Color {
+            color_space: ColorSpace::DisplayP3,
+            ..Color::new_srgb(red, green, blue)
+        }
+    }
+
+    #[test]
+    fn round_trips() {
+        let colors = [
+            Color::new_srgba(0.2, 0.4, 0.6, 0.5),
+            Color::new_srgb(1.0, 1.0, 1.0),
+            Color::new_srgb(0.0, 0.0, 0.0),
+            Color::new_srgb(0.5, 0.5, 0.5),
+            Color::new_srgb(1.0, 0.0, 0.0),
+            Color::new_srgb(0.02, 0.9, 0.3),
+        ];
+        for color in colors {
+            assert!(close(Color::from_hsl(color.to_hsl()), color), "{color}");
+            assert!(close(Color::from_hsv(color.to_hsv()), color), "{color}");
+            assert!(close(Color::from_lab(color.to_lab()), color), "{color}");
+            assert!(close(Color::from_oklab(color.to_oklab()), color), "{color}");
+            assert!(close(Color::from_oklch(color.to_oklch()), color), "{color}");
+            let linear = color.to_linear_srgb();
+            assert!(close(Color::from_linear_srgb(linear), color), "{color}");
+            assert!(close(color.to_display_p3().to_srgb(), color), "{color}");
+        }
+    }
+
+    #[test]
+    fn known_values() {
+        let red = Color::new_srgb(1.0, 0.0, 0.0);
+        let hsv = red.to_hsv();
+        assert_eq!((hsv.hue, hsv.saturation, hsv.value), (0.0, 1.0, 1.0));
+        let hsl = Color::new_srgb(0.25, 0.5, 0.75).to_hsl();
+        assert!((hsl.hue - 210.0).abs() < 1e-3);
+        assert!((hsl.saturation - 0.5).abs() < 1e-3);
+        assert!((hsl.lightness - 0.5).abs() < 1e-3);
+        // https://www.w3.org/TR/css-color-4/#specifying-lab-lch
+        let lab = red.to_lab();
+        assert!((lab.lightness - 54.29).abs() < 0.01);
+        assert!((lab.a - 80.81).abs() < 0.01);
+        assert!((lab.b - 69.89).abs() < 0.01);
+        let white = Color::new_srgb(1.0, 1.0, 1.0).to_lab();
+        assert!((white.lightness - 100.0).abs() < 1e-3 && white.a.abs() < 1e-3);
+        let oklch = red.to_oklch();
+        assert!((oklch.lightness - 0.628).abs() < 1e-3);
+        assert!((oklch.chroma - 0.2577).abs() < 1e-3);
+        assert!((oklch.hue - 29.23).abs() < 0.01);
+        assert_eq!(Color::new_srgb(0.5, 0.5, 0.5).to_oklch().hue, 0.0);
+        let linear = Color::new_srgb(0.5, 0.5, 0.5).to_linear_srgb();
+        assert!((linear.red - 0.214).abs() < 1e-3);
+    }
+
+    #[test]
+    fn spaces() {
+        // sRGB red is inside of P3
+        let red = Color::new_srgb(1.0, 0.0, 0.0).to_display_p3();
+        assert_eq!(red.color_space, ColorSpace::DisplayP3);
+        assert!(close(red, p3(0.9175, 0.2003, 0.1386)));
+
+        // P3 red is not inside of sRGB
+        let extended = p3(1.0, 0.0, 0.0).to_extended_srgb();
+        assert_eq!(extended.color_space, ColorSpace::ExtendedSRGB);
+        assert!(extended.red > 1.0 && extended.green < 0.0 && extended.blue < 0.0);
+        assert!(close(extended.to_display_p3(), p3(1.0, 0.0, 0.0)));
+
+        // gamut mapping keeps the hue and lightness, and the result is in gamut
+        let mapped = p3(0.0, 1.0, 0.0).to_srgb();
+        assert_eq!(mapped.color_space, ColorSpace::SRGB);
+        let (before, after) = (p3(0.0, 1.0, 0.0).to_oklch(), mapped.to_oklch());
+        assert!((before.hue - after.hue).abs() < 3.0);
+        assert!((before.lightness - after.lightness).abs() < 0.02);
+        assert!(after.chroma < before.chroma);
+        let clipped = Color::new_srgb(0.0, 1.0, 0.0);
+        assert!(!close(mapped, clipped));
+
+        assert!(close(
+            p3(1.0, 1.0, 1.0).to_srgb(),
+            Color::new_srgb(1.0, 1.0, 1.0)
+        ));
+        let too_bright = Color {
+            color_space: ColorSpace::ExtendedSRGB,
+            ..Color::new_srgb(2.0, 2.0, 1.5)
+        };
+        assert_eq!(too_bright.to_srgb(), Color::new_srgb(1.0, 1.0, 1.0));
+
+        let vivid = Color::from_oklch(Oklch {
+            lightness: 0.7,
+            chroma: 0.4,
+            hue: 150.0,
+            alpha: 1.0,
+        });
+        assert_eq!(vivid.color_space, ColorSpace::ExtendedSRGB);
+        assert_eq!(vivid.to_srgb().color_space, ColorSpace::SRGB);
+    }
+}
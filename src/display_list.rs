@@ -0,0 +1,432 @@
+//! Rust-side mirror of the draw commands that a recording
+//! [DisplayListBuilder](crate::DisplayListBuilder) forwards to impeller.
+//!
+//! Impeller display lists are opaque. With the `recording` feature, a builder created with
+//! [DisplayListBuilder::new_recording](crate::DisplayListBuilder::new_recording) also keeps
+//! a [RecordedDisplayList], so tests can check what was drawn without a GPU context.
+//!
+//! Recordings can be [encoded](RecordedDisplayList::to_bytes) to bytes, and
+//! [replayed](RecordedDisplayList::to_display_list) into a new impeller display list,
+//! exported as [svg](RecordedDisplayList::to_svg), or
+//! [rasterized](RecordedDisplayList::rasterize) on the CPU. A [DamageTracker] compares
+//! the recordings of consecutive frames to find what needs to be repainted, and
+//! [culled](RecordedDisplayList::culled) copies only keep what a viewport shows.
+//! Recordings implement [Display](std::fmt::Display) to print their ops for debugging, and
+//! [stats](RecordedDisplayList::stats) counts them and estimates their cost.
+//! [hit_test](RecordedDisplayList::hit_test) finds the ops that draw at a point.
+use std::sync::Arc;
+
+mod bounds;
+mod codec;
+mod cull;
+mod damage;
+mod dump;
+mod hit;
+mod raster;
+mod replay;
+mod stats;
+mod svg;
+pub use damage::DamageTracker;
+pub use hit::HitResult;
+pub use stats::DisplayListStats;
+
+use crate::{
+    ClipOperation, Color, Matrix, Point, RecordedImageFilter, RecordedPaint, RecordedParagraph,
+    RecordedPath, RecordedTexture, Rect, RoundingRadii, TextureSampling,
+};
+
+/// A single call on a [DisplayListBuilder](crate::DisplayListBuilder), along with its arguments.
+///
+/// Paints, paths and other objects are captured as they were at the time of the call.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DisplayOp {
+    /// @see [DisplayListBuilder::save](crate::DisplayListBuilder::save)
+    Save,
+    /// @see [DisplayListBuilder::save_layer](crate::DisplayListBuilder::save_layer)
+    #[allow(missing_docs)]
+    SaveLayer {
+        bounds: Rect,
+        paint: Option<RecordedPaint>,
+        backdrop: Option<Arc<RecordedImageFilter>>,
+    },
+    /// @see [DisplayListBuilder::restore](crate::DisplayListBuilder::restore)
+    Restore,
+    /// @see [DisplayListBuilder::restore_to_count](crate::DisplayListBuilder::restore_to_count)
+    RestoreToCount(u32),
+    /// @see [DisplayListBuilder::scale](crate::DisplayListBuilder::scale)
+    #[allow(missing_docs)]
+    Scale { x_scale: f32, y_scale: f32 },
+    /// @see [DisplayListBuilder::rotate](crate::DisplayListBuilder::rotate)
+    #[allow(missing_docs)]
+    Rotate { angle_degrees: f32 },
+    /// @see [DisplayListBuilder::translate](crate::DisplayListBuilder::translate)
+    #[allow(missing_docs)]
+    Translate {
+        x_translation: f32,
+        y_translation: f32,
+    },
+    /// @see [DisplayListBuilder::transform](crate::DisplayListBuilder::transform)
+    Transform(Matrix),
+    /// @see [DisplayListBuilder::set_transform](crate::DisplayListBuilder::set_transform)
+    SetTransform(Matrix),
+    /// @see [DisplayListBuilder::reset_transform](crate::DisplayListBuilder::reset_transform)
+    ResetTransform,
+    /// @see [DisplayListBuilder::clip_rect](crate::DisplayListBuilder::clip_rect)
+    #[allow(missing_docs)]
+    ClipRect { rect: Rect, op: ClipOperation },
+    /// @see [DisplayListBuilder::clip_oval](crate::DisplayListBuilder::clip_oval)
+    #[allow(missing_docs)]
+    ClipOval {
+        oval_bounds: Rect,
+        op: ClipOperation,
+    },
+    /// @see [DisplayListBuilder::clip_rounded_rect](crate::DisplayListBuilder::clip_rounded_rect)
+    #[allow(missing_docs)]
+    ClipRoundedRect {
+        rect: Rect,
+        radii: RoundingRadii,
+        op: ClipOperation,
+    },
+    /// @see [DisplayListBuilder::clip_path](crate::DisplayListBuilder::clip_path)
+    #[allow(missing_docs)]
+    ClipPath {
+        path: Arc<RecordedPath>,
+        op: ClipOperation,
+    },
+    /// @see [DisplayListBuilder::draw_paint](crate::DisplayListBuilder::draw_paint)
+    #[allow(missing_docs)]
+    DrawPaint { paint: RecordedPaint },
+    /// @see [DisplayListBuilder::draw_line](crate::DisplayListBuilder::draw_line)
+    #[allow(missing_docs)]
+    DrawLine {
+        from: Point,
+        to: Point,
+        paint: RecordedPaint,
+    },
+    /// @see [DisplayListBuilder::draw_dashed_line](crate::DisplayListBuilder::draw_dashed_line)
+    #[allow(missing_docs)]
+    DrawDashedLine {
+        from: Point,
+        to: Point,
+        on_length: f32,
+        off_length: f32,
+        paint: RecordedPaint,
+    },
+    /// @see [DisplayListBuilder::draw_rect](crate::DisplayListBuilder::draw_rect)
+    #[allow(missing_docs)]
+    DrawRect { rect: Rect, paint: RecordedPaint },
+    /// @see [DisplayListBuilder::draw_oval](crate::DisplayListBuilder::draw_oval)
+    #[allow(missing_docs)]
+    DrawOval {
+        oval_bounds: Rect,
+        paint: RecordedPaint,
+    },
+    /// @see [DisplayListBuilder::draw_rounded_rect](crate::DisplayListBuilder::draw_rounded_rect)
+    #[allow(missing_docs)]
+    DrawRoundedRect {
+        rect: Rect,
+        radii: RoundingRadii,
+        paint: RecordedPaint,
+    },
+    /// @see [DisplayListBuilder::draw_rounded_rect_difference](crate::DisplayListBuilder::draw_rounded_rect_difference)
+    #[allow(missing_docs)]
+    DrawRoundedRectDifference {
+        outer_rect: Rect,
+        outer_radii: RoundingRadii,
+        inner_rect: Rect,
+        inner_radii: RoundingRadii,
+        paint: RecordedPaint,
+    },
+    /// @see [DisplayListBuilder::draw_path](crate::DisplayListBuilder::draw_path)
+    #[allow(missing_docs)]
+    DrawPath {
+        path: Arc<RecordedPath>,
+        paint: RecordedPaint,
+    },
+    /// @see [DisplayListBuilder::draw_display_list](crate::DisplayListBuilder::draw_display_list)
+    ///
+    /// `display_list` is `None` if the drawn list was not built by a recording builder.
+    #[allow(missing_docs)]
+    DrawDisplayList {
+        display_list: Option<Arc<RecordedDisplayList>>,
+        opacity: f32,
+    },
+    /// @see [DisplayListBuilder::draw_paragraph](crate::DisplayListBuilder::draw_paragraph)
+    #[allow(missing_docs)]
+    DrawParagraph {
+        paragraph: Arc<RecordedParagraph>,
+        point: Point,
+    },
+    /// @see [DisplayListBuilder::draw_shadow](crate::DisplayListBuilder::draw_shadow)
+    #[allow(missing_docs)]
+    DrawShadow {
+        path: Arc<RecordedPath>,
+        color: Color,
+        elevation: f32,
+        occluder_is_transparent: bool,
+        device_pixel_ratio: f32,
+    },
+    /// @see [DisplayListBuilder::draw_texture](crate::DisplayListBuilder::draw_texture)
+    #[allow(missing_docs)]
+    DrawTexture {
+        texture: Arc<RecordedTexture>,
+        point: Point,
+        sampling: TextureSampling,
+        paint: RecordedPaint,
+    },
+    /// @see [DisplayListBuilder::draw_texture_rect](crate::DisplayListBuilder::draw_texture_rect)
+    #[allow(missing_docs)]
+    DrawTextureRect {
+        texture: Arc<RecordedTexture>,
+        src_rect: Rect,
+        dst_rect: Rect,
+        sampling: TextureSampling,
+        paint: Option<RecordedPaint>,
+    },
+    /// @see [DisplayListBuilder::set_tag](crate::DisplayListBuilder::set_tag)
+    SetTag(u64),
+}
+impl DisplayOp {
+    /// The paint used by this op, if it has one.
+    pub fn paint(&self) -> Option<&RecordedPaint> {
+        match self {
+            DisplayOp::SaveLayer { paint, .. } | DisplayOp::DrawTextureRect { paint, .. } => {
+                paint.as_ref()
+            }
+            DisplayOp::DrawPaint { paint }
+            | DisplayOp::DrawLine { paint, .. }
+            | DisplayOp::DrawDashedLine { paint, .. }
+            | DisplayOp::DrawRect { paint, .. }
+            | DisplayOp::DrawOval { paint, .. }
+            | DisplayOp::DrawRoundedRect { paint, .. }
+            | DisplayOp::DrawRoundedRectDifference { paint, .. }
+            | DisplayOp::DrawPath { paint, .. }
+            | DisplayOp::DrawTexture { paint, .. } => Some(paint),
+            _ => None,
+        }
+    }
+    /// The transform after this op, or `None` if the op doesn't change the transform.
+    ///
+    /// `base` is what [DisplayOp::ResetTransform] resets to. It is the identity, except
+    /// inside nested display lists, where it's the transform they were drawn with.
+    pub(crate) fn next_transform(&self, current: &Matrix, base: &Matrix) -> Option<Matrix> {
+        let local = match self {
+            DisplayOp::Scale { x_scale, y_scale } => Matrix::scale(*x_scale, *y_scale, 1.0),
+            DisplayOp::Rotate { angle_degrees } => {
+                Matrix::rotation(0.0, 0.0, 1.0, euclid::Angle::degrees(*angle_degrees))
+            }
+            DisplayOp::Translate {
+                x_translation,
+                y_translation,
+            } => Matrix::translation(*x_translation, *y_translation, 0.0),
+            DisplayOp::Transform(transform) => *transform,
+            DisplayOp::SetTransform(transform) => return Some(transform.then(base)),
+            DisplayOp::ResetTransform => return Some(*base),
+            _ => return None,
+        };
+        Some(local.then(current))
+    }
+}
+/// A pass over the ops of a recorded display list, like finding its bounds or rasterizing it.
+///
+/// [walk] keeps the stack of saves and the transforms of its levels, and hands the rest
+/// of the ops to the pass.
+pub(crate) trait Pass {
+    /// What a save pushed onto the stack.
+    type Level;
+    /// The current transform of a level.
+    fn transform(level: &mut Self::Level) -> &mut Matrix;
+    /// An op changed the transform of the top level.
+    fn transformed(&mut self, _op: &DisplayOp) {}
+    /// The level that a [DisplayOp::Save] or [DisplayOp::SaveLayer] pushes on top of `top`.
+    fn save(&mut self, top: &Self::Level, op: &DisplayOp) -> Self::Level;
+    /// A level was popped by a [DisplayOp::Restore] or [DisplayOp::RestoreToCount].
+    fn restore(&mut self, level: Self::Level, parent: &mut Self::Level);
+    /// A level was never restored before the end of the list.
+    fn unwind(&mut self, level: Self::Level, parent: &mut Self::Level) {
+        self.restore(level, parent);
+    }
+    /// Any other op, drawn in the top level. `index` is where it is in the list.
+    fn op(&mut self, top: &mut Self::Level, index: usize, op: &DisplayOp);
+}
+/// Walk the ops of a list that is drawn in `base`, and return `base` at the end of the list.
+///
+/// Restores never pop `base`, and [DisplayOp::ResetTransform] resets to its transform.
+pub(crate) fn walk<P: Pass>(
+    pass: &mut P,
+    list: &RecordedDisplayList,
+    mut base: P::Level,
+) -> P::Level {
+    let base_transform = *P::transform(&mut base);
+    let mut stack = vec![base];
+    for (index, op) in list.ops().iter().enumerate() {
+        let top = stack.last_mut().unwrap();
+        let transform = P::transform(top);
+        if let Some(next) = op.next_transform(transform, &base_transform) {
+            *transform = next;
+            pass.transformed(op);
+            continue;
+        }
+        let restores = match op {
+            DisplayOp::Save | DisplayOp::SaveLayer { .. } => {
+                let level = pass.save(top, op);
+                stack.push(level);
+                continue;
+            }
+            DisplayOp::Restore => 1.min(stack.len() - 1),
+            DisplayOp::RestoreToCount(count) => stack.len().saturating_sub(*count as usize),
+            op => {
+                pass.op(top, index, op);
+                continue;
+            }
+        };
+        for _ in 0..restores {
+            let level = stack.pop().unwrap();
+            pass.restore(level, stack.last_mut().unwrap());
+        }
+    }
+    while stack.len() > 1 {
+        let level = stack.pop().unwrap();
+        pass.unwind(level, stack.last_mut().unwrap());
+    }
+    stack.pop().unwrap()
+}
+/// The blur sigma impeller uses for [DisplayListBuilder::draw_shadow](crate::DisplayListBuilder::draw_shadow).
+///
+/// <https://github.com/flutter/engine/blob/main/impeller/display_list/dl_dispatcher.cc>
+pub(crate) fn shadow_sigma(elevation: f32, device_pixel_ratio: f32) -> f32 {
+    // light radius / light height
+    const LIGHT_RADIUS: f32 = 800.0 / 600.0;
+    let radius = LIGHT_RADIUS * elevation * device_pixel_ratio;
+    if radius > 0.0 {
+        radius / 3f32.sqrt() + 0.5
+    } else {
+        0.0
+    }
+}
+/// How deeply recorded objects, like composed image filters or display lists drawn into
+/// display lists, can nest before decoding or replaying them fails. Crafted input could
+/// overflow the stack otherwise.
+const MAX_DEPTH: usize = 64;
+/// The ops recorded by a [DisplayListBuilder](crate::DisplayListBuilder) in recording mode.
+///
+/// @see [DisplayList::ops](crate::DisplayList::ops)
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RecordedDisplayList {
+    cull_rect: Option<Rect>,
+    ops: Vec<DisplayOp>,
+}
+impl RecordedDisplayList {
+    /// Create an empty recording with an optional cull rect.
+    ///
+    /// @see [DisplayListBuilder::new](crate::DisplayListBuilder::new)
+    pub fn new(cull_rect: Option<Rect>) -> Self {
+        Self {
+            cull_rect,
+            ops: vec![],
+        }
+    }
+    /// The cull rect that the builder was created with.
+    pub fn cull_rect(&self) -> Option<Rect> {
+        self.cull_rect
+    }
+    /// The recorded ops, in the order they were called.
+    pub fn ops(&self) -> &[DisplayOp] {
+        &self.ops
+    }
+    /// Append an op.
+    pub fn push(&mut self, op: DisplayOp) -> &mut Self {
+        self.ops.push(op);
+        self
+    }
+    /// Take the recorded ops, leaving an empty recording with the same cull rect.
+    pub(crate) fn take(&mut self) -> Self {
+        Self {
+            cull_rect: self.cull_rect,
+            ops: std::mem::take(&mut self.ops),
+        }
+    }
+}
+/// The rust side copy of an impeller object, like the [RecordedPaint] of a [Paint](crate::Paint).
+///
+/// Without the `recording` feature, nothing is kept and the closures that build the copy are
+/// never called, so objects don't pay for copies that no builder can record.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Recording<T>(
+    #[cfg(feature = "recording")] T,
+    #[cfg(not(feature = "recording"))] std::marker::PhantomData<T>,
+);
+impl<T> Recording<T> {
+    /// Record the value built by `value`.
+    pub(crate) fn new(value: impl FnOnce() -> T) -> Self {
+        #[cfg(feature = "recording")]
+        return Self(value());
+        #[cfg(not(feature = "recording"))]
+        {
+            let _ = value;
+            Self(std::marker::PhantomData)
+        }
+    }
+    /// Change the recorded value in place.
+    pub(crate) fn update(&mut self, update: impl FnOnce(&mut T)) {
+        #[cfg(feature = "recording")]
+        update(&mut self.0);
+        #[cfg(not(feature = "recording"))]
+        let _ = update;
+    }
+    /// Turn the recorded value into another one.
+    pub(crate) fn map<U>(self, map: impl FnOnce(T) -> U) -> Recording<U> {
+        #[cfg(feature = "recording")]
+        return Recording(map(self.0));
+        #[cfg(not(feature = "recording"))]
+        {
+            let _ = map;
+            Recording(std::marker::PhantomData)
+        }
+    }
+    /// The recorded value.
+    ///
+    /// Only recording builders read it, and they can't be created without the feature.
+    pub(crate) fn get(&self) -> &T {
+        #[cfg(feature = "recording")]
+        return &self.0;
+        #[cfg(not(feature = "recording"))]
+        unreachable!("nothing is recorded without the recording feature")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Size;
+
+    #[test]
+    fn paints_of_ops() {
+        let red = RecordedPaint {
+            color: Color::new_srgb(1.0, 0.0, 0.0),
+            ..Default::default()
+        };
+        let rect = Rect::new(Point::zero(), Size::new(10.0, 10.0));
+        let mut recording = RecordedDisplayList::new(Some(rect));
+        recording
+            .push(DisplayOp::Save)
+            .push(DisplayOp::DrawRect {
+                rect,
+                paint: red.clone(),
+            })
+            .push(DisplayOp::DrawTextureRect {
+                texture: Arc::new(RecordedTexture::new_rgba8(1, 1, vec![0; 4]).unwrap()),
+                src_rect: rect,
+                dst_rect: rect,
+                sampling: TextureSampling::Linear,
+                paint: None,
+            })
+            .push(DisplayOp::Restore);
+        let paints: Vec<_> = recording.ops().iter().map(DisplayOp::paint).collect();
+        assert_eq!(paints, [None, Some(&red), None, None]);
+        let taken = recording.take();
+        assert_eq!(taken.ops().len(), 4);
+        assert!(recording.ops().is_empty());
+        assert_eq!(recording.cull_rect(), Some(rect));
+    }
+}
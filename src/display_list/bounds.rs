@@ -0,0 +1,456 @@
+//! Conservative bounds of what a recorded display list draws.
+//!
+//! Everything is tracked in device space as boxes, so rotated shapes and clips grow
+//! to their axis aligned bounds. Difference clips never shrink the bounds.
+use std::f32::consts::SQRT_2;
+
+use super::{shadow_sigma, walk, DisplayOp, Pass, RecordedDisplayList};
+use crate::paint::blend::blend;
+use crate::{
+    BlendMode, ClipOperation, DrawStyle, Matrix, Point, RecordedColorFilter, RecordedImageFilter,
+    RecordedMaskFilter, RecordedPaint, Rect, Size, StrokeCap, StrokeJoin,
+};
+
+pub(super) type Box2D = euclid::Box2D<f32, euclid::UnknownUnit>;
+
+/// The bounds of something that may cover everything, like [DisplayOp::DrawPaint].
+pub(super) const UNBOUNDED: Box2D = Box2D::new(
+    Point::new(f32::NEG_INFINITY, f32::NEG_INFINITY),
+    Point::new(f32::INFINITY, f32::INFINITY),
+);
+
+pub(super) fn transform_box(transform: &Matrix, b: &Box2D) -> Box2D {
+    if b.is_empty() {
+        return *b;
+    }
+    if !(b.min.x.is_finite() && b.min.y.is_finite() && b.max.x.is_finite() && b.max.y.is_finite()) {
+        return UNBOUNDED;
+    }
+    // points behind the camera of a perspective transform can go anywhere
+    transform.outer_transformed_box2d(b).unwrap_or(UNBOUNDED)
+}
+fn union(a: Option<Box2D>, b: Box2D) -> Option<Box2D> {
+    if b.is_empty() {
+        return a;
+    }
+    Some(a.map_or(b, |a| a.union(&b)))
+}
+/// The bounds after an image filter, in the coordinates of the filter.
+fn filter_bounds(filter: &RecordedImageFilter, b: Box2D) -> Box2D {
+    match filter {
+        // a gaussian is practically zero after 3 sigma
+        RecordedImageFilter::Blur {
+            x_sigma, y_sigma, ..
+        } => b.inflate(3.0 * x_sigma.abs(), 3.0 * y_sigma.abs()),
+        RecordedImageFilter::Dilate { x_radius, y_radius } => {
+            b.inflate(x_radius.abs(), y_radius.abs())
+        }
+        RecordedImageFilter::Erode { .. } => b,
+        RecordedImageFilter::Matrix { matrix, .. } => transform_box(matrix, &b),
+        RecordedImageFilter::Compose { outer, inner } => {
+            filter_bounds(outer, filter_bounds(inner, b))
+        }
+        RecordedImageFilter::FragmentProgram { .. } => UNBOUNDED,
+    }
+}
+/// Whether the paint changes pixels that a layer left transparent, when compositing the layer.
+pub(super) fn affects_transparent(paint: &RecordedPaint) -> bool {
+    let transparent = [0.0; 4];
+    let color_filter = match paint.color_filter.as_deref() {
+        Some(RecordedColorFilter::Matrix(matrix)) => matrix.m[19] > 0.0,
+        Some(RecordedColorFilter::Blend { color, blend_mode }) => {
+            let a = color.alpha;
+            let color = [color.red * a, color.green * a, color.blue * a, a];
+            blend(*blend_mode, color, transparent)[3] > 0.0
+        }
+        None => false,
+    };
+    color_filter
+        || matches!(
+            paint.blend_mode,
+            BlendMode::Clear
+                | BlendMode::Source
+                | BlendMode::SourceIn
+                | BlendMode::DestinationIn
+                | BlendMode::SourceOut
+                | BlendMode::DestinationATop
+                | BlendMode::Modulate
+        )
+}
+/// How far a stroke reaches outside of its path.
+fn stroke_outset(paint: &RecordedPaint, has_corners: bool) -> f32 {
+    let mut scale: f32 = 1.0;
+    if has_corners && paint.stroke_join == StrokeJoin::Miter {
+        scale = scale.max(paint.stroke_miter);
+    }
+    if paint.stroke_cap == StrokeCap::Square {
+        scale = scale.max(SQRT_2);
+    }
+    paint.stroke_width / 2.0 * scale
+}
+/// Grow the local bounds of a shape by the draw style and filters of the paint.
+///
+/// Returns the local bounds, and the outset in pixels for hairlines.
+fn paint_bounds(shape: Box2D, paint: &RecordedPaint, stroked: bool, corners: bool) -> (Box2D, f32) {
+    let mut b = shape;
+    let mut hairline = 0.0;
+    if stroked {
+        let outset = stroke_outset(paint, corners);
+        b = b.inflate(outset, outset);
+        if paint.stroke_width <= 0.0 {
+            hairline = 1.0;
+        }
+    }
+    if let Some(filter) = &paint.mask_filter {
+        let RecordedMaskFilter::Blur { sigma, .. } = filter.as_ref();
+        b = b.inflate(3.0 * sigma.abs(), 3.0 * sigma.abs());
+    }
+    if let Some(filter) = &paint.image_filter {
+        b = filter_bounds(filter, b);
+    }
+    (b, hairline)
+}
+/// The local bounds of a draw op, and the outset in pixels for hairlines.
+///
+/// `None` for ops that don't draw, or that draw everything inside the clip.
+pub(super) fn draw_bounds(op: &DisplayOp) -> Option<(Box2D, f32)> {
+    let shape = |rect: &Rect, paint: &RecordedPaint, corners: bool| {
+        let stroked = paint.draw_style != DrawStyle::Fill;
+        paint_bounds(rect.to_box2d(), paint, stroked, corners)
+    };
+    let line = |from: Point, to: Point, paint: &RecordedPaint| {
+        paint_bounds(Box2D::from_points([from, to]), paint, true, false)
+    };
+    Some(match op {
+        DisplayOp::DrawLine { from, to, paint } => line(*from, *to, paint),
+        DisplayOp::DrawDashedLine {
+            from, to, paint, ..
+        } => line(*from, *to, paint),
+        DisplayOp::DrawRect { rect, paint } => shape(rect, paint, true),
+        DisplayOp::DrawOval { oval_bounds, paint } => shape(oval_bounds, paint, false),
+        DisplayOp::DrawRoundedRect { rect, paint, .. } => shape(rect, paint, true),
+        DisplayOp::DrawRoundedRectDifference {
+            outer_rect, paint, ..
+        } => shape(outer_rect, paint, true),
+        DisplayOp::DrawPath { path, paint } => shape(&path.tight_bounds(), paint, true),
+        DisplayOp::DrawParagraph { paragraph, point } => {
+            let width = paragraph.width.max(paragraph.longest_line_width);
+            let size = Size::new(width, paragraph.height);
+            (Rect::new(*point, size).to_box2d(), 0.0)
+        }
+        DisplayOp::DrawShadow {
+            path,
+            elevation,
+            device_pixel_ratio,
+            ..
+        } => {
+            // the blur, and the offset of the shadow from the light above
+            let sigma = shadow_sigma(*elevation, *device_pixel_ratio);
+            let outset = 3.0 * sigma + elevation.abs() * device_pixel_ratio * 800.0 / 600.0;
+            (path.tight_bounds().to_box2d().inflate(outset, outset), 0.0)
+        }
+        DisplayOp::DrawTexture {
+            texture,
+            point,
+            paint,
+            ..
+        } => {
+            let size = Size::new(texture.width as f32, texture.height as f32);
+            paint_bounds(Rect::new(*point, size).to_box2d(), paint, false, false)
+        }
+        DisplayOp::DrawTextureRect {
+            dst_rect, paint, ..
+        } => {
+            let paint = paint.clone().unwrap_or_default();
+            paint_bounds(dst_rect.to_box2d(), &paint, false, false)
+        }
+        _ => return None,
+    })
+}
+
+/// The device clip of a save, and the layer it started.
+struct Save {
+    transform: Matrix,
+    /// The device bounds of the clip.
+    clip: Box2D,
+    layer: Option<Layer>,
+}
+struct Layer {
+    /// What was drawn before the layer.
+    outer: Option<Box2D>,
+    paint: Option<RecordedPaint>,
+    /// The device bounds of the layer, inside the clip.
+    bounds: Box2D,
+    transform: Matrix,
+}
+/// Collects the bounds of a list.
+struct Bounds {
+    drawn: Option<Box2D>,
+}
+impl Pass for Bounds {
+    type Level = Save;
+    fn transform(save: &mut Save) -> &mut Matrix {
+        &mut save.transform
+    }
+    fn save(&mut self, top: &Save, op: &DisplayOp) -> Save {
+        let (transform, clip) = (top.transform, top.clip);
+        let DisplayOp::SaveLayer {
+            bounds,
+            paint,
+            backdrop,
+        } = op
+        else {
+            return Save {
+                transform,
+                clip,
+                layer: None,
+            };
+        };
+        let bounds = transform_box(&transform, &bounds.to_box2d()).intersection_unchecked(&clip);
+        // the layer starts out with the filtered backdrop
+        if backdrop.is_some() {
+            self.drawn = union(self.drawn, bounds);
+        }
+        Save {
+            transform,
+            clip: bounds,
+            layer: Some(Layer {
+                outer: self.drawn.take(),
+                paint: paint.clone(),
+                bounds,
+                transform,
+            }),
+        }
+    }
+    /// Composite a layer that is being restored into what was drawn before it.
+    fn restore(&mut self, save: Save, parent: &mut Save) {
+        let Some(layer) = save.layer else {
+            return;
+        };
+        let content = std::mem::replace(&mut self.drawn, layer.outer);
+        let content = composite_bounds(
+            layer.paint.as_ref(),
+            layer.bounds,
+            &layer.transform,
+            content,
+        );
+        if let Some(content) = content {
+            self.drawn = union(self.drawn, content.intersection_unchecked(&parent.clip));
+        }
+    }
+    fn op(&mut self, top: &mut Save, _index: usize, op: &DisplayOp) {
+        if let Some(clip) = clip_after(op, &top.transform, top.clip) {
+            top.clip = clip;
+        } else if let Some(drawn) = op_bounds(op, &top.transform, top.clip) {
+            self.drawn = union(self.drawn, drawn);
+        }
+    }
+}
+/// The device bounds of the clip after a clip op, or `None` for ops that don't clip.
+///
+/// Difference clips keep the clip as it is.
+pub(super) fn clip_after(op: &DisplayOp, transform: &Matrix, clip: Box2D) -> Option<Box2D> {
+    let (rect, op) = match op {
+        DisplayOp::ClipRect { rect, op } | DisplayOp::ClipRoundedRect { rect, op, .. } => {
+            (*rect, *op)
+        }
+        DisplayOp::ClipOval { oval_bounds, op } => (*oval_bounds, *op),
+        DisplayOp::ClipPath { path, op } => (path.tight_bounds(), *op),
+        _ => return None,
+    };
+    Some(match op {
+        ClipOperation::Intersect => {
+            clip.intersection_unchecked(&transform_box(transform, &rect.to_box2d()))
+        }
+        ClipOperation::Difference => clip,
+    })
+}
+/// The device bounds of a single op that is drawn with the transform, inside the clip.
+///
+/// A [DisplayOp::SaveLayer] op has the bounds of an empty layer, which only draws if it
+/// has a backdrop or its paint changes transparent pixels.
+pub(super) fn op_bounds(op: &DisplayOp, transform: &Matrix, clip: Box2D) -> Option<Box2D> {
+    match op {
+        DisplayOp::SaveLayer {
+            bounds,
+            paint,
+            backdrop,
+        } => {
+            let bounds = transform_box(transform, &bounds.to_box2d()).intersection_unchecked(&clip);
+            let content = backdrop.as_ref().map(|_| bounds);
+            composite_bounds(paint.as_ref(), bounds, transform, content)
+                .map(|content| content.intersection_unchecked(&clip))
+                .filter(|content| !content.is_empty())
+        }
+        DisplayOp::DrawPaint { .. }
+        | DisplayOp::DrawDisplayList {
+            display_list: None, ..
+        } => Some(clip).filter(|clip| !clip.is_empty()),
+        DisplayOp::DrawDisplayList {
+            display_list: Some(display_list),
+            ..
+        } => list_bounds(display_list, *transform, clip),
+        op => {
+            let (local, hairline) = draw_bounds(op)?;
+            let device = transform_box(transform, &local).inflate(hairline, hairline);
+            Some(device.intersection_unchecked(&clip)).filter(|device| !device.is_empty())
+        }
+    }
+}
+/// The device bounds of what a layer draws when it's composited with its paint.
+///
+/// - bounds     The device bounds of the layer, inside the clip.
+/// - transform  The transform that the layer was saved with.
+/// - content    The device bounds of what was drawn into the layer.
+pub(super) fn composite_bounds(
+    paint: Option<&RecordedPaint>,
+    bounds: Box2D,
+    transform: &Matrix,
+    mut content: Option<Box2D>,
+) -> Option<Box2D> {
+    let Some(paint) = paint else {
+        return content;
+    };
+    if affects_transparent(paint) {
+        content = union(content, bounds);
+    }
+    if let (Some(filter), Some(drawn)) = (&paint.image_filter, content) {
+        // filters apply in the coordinates that the layer was saved with
+        content = Some(match transform.inverse() {
+            Some(inverse) => {
+                let local = filter_bounds(filter, transform_box(&inverse, &drawn));
+                transform_box(transform, &local)
+            }
+            None => UNBOUNDED,
+        });
+    }
+    content
+}
+fn list_bounds(list: &RecordedDisplayList, base: Matrix, clip: Box2D) -> Option<Box2D> {
+    let mut bounds = Bounds { drawn: None };
+    let base = Save {
+        transform: base,
+        clip,
+        layer: None,
+    };
+    walk(&mut bounds, list, base);
+    bounds.drawn
+}
+
+impl RecordedDisplayList {
+    /// Conservative bounds of everything that the recording draws.
+    ///
+    /// This accounts for transforms, clips, the cull rect, stroke widths and
+    /// miters, mask blurs, image filters, backdrop filters, shadows and nested
+    /// display lists. The bounds of rotated or clipped content are the axis
+    /// aligned bounds of the shapes, so they may be larger than what is really drawn.
+    ///
+    /// Ops like [DisplayOp::DrawPaint] fill the clip. Without a clip or cull rect,
+    /// their bounds are infinitely large.
+    ///
+    /// @return `None` if nothing is drawn.
+    pub fn bounds(&self) -> Option<Rect> {
+        let cull = self.cull_rect().map_or(UNBOUNDED, |rect| rect.to_box2d());
+        let drawn = list_bounds(self, Matrix::identity(), cull)?;
+        let limit = Point::splat(f32::MAX);
+        Some(Box2D::new(drawn.min.max(-limit), drawn.max.min(limit)).to_rect())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::fixtures::rect;
+    use crate::{Canvas, RecordedPath};
+
+    #[test]
+    fn bounds_of_ops() {
+        let fill = RecordedPaint::default();
+        let mut recording = RecordedDisplayList::new(None);
+        assert_eq!(recording.bounds(), None);
+        recording
+            .save()
+            .translate(10.0, 0.0)
+            .scale(2.0, 2.0)
+            .draw_rect(&rect(0.0, 0.0, 5.0, 5.0), &fill)
+            .restore();
+        assert_eq!(recording.bounds(), Some(rect(10.0, 0.0, 10.0, 10.0)));
+
+        // a round stroke reaches out by half its width, a miter by up to the miter limit
+        let mut stroke = RecordedPaint {
+            draw_style: DrawStyle::Stroke,
+            stroke_width: 2.0,
+            stroke_join: StrokeJoin::Round,
+            ..Default::default()
+        };
+        let mut recording = RecordedDisplayList::new(None);
+        recording.draw_oval(&rect(0.0, 0.0, 10.0, 10.0), &stroke);
+        assert_eq!(recording.bounds(), Some(rect(-1.0, -1.0, 12.0, 12.0)));
+        stroke.stroke_join = StrokeJoin::Miter;
+        let mut path = RecordedPath::default();
+        path.move_to(Point::zero()).line_to(Point::new(10.0, 10.0));
+        recording.draw_path(&Arc::new(path), &stroke);
+        assert_eq!(recording.bounds(), Some(rect(-4.0, -4.0, 18.0, 18.0)));
+
+        // blurs grow by three sigma, clips cut everything down
+        let blurred = RecordedPaint {
+            mask_filter: Some(Arc::new(RecordedMaskFilter::Blur {
+                style: crate::BlurStyle::Normal,
+                sigma: 2.0,
+            })),
+            ..Default::default()
+        };
+        let mut recording = RecordedDisplayList::new(None);
+        recording.draw_rect(&rect(0.0, 0.0, 10.0, 10.0), &blurred);
+        assert_eq!(recording.bounds(), Some(rect(-6.0, -6.0, 22.0, 22.0)));
+        recording
+            .clip_rect(&rect(-2.0, -2.0, 4.0, 100.0), ClipOperation::Intersect)
+            .draw_paint(&fill);
+        assert_eq!(recording.bounds(), Some(rect(-6.0, -6.0, 22.0, 104.0)));
+    }
+
+    #[test]
+    fn bounds_of_layers() {
+        let fill = RecordedPaint::default();
+        let blur = Arc::new(RecordedImageFilter::Blur {
+            x_sigma: 1.0,
+            y_sigma: 2.0,
+            tile_mode: crate::TileMode::Decal,
+        });
+        let mut recording = RecordedDisplayList::new(Some(rect(0.0, 0.0, 100.0, 100.0)));
+        recording
+            .scale(2.0, 2.0)
+            .save_layer(
+                &rect(0.0, 0.0, 50.0, 50.0),
+                Some(&RecordedPaint {
+                    image_filter: Some(blur.clone()),
+                    ..Default::default()
+                }),
+                None,
+            )
+            .draw_rect(&rect(10.0, 10.0, 10.0, 10.0), &fill)
+            .restore();
+        // the blur is scaled along with the layer
+        assert_eq!(recording.bounds(), Some(rect(14.0, 8.0, 32.0, 44.0)));
+
+        // backdrop filters fill the layer, limited by the cull rect
+        let mut recording = RecordedDisplayList::new(Some(rect(0.0, 0.0, 100.0, 100.0)));
+        recording
+            .save_layer(&rect(50.0, 50.0, 100.0, 100.0), None, Some(&blur))
+            .restore();
+        assert_eq!(recording.bounds(), Some(rect(50.0, 50.0, 50.0, 50.0)));
+
+        // nested lists use the transform they are drawn with
+        let mut nested = RecordedDisplayList::new(None);
+        nested.draw_rect(&rect(0.0, 0.0, 1.0, 1.0), &fill);
+        let mut recording = RecordedDisplayList::new(None);
+        recording
+            .translate(5.0, 5.0)
+            .draw_display_list(&Arc::new(nested), 1.0);
+        assert_eq!(recording.bounds(), Some(rect(5.0, 5.0, 1.0, 1.0)));
+        recording.draw_paint(&fill);
+        assert!(recording.bounds().unwrap().width().is_infinite());
+    }
+}
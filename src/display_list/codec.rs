@@ -0,0 +1,1022 @@
+//! A versioned binary encoding of [RecordedDisplayList]s.
+//!
+//! Everything is little endian. The data starts with [MAGIC] and the version, followed by
+//! the display list. Objects that are shared with an [Arc] (paths, paints' filters,
+//! textures, paragraphs and nested display lists) are only written once. Every
+//! reference to them starts with an index, and the first time an index shows up,
+//! the object itself follows.
+use std::any::Any;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use super::{DisplayOp, RecordedDisplayList, MAX_DEPTH};
+use crate::{
+    BlendMode, BlurStyle, ClipOperation, Color, ColorMatrix, ColorSpace, DrawStyle, FillType,
+    FontStyle, FontWeight, Matrix, ParagraphItem, ParseError, PathSegment, Point,
+    RecordedColorFilter, RecordedColorSource, RecordedImageFilter, RecordedMaskFilter,
+    RecordedPaint, RecordedParagraph, RecordedParagraphStyle, RecordedPath, RecordedTextDecoration,
+    RecordedTexture, Rect, RoundingRadii, Size, StrokeCap, StrokeJoin, TextAlignment,
+    TextDecorationStyle, TextDecorationType, TextDirection, TextureSampling, TileMode,
+};
+
+/// The bytes at the start of every encoded display list.
+const MAGIC: &[u8; 8] = b"IMPDLIST";
+
+struct Encoder {
+    bytes: Vec<u8>,
+    /// The indices of the shared objects written so far, by address.
+    shared: HashMap<*const (), u32>,
+    /// The first thing that could not be encoded.
+    error: Option<&'static str>,
+}
+impl Encoder {
+    fn u8(&mut self, value: u8) {
+        self.bytes.push(value);
+    }
+    fn u32(&mut self, value: u32) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+    fn u64(&mut self, value: u64) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+    fn f32(&mut self, value: f32) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+    fn len(&mut self, len: usize) {
+        match u32::try_from(len) {
+            Ok(len) => self.u32(len),
+            Err(_) => self.fail("too many items to encode"),
+        }
+    }
+    fn bytes(&mut self, bytes: &[u8]) {
+        self.len(bytes.len());
+        self.bytes.extend_from_slice(bytes);
+    }
+    fn fail(&mut self, message: &'static str) {
+        self.error.get_or_insert(message);
+    }
+}
+struct Decoder<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+    /// The shared objects read so far, as `Arc<T>`s. `None` while the object is still being read.
+    shared: Vec<Option<Box<dyn Any>>>,
+    /// How many shared objects are being read, one inside the other.
+    depth: usize,
+}
+impl Decoder<'_> {
+    fn error(&self, message: &'static str) -> ParseError {
+        ParseError {
+            position: self.pos,
+            message,
+        }
+    }
+    fn take<const N: usize>(&mut self) -> Result<[u8; N], ParseError> {
+        let bytes = self
+            .bytes
+            .get(self.pos..self.pos + N)
+            .ok_or(self.error("unexpected end of data"))?;
+        self.pos += N;
+        Ok(bytes.try_into().unwrap())
+    }
+    fn u8(&mut self) -> Result<u8, ParseError> {
+        Ok(self.take::<1>()?[0])
+    }
+    fn u32(&mut self) -> Result<u32, ParseError> {
+        self.take().map(u32::from_le_bytes)
+    }
+    fn u64(&mut self) -> Result<u64, ParseError> {
+        self.take().map(u64::from_le_bytes)
+    }
+    fn f32(&mut self) -> Result<f32, ParseError> {
+        self.take().map(f32::from_le_bytes)
+    }
+    fn bytes(&mut self) -> Result<&[u8], ParseError> {
+        let len = self.len(1)?;
+        self.pos += len;
+        Ok(&self.bytes[self.pos - len..self.pos])
+    }
+    /// A length of items that take at least `min_size` bytes each.
+    fn len(&mut self, min_size: usize) -> Result<usize, ParseError> {
+        let len = self.u32()? as usize;
+        if len.saturating_mul(min_size) > self.bytes.len() - self.pos {
+            return Err(self.error("length is larger than the remaining data"));
+        }
+        Ok(len)
+    }
+}
+
+trait Encode {
+    fn encode(&self, e: &mut Encoder);
+}
+trait Decode: Sized {
+    fn decode(d: &mut Decoder) -> Result<Self, ParseError>;
+}
+impl Encode for u32 {
+    fn encode(&self, e: &mut Encoder) {
+        e.u32(*self);
+    }
+}
+impl Decode for u32 {
+    fn decode(d: &mut Decoder) -> Result<Self, ParseError> {
+        d.u32()
+    }
+}
+impl Encode for u64 {
+    fn encode(&self, e: &mut Encoder) {
+        e.u64(*self);
+    }
+}
+impl Decode for u64 {
+    fn decode(d: &mut Decoder) -> Result<Self, ParseError> {
+        d.u64()
+    }
+}
+impl Encode for f32 {
+    fn encode(&self, e: &mut Encoder) {
+        e.f32(*self);
+    }
+}
+impl Decode for f32 {
+    fn decode(d: &mut Decoder) -> Result<Self, ParseError> {
+        d.f32()
+    }
+}
+impl Encode for bool {
+    fn encode(&self, e: &mut Encoder) {
+        e.u8(*self as u8);
+    }
+}
+impl Decode for bool {
+    fn decode(d: &mut Decoder) -> Result<Self, ParseError> {
+        match d.u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(d.error("invalid bool")),
+        }
+    }
+}
+impl<T: Encode> Encode for Option<T> {
+    fn encode(&self, e: &mut Encoder) {
+        match self {
+            None => e.u8(0),
+            Some(value) => {
+                e.u8(1);
+                value.encode(e);
+            }
+        }
+    }
+}
+impl<T: Decode> Decode for Option<T> {
+    fn decode(d: &mut Decoder) -> Result<Self, ParseError> {
+        match bool::decode(d)? {
+            false => Ok(None),
+            true => T::decode(d).map(Some),
+        }
+    }
+}
+impl<T: Encode> Encode for [T] {
+    fn encode(&self, e: &mut Encoder) {
+        e.len(self.len());
+        for item in self {
+            item.encode(e);
+        }
+    }
+}
+impl<T: Encode> Encode for Vec<T> {
+    fn encode(&self, e: &mut Encoder) {
+        self.as_slice().encode(e);
+    }
+}
+impl<T: Decode> Decode for Vec<T> {
+    fn decode(d: &mut Decoder) -> Result<Self, ParseError> {
+        let len = d.len(1)?;
+        // items can be much larger in memory than on the wire
+        let remaining = d.bytes.len() - d.pos;
+        let mut items = Vec::with_capacity(len.min(remaining / std::mem::size_of::<T>().max(1)));
+        for _ in 0..len {
+            items.push(T::decode(d)?);
+        }
+        Ok(items)
+    }
+}
+impl Encode for String {
+    fn encode(&self, e: &mut Encoder) {
+        e.bytes(self.as_bytes());
+    }
+}
+impl Decode for String {
+    fn decode(d: &mut Decoder) -> Result<Self, ParseError> {
+        let start = d.pos;
+        let text = std::str::from_utf8(d.bytes()?).map_err(|_| ParseError {
+            position: start,
+            message: "invalid utf-8",
+        })?;
+        Ok(text.to_owned())
+    }
+}
+impl<T: Encode + Any> Encode for Arc<T> {
+    fn encode(&self, e: &mut Encoder) {
+        let next = e.shared.len() as u32;
+        let index = *e.shared.entry(Arc::as_ptr(self).cast()).or_insert(next);
+        e.u32(index);
+        if index == next {
+            self.as_ref().encode(e);
+        }
+    }
+}
+impl<T: Decode + Any> Decode for Arc<T> {
+    fn decode(d: &mut Decoder) -> Result<Self, ParseError> {
+        let index = d.u32()? as usize;
+        if index == d.shared.len() {
+            if d.depth == MAX_DEPTH {
+                return Err(d.error("objects are nested too deeply"));
+            }
+            d.shared.push(None);
+            d.depth += 1;
+            let value = T::decode(d).map(Arc::new);
+            d.depth -= 1;
+            let value = value?;
+            d.shared[index] = Some(Box::new(value.clone()));
+            return Ok(value);
+        }
+        d.shared
+            .get(index)
+            .and_then(|shared| shared.as_ref()?.downcast_ref::<Arc<T>>())
+            .cloned()
+            .ok_or(d.error("invalid reference to a shared object"))
+    }
+}
+
+/// Encode fieldless enums by their discriminant.
+macro_rules! enums {
+    ($($ty:ident { $($variant:ident),* $(,)? })*) => {$(
+        impl Encode for $ty {
+            fn encode(&self, e: &mut Encoder) {
+                e.u8(*self as u8);
+            }
+        }
+        impl Decode for $ty {
+            fn decode(d: &mut Decoder) -> Result<Self, ParseError> {
+                let value = d.u8()?;
+                [$($ty::$variant),*]
+                    .into_iter()
+                    .find(|v| *v as u8 == value)
+                    .ok_or(d.error(concat!("invalid ", stringify!($ty))))
+            }
+        }
+    )*};
+}
+enums! {
+    FillType { NonZero, Odd }
+    ClipOperation { Difference, Intersect }
+    BlendMode {
+        Clear, Source, Destination, SourceOver, DestinationOver, SourceIn, DestinationIn,
+        SourceOut, DestinationOut, SourceATop, DestinationATop, Xor, Plus, Modulate, Screen,
+        Overlay, Darken, Lighten, ColorDodge, ColorBurn, HardLight, SoftLight, Difference,
+        Exclusion, Multiply, Hue, Saturation, Color, Luminosity,
+    }
+    DrawStyle { Fill, Stroke, StrokeAndFill }
+    StrokeCap { Butt, Round, Square }
+    StrokeJoin { Miter, Round, Bevel }
+    TextureSampling { NearestNeighbor, Linear }
+    TileMode { Clamp, Repeat, Mirror, Decal }
+    BlurStyle { Normal, Solid, Outer, Inner }
+    ColorSpace { SRGB, ExtendedSRGB, DisplayP3 }
+    FontWeight { Thin, ExtraLight, Light, Regular, Medium, SemiBold, Bold, ExtraBold, Black }
+    FontStyle { Normal, Italic }
+    TextAlignment { Left, Right, Center, Justify, Start, End }
+    TextDirection { RTL, LTR }
+    TextDecorationStyle { Solid, Double, Dotted, Dashed, Wavy }
+}
+
+/// Encode structs field by field, in order.
+macro_rules! structs {
+    ($($ty:ident { $($field:ident),* $(,)? })*) => {$(
+        impl Encode for $ty {
+            fn encode(&self, e: &mut Encoder) {
+                $(self.$field.encode(e);)*
+            }
+        }
+        impl Decode for $ty {
+            fn decode(d: &mut Decoder) -> Result<Self, ParseError> {
+                Ok(Self { $($field: Decode::decode(d)?),* })
+            }
+        }
+    )*};
+}
+structs! {
+    Rect { origin, size }
+    Color { red, green, blue, alpha, color_space }
+    ColorMatrix { m }
+    RoundingRadii { top_left, top_right, bottom_left, bottom_right }
+    RecordedPaint {
+        color, blend_mode, draw_style, stroke_cap, stroke_join, stroke_width, stroke_miter,
+        color_filter, image_filter, color_source, mask_filter,
+    }
+    RecordedParagraphStyle {
+        foreground, background, font_weight, font_style, font_family, font_size, height,
+        text_alignment, text_direction, text_decoration, max_lines, locale, ellipsis,
+    }
+    RecordedTextDecoration { types, color, style, thickness_multiplier }
+    RecordedParagraph { items, width, height, longest_line_width, alphabetic_baseline }
+}
+impl<const N: usize> Encode for [f32; N] {
+    fn encode(&self, e: &mut Encoder) {
+        for value in self {
+            e.f32(*value);
+        }
+    }
+}
+impl<const N: usize> Decode for [f32; N] {
+    fn decode(d: &mut Decoder) -> Result<Self, ParseError> {
+        let mut values = [0.0; N];
+        for value in &mut values {
+            *value = d.f32()?;
+        }
+        Ok(values)
+    }
+}
+impl Encode for Point {
+    fn encode(&self, e: &mut Encoder) {
+        e.f32(self.x);
+        e.f32(self.y);
+    }
+}
+impl Decode for Point {
+    fn decode(d: &mut Decoder) -> Result<Self, ParseError> {
+        Ok(Point::new(d.f32()?, d.f32()?))
+    }
+}
+impl Encode for Size {
+    fn encode(&self, e: &mut Encoder) {
+        e.f32(self.width);
+        e.f32(self.height);
+    }
+}
+impl Decode for Size {
+    fn decode(d: &mut Decoder) -> Result<Self, ParseError> {
+        Ok(Size::new(d.f32()?, d.f32()?))
+    }
+}
+impl Encode for Matrix {
+    fn encode(&self, e: &mut Encoder) {
+        self.to_array().encode(e);
+    }
+}
+impl Decode for Matrix {
+    fn decode(d: &mut Decoder) -> Result<Self, ParseError> {
+        Ok(Matrix::from_array(Decode::decode(d)?))
+    }
+}
+impl Encode for TextDecorationType {
+    fn encode(&self, e: &mut Encoder) {
+        e.u32(self.bits() as u32);
+    }
+}
+impl Decode for TextDecorationType {
+    fn decode(d: &mut Decoder) -> Result<Self, ParseError> {
+        let bits = d.u32()?;
+        TextDecorationType::from_bits(bits as _).ok_or(d.error("invalid TextDecorationType"))
+    }
+}
+
+/// Encode enums with fields as a tag followed by the fields of the variant.
+///
+/// The fields of tuple variants are named too, so they can be bound in patterns.
+macro_rules! tagged {
+    ($ty:ident {
+        $($tag:literal => $variant:ident
+            $({ $($field:ident),* $(,)? })?
+            $(( $($tuple_field:ident),* ))?
+        ),* $(,)?
+    }) => {
+        impl Encode for $ty {
+            fn encode(&self, e: &mut Encoder) {
+                match self {
+                    $($ty::$variant $({ $($field),* })? $(( $($tuple_field),* ))? => {
+                        e.u8($tag);
+                        $($($field.encode(e);)*)?
+                        $($($tuple_field.encode(e);)*)?
+                    })*
+                }
+            }
+        }
+        impl Decode for $ty {
+            fn decode(d: &mut Decoder) -> Result<Self, ParseError> {
+                match d.u8()? {
+                    $($tag => Ok($ty::$variant
+                        $({ $($field: Decode::decode(d)?),* })?
+                        $(( $({
+                            let $tuple_field = Decode::decode(d)?;
+                            $tuple_field
+                        }),* ))?
+                    ),)*
+                    _ => Err(d.error(concat!("invalid ", stringify!($ty)))),
+                }
+            }
+        }
+    };
+}
+tagged!(RecordedColorFilter {
+    0 => Blend { color, blend_mode },
+    1 => Matrix(matrix),
+});
+tagged!(RecordedMaskFilter {
+    0 => Blur { style, sigma },
+});
+tagged!(ParagraphItem {
+    0 => PushStyle(style),
+    1 => PopStyle,
+    2 => Text(text),
+});
+
+impl Encode for RecordedColorSource {
+    fn encode(&self, e: &mut Encoder) {
+        match self {
+            RecordedColorSource::LinearGradient {
+                start,
+                end,
+                colors,
+                stops,
+                tile_mode,
+                transformation,
+            } => {
+                e.u8(0);
+                start.encode(e);
+                end.encode(e);
+                colors.encode(e);
+                stops.encode(e);
+                tile_mode.encode(e);
+                transformation.encode(e);
+            }
+            RecordedColorSource::RadialGradient {
+                center,
+                radius,
+                colors,
+                stops,
+                tile_mode,
+                transformation,
+            } => {
+                e.u8(1);
+                center.encode(e);
+                radius.encode(e);
+                colors.encode(e);
+                stops.encode(e);
+                tile_mode.encode(e);
+                transformation.encode(e);
+            }
+            RecordedColorSource::ConicalGradient {
+                start_center,
+                start_radius,
+                end_center,
+                end_radius,
+                colors,
+                stops,
+                tile_mode,
+                transformation,
+            } => {
+                e.u8(2);
+                start_center.encode(e);
+                start_radius.encode(e);
+                end_center.encode(e);
+                end_radius.encode(e);
+                colors.encode(e);
+                stops.encode(e);
+                tile_mode.encode(e);
+                transformation.encode(e);
+            }
+            RecordedColorSource::SweepGradient {
+                center,
+                start,
+                end,
+                colors,
+                stops,
+                tile_mode,
+                transformation,
+            } => {
+                e.u8(3);
+                center.encode(e);
+                start.encode(e);
+                end.encode(e);
+                colors.encode(e);
+                stops.encode(e);
+                tile_mode.encode(e);
+                transformation.encode(e);
+            }
+            RecordedColorSource::Image {
+                image,
+                horizontal_tile_mode,
+                vertical_tile_mode,
+                sampling,
+                transformation,
+            } => {
+                e.u8(4);
+                image.encode(e);
+                horizontal_tile_mode.encode(e);
+                vertical_tile_mode.encode(e);
+                sampling.encode(e);
+                transformation.encode(e);
+            }
+            RecordedColorSource::FragmentProgram { .. } => {
+                e.fail("fragment program color sources can't be encoded");
+            }
+        }
+    }
+}
+impl Decode for RecordedColorSource {
+    fn decode(d: &mut Decoder) -> Result<Self, ParseError> {
+        Ok(match d.u8()? {
+            0 => RecordedColorSource::LinearGradient {
+                start: Decode::decode(d)?,
+                end: Decode::decode(d)?,
+                colors: Decode::decode(d)?,
+                stops: Decode::decode(d)?,
+                tile_mode: Decode::decode(d)?,
+                transformation: Decode::decode(d)?,
+            },
+            1 => RecordedColorSource::RadialGradient {
+                center: Decode::decode(d)?,
+                radius: Decode::decode(d)?,
+                colors: Decode::decode(d)?,
+                stops: Decode::decode(d)?,
+                tile_mode: Decode::decode(d)?,
+                transformation: Decode::decode(d)?,
+            },
+            2 => RecordedColorSource::ConicalGradient {
+                start_center: Decode::decode(d)?,
+                start_radius: Decode::decode(d)?,
+                end_center: Decode::decode(d)?,
+                end_radius: Decode::decode(d)?,
+                colors: Decode::decode(d)?,
+                stops: Decode::decode(d)?,
+                tile_mode: Decode::decode(d)?,
+                transformation: Decode::decode(d)?,
+            },
+            3 => RecordedColorSource::SweepGradient {
+                center: Decode::decode(d)?,
+                start: Decode::decode(d)?,
+                end: Decode::decode(d)?,
+                colors: Decode::decode(d)?,
+                stops: Decode::decode(d)?,
+                tile_mode: Decode::decode(d)?,
+                transformation: Decode::decode(d)?,
+            },
+            4 => RecordedColorSource::Image {
+                image: Decode::decode(d)?,
+                horizontal_tile_mode: Decode::decode(d)?,
+                vertical_tile_mode: Decode::decode(d)?,
+                sampling: Decode::decode(d)?,
+                transformation: Decode::decode(d)?,
+            },
+            _ => return Err(d.error("invalid RecordedColorSource")),
+        })
+    }
+}
+impl Encode for RecordedImageFilter {
+    fn encode(&self, e: &mut Encoder) {
+        match self {
+            RecordedImageFilter::Blur {
+                x_sigma,
+                y_sigma,
+                tile_mode,
+            } => {
+                e.u8(0);
+                x_sigma.encode(e);
+                y_sigma.encode(e);
+                tile_mode.encode(e);
+            }
+            RecordedImageFilter::Dilate { x_radius, y_radius } => {
+                e.u8(1);
+                x_radius.encode(e);
+                y_radius.encode(e);
+            }
+            RecordedImageFilter::Erode { x_radius, y_radius } => {
+                e.u8(2);
+                x_radius.encode(e);
+                y_radius.encode(e);
+            }
+            RecordedImageFilter::Matrix { matrix, sampling } => {
+                e.u8(3);
+                matrix.encode(e);
+                sampling.encode(e);
+            }
+            RecordedImageFilter::Compose { outer, inner } => {
+                e.u8(4);
+                outer.encode(e);
+                inner.encode(e);
+            }
+            RecordedImageFilter::FragmentProgram { .. } => {
+                e.fail("fragment program image filters can't be encoded");
+            }
+        }
+    }
+}
+impl Decode for RecordedImageFilter {
+    fn decode(d: &mut Decoder) -> Result<Self, ParseError> {
+        Ok(match d.u8()? {
+            0 => RecordedImageFilter::Blur {
+                x_sigma: Decode::decode(d)?,
+                y_sigma: Decode::decode(d)?,
+                tile_mode: Decode::decode(d)?,
+            },
+            1 => RecordedImageFilter::Dilate {
+                x_radius: Decode::decode(d)?,
+                y_radius: Decode::decode(d)?,
+            },
+            2 => RecordedImageFilter::Erode {
+                x_radius: Decode::decode(d)?,
+                y_radius: Decode::decode(d)?,
+            },
+            3 => RecordedImageFilter::Matrix {
+                matrix: Decode::decode(d)?,
+                sampling: Decode::decode(d)?,
+            },
+            4 => RecordedImageFilter::Compose {
+                outer: Decode::decode(d)?,
+                inner: Decode::decode(d)?,
+            },
+            _ => return Err(d.error("invalid RecordedImageFilter")),
+        })
+    }
+}
+impl Encode for RecordedTexture {
+    fn encode(&self, e: &mut Encoder) {
+        let Some(pixels) = &self.pixels else {
+            e.fail("texture pixels were not recorded");
+            return;
+        };
+        e.u32(self.width);
+        e.u32(self.height);
+        e.bytes(pixels);
+    }
+}
+impl Decode for RecordedTexture {
+    fn decode(d: &mut Decoder) -> Result<Self, ParseError> {
+        let start = d.pos;
+        let width = d.u32()?;
+        let height = d.u32()?;
+        let pixels = d.bytes()?;
+        RecordedTexture::new_rgba8(width, height, pixels).map_err(|message| ParseError {
+            position: start,
+            message,
+        })
+    }
+}
+impl Encode for RecordedPath {
+    fn encode(&self, e: &mut Encoder) {
+        self.fill_type().encode(e);
+        e.len(self.verbs().len());
+        for segment in self {
+            match segment {
+                PathSegment::MoveTo(p) => {
+                    e.u8(0);
+                    p.encode(e);
+                }
+                PathSegment::LineTo(p) => {
+                    e.u8(1);
+                    p.encode(e);
+                }
+                PathSegment::QuadTo(c, p) => {
+                    e.u8(2);
+                    c.encode(e);
+                    p.encode(e);
+                }
+                PathSegment::CubicTo(c1, c2, p) => {
+                    e.u8(3);
+                    c1.encode(e);
+                    c2.encode(e);
+                    p.encode(e);
+                }
+                PathSegment::Close => e.u8(4),
+            }
+        }
+    }
+}
+impl Decode for RecordedPath {
+    fn decode(d: &mut Decoder) -> Result<Self, ParseError> {
+        let mut path = RecordedPath::new(Decode::decode(d)?);
+        for _ in 0..d.len(1)? {
+            path.push(match d.u8()? {
+                0 => PathSegment::MoveTo(Decode::decode(d)?),
+                1 => PathSegment::LineTo(Decode::decode(d)?),
+                2 => PathSegment::QuadTo(Decode::decode(d)?, Decode::decode(d)?),
+                3 => {
+                    PathSegment::CubicTo(Decode::decode(d)?, Decode::decode(d)?, Decode::decode(d)?)
+                }
+                4 => PathSegment::Close,
+                _ => return Err(d.error("invalid PathSegment")),
+            });
+        }
+        Ok(path)
+    }
+}
+tagged!(DisplayOp {
+    0 => Save,
+    1 => SaveLayer { bounds, paint, backdrop },
+    2 => Restore,
+    3 => RestoreToCount(count),
+    4 => Scale { x_scale, y_scale },
+    5 => Rotate { angle_degrees },
+    6 => Translate { x_translation, y_translation },
+    7 => Transform(transform),
+    8 => SetTransform(transform),
+    9 => ResetTransform,
+    10 => ClipRect { rect, op },
+    11 => ClipOval { oval_bounds, op },
+    12 => ClipRoundedRect { rect, radii, op },
+    13 => ClipPath { path, op },
+    14 => DrawPaint { paint },
+    15 => DrawLine { from, to, paint },
+    16 => DrawDashedLine { from, to, on_length, off_length, paint },
+    17 => DrawRect { rect, paint },
+    18 => DrawOval { oval_bounds, paint },
+    19 => DrawRoundedRect { rect, radii, paint },
+    20 => DrawRoundedRectDifference { outer_rect, outer_radii, inner_rect, inner_radii, paint },
+    21 => DrawPath { path, paint },
+    22 => DrawDisplayList { display_list, opacity },
+    23 => DrawParagraph { paragraph, point },
+    24 => DrawShadow { path, color, elevation, occluder_is_transparent, device_pixel_ratio },
+    25 => DrawTexture { texture, point, sampling, paint },
+    26 => DrawTextureRect { texture, src_rect, dst_rect, sampling, paint },
+    27 => SetTag(tag),
+});
+impl Encode for RecordedDisplayList {
+    fn encode(&self, e: &mut Encoder) {
+        self.cull_rect.encode(e);
+        self.ops.encode(e);
+    }
+}
+impl Decode for RecordedDisplayList {
+    fn decode(d: &mut Decoder) -> Result<Self, ParseError> {
+        Ok(Self {
+            cull_rect: Decode::decode(d)?,
+            ops: Decode::decode(d)?,
+        })
+    }
+}
+
+impl RecordedDisplayList {
+    /// The version of the encoding written by [Self::to_bytes].
+    ///
+    /// [Self::from_bytes] reads this and all older versions.
+    ///
+    /// Version 2 added [DisplayOp::SetTag].
+    pub const ENCODING_VERSION: u32 = 2;
+    /// Encode the recording, including every path, paint, texture and paragraph in it.
+    ///
+    /// Objects that are shared between ops (like a path that is drawn twice) are only
+    /// written once, and are shared again after decoding.
+    ///
+    /// Fails if the recording contains fragment programs, which are opaque, or textures
+    /// that were not created with [Context::create_texture_from_recording](crate::Context::create_texture_from_recording)
+    /// and so don't have their pixels recorded.
+    ///
+    /// @see [Self::from_bytes]
+    pub fn to_bytes(&self) -> Result<Vec<u8>, &'static str> {
+        let mut encoder = Encoder {
+            bytes: MAGIC.to_vec(),
+            shared: HashMap::new(),
+            error: None,
+        };
+        encoder.u32(Self::ENCODING_VERSION);
+        self.encode(&mut encoder);
+        match encoder.error {
+            Some(error) => Err(error),
+            None => Ok(encoder.bytes),
+        }
+    }
+    /// Decode a recording written by [Self::to_bytes].
+    ///
+    /// Use [Self::to_display_list] to turn it back into a [DisplayList](crate::DisplayList).
+    ///
+    /// Fails if shared objects, like composed image filters or nested display lists, are
+    /// nested more than 64 levels deep.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ParseError> {
+        let mut decoder = Decoder {
+            bytes,
+            pos: 0,
+            shared: vec![],
+            depth: 0,
+        };
+        if decoder.take::<8>().ok().as_ref() != Some(MAGIC) {
+            return Err(ParseError {
+                position: 0,
+                message: "not an encoded display list",
+            });
+        }
+        let version = decoder.u32()?;
+        if version == 0 || version > Self::ENCODING_VERSION {
+            return Err(ParseError {
+                position: MAGIC.len(),
+                message: "unsupported encoding version",
+            });
+        }
+        let list = Self::decode(&mut decoder)?;
+        if decoder.pos != bytes.len() {
+            return Err(decoder.error("trailing data after the display list"));
+        }
+        Ok(list)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::fixtures::rect;
+
+    fn sample() -> RecordedDisplayList {
+        let mut path = RecordedPath::default();
+        path.move_to(Point::new(0.0, 0.0))
+            .quadratic_curve_to(Point::new(50.0, 100.0), Point::new(100.0, 0.0))
+            .close();
+        let path = Arc::new(path);
+        let texture = Arc::new(RecordedTexture::new_rgba8(1, 2, vec![255; 8]).unwrap());
+        let gradient = RecordedPaint {
+            color_source: Some(Arc::new(RecordedColorSource::LinearGradient {
+                start: Point::zero(),
+                end: Point::new(100.0, 0.0),
+                colors: vec![Color::new_srgb(1.0, 0.0, 0.0), Color::TRANSPARENT],
+                stops: vec![0.0, 1.0],
+                tile_mode: TileMode::Mirror,
+                transformation: Some(Matrix::translation(1.0, 2.0, 0.0)),
+            })),
+            mask_filter: Some(Arc::new(RecordedMaskFilter::Blur {
+                style: BlurStyle::Outer,
+                sigma: 3.0,
+            })),
+            ..Default::default()
+        };
+        let blur = Arc::new(RecordedImageFilter::Blur {
+            x_sigma: 2.0,
+            y_sigma: 4.0,
+            tile_mode: TileMode::Decal,
+        });
+        let paragraph = Arc::new(RecordedParagraph {
+            items: vec![
+                ParagraphItem::PushStyle(Arc::new(RecordedParagraphStyle {
+                    font_family: Some("Roboto".into()),
+                    font_weight: Some(FontWeight::Bold),
+                    text_decoration: Some(RecordedTextDecoration {
+                        types: TextDecorationType::UNDERLINE | TextDecorationType::OVERLINE,
+                        color: Color::new_srgb(0.0, 0.0, 1.0),
+                        style: TextDecorationStyle::Wavy,
+                        thickness_multiplier: 2.0,
+                    }),
+                    ..Default::default()
+                })),
+                ParagraphItem::Text("héllo".into()),
+                ParagraphItem::PopStyle,
+            ],
+            width: 200.0,
+            height: 20.0,
+            longest_line_width: 50.0,
+            alphabetic_baseline: 15.0,
+        });
+        let mut nested = RecordedDisplayList::new(None);
+        nested.push(DisplayOp::DrawPath {
+            path: path.clone(),
+            paint: RecordedPaint::default(),
+        });
+        let mut list = RecordedDisplayList::new(Some(rect(0.0, 0.0, 800.0, 600.0)));
+        list.push(DisplayOp::SaveLayer {
+            bounds: rect(0.0, 0.0, 100.0, 100.0),
+            paint: None,
+            backdrop: Some(blur.clone()),
+        })
+        .push(DisplayOp::Rotate {
+            angle_degrees: 45.0,
+        })
+        .push(DisplayOp::ClipPath {
+            path: path.clone(),
+            op: ClipOperation::Difference,
+        })
+        .push(DisplayOp::DrawRect {
+            rect: rect(1.0, 2.0, 3.0, 4.0),
+            paint: gradient,
+        })
+        .push(DisplayOp::DrawParagraph {
+            paragraph,
+            point: Point::new(5.0, 6.0),
+        })
+        .push(DisplayOp::DrawTextureRect {
+            texture: texture.clone(),
+            src_rect: rect(0.0, 0.0, 1.0, 2.0),
+            dst_rect: rect(10.0, 10.0, 10.0, 20.0),
+            sampling: TextureSampling::NearestNeighbor,
+            paint: None,
+        })
+        .push(DisplayOp::DrawDisplayList {
+            display_list: Some(Arc::new(nested)),
+            opacity: 0.5,
+        })
+        .push(DisplayOp::SetTag(u64::MAX - 1))
+        .push(DisplayOp::RestoreToCount(1));
+        list
+    }
+
+    #[test]
+    fn round_trip() {
+        let list = sample();
+        let bytes = list.to_bytes().unwrap();
+        let decoded = RecordedDisplayList::from_bytes(&bytes).unwrap();
+        assert_eq!(decoded, list);
+        // the clipped path and the path in the nested list are still the same object
+        let DisplayOp::ClipPath { path, .. } = &decoded.ops()[2] else {
+            panic!()
+        };
+        let DisplayOp::DrawDisplayList {
+            display_list: Some(nested),
+            ..
+        } = &decoded.ops()[6]
+        else {
+            panic!()
+        };
+        let DisplayOp::DrawPath {
+            path: nested_path, ..
+        } = &nested.ops()[0]
+        else {
+            panic!()
+        };
+        assert!(Arc::ptr_eq(path, nested_path));
+        // and it was only written once
+        assert_eq!(decoded.to_bytes().unwrap(), bytes);
+    }
+
+    #[test]
+    fn invalid_data() {
+        let bytes = sample().to_bytes().unwrap();
+        let error = |bytes: &[u8]| RecordedDisplayList::from_bytes(bytes).unwrap_err().message;
+        assert_eq!(error(b"not a display list"), "not an encoded display list");
+        assert_eq!(error(&bytes[..bytes.len() - 1]), "unexpected end of data");
+        let mut future = bytes.clone();
+        future[8] = RecordedDisplayList::ENCODING_VERSION as u8 + 1;
+        assert_eq!(error(&future), "unsupported encoding version");
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert_eq!(error(&trailing), "trailing data after the display list");
+        // opaque objects can't be encoded
+        let mut list = RecordedDisplayList::new(None);
+        list.push(DisplayOp::DrawTexture {
+            texture: Arc::new(RecordedTexture {
+                width: 1,
+                height: 1,
+                pixels: None,
+            }),
+            point: Point::zero(),
+            sampling: TextureSampling::Linear,
+            paint: RecordedPaint::default(),
+        });
+        assert_eq!(list.to_bytes(), Err("texture pixels were not recorded"));
+    }
+
+    #[test]
+    fn deep_nesting() {
+        let error = |list: &RecordedDisplayList| {
+            RecordedDisplayList::from_bytes(&list.to_bytes().unwrap())
+                .unwrap_err()
+                .message
+        };
+        let blur = RecordedImageFilter::Blur {
+            x_sigma: 1.0,
+            y_sigma: 1.0,
+            tile_mode: TileMode::Clamp,
+        };
+        let nested_filters = |depth| {
+            let mut filter = Arc::new(blur.clone());
+            for _ in 0..depth {
+                filter = Arc::new(RecordedImageFilter::Compose {
+                    outer: Arc::new(blur.clone()),
+                    inner: filter,
+                });
+            }
+            let mut list = RecordedDisplayList::new(None);
+            list.push(DisplayOp::SaveLayer {
+                bounds: rect(0.0, 0.0, 10.0, 10.0),
+                paint: None,
+                backdrop: Some(filter),
+            });
+            list
+        };
+        let shallow = nested_filters(MAX_DEPTH - 2);
+        assert_eq!(
+            RecordedDisplayList::from_bytes(&shallow.to_bytes().unwrap()).unwrap(),
+            shallow
+        );
+        assert_eq!(
+            error(&nested_filters(MAX_DEPTH)),
+            "objects are nested too deeply"
+        );
+
+        let mut list = RecordedDisplayList::new(None);
+        for _ in 0..=MAX_DEPTH {
+            let mut outer = RecordedDisplayList::new(None);
+            outer.push(DisplayOp::DrawDisplayList {
+                display_list: Some(Arc::new(list)),
+                opacity: 1.0,
+            });
+            list = outer;
+        }
+        assert_eq!(error(&list), "objects are nested too deeply");
+    }
+}
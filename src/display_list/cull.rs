@@ -0,0 +1,416 @@
+//! Dropping the ops of a recorded display list that can't be seen in a viewport.
+//!
+//! Every level of saves tracks the device region that matters for the viewport. Image
+//! filters on layers grow that region by what they read, so a blurred layer keeps the
+//! content next to the viewport too. Backdrop filters read what was drawn before them,
+//! which would need to look back, so they grow the viewport and culling starts over.
+use std::sync::Arc;
+
+use super::bounds::{affects_transparent, draw_bounds, transform_box, Box2D, UNBOUNDED};
+use super::{walk, DisplayOp, Pass, RecordedDisplayList};
+use crate::{ClipOperation, Matrix, RecordedImageFilter, Rect};
+
+/// The region that an image filter reads to fill a region, in the coordinates of the filter.
+fn filter_inputs(filter: &RecordedImageFilter, b: Box2D) -> Box2D {
+    match filter {
+        RecordedImageFilter::Blur {
+            x_sigma, y_sigma, ..
+        } => b.inflate(3.0 * x_sigma.abs(), 3.0 * y_sigma.abs()),
+        RecordedImageFilter::Dilate { x_radius, y_radius }
+        | RecordedImageFilter::Erode { x_radius, y_radius } => {
+            b.inflate(x_radius.abs(), y_radius.abs())
+        }
+        RecordedImageFilter::Matrix { matrix, .. } => match matrix.inverse() {
+            Some(inverse) => transform_box(&inverse, &b),
+            None => UNBOUNDED,
+        },
+        RecordedImageFilter::Compose { outer, inner } => {
+            filter_inputs(inner, filter_inputs(outer, b))
+        }
+        RecordedImageFilter::FragmentProgram { .. } => UNBOUNDED,
+    }
+}
+/// Whether [filter_inputs] only ever grows a region.
+fn inflates(filter: &RecordedImageFilter) -> bool {
+    match filter {
+        RecordedImageFilter::Blur { .. }
+        | RecordedImageFilter::Dilate { .. }
+        | RecordedImageFilter::Erode { .. } => true,
+        RecordedImageFilter::Compose { outer, inner } => inflates(outer) && inflates(inner),
+        RecordedImageFilter::Matrix { .. } | RecordedImageFilter::FragmentProgram { .. } => false,
+    }
+}
+/// [filter_inputs] for a device region, with a filter that applies in the coordinates of
+/// `transform`.
+fn device_filter_inputs(filter: &RecordedImageFilter, transform: &Matrix, b: Box2D) -> Box2D {
+    if b.is_empty() {
+        return b;
+    }
+    match transform.inverse() {
+        Some(inverse) => {
+            let local = filter_inputs(filter, transform_box(&inverse, &b));
+            transform_box(transform, &local)
+        }
+        None => UNBOUNDED,
+    }
+}
+
+/// What culling the ops inside a save needs to know.
+#[derive(Clone, Copy)]
+struct Level {
+    transform: Matrix,
+    /// The device bounds of the clip.
+    clip: Box2D,
+    /// The device region where drawing can change what the viewport shows.
+    needed: Box2D,
+    /// Whether `needed` contains the viewport, so that growing the viewport grows it too.
+    inflated: bool,
+    /// Where the op that pushed this level is in the output.
+    start: usize,
+    /// Whether anything that draws was kept since the save.
+    drew: bool,
+    /// Whether the save is a layer that changes the viewport even when it's empty.
+    draws_empty: bool,
+}
+
+struct Culler {
+    viewport: Box2D,
+    /// Whether the viewport grew since culling started.
+    grown: bool,
+}
+impl Culler {
+    /// Make sure the region that a visible backdrop filter reads is kept.
+    fn grow(&mut self, read: Box2D, inflated: bool) {
+        let read = if inflated { read } else { UNBOUNDED };
+        if read.is_empty() {
+            return;
+        }
+        let viewport = self.viewport.union(&read);
+        if viewport != self.viewport {
+            self.viewport = viewport;
+            self.grown = true;
+        }
+    }
+    /// Cull a list that is drawn in the `base` level.
+    ///
+    /// @return the culled list, and whether anything in it is kept.
+    fn list(&mut self, list: &RecordedDisplayList, base: Level) -> (RecordedDisplayList, bool) {
+        let mut pass = ListCuller {
+            culler: self,
+            out: RecordedDisplayList::new(list.cull_rect()),
+        };
+        let base = walk(&mut pass, list, base);
+        (pass.out, base.drew)
+    }
+}
+/// Culls the ops of one list into `out`.
+struct ListCuller<'a> {
+    culler: &'a mut Culler,
+    out: RecordedDisplayList,
+}
+impl ListCuller<'_> {
+    /// Close a level, and drop it from the output if nothing visible was drawn in it.
+    fn close(&mut self, level: Level, parent: &mut Level, restore: bool) {
+        if level.drew || level.draws_empty {
+            if restore {
+                self.out.push(DisplayOp::Restore);
+            }
+            parent.drew = true;
+        } else {
+            self.out.ops.truncate(level.start);
+        }
+    }
+}
+impl Pass for ListCuller<'_> {
+    type Level = Level;
+    fn transform(level: &mut Level) -> &mut Matrix {
+        &mut level.transform
+    }
+    fn transformed(&mut self, op: &DisplayOp) {
+        self.out.push(op.clone());
+    }
+    fn save(&mut self, top: &Level, op: &DisplayOp) -> Level {
+        let start = self.out.ops().len();
+        self.out.push(op.clone());
+        let DisplayOp::SaveLayer {
+            bounds,
+            paint,
+            backdrop,
+        } = op
+        else {
+            return Level {
+                start,
+                drew: false,
+                draws_empty: false,
+                ..*top
+            };
+        };
+        let layer =
+            transform_box(&top.transform, &bounds.to_box2d()).intersection_unchecked(&top.clip);
+        let filter = paint
+            .as_ref()
+            .and_then(|paint| paint.image_filter.as_deref());
+        // the region that the composited layer reads from the layer
+        let read = match filter {
+            Some(filter) => device_filter_inputs(filter, &top.transform, top.needed),
+            None => top.needed,
+        };
+        let layer_visible = read.intersects(&layer);
+        let needed = if layer_visible { read } else { Box2D::zero() };
+        if let (true, Some(backdrop)) = (layer_visible, backdrop) {
+            let read = device_filter_inputs(backdrop, &top.transform, layer);
+            self.culler.grow(read, top.inflated);
+        }
+        Level {
+            transform: top.transform,
+            clip: layer,
+            needed,
+            inflated: top.inflated && filter.is_none_or(inflates),
+            start,
+            drew: false,
+            draws_empty: layer_visible
+                && (backdrop.is_some() || paint.as_ref().is_some_and(affects_transparent)),
+        }
+    }
+    fn restore(&mut self, level: Level, parent: &mut Level) {
+        self.close(level, parent, true);
+    }
+    fn unwind(&mut self, level: Level, parent: &mut Level) {
+        // saves that are never restored stay that way
+        self.close(level, parent, false);
+    }
+    fn op(&mut self, top: &mut Level, _index: usize, op: &DisplayOp) {
+        let needed = top.needed;
+        let visible = |reach: Box2D| reach.intersects(&needed);
+        let clip_to = |top: &mut Level, rect: Rect, clip_op: ClipOperation| {
+            if clip_op == ClipOperation::Intersect {
+                let rect = transform_box(&top.transform, &rect.to_box2d());
+                top.clip = top.clip.intersection_unchecked(&rect);
+            }
+        };
+        let drew = match op {
+            DisplayOp::ClipRect { rect, op: clip_op } => {
+                clip_to(top, *rect, *clip_op);
+                self.out.push(op.clone());
+                return;
+            }
+            DisplayOp::ClipOval {
+                oval_bounds,
+                op: clip_op,
+            } => {
+                clip_to(top, *oval_bounds, *clip_op);
+                self.out.push(op.clone());
+                return;
+            }
+            DisplayOp::ClipRoundedRect {
+                rect, op: clip_op, ..
+            } => {
+                clip_to(top, *rect, *clip_op);
+                self.out.push(op.clone());
+                return;
+            }
+            DisplayOp::ClipPath { path, op: clip_op } => {
+                clip_to(top, path.tight_bounds(), *clip_op);
+                self.out.push(op.clone());
+                return;
+            }
+            DisplayOp::SetTag(_) => {
+                self.out.push(op.clone());
+                return;
+            }
+            DisplayOp::DrawPaint { .. }
+            | DisplayOp::DrawDisplayList {
+                display_list: None, ..
+            } => visible(top.clip).then(|| op.clone()),
+            DisplayOp::DrawDisplayList {
+                display_list: Some(nested),
+                opacity,
+            } => {
+                let base = Level {
+                    start: 0,
+                    drew: false,
+                    draws_empty: false,
+                    ..*top
+                };
+                let (culled, drew) = self.culler.list(nested, base);
+                drew.then(|| DisplayOp::DrawDisplayList {
+                    // keep sharing the list if nothing was culled from it
+                    display_list: Some(if culled.ops() == nested.ops() {
+                        nested.clone()
+                    } else {
+                        Arc::new(culled)
+                    }),
+                    opacity: *opacity,
+                })
+            }
+            op => match draw_bounds(op) {
+                Some((local, hairline)) => {
+                    let device = transform_box(&top.transform, &local)
+                        .inflate(hairline, hairline)
+                        .intersection_unchecked(&top.clip);
+                    visible(device).then(|| op.clone())
+                }
+                None => Some(op.clone()),
+            },
+        };
+        if let Some(op) = drew {
+            self.out.push(op);
+            top.drew = true;
+        }
+    }
+}
+
+impl RecordedDisplayList {
+    /// A copy of the recording without the ops that can't change what is drawn
+    /// inside the viewport.
+    ///
+    /// Draws are culled by their [bounds](Self::bounds), and saves and layers that are
+    /// left empty are removed along with the transforms and clips inside of them.
+    /// Nested display lists are culled too. Content that filters and backdrop filters
+    /// can pull into the viewport is kept.
+    ///
+    /// The cull rect of the recording is kept as it is, and
+    /// [restore_to_count](DisplayOp::RestoreToCount) ops become plain restores.
+    ///
+    /// ```rust
+    /// # use impellers::*;
+    /// let mut list = RecordedDisplayList::new(None);
+    /// for x in 0..100 {
+    ///     list.push(DisplayOp::DrawRect {
+    ///         rect: Rect::new(Point::new(x as f32 * 100.0, 0.0), Size::new(50.0, 50.0)),
+    ///         paint: RecordedPaint::default(),
+    ///     });
+    /// }
+    /// let viewport = Rect::new(Point::new(1000.0, 0.0), Size::new(800.0, 600.0));
+    /// assert_eq!(list.culled(&viewport).ops().len(), 8);
+    /// ```
+    pub fn culled(&self, viewport: &Rect) -> RecordedDisplayList {
+        let mut culler = Culler {
+            viewport: viewport.to_box2d(),
+            grown: false,
+        };
+        loop {
+            let base = Level {
+                transform: Matrix::identity(),
+                clip: self.cull_rect().map_or(UNBOUNDED, |rect| rect.to_box2d()),
+                needed: culler.viewport,
+                inflated: true,
+                start: 0,
+                drew: false,
+                draws_empty: false,
+            };
+            let (culled, _) = culler.list(self, base);
+            if !culler.grown {
+                return culled;
+            }
+            culler.grown = false;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::fixtures::rect;
+    use crate::{RecordedPaint, TileMode};
+    fn draw(x: f32, y: f32) -> DisplayOp {
+        DisplayOp::DrawRect {
+            rect: rect(x, y, 10.0, 10.0),
+            paint: RecordedPaint::default(),
+        }
+    }
+
+    #[test]
+    fn culling_to_viewport() {
+        let viewport = rect(0.0, 0.0, 100.0, 100.0);
+        let blur = |sigma| {
+            Arc::new(RecordedImageFilter::Blur {
+                x_sigma: sigma,
+                y_sigma: sigma,
+                tile_mode: TileMode::Decal,
+            })
+        };
+        let mut nested = RecordedDisplayList::new(None);
+        nested.push(draw(0.0, 0.0)).push(draw(500.0, 0.0));
+        let nested = Arc::new(nested);
+
+        let mut list = RecordedDisplayList::new(None);
+        list.push(draw(50.0, 50.0))
+            // an empty save is dropped with everything in it
+            .push(DisplayOp::Save)
+            .push(DisplayOp::Translate {
+                x_translation: 200.0,
+                y_translation: 0.0,
+            })
+            .push(draw(0.0, 0.0))
+            .push(DisplayOp::Restore)
+            // the translate brings the rect into the viewport
+            .push(DisplayOp::Save)
+            .push(DisplayOp::Translate {
+                x_translation: -200.0,
+                y_translation: 0.0,
+            })
+            .push(draw(200.0, 0.0))
+            .push(DisplayOp::Restore)
+            // the blur reaches into the viewport
+            .push(DisplayOp::SaveLayer {
+                bounds: rect(-1000.0, -1000.0, 2000.0, 2000.0),
+                paint: Some(RecordedPaint {
+                    image_filter: Some(blur(10.0)),
+                    ..Default::default()
+                }),
+                backdrop: None,
+            })
+            .push(draw(120.0, 0.0))
+            .push(draw(200.0, 0.0))
+            .push(DisplayOp::Restore)
+            .push(DisplayOp::DrawDisplayList {
+                display_list: Some(nested.clone()),
+                opacity: 1.0,
+            });
+        let culled = list.culled(&viewport);
+        assert_eq!(
+            culled.ops()[..8],
+            [
+                draw(50.0, 50.0),
+                DisplayOp::Save,
+                list.ops()[6].clone(),
+                draw(200.0, 0.0),
+                DisplayOp::Restore,
+                list.ops()[9].clone(),
+                draw(120.0, 0.0),
+                DisplayOp::Restore,
+            ]
+        );
+        let DisplayOp::DrawDisplayList {
+            display_list: Some(culled_nested),
+            ..
+        } = &culled.ops()[8]
+        else {
+            panic!("the nested list should be kept");
+        };
+        assert_eq!(culled_nested.ops(), [draw(0.0, 0.0)]);
+        assert_eq!(culled.ops().len(), 9);
+        // a list that is entirely visible is left alone
+        assert_eq!(list.culled(&rect(-1000.0, -1000.0, 3000.0, 3000.0)), list);
+
+        // a backdrop blur in the viewport reads what is drawn just outside of it
+        let mut list = RecordedDisplayList::new(None);
+        list.push(draw(110.0, 0.0))
+            .push(draw(300.0, 0.0))
+            .push(DisplayOp::RestoreToCount(1))
+            .push(DisplayOp::SaveLayer {
+                bounds: rect(0.0, 0.0, 100.0, 100.0),
+                paint: None,
+                backdrop: Some(blur(5.0)),
+            })
+            .push(DisplayOp::RestoreToCount(1));
+        let culled = list.culled(&viewport);
+        assert_eq!(
+            culled.ops(),
+            [draw(110.0, 0.0), list.ops()[3].clone(), DisplayOp::Restore]
+        );
+    }
+}
@@ -0,0 +1,539 @@
+//! Finding what changed between two frames, by diffing their recorded display lists.
+//!
+//! Every draw op becomes an item, keyed by the op, the transform it was drawn with and
+//! the clips and layers around it. Items that appear in both frames, in the same order,
+//! are unchanged. The bounds of all other items are damaged.
+use std::sync::Arc;
+
+use super::bounds::{clip_after, composite_bounds, op_bounds, transform_box, Box2D, UNBOUNDED};
+use super::{walk, DisplayOp, Pass, RecordedDisplayList};
+use crate::{
+    Matrix, ParagraphItem, Point, RecordedColorSource, RecordedImageFilter, RecordedPaint,
+    RecordedTexture, Rect,
+};
+
+/// The largest number of item pairs compared when aligning two frames.
+///
+/// Beyond this, all items between the common start and end of the frames are damaged.
+const MAX_COMPARISONS: usize = 1 << 22;
+
+/// A clip or layer op, with the transform and the device clip it was called with.
+#[derive(Debug, Clone, PartialEq)]
+struct State {
+    op: DisplayOp,
+    transform: Matrix,
+    clip: Box2D,
+}
+/// A draw op, along with everything that affects how it's drawn.
+#[derive(Debug, Clone)]
+struct Item {
+    context: Arc<Vec<State>>,
+    transform: Matrix,
+    op: DisplayOp,
+    /// The parts of the op and its context that can't be compared by value.
+    opaque: Vec<Opaque>,
+    bounds: Option<Box2D>,
+    /// Whether this item filters what was drawn before it.
+    reads_backdrop: bool,
+}
+impl Item {
+    fn same(&self, other: &Item) -> bool {
+        let context = Arc::ptr_eq(&self.context, &other.context) || self.context == other.context;
+        let op = match (&self.op, &other.op) {
+            // nested lists are usually reused as they are
+            (
+                DisplayOp::DrawDisplayList {
+                    display_list: Some(a),
+                    opacity: a_opacity,
+                },
+                DisplayOp::DrawDisplayList {
+                    display_list: Some(b),
+                    opacity: b_opacity,
+                },
+            ) => a_opacity == b_opacity && (Arc::ptr_eq(a, b) || a == b),
+            (a, b) => a == b,
+        };
+        // the rest is equal, so the opaque parts line up
+        context
+            && op
+            && self.transform == other.transform
+            && self.opaque.len() == other.opaque.len()
+            && self
+                .opaque
+                .iter()
+                .zip(&other.opaque)
+                .all(|parts| match parts {
+                    (Opaque::Texture(a), Opaque::Texture(b)) => Arc::ptr_eq(a, b),
+                    _ => false,
+                })
+    }
+}
+/// A part of an op that can't be compared by value.
+#[derive(Debug, Clone)]
+enum Opaque {
+    /// Textures without pixels are only the same if they are the same texture.
+    Texture(Arc<RecordedTexture>),
+    /// Fragment programs and display lists that weren't recorded may have changed.
+    Unknown,
+}
+fn texture_parts(texture: &Arc<RecordedTexture>, parts: &mut Vec<Opaque>) {
+    if texture.pixels.is_none() {
+        parts.push(Opaque::Texture(texture.clone()));
+    }
+}
+fn filter_parts(filter: &RecordedImageFilter, parts: &mut Vec<Opaque>) {
+    match filter {
+        RecordedImageFilter::Compose { outer, inner } => {
+            filter_parts(outer, parts);
+            filter_parts(inner, parts);
+        }
+        RecordedImageFilter::FragmentProgram { .. } => parts.push(Opaque::Unknown),
+        _ => {}
+    }
+}
+fn paint_parts(paint: &RecordedPaint, parts: &mut Vec<Opaque>) {
+    match paint.color_source.as_deref() {
+        Some(RecordedColorSource::Image { image, .. }) => texture_parts(image, parts),
+        Some(RecordedColorSource::FragmentProgram { .. }) => parts.push(Opaque::Unknown),
+        _ => {}
+    }
+    if let Some(filter) = &paint.image_filter {
+        filter_parts(filter, parts);
+    }
+}
+/// The parts of an op that [DisplayOp]'s `PartialEq` can't see changing, in order.
+fn op_parts(op: &DisplayOp, parts: &mut Vec<Opaque>) {
+    if let Some(paint) = op.paint() {
+        paint_parts(paint, parts);
+    }
+    match op {
+        DisplayOp::SaveLayer {
+            backdrop: Some(backdrop),
+            ..
+        } => filter_parts(backdrop, parts),
+        DisplayOp::DrawTexture { texture, .. } | DisplayOp::DrawTextureRect { texture, .. } => {
+            texture_parts(texture, parts)
+        }
+        DisplayOp::DrawDisplayList {
+            display_list: Some(list),
+            ..
+        } => {
+            for op in list.ops() {
+                op_parts(op, parts);
+            }
+        }
+        DisplayOp::DrawDisplayList {
+            display_list: None, ..
+        } => parts.push(Opaque::Unknown),
+        DisplayOp::DrawParagraph { paragraph, .. } => {
+            for item in &paragraph.items {
+                if let ParagraphItem::PushStyle(style) = item {
+                    let paints = style.foreground.iter().chain(&style.background);
+                    paints.for_each(|paint| paint_parts(paint, parts));
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// The clips and layers around the ops inside a save.
+#[derive(Clone)]
+struct Level {
+    transform: Matrix,
+    /// The device bounds of the clip.
+    clip: Box2D,
+    context: Arc<Vec<State>>,
+}
+/// Collects the items of a list.
+struct Items {
+    items: Vec<Item>,
+}
+impl Items {
+    fn push(&mut self, top: &Level, op: &DisplayOp, reads_backdrop: bool) {
+        // the bounds of the op on its own, grown by the layers around it
+        let mut bounds = op_bounds(op, &top.transform, top.clip);
+        for state in top.context.iter().rev() {
+            if let DisplayOp::SaveLayer {
+                bounds: layer,
+                paint,
+                ..
+            } = &state.op
+            {
+                let layer = transform_box(&state.transform, &layer.to_box2d())
+                    .intersection_unchecked(&state.clip);
+                bounds = composite_bounds(paint.as_ref(), layer, &state.transform, bounds)
+                    .map(|bounds| bounds.intersection_unchecked(&state.clip))
+                    .filter(|bounds| !bounds.is_empty());
+            }
+        }
+        let mut opaque = vec![];
+        for state in top.context.iter() {
+            op_parts(&state.op, &mut opaque);
+        }
+        op_parts(op, &mut opaque);
+        self.items.push(Item {
+            context: top.context.clone(),
+            transform: top.transform,
+            op: op.clone(),
+            opaque,
+            bounds,
+            reads_backdrop,
+        });
+    }
+}
+impl Pass for Items {
+    type Level = Level;
+    fn transform(level: &mut Level) -> &mut Matrix {
+        &mut level.transform
+    }
+    fn save(&mut self, top: &Level, op: &DisplayOp) -> Level {
+        let mut level = top.clone();
+        if let DisplayOp::SaveLayer {
+            bounds, backdrop, ..
+        } = op
+        {
+            // the layer itself draws if it has a backdrop, or erases what's below it
+            self.push(top, op, backdrop.is_some());
+            Arc::make_mut(&mut level.context).push(State {
+                op: op.clone(),
+                transform: top.transform,
+                clip: top.clip,
+            });
+            level.clip =
+                transform_box(&top.transform, &bounds.to_box2d()).intersection_unchecked(&top.clip);
+        }
+        level
+    }
+    fn restore(&mut self, _level: Level, _parent: &mut Level) {}
+    fn op(&mut self, top: &mut Level, _index: usize, op: &DisplayOp) {
+        if let Some(clip) = clip_after(op, &top.transform, top.clip) {
+            Arc::make_mut(&mut top.context).push(State {
+                op: op.clone(),
+                transform: top.transform,
+                clip: top.clip,
+            });
+            top.clip = clip;
+        } else if !matches!(op, DisplayOp::SetTag(_)) {
+            // tags don't draw anything
+            self.push(top, op, false);
+        }
+    }
+}
+fn items(list: &RecordedDisplayList) -> Vec<Item> {
+    let mut items = Items { items: vec![] };
+    let base = Level {
+        transform: Matrix::identity(),
+        clip: list.cull_rect().map_or(UNBOUNDED, |rect| rect.to_box2d()),
+        context: Arc::default(),
+    };
+    walk(&mut items, list, base);
+    items.items
+}
+/// Which items of each frame are not in the longest common sequence of items.
+fn unmatched(previous: &[Item], current: &[Item]) -> (Vec<bool>, Vec<bool>) {
+    let mut old = vec![true; previous.len()];
+    let mut new = vec![true; current.len()];
+    let prefix = previous
+        .iter()
+        .zip(current)
+        .take_while(|(a, b)| a.same(b))
+        .count();
+    let suffix = previous[prefix..]
+        .iter()
+        .rev()
+        .zip(current[prefix..].iter().rev())
+        .take_while(|(a, b)| a.same(b))
+        .count();
+    for i in 0..prefix {
+        old[i] = false;
+        new[i] = false;
+    }
+    for i in 0..suffix {
+        old[previous.len() - 1 - i] = false;
+        new[current.len() - 1 - i] = false;
+    }
+    let a = &previous[prefix..previous.len() - suffix];
+    let b = &current[prefix..current.len() - suffix];
+    if a.is_empty() || b.is_empty() || a.len() * b.len() > MAX_COMPARISONS {
+        return (old, new);
+    }
+    // lengths of the longest common sequences of the ends of a and b
+    let width = b.len() + 1;
+    let mut lengths = vec![0u32; (a.len() + 1) * width];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lengths[i * width + j] = if a[i].same(&b[j]) {
+                lengths[(i + 1) * width + j + 1] + 1
+            } else {
+                lengths[(i + 1) * width + j].max(lengths[i * width + j + 1])
+            };
+        }
+    }
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        if a[i].same(&b[j]) {
+            old[prefix + i] = false;
+            new[prefix + j] = false;
+            i += 1;
+            j += 1;
+        } else if lengths[(i + 1) * width + j] >= lengths[i * width + j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    (old, new)
+}
+/// Add a box to the damage, merging it with the boxes it touches.
+fn add_damage(damage: &mut Vec<Box2D>, b: Box2D) {
+    let mut b = b.round_out();
+    if b.is_empty() {
+        return;
+    }
+    while let Some(i) = damage.iter().position(|d| {
+        d.min.x <= b.max.x && b.min.x <= d.max.x && d.min.y <= b.max.y && b.min.y <= d.max.y
+    }) {
+        b = b.union(&damage.swap_remove(i));
+    }
+    damage.push(b);
+}
+
+/// Tracks the recorded display lists of consecutive frames, to find what changed.
+///
+/// Each frame is compared with the previous one, producing a list of dirty rects.
+/// Only the areas inside of them need to be repainted, for example with
+/// [DisplayListBuilder::clip_rect](crate::DisplayListBuilder::clip_rect), if the
+/// rest of the previous frame was kept around.
+///
+/// Nested display lists are compared by identity first, so reusing the same
+/// [DisplayList](crate::DisplayList) between frames is cheap. Textures without recorded
+/// pixels are only unchanged if they are the same texture, and ops that use fragment
+/// programs or unrecorded display lists are always damaged, even inside reused lists.
+///
+/// ```rust
+/// # use impellers::*;
+/// # use std::sync::Arc;
+/// let mut frame = RecordedDisplayList::new(None);
+/// frame.draw_rect(&Rect::new(Point::zero(), Size::new(10.0, 10.0)), &RecordedPaint::default());
+/// let mut tracker = DamageTracker::new();
+/// // the first frame damages everything it draws
+/// assert_eq!(tracker.update(&frame).len(), 1);
+/// assert!(tracker.update(&frame).is_empty());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct DamageTracker {
+    cull_rect: Option<Rect>,
+    items: Vec<Item>,
+}
+impl DamageTracker {
+    /// Create a tracker, which treats the frame before the first one as empty.
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Forget the previous frame, as if it was empty.
+    pub fn reset(&mut self) {
+        self.cull_rect = None;
+        self.items.clear();
+    }
+    /// Compare a frame with the previous one, and remember it for the next update.
+    ///
+    /// - display_list  The recording of the new frame.
+    ///
+    /// @return         The dirty rects in device coordinates, rounded out to whole pixels.
+    ///                 They don't overlap each other.
+    pub fn update(&mut self, display_list: &RecordedDisplayList) -> Vec<Rect> {
+        let items = items(display_list);
+        let (old, new) = if self.cull_rect == display_list.cull_rect() {
+            unmatched(&self.items, &items)
+        } else {
+            (vec![true; self.items.len()], vec![true; items.len()])
+        };
+        let mut damage = vec![];
+        let changed = self
+            .items
+            .iter()
+            .zip(old)
+            .chain(items.iter().zip(new.iter().copied()));
+        for (item, changed) in changed {
+            if let (true, Some(bounds)) = (changed, item.bounds) {
+                add_damage(&mut damage, bounds);
+            }
+        }
+        // backdrop filters spread any damage below them over their whole layer
+        let mut spread = true;
+        while spread {
+            spread = false;
+            for (item, changed) in items.iter().zip(&new) {
+                let Some(bounds) = item.bounds.filter(|_| item.reads_backdrop && !changed) else {
+                    continue;
+                };
+                let bounds = bounds.round_out();
+                let inside = damage.iter().any(|d| d.contains_box(&bounds));
+                if !inside && damage.iter().any(|d| d.intersects(&bounds)) {
+                    add_damage(&mut damage, bounds);
+                    spread = true;
+                }
+            }
+        }
+        self.cull_rect = display_list.cull_rect();
+        self.items = items;
+        let limit = Point::splat(f32::MAX);
+        damage
+            .into_iter()
+            .map(|d| Box2D::new(d.min.max(-limit), d.max.min(limit)).to_rect())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::fixtures::rect;
+    use crate::{Canvas, ClipOperation, Color, RecordedPaint, TextureSampling};
+
+    #[test]
+    fn damage_between_frames() {
+        let fill = RecordedPaint::default();
+        let red = RecordedPaint {
+            color: Color::new_srgb(1.0, 0.0, 0.0),
+            ..Default::default()
+        };
+        let mut icon = RecordedDisplayList::new(None);
+        icon.draw_oval(&rect(0.0, 0.0, 4.0, 4.0), &fill);
+        let icon = Arc::new(icon);
+        let frame = |color: &RecordedPaint, x: f32, extra: bool| {
+            let mut frame = RecordedDisplayList::new(None);
+            frame
+                .save()
+                .clip_rect(&rect(0.0, 0.0, 100.0, 100.0), ClipOperation::Intersect)
+                .draw_rect(&rect(0.0, 0.0, 10.0, 10.0), color)
+                .save()
+                .translate(x, 50.0)
+                .draw_display_list(&icon, 1.0)
+                .restore();
+            if extra {
+                frame.draw_line(Point::new(0.0, 90.0), Point::new(10.5, 90.0), &fill);
+            }
+            frame
+                .draw_rect(&rect(20.0, 0.0, 10.0, 10.0), &fill)
+                .restore();
+            frame
+        };
+        let mut tracker = DamageTracker::new();
+        let mut damage = tracker.update(&frame(&fill, 0.0, false));
+        damage.sort_by(|a, b| {
+            (a.origin.x, a.origin.y)
+                .partial_cmp(&(b.origin.x, b.origin.y))
+                .unwrap()
+        });
+        assert_eq!(
+            damage,
+            [
+                rect(0.0, 0.0, 10.0, 10.0),
+                rect(0.0, 50.0, 4.0, 4.0),
+                rect(20.0, 0.0, 10.0, 10.0)
+            ]
+        );
+        assert!(tracker.update(&frame(&fill, 0.0, false)).is_empty());
+        // only the changed color is damaged
+        assert_eq!(
+            tracker.update(&frame(&red, 0.0, false)),
+            [rect(0.0, 0.0, 10.0, 10.0)]
+        );
+        // a moved nested list damages where it was and where it is
+        let mut damage = tracker.update(&frame(&red, 60.0, false));
+        damage.sort_by(|a, b| a.origin.x.total_cmp(&b.origin.x));
+        assert_eq!(
+            damage,
+            [rect(0.0, 50.0, 4.0, 4.0), rect(60.0, 50.0, 4.0, 4.0)]
+        );
+        // an inserted op doesn't damage the ops after it, and hairlines reach a pixel out
+        assert_eq!(
+            tracker.update(&frame(&red, 60.0, true)),
+            [rect(0.0, 89.0, 12.0, 2.0)]
+        );
+        tracker.reset();
+        assert_eq!(tracker.update(&RecordedDisplayList::new(None)), []);
+    }
+
+    #[test]
+    fn opaque_ops() {
+        let clip = rect(0.0, 0.0, 10.0, 10.0);
+        let frame = |op: DisplayOp| {
+            let mut frame = RecordedDisplayList::new(None);
+            frame.clip_rect(&clip, ClipOperation::Intersect).push(op);
+            frame
+        };
+        let texture = || {
+            Arc::new(RecordedTexture {
+                width: 4,
+                height: 4,
+                pixels: None,
+            })
+        };
+        let draw_texture = |texture: &Arc<RecordedTexture>| {
+            let mut frame = RecordedDisplayList::new(None);
+            frame.draw_texture(
+                texture,
+                Point::zero(),
+                TextureSampling::NearestNeighbor,
+                &RecordedPaint::default(),
+            );
+            frame
+        };
+        let mut tracker = DamageTracker::new();
+        let (a, b) = (texture(), texture());
+        tracker.update(&draw_texture(&a));
+        assert!(tracker.update(&draw_texture(&a)).is_empty());
+        // a different texture of the same size may have different pixels
+        assert_eq!(
+            tracker.update(&draw_texture(&b)),
+            [rect(0.0, 0.0, 4.0, 4.0)]
+        );
+
+        let unrecorded = || {
+            frame(DisplayOp::DrawDisplayList {
+                display_list: None,
+                opacity: 1.0,
+            })
+        };
+        tracker.update(&unrecorded());
+        assert_eq!(tracker.update(&unrecorded()), [clip]);
+
+        let program = RecordedPaint {
+            color_source: Some(Arc::new(RecordedColorSource::FragmentProgram {
+                samplers: vec![],
+                uniform_data: vec![1, 2, 3, 4],
+            })),
+            ..Default::default()
+        };
+        let filtered = RecordedPaint {
+            image_filter: Some(Arc::new(RecordedImageFilter::FragmentProgram {
+                samplers: vec![],
+                uniform_data: vec![],
+            })),
+            ..Default::default()
+        };
+        for paint in [program, filtered] {
+            let paint = || {
+                frame(DisplayOp::DrawPaint {
+                    paint: paint.clone(),
+                })
+            };
+            tracker.update(&paint());
+            assert_eq!(tracker.update(&paint()), [clip]);
+        }
+
+        // reusing a nested list doesn't hide what's opaque inside of it
+        let nested = Arc::new(unrecorded());
+        let reused = || {
+            frame(DisplayOp::DrawDisplayList {
+                display_list: Some(nested.clone()),
+                opacity: 1.0,
+            })
+        };
+        tracker.update(&reused());
+        assert_eq!(tracker.update(&reused()), [clip]);
+    }
+}
@@ -0,0 +1,558 @@
+//! A readable listing of recorded display lists, for debugging.
+//!
+//! Every op is a line, indented by the save depth. Paints only list the fields that
+//! differ from the default paint, except for the color.
+use std::fmt::{self, Write};
+
+use super::{DisplayOp, RecordedDisplayList};
+use crate::{
+    Color, Matrix, ParagraphItem, Point, RecordedColorFilter, RecordedColorSource,
+    RecordedImageFilter, RecordedMaskFilter, RecordedPaint, RecordedPath, Rect, RoundingRadii,
+};
+
+/// Longer text and path data is cut off.
+const MAX_SNIPPET: usize = 48;
+
+fn snippet(text: &str) -> String {
+    match text.char_indices().nth(MAX_SNIPPET) {
+        Some((end, _)) => format!("{}…", &text[..end]),
+        None => text.to_owned(),
+    }
+}
+fn point(p: &Point) -> String {
+    format!("{},{}", p.x, p.y)
+}
+fn rect(r: &Rect) -> String {
+    format!(
+        "{},{} {}x{}",
+        r.origin.x, r.origin.y, r.size.width, r.size.height
+    )
+}
+fn radii(r: &RoundingRadii) -> String {
+    let corners = [r.top_left, r.top_right, r.bottom_right, r.bottom_left];
+    if corners.iter().all(|c| *c == r.top_left) {
+        format!("radii={}", point(&r.top_left))
+    } else {
+        format!("radii=[{}]", corners.map(|c| point(&c)).join(" "))
+    }
+}
+fn color(c: &Color) -> String {
+    let byte = |v: f32| (v.clamp(0.0, 1.0) * 255.0).round() as u8;
+    format!(
+        "#{:02x}{:02x}{:02x}{:02x}",
+        byte(c.red),
+        byte(c.green),
+        byte(c.blue),
+        byte(c.alpha)
+    )
+}
+/// 2d transforms as `[a b c d e f]`, like svg, and anything else as all 16 values.
+fn matrix(m: &Matrix) -> String {
+    if m.is_2d() {
+        format!(
+            "[{} {} {} {} {} {}]",
+            m.m11, m.m12, m.m21, m.m22, m.m41, m.m42
+        )
+    } else {
+        let values = m.to_array().map(|v| v.to_string());
+        format!("[{}]", values.join(" "))
+    }
+}
+fn path(p: &RecordedPath) -> String {
+    format!("{:?} \"{}\"", p.fill_type(), snippet(&p.to_svg_path_data()))
+}
+fn colors(colors: &[Color], stops: &[f32]) -> String {
+    let colors: Vec<String> = colors.iter().map(color).collect();
+    if stops.is_empty() {
+        colors.join(" ")
+    } else {
+        let stops = stops.iter().map(f32::to_string);
+        let pairs: Vec<String> = colors
+            .iter()
+            .zip(stops)
+            .map(|(c, s)| format!("{c}@{s}"))
+            .collect();
+        pairs.join(" ")
+    }
+}
+fn color_filter(filter: &RecordedColorFilter) -> String {
+    match filter {
+        RecordedColorFilter::Blend {
+            color: c,
+            blend_mode,
+        } => {
+            format!("blend({}, {blend_mode:?})", color(c))
+        }
+        RecordedColorFilter::Matrix(m) => {
+            let values: Vec<String> = m.m.iter().map(f32::to_string).collect();
+            format!("matrix([{}])", values.join(" "))
+        }
+    }
+}
+fn image_filter(filter: &RecordedImageFilter) -> String {
+    match filter {
+        RecordedImageFilter::Blur {
+            x_sigma,
+            y_sigma,
+            tile_mode,
+        } => format!("blur({x_sigma}, {y_sigma}, {tile_mode:?})"),
+        RecordedImageFilter::Dilate { x_radius, y_radius } => {
+            format!("dilate({x_radius}, {y_radius})")
+        }
+        RecordedImageFilter::Erode { x_radius, y_radius } => {
+            format!("erode({x_radius}, {y_radius})")
+        }
+        RecordedImageFilter::Matrix {
+            matrix: m,
+            sampling,
+        } => format!("matrix({}, {sampling:?})", matrix(m)),
+        RecordedImageFilter::Compose { outer, inner } => {
+            format!("compose({}, {})", image_filter(outer), image_filter(inner))
+        }
+        RecordedImageFilter::FragmentProgram {
+            samplers,
+            uniform_data,
+        } => format!(
+            "fragment_program({} samplers, {} uniform bytes)",
+            samplers.len(),
+            uniform_data.len()
+        ),
+    }
+}
+fn color_source(source: &RecordedColorSource) -> String {
+    let transformation = |t: &Option<Matrix>| match t {
+        Some(t) => format!(", {}", matrix(t)),
+        None => String::new(),
+    };
+    match source {
+        RecordedColorSource::LinearGradient {
+            start,
+            end,
+            colors: c,
+            stops,
+            tile_mode,
+            transformation: t,
+        } => format!(
+            "linear({} -> {}, {}, {tile_mode:?}{})",
+            point(start),
+            point(end),
+            colors(c, stops),
+            transformation(t)
+        ),
+        RecordedColorSource::RadialGradient {
+            center,
+            radius,
+            colors: c,
+            stops,
+            tile_mode,
+            transformation: t,
+        } => format!(
+            "radial({} r={radius}, {}, {tile_mode:?}{})",
+            point(center),
+            colors(c, stops),
+            transformation(t)
+        ),
+        RecordedColorSource::ConicalGradient {
+            start_center,
+            start_radius,
+            end_center,
+            end_radius,
+            colors: c,
+            stops,
+            tile_mode,
+            transformation: t,
+        } => format!(
+            "conical({} r={start_radius} -> {} r={end_radius}, {}, {tile_mode:?}{})",
+            point(start_center),
+            point(end_center),
+            colors(c, stops),
+            transformation(t)
+        ),
+        RecordedColorSource::SweepGradient {
+            center,
+            start,
+            end,
+            colors: c,
+            stops,
+            tile_mode,
+            transformation: t,
+        } => format!(
+            "sweep({} {start}..{end}, {}, {tile_mode:?}{})",
+            point(center),
+            colors(c, stops),
+            transformation(t)
+        ),
+        RecordedColorSource::Image {
+            image,
+            horizontal_tile_mode,
+            vertical_tile_mode,
+            sampling,
+            transformation: t,
+        } => format!(
+            "image({}x{}, {horizontal_tile_mode:?} {vertical_tile_mode:?}, {sampling:?}{})",
+            image.width,
+            image.height,
+            transformation(t)
+        ),
+        RecordedColorSource::FragmentProgram {
+            samplers,
+            uniform_data,
+        } => format!(
+            "fragment_program({} samplers, {} uniform bytes)",
+            samplers.len(),
+            uniform_data.len()
+        ),
+    }
+}
+fn paint(p: &RecordedPaint) -> String {
+    let default = RecordedPaint::default();
+    let mut out = format!("color={}", color(&p.color));
+    if p.blend_mode != default.blend_mode {
+        let _ = write!(out, " blend={:?}", p.blend_mode);
+    }
+    if p.draw_style != default.draw_style {
+        let _ = write!(out, " style={:?}", p.draw_style);
+    }
+    if p.stroke_width != default.stroke_width {
+        let _ = write!(out, " stroke_width={}", p.stroke_width);
+    }
+    if p.stroke_cap != default.stroke_cap {
+        let _ = write!(out, " cap={:?}", p.stroke_cap);
+    }
+    if p.stroke_join != default.stroke_join {
+        let _ = write!(out, " join={:?}", p.stroke_join);
+    }
+    if p.stroke_miter != default.stroke_miter {
+        let _ = write!(out, " miter={}", p.stroke_miter);
+    }
+    if let Some(source) = &p.color_source {
+        let _ = write!(out, " source={}", color_source(source));
+    }
+    if let Some(filter) = &p.color_filter {
+        let _ = write!(out, " color_filter={}", color_filter(filter));
+    }
+    if let Some(filter) = &p.image_filter {
+        let _ = write!(out, " image_filter={}", image_filter(filter));
+    }
+    if let Some(filter) = &p.mask_filter {
+        let RecordedMaskFilter::Blur { style, sigma } = filter.as_ref();
+        let _ = write!(out, " mask_filter=blur({style:?}, {sigma})");
+    }
+    out
+}
+
+fn list(
+    f: &mut fmt::Formatter<'_>,
+    list: &RecordedDisplayList,
+    depth: usize,
+    base: Matrix,
+) -> fmt::Result {
+    let mut transforms = vec![base];
+    for op in list.ops() {
+        let transform = *transforms.last().unwrap();
+        let line = if let Some(next) = op.next_transform(&transform, &base) {
+            *transforms.last_mut().unwrap() = next;
+            let call = match op {
+                DisplayOp::Scale { x_scale, y_scale } => format!("scale {x_scale}, {y_scale}"),
+                DisplayOp::Rotate { angle_degrees } => format!("rotate {angle_degrees}°"),
+                DisplayOp::Translate {
+                    x_translation,
+                    y_translation,
+                } => format!("translate {x_translation}, {y_translation}"),
+                DisplayOp::Transform(m) => format!("transform {}", matrix(m)),
+                DisplayOp::SetTransform(m) => format!("set_transform {}", matrix(m)),
+                _ => "reset_transform".to_owned(),
+            };
+            format!("{call} => {}", matrix(&next))
+        } else {
+            match op {
+                DisplayOp::Save => {
+                    transforms.push(transform);
+                    writeln!(
+                        f,
+                        "{:indent$}save",
+                        "",
+                        indent = (depth + transforms.len() - 2) * 2
+                    )?;
+                    continue;
+                }
+                DisplayOp::SaveLayer {
+                    bounds,
+                    paint: p,
+                    backdrop,
+                } => {
+                    let mut line = format!("save_layer {}", rect(bounds));
+                    if let Some(p) = p {
+                        let _ = write!(line, " {}", paint(p));
+                    }
+                    if let Some(backdrop) = backdrop {
+                        let _ = write!(line, " backdrop={}", image_filter(backdrop));
+                    }
+                    transforms.push(transform);
+                    writeln!(
+                        f,
+                        "{:indent$}{line}",
+                        "",
+                        indent = (depth + transforms.len() - 2) * 2
+                    )?;
+                    continue;
+                }
+                DisplayOp::Restore => {
+                    if transforms.len() > 1 {
+                        transforms.pop();
+                    }
+                    "restore".to_owned()
+                }
+                DisplayOp::RestoreToCount(count) => {
+                    transforms.truncate((*count as usize).max(1));
+                    format!("restore_to_count {count}")
+                }
+                DisplayOp::ClipRect { rect: r, op } => format!("clip_rect {op:?} {}", rect(r)),
+                DisplayOp::ClipOval { oval_bounds, op } => {
+                    format!("clip_oval {op:?} {}", rect(oval_bounds))
+                }
+                DisplayOp::ClipRoundedRect {
+                    rect: r,
+                    radii: rr,
+                    op,
+                } => {
+                    format!("clip_rounded_rect {op:?} {} {}", rect(r), radii(rr))
+                }
+                DisplayOp::ClipPath { path: p, op } => format!("clip_path {op:?} {}", path(p)),
+                DisplayOp::DrawPaint { paint: p } => format!("draw_paint {}", paint(p)),
+                DisplayOp::DrawLine { from, to, paint: p } => {
+                    format!("draw_line {} -> {} {}", point(from), point(to), paint(p))
+                }
+                DisplayOp::DrawDashedLine {
+                    from,
+                    to,
+                    on_length,
+                    off_length,
+                    paint: p,
+                } => format!(
+                    "draw_dashed_line {} -> {} dashes={on_length},{off_length} {}",
+                    point(from),
+                    point(to),
+                    paint(p)
+                ),
+                DisplayOp::DrawRect { rect: r, paint: p } => {
+                    format!("draw_rect {} {}", rect(r), paint(p))
+                }
+                DisplayOp::DrawOval {
+                    oval_bounds,
+                    paint: p,
+                } => format!("draw_oval {} {}", rect(oval_bounds), paint(p)),
+                DisplayOp::DrawRoundedRect {
+                    rect: r,
+                    radii: rr,
+                    paint: p,
+                } => format!("draw_rounded_rect {} {} {}", rect(r), radii(rr), paint(p)),
+                DisplayOp::DrawRoundedRectDifference {
+                    outer_rect,
+                    outer_radii,
+                    inner_rect,
+                    inner_radii,
+                    paint: p,
+                } => format!(
+                    "draw_rounded_rect_difference {} {} - {} {} {}",
+                    rect(outer_rect),
+                    radii(outer_radii),
+                    rect(inner_rect),
+                    radii(inner_radii),
+                    paint(p)
+                ),
+                DisplayOp::DrawPath { path: p, paint: pp } => {
+                    format!("draw_path {} {}", path(p), paint(pp))
+                }
+                DisplayOp::DrawDisplayList {
+                    display_list,
+                    opacity,
+                } => {
+                    let indent = (depth + transforms.len() - 1) * 2;
+                    match display_list {
+                        Some(nested) => {
+                            writeln!(f, "{:indent$}draw_display_list opacity={opacity}", "")?;
+                            self::list(f, nested, depth + transforms.len(), transform)?;
+                        }
+                        None => writeln!(
+                            f,
+                            "{:indent$}draw_display_list opacity={opacity} (not recorded)",
+                            ""
+                        )?,
+                    }
+                    continue;
+                }
+                DisplayOp::DrawParagraph {
+                    paragraph,
+                    point: p,
+                } => {
+                    let text: String = paragraph
+                        .items
+                        .iter()
+                        .filter_map(|item| match item {
+                            ParagraphItem::Text(text) => Some(text.as_str()),
+                            _ => None,
+                        })
+                        .collect();
+                    format!(
+                        "draw_paragraph {} {:?} width={} height={}",
+                        point(p),
+                        snippet(&text),
+                        paragraph.width,
+                        paragraph.height
+                    )
+                }
+                DisplayOp::DrawShadow {
+                    path: p,
+                    color: c,
+                    elevation,
+                    occluder_is_transparent,
+                    device_pixel_ratio,
+                } => format!(
+                    "draw_shadow {} color={} elevation={elevation} dpr={device_pixel_ratio}{}",
+                    path(p),
+                    color(c),
+                    if *occluder_is_transparent {
+                        " transparent_occluder"
+                    } else {
+                        ""
+                    }
+                ),
+                DisplayOp::DrawTexture {
+                    texture,
+                    point: p,
+                    sampling,
+                    paint: pp,
+                } => format!(
+                    "draw_texture {}x{} at {} {sampling:?} {}",
+                    texture.width,
+                    texture.height,
+                    point(p),
+                    paint(pp)
+                ),
+                DisplayOp::DrawTextureRect {
+                    texture,
+                    src_rect,
+                    dst_rect,
+                    sampling,
+                    paint: p,
+                } => {
+                    let mut line = format!(
+                        "draw_texture_rect {}x{} {} -> {} {sampling:?}",
+                        texture.width,
+                        texture.height,
+                        rect(src_rect),
+                        rect(dst_rect)
+                    );
+                    if let Some(p) = p {
+                        let _ = write!(line, " {}", paint(p));
+                    }
+                    line
+                }
+                DisplayOp::SetTag(tag) => format!("set_tag {tag}"),
+                // transforms are handled above
+                _ => continue,
+            }
+        };
+        writeln!(
+            f,
+            "{:indent$}{line}",
+            "",
+            indent = (depth + transforms.len() - 1) * 2
+        )?;
+    }
+    Ok(())
+}
+
+/// One op per line, indented by save depth.
+///
+/// Transform ops show the transform they result in, and paints show
+/// their color and any fields that are not the default.
+///
+/// ```rust
+/// # use impellers::*;
+/// let mut recording = RecordedDisplayList::new(None);
+/// recording
+///     .save()
+///     .translate(10.0, 0.0)
+///     .draw_rect(&Rect::from_size(Size::new(5.0, 5.0)), &RecordedPaint::default())
+///     .restore();
+/// assert_eq!(
+///     recording.to_string(),
+///     "save\n  translate 10, 0 => [1 0 0 1 10 0]\n  draw_rect 0,0 5x5 color=#000000ff\nrestore\n"
+/// );
+/// ```
+impl fmt::Display for RecordedDisplayList {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(cull_rect) = self.cull_rect() {
+            writeln!(f, "cull_rect {}", rect(&cull_rect))?;
+        }
+        list(f, self, 0, Matrix::identity())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::{BlendMode, Canvas, ClipOperation, DrawStyle, RecordedParagraph, Size, TileMode};
+
+    #[test]
+    fn dump_is_indented() {
+        let rect = Rect::new(Point::new(1.0, 2.0), Size::new(3.0, 4.0));
+        let mut nested = RecordedDisplayList::new(None);
+        nested
+            .reset_transform()
+            .draw_oval(&rect, &RecordedPaint::default());
+        let paragraph = RecordedParagraph {
+            items: vec![
+                ParagraphItem::Text("Hello, ".to_owned()),
+                ParagraphItem::PopStyle,
+                ParagraphItem::Text("\"world\"".to_owned()),
+            ],
+            width: 100.0,
+            height: 20.0,
+            ..Default::default()
+        };
+        let stroke = RecordedPaint {
+            color: Color::new_srgba(1.0, 0.0, 0.0, 0.5),
+            blend_mode: BlendMode::Multiply,
+            draw_style: DrawStyle::Stroke,
+            stroke_width: 2.0,
+            image_filter: Some(Arc::new(RecordedImageFilter::Blur {
+                x_sigma: 1.0,
+                y_sigma: 2.0,
+                tile_mode: TileMode::Decal,
+            })),
+            ..Default::default()
+        };
+        let mut recording = RecordedDisplayList::new(Some(rect));
+        recording
+            .save_layer(&rect, None, None)
+            .scale(2.0, 2.0)
+            .clip_rect(&rect, ClipOperation::Difference)
+            .save()
+            .draw_rect(&rect, &stroke)
+            .restore()
+            .draw_display_list(&Arc::new(nested), 0.5)
+            .restore()
+            .draw_paragraph(&Arc::new(paragraph), Point::zero());
+        assert_eq!(
+            recording.to_string(),
+            r#"cull_rect 1,2 3x4
+save_layer 1,2 3x4
+  scale 2, 2 => [2 0 0 2 0 0]
+  clip_rect Difference 1,2 3x4
+  save
+    draw_rect 1,2 3x4 color=#ff000080 blend=Multiply style=Stroke stroke_width=2 image_filter=blur(1, 2, Decal)
+  restore
+  draw_display_list opacity=0.5
+    reset_transform => [2 0 0 2 0 0]
+    draw_oval 1,2 3x4 color=#000000ff
+restore
+draw_paragraph 0,0 "Hello, \"world\"" width=100 height=20
+"#
+        );
+    }
+}
@@ -0,0 +1,367 @@
+//! Finding the ops of a recorded display list that draw at a point.
+//!
+//! The point is mapped into the local coordinates of every op, and tested against the
+//! geometry of the op. Clips and save layer bounds are tested the same way. Paint effects
+//! like blurs and image filters are ignored.
+use std::borrow::Cow;
+
+use super::{walk, DisplayOp, Pass, RecordedDisplayList};
+use crate::{
+    ClipOperation, DrawStyle, FillType, Matrix, Point, RecordedPaint, RecordedPath, Rect, Size,
+};
+
+/// An op that draws at the point passed to [RecordedDisplayList::hit_test].
+#[derive(Debug, Clone, PartialEq)]
+pub struct HitResult {
+    /// The tag that was set with [DisplayListBuilder::set_tag](crate::DisplayListBuilder::set_tag)
+    /// when the op was recorded.
+    pub tag: Option<u64>,
+    /// The index of the op in the display list. For ops in nested display lists, the
+    /// index of the [DisplayOp::DrawDisplayList] op comes first, followed by the index
+    /// in the nested list.
+    pub indices: Vec<usize>,
+    /// The op that was hit.
+    pub op: DisplayOp,
+    /// The point in the coordinates that the op was drawn in.
+    pub local_point: Point,
+}
+
+/// Whether the point is inside the clips of a save, and the tag inside of it.
+#[derive(Clone, Copy)]
+struct Level {
+    transform: Matrix,
+    /// Whether the point is outside of the clip.
+    clipped: bool,
+    tag: Option<u64>,
+}
+
+/// The outline of a shape that is drawn or clipped to.
+fn outline(op: &DisplayOp) -> Option<Cow<'_, RecordedPath>> {
+    let mut outline = RecordedPath::default();
+    match op {
+        DisplayOp::ClipRect { rect, .. } | DisplayOp::DrawRect { rect, .. } => {
+            outline.add_rect(rect);
+        }
+        DisplayOp::ClipOval { oval_bounds, .. } | DisplayOp::DrawOval { oval_bounds, .. } => {
+            outline.add_oval(oval_bounds);
+        }
+        DisplayOp::ClipRoundedRect { rect, radii, .. }
+        | DisplayOp::DrawRoundedRect { rect, radii, .. } => {
+            outline.add_rounded_rect(rect, radii);
+        }
+        DisplayOp::DrawRoundedRectDifference {
+            outer_rect,
+            outer_radii,
+            inner_rect,
+            inner_radii,
+            ..
+        } => {
+            outline.set_fill_type(FillType::Odd);
+            outline
+                .add_rounded_rect(outer_rect, outer_radii)
+                .add_rounded_rect(inner_rect, inner_radii);
+        }
+        DisplayOp::ClipPath { path, .. } | DisplayOp::DrawPath { path, .. } => {
+            return Some(Cow::Borrowed(path.as_ref()))
+        }
+        _ => return None,
+    }
+    Some(Cow::Owned(outline))
+}
+/// Whether the stroke of the path with the paint covers the local point.
+fn hit_stroke(
+    path: &RecordedPath,
+    paint: &RecordedPaint,
+    transform: &Matrix,
+    point: Point,
+) -> bool {
+    let width = if paint.stroke_width > 0.0 {
+        paint.stroke_width
+    } else {
+        // hairlines are a pixel wide, whatever the transform
+        let scale = (transform.m11 * transform.m22 - transform.m12 * transform.m21)
+            .abs()
+            .sqrt();
+        1.0 / scale
+    };
+    path.stroke_to_path(
+        width,
+        paint.stroke_cap,
+        paint.stroke_join,
+        paint.stroke_miter,
+    )
+    .contains(point)
+}
+
+struct HitTester {
+    point: Point,
+    /// The indices of the nested display lists that are being tested.
+    indices: Vec<usize>,
+    hits: Vec<HitResult>,
+}
+impl HitTester {
+    fn list(&mut self, list: &RecordedDisplayList, base: Level) {
+        walk(self, list, base);
+    }
+    /// Whether a draw op covers the local point.
+    fn hits_op(op: &DisplayOp, transform: &Matrix, local: Point) -> bool {
+        match op {
+            // these fill the clip
+            DisplayOp::DrawPaint { .. } | DisplayOp::DrawDisplayList { .. } => true,
+            DisplayOp::DrawLine { from, to, paint } => {
+                let mut line = RecordedPath::default();
+                line.move_to(*from).line_to(*to);
+                hit_stroke(&line, paint, transform, local)
+            }
+            DisplayOp::DrawDashedLine {
+                from,
+                to,
+                on_length,
+                off_length,
+                paint,
+            } => {
+                let mut line = RecordedPath::default();
+                line.move_to(*from).line_to(*to);
+                let dashes = line.dash(&[*on_length, *off_length], 0.0);
+                hit_stroke(&dashes, paint, transform, local)
+            }
+            DisplayOp::DrawParagraph { paragraph, point } => {
+                let width = paragraph.width.max(paragraph.longest_line_width);
+                Rect::new(*point, Size::new(width, paragraph.height)).contains(local)
+            }
+            DisplayOp::DrawTexture { texture, point, .. } => {
+                let size = Size::new(texture.width as f32, texture.height as f32);
+                Rect::new(*point, size).contains(local)
+            }
+            DisplayOp::DrawTextureRect { dst_rect, .. } => dst_rect.contains(local),
+            op => {
+                let (Some(outline), Some(paint)) = (outline(op), op.paint()) else {
+                    // shadows are decorations of what casts them
+                    return false;
+                };
+                let fill = || outline.contains(local);
+                let stroke = || hit_stroke(&outline, paint, transform, local);
+                match paint.draw_style {
+                    DrawStyle::Fill => fill(),
+                    DrawStyle::Stroke => stroke(),
+                    DrawStyle::StrokeAndFill => fill() || stroke(),
+                }
+            }
+        }
+    }
+}
+
+impl Pass for HitTester {
+    type Level = Level;
+    fn transform(level: &mut Level) -> &mut Matrix {
+        &mut level.transform
+    }
+    fn save(&mut self, top: &Level, op: &DisplayOp) -> Level {
+        let DisplayOp::SaveLayer { bounds, .. } = op else {
+            return *top;
+        };
+        let inside = top
+            .transform
+            .inverse()
+            .and_then(|inverse| inverse.transform_point2d(self.point))
+            .is_some_and(|local| bounds.contains(local));
+        Level {
+            clipped: top.clipped || !inside,
+            ..*top
+        }
+    }
+    fn restore(&mut self, _level: Level, _parent: &mut Level) {}
+    fn op(&mut self, top: &mut Level, index: usize, op: &DisplayOp) {
+        let local = top
+            .transform
+            .inverse()
+            .and_then(|inverse| inverse.transform_point2d(self.point));
+        let hit = match op {
+            DisplayOp::SetTag(tag) => {
+                top.tag = Some(*tag);
+                return;
+            }
+            DisplayOp::ClipRect { op: clip_op, .. }
+            | DisplayOp::ClipOval { op: clip_op, .. }
+            | DisplayOp::ClipRoundedRect { op: clip_op, .. }
+            | DisplayOp::ClipPath { op: clip_op, .. } => {
+                let inside = local.is_some_and(|local| outline(op).unwrap().contains(local));
+                let outside_clip = match clip_op {
+                    ClipOperation::Intersect => !inside,
+                    ClipOperation::Difference => inside,
+                };
+                top.clipped |= outside_clip;
+                return;
+            }
+            _ if top.clipped => return,
+            DisplayOp::DrawDisplayList {
+                display_list: Some(nested),
+                ..
+            } => {
+                self.indices.push(index);
+                self.list(nested, *top);
+                self.indices.pop();
+                return;
+            }
+            _ => {
+                let Some(local) = local else {
+                    return;
+                };
+                let hit = Self::hits_op(op, &top.transform, local);
+                hit.then_some(local)
+            }
+        };
+        if let Some(local_point) = hit {
+            let mut indices = self.indices.clone();
+            indices.push(index);
+            self.hits.push(HitResult {
+                tag: top.tag,
+                indices,
+                op: op.clone(),
+                local_point,
+            });
+        }
+    }
+}
+
+impl RecordedDisplayList {
+    /// Find the ops that draw at a point, topmost first.
+    ///
+    /// Each op is tested with its own geometry, in its own coordinates, inside its clips:
+    /// rects, ovals, rounded rects and paths are tested by their fill or stroke,
+    /// paragraphs by their layout box and textures by where they are drawn. Ops in
+    /// nested display lists are tested too. Ops like [DisplayOp::DrawPaint] fill the clip,
+    /// so they hit everywhere inside of it, and shadows are never hit.
+    ///
+    /// Strokes are tested with the caps and joins of their paint, like
+    /// [RecordedPath::stroke_to_path] outlines them, and blurs and image filters are ignored.
+    ///
+    /// Use [DisplayListBuilder::set_tag](crate::DisplayListBuilder::set_tag) to find out
+    /// what an op was drawn for.
+    ///
+    /// ```rust
+    /// # use impellers::*;
+    /// let mut list = RecordedDisplayList::new(None);
+    /// list.push(DisplayOp::SetTag(1))
+    ///     .push(DisplayOp::DrawOval {
+    ///         oval_bounds: Rect::new(Point::zero(), Size::new(100.0, 100.0)),
+    ///         paint: RecordedPaint::default(),
+    ///     });
+    /// let hits = list.hit_test(Point::new(50.0, 50.0));
+    /// assert_eq!(hits[0].tag, Some(1));
+    /// // the corner of the bounds is outside of the oval
+    /// assert!(list.hit_test(Point::new(5.0, 5.0)).is_empty());
+    /// ```
+    pub fn hit_test(&self, point: Point) -> Vec<HitResult> {
+        let mut tester = HitTester {
+            point,
+            indices: vec![],
+            hits: vec![],
+        };
+        let base = Level {
+            transform: Matrix::identity(),
+            clipped: false,
+            tag: None,
+        };
+        tester.list(self, base);
+        tester.hits.reverse();
+        tester.hits
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::{RoundingRadii, StrokeCap};
+
+    #[test]
+    fn hit_testing() {
+        let square = Rect::new(Point::zero(), Size::new(100.0, 100.0));
+        let stroke = RecordedPaint {
+            draw_style: DrawStyle::Stroke,
+            stroke_width: 10.0,
+            ..Default::default()
+        };
+        let mut nested = RecordedDisplayList::new(None);
+        nested.push(DisplayOp::DrawRoundedRect {
+            rect: square,
+            radii: RoundingRadii {
+                top_left: Point::new(50.0, 50.0),
+                ..Default::default()
+            },
+            paint: RecordedPaint::default(),
+        });
+        let mut list = RecordedDisplayList::new(None);
+        list.push(DisplayOp::SetTag(1))
+            .push(DisplayOp::DrawRect {
+                rect: square,
+                paint: stroke.clone(),
+            })
+            .push(DisplayOp::Save)
+            .push(DisplayOp::SetTag(2))
+            .push(DisplayOp::Translate {
+                x_translation: 100.0,
+                y_translation: 0.0,
+            })
+            .push(DisplayOp::Scale {
+                x_scale: 2.0,
+                y_scale: 2.0,
+            })
+            .push(DisplayOp::DrawDisplayList {
+                display_list: Some(Arc::new(nested)),
+                opacity: 1.0,
+            })
+            .push(DisplayOp::Restore)
+            .push(DisplayOp::ClipOval {
+                oval_bounds: square,
+                op: ClipOperation::Difference,
+            })
+            .push(DisplayOp::DrawLine {
+                from: Point::new(0.0, 50.0),
+                to: Point::new(300.0, 50.0),
+                paint: RecordedPaint {
+                    stroke_cap: StrokeCap::Round,
+                    stroke_width: 8.0,
+                    ..Default::default()
+                },
+            });
+
+        // the stroke of the rect, but not the inside
+        let hits = list.hit_test(Point::new(102.0, 104.0));
+        assert_eq!(hits.len(), 2);
+        assert_eq!(hits[0].tag, Some(2));
+        assert_eq!(hits[0].indices, [6, 0]);
+        assert_eq!(hits[0].local_point, Point::new(1.0, 52.0));
+        assert_eq!(hits[1].tag, Some(1));
+        assert_eq!(hits[1].indices, [1]);
+        assert!(list.hit_test(Point::new(50.0, 80.0)).is_empty());
+        // the stroke has miter joins, so its corners are square
+        let hits = list.hit_test(Point::new(-4.5, -4.5));
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].indices, [1]);
+        // the rounded corner of the nested rect
+        assert!(list.hit_test(Point::new(110.0, 10.0)).is_empty());
+        // the tag is restored after the nested list
+        let hits = list.hit_test(Point::new(150.0, 50.0));
+        assert_eq!(hits.len(), 2);
+        assert_eq!(hits[0].tag, Some(1));
+        assert_eq!(hits[0].indices, [9]);
+        // the line is clipped out by the oval, but not its round cap
+        assert!(list.hit_test(Point::new(50.0, 50.5)).is_empty());
+        let hits = list.hit_test(Point::new(-3.0, 50.0));
+        assert_eq!(hits.len(), 2);
+        assert!(matches!(hits[0].op, DisplayOp::DrawLine { .. }));
+
+        // butt caps end where the line does
+        let mut line = RecordedDisplayList::new(None);
+        line.push(DisplayOp::DrawLine {
+            from: Point::zero(),
+            to: Point::new(100.0, 0.0),
+            paint: stroke,
+        });
+        assert_eq!(line.hit_test(Point::new(99.0, 4.0)).len(), 1);
+        assert!(line.hit_test(Point::new(101.0, 0.0)).is_empty());
+    }
+}
@@ -0,0 +1,1260 @@
+//! Drawing recorded display lists on the CPU, as a reference for headless tests.
+//!
+//! Everything is drawn in premultiplied floats at full canvas size. Geometry is turned
+//! into a coverage mask by sampling [SUBSAMPLES] scanlines per pixel row, with exact
+//! horizontal coverage on each scanline. Clips are coverage masks too.
+use std::rc::Rc;
+
+use super::{shadow_sigma, walk, DisplayOp, Pass, RecordedDisplayList};
+use crate::paint::blend::{blend, Rgba};
+use crate::paint::sample_gradient;
+use crate::{
+    BlendMode, BlurStyle, ClipOperation, Color, DrawStyle, FillType, Matrix, Point,
+    RecordedColorFilter, RecordedColorSource, RecordedImageFilter, RecordedMaskFilter,
+    RecordedPaint, RecordedPath, RecordedTexture, Rect, Size, TextureSampling, TileMode,
+};
+
+/// The number of scanlines sampled per pixel row.
+const SUBSAMPLES: usize = 16;
+/// The flattening tolerance, in pixels.
+const TOLERANCE: f32 = 0.1;
+
+/// The clip mask of a save, and the offscreen layer it started.
+struct Save {
+    transform: Matrix,
+    clip: Option<Rc<Vec<f32>>>,
+    layer: Option<Layer>,
+}
+/// An offscreen buffer started by a save layer.
+struct Layer {
+    /// The pixels that the layer is composited onto when it's restored.
+    parent: Vec<Rgba>,
+    paint: Option<RecordedPaint>,
+    /// The clip when the layer was saved, intersected with its bounds.
+    mask: Rc<Vec<f32>>,
+    transform: Matrix,
+}
+
+/// Where the colors of a draw come from.
+enum Source<'a> {
+    Solid(Rgba),
+    Gradient {
+        source: &'a RecordedColorSource,
+        /// Maps device coordinates to the coordinates of the gradient.
+        inverse: Matrix,
+    },
+    Image {
+        image: Image,
+        /// Maps device coordinates to texels.
+        inverse: Matrix,
+        tile_modes: (TileMode, TileMode),
+        sampling: TextureSampling,
+        /// Texels outside of this area are never sampled.
+        area: Rect,
+    },
+}
+/// The colors of a draw, before they are blended.
+struct Shader<'a> {
+    source: Source<'a>,
+    alpha: f32,
+    color_filter: Option<&'a RecordedColorFilter>,
+}
+impl Shader<'_> {
+    fn shade(&self, x: usize, y: usize) -> Rgba {
+        let device = Point::new(x as f32 + 0.5, y as f32 + 0.5);
+        let color = match &self.source {
+            Source::Solid(color) => *color,
+            Source::Gradient { source, inverse } => inverse
+                .transform_point2d(device)
+                .and_then(|p| gradient(source, p))
+                .map_or([0.0; 4], premultiply),
+            Source::Image {
+                image,
+                inverse,
+                tile_modes,
+                sampling,
+                area,
+            } => match inverse.transform_point2d(device) {
+                Some(p) => image.sample(p, *tile_modes, *sampling, area),
+                None => [0.0; 4],
+            },
+        };
+        let color = match self.color_filter {
+            Some(filter) => color_filter(filter, color),
+            None => color,
+        };
+        color.map(|c| c * self.alpha)
+    }
+}
+
+/// Premultiplied pixels.
+struct Image {
+    width: usize,
+    height: usize,
+    pixels: Vec<Rgba>,
+}
+impl Image {
+    fn from_texture(texture: &RecordedTexture) -> Self {
+        let pixels = match &texture.pixels {
+            Some(pixels) => pixels
+                .chunks_exact(4)
+                .map(|p| {
+                    let a = p[3] as f32 / 255.0;
+                    [
+                        p[0] as f32 / 255.0 * a,
+                        p[1] as f32 / 255.0 * a,
+                        p[2] as f32 / 255.0 * a,
+                        a,
+                    ]
+                })
+                .collect(),
+            None => vec![[0.0; 4]; texture.width as usize * texture.height as usize],
+        };
+        Image {
+            width: texture.width as usize,
+            height: texture.height as usize,
+            pixels,
+        }
+    }
+    fn sample(
+        &self,
+        p: Point,
+        tile_modes: (TileMode, TileMode),
+        sampling: TextureSampling,
+        area: &Rect,
+    ) -> Rgba {
+        let texel = |i: i64, j: i64| -> Rgba {
+            let x = tile(
+                i,
+                area.min_x().floor() as i64,
+                area.max_x().ceil() as i64,
+                tile_modes.0,
+            );
+            let y = tile(
+                j,
+                area.min_y().floor() as i64,
+                area.max_y().ceil() as i64,
+                tile_modes.1,
+            );
+            match (x, y) {
+                (Some(x), Some(y)) if x < self.width && y < self.height => {
+                    self.pixels[y * self.width + x]
+                }
+                _ => [0.0; 4],
+            }
+        };
+        match sampling {
+            TextureSampling::NearestNeighbor => texel(p.x.floor() as i64, p.y.floor() as i64),
+            TextureSampling::Linear => {
+                let (x, y) = (p.x - 0.5, p.y - 0.5);
+                let (i, j) = (x.floor(), y.floor());
+                let (fx, fy) = (x - i, y - j);
+                let (i, j) = (i as i64, j as i64);
+                let [a, b, c, d] = [
+                    texel(i, j),
+                    texel(i + 1, j),
+                    texel(i, j + 1),
+                    texel(i + 1, j + 1),
+                ];
+                std::array::from_fn(|k| {
+                    (a[k] * (1.0 - fx) + b[k] * fx) * (1.0 - fy)
+                        + (c[k] * (1.0 - fx) + d[k] * fx) * fy
+                })
+            }
+        }
+    }
+}
+/// Map a texel index into `start..end` with the tile mode. `None` if it's outside a decal.
+fn tile(i: i64, start: i64, end: i64, mode: TileMode) -> Option<usize> {
+    let len = end - start;
+    if len <= 0 {
+        return None;
+    }
+    let i = i - start;
+    let i = match mode {
+        TileMode::Clamp => i.clamp(0, len - 1),
+        TileMode::Repeat => i.rem_euclid(len),
+        TileMode::Mirror => {
+            let i = i.rem_euclid(2 * len);
+            if i < len {
+                i
+            } else {
+                2 * len - 1 - i
+            }
+        }
+        TileMode::Decal if !(0..len).contains(&i) => return None,
+        TileMode::Decal => i,
+    };
+    usize::try_from(i + start).ok()
+}
+
+/// The unpremultiplied color of a gradient at a point in gradient coordinates.
+fn gradient(source: &RecordedColorSource, p: Point) -> Option<Color> {
+    match source {
+        RecordedColorSource::LinearGradient {
+            start,
+            end,
+            colors,
+            stops,
+            tile_mode,
+            ..
+        } => {
+            let d = *end - *start;
+            let length = d.square_length();
+            let t = if length > 0.0 {
+                (p - *start).dot(d) / length
+            } else {
+                0.0
+            };
+            sample_gradient(colors, stops, *tile_mode, t)
+        }
+        RecordedColorSource::RadialGradient {
+            center,
+            radius,
+            colors,
+            stops,
+            tile_mode,
+            ..
+        } => {
+            if *radius <= 0.0 {
+                return None;
+            }
+            sample_gradient(colors, stops, *tile_mode, (p - *center).length() / radius)
+        }
+        RecordedColorSource::ConicalGradient {
+            start_center,
+            start_radius,
+            end_center,
+            end_radius,
+            colors,
+            stops,
+            tile_mode,
+            ..
+        } => {
+            // the largest t where p is on the circle interpolated between the two circles
+            let cd = *end_center - *start_center;
+            let pd = p - *start_center;
+            let dr = end_radius - start_radius;
+            let a = cd.dot(cd) - dr * dr;
+            let b = pd.dot(cd) + start_radius * dr;
+            let c = pd.dot(pd) - start_radius * start_radius;
+            let radius_at = |t: f32| start_radius + t * dr;
+            let t = if a.abs() < 1e-6 {
+                if b == 0.0 {
+                    return None;
+                }
+                Some(c / (2.0 * b))
+            } else {
+                let discriminant = b * b - a * c;
+                if discriminant < 0.0 {
+                    return None;
+                }
+                let sqrt = discriminant.sqrt();
+                let (t0, t1) = ((b + sqrt) / a, (b - sqrt) / a);
+                let (t0, t1) = (t0.max(t1), t0.min(t1));
+                [t0, t1].into_iter().find(|t| radius_at(*t) >= 0.0)
+            }?;
+            if radius_at(t) < 0.0 {
+                return None;
+            }
+            sample_gradient(colors, stops, *tile_mode, t)
+        }
+        RecordedColorSource::SweepGradient {
+            center,
+            start,
+            end,
+            colors,
+            stops,
+            tile_mode,
+            ..
+        } => {
+            let d = p - *center;
+            let angle = d.y.atan2(d.x).to_degrees().rem_euclid(360.0);
+            if end == start {
+                return None;
+            }
+            sample_gradient(colors, stops, *tile_mode, (angle - start) / (end - start))
+        }
+        RecordedColorSource::Image { .. } | RecordedColorSource::FragmentProgram { .. } => None,
+    }
+}
+
+fn premultiply(color: Color) -> Rgba {
+    let a = color.alpha.clamp(0.0, 1.0);
+    [color.red * a, color.green * a, color.blue * a, a]
+}
+fn unpremultiply(color: Rgba) -> Rgba {
+    let a = color[3];
+    if a > 0.0 {
+        [color[0] / a, color[1] / a, color[2] / a, a]
+    } else {
+        [0.0; 4]
+    }
+}
+fn color_filter(filter: &RecordedColorFilter, color: Rgba) -> Rgba {
+    match filter {
+        RecordedColorFilter::Matrix(matrix) => {
+            let [red, green, blue, alpha] = unpremultiply(color);
+            premultiply(matrix.apply(Color::new_srgba(red, green, blue, alpha)))
+        }
+        RecordedColorFilter::Blend {
+            color: src,
+            blend_mode,
+        } => blend(*blend_mode, premultiply(*src), color),
+    }
+}
+/// How much the transform scales x and y, for scaling blur sigmas and radii.
+fn scale(transform: &Matrix) -> (f32, f32) {
+    (
+        transform.m11.hypot(transform.m12),
+        transform.m21.hypot(transform.m22),
+    )
+}
+
+/// A separable gaussian blur, treating everything outside as transparent.
+fn blur<const N: usize>(data: &mut [[f32; N]], width: usize, height: usize, sigma: (f32, f32)) {
+    let kernel = |sigma: f32| {
+        // wider kernels can't reach any more pixels
+        let radius = ((sigma * 3.0).ceil() as usize).min(width.max(height));
+        let weights: Vec<f32> = (0..=radius * 2)
+            .map(|i| {
+                let x = i as f32 - radius as f32;
+                (-x * x / (2.0 * sigma * sigma)).exp()
+            })
+            .collect();
+        let sum: f32 = weights.iter().sum();
+        weights.into_iter().map(|w| w / sum).collect::<Vec<_>>()
+    };
+    let pass = |data: &mut [[f32; N]], sigma: f32, horizontal: bool| {
+        if !sigma.is_finite() || sigma <= 0.0 {
+            return;
+        }
+        let weights = kernel(sigma);
+        let radius = (weights.len() / 2) as isize;
+        let (lines, len) = if horizontal {
+            (height, width)
+        } else {
+            (width, height)
+        };
+        let index = |line: usize, i: usize| {
+            if horizontal {
+                line * width + i
+            } else {
+                i * width + line
+            }
+        };
+        let mut buffer = vec![[0.0; N]; len];
+        for line in 0..lines {
+            for (i, out) in buffer.iter_mut().enumerate() {
+                *out = [0.0; N];
+                for (k, w) in weights.iter().enumerate() {
+                    let j = i as isize + k as isize - radius;
+                    if j >= 0 && (j as usize) < len {
+                        let v = data[index(line, j as usize)];
+                        for c in 0..N {
+                            out[c] += v[c] * w;
+                        }
+                    }
+                }
+            }
+            for (i, v) in buffer.iter().enumerate() {
+                data[index(line, i)] = *v;
+            }
+        }
+    };
+    pass(data, sigma.0, true);
+    pass(data, sigma.1, false);
+}
+/// Dilate (max) or erode (min) every channel over a rectangle around each pixel.
+fn morphology(data: &mut [Rgba], width: usize, height: usize, radius: (f32, f32), dilate: bool) {
+    let pass = |data: &mut [Rgba], radius: f32, horizontal: bool| {
+        let (lines, len) = if horizontal {
+            (height, width)
+        } else {
+            (width, height)
+        };
+        let radius = (radius.round() as isize).min(len as isize);
+        if radius <= 0 {
+            return;
+        }
+        let index = |line: usize, i: usize| {
+            if horizontal {
+                line * width + i
+            } else {
+                i * width + line
+            }
+        };
+        let mut buffer = vec![[0.0; 4]; len];
+        for line in 0..lines {
+            for (i, out) in buffer.iter_mut().enumerate() {
+                let from = (i as isize - radius).max(0) as usize;
+                let to = (i as isize + radius).min(len as isize - 1) as usize;
+                *out = data[index(line, from)];
+                for j in from + 1..=to {
+                    let v = data[index(line, j)];
+                    for c in 0..4 {
+                        out[c] = if dilate {
+                            out[c].max(v[c])
+                        } else {
+                            out[c].min(v[c])
+                        };
+                    }
+                }
+            }
+            for (i, v) in buffer.iter().enumerate() {
+                data[index(line, i)] = *v;
+            }
+        }
+    };
+    pass(data, radius.0, true);
+    pass(data, radius.1, false);
+}
+
+struct Rasterizer {
+    width: usize,
+    height: usize,
+    pixels: Vec<Rgba>,
+}
+impl Rasterizer {
+    fn transparent(&self) -> Vec<Rgba> {
+        vec![[0.0; 4]; self.width * self.height]
+    }
+    /// The coverage of a path in device coordinates, using its fill type.
+    fn coverage(&self, path: &RecordedPath) -> Vec<f32> {
+        let (width, height) = (self.width, self.height);
+        let mut out = vec![0.0; width * height];
+        let mut edges = vec![];
+        for polyline in path.flatten(TOLERANCE) {
+            let points = &polyline.points;
+            // filling closes every contour
+            for (i, a) in points.iter().enumerate() {
+                let b = points[(i + 1) % points.len()];
+                if a.y != b.y
+                    && a.x.is_finite()
+                    && a.y.is_finite()
+                    && b.x.is_finite()
+                    && b.y.is_finite()
+                {
+                    edges.push((*a, b));
+                }
+            }
+        }
+        if edges.is_empty() {
+            return out;
+        }
+        let top = edges
+            .iter()
+            .map(|(a, b)| a.y.min(b.y))
+            .fold(f32::INFINITY, f32::min);
+        let bottom = edges
+            .iter()
+            .map(|(a, b)| a.y.max(b.y))
+            .fold(f32::NEG_INFINITY, f32::max);
+        let first_row = top.floor().clamp(0.0, height as f32) as usize;
+        let last_row = bottom.ceil().clamp(0.0, height as f32) as usize;
+        let weight = 1.0 / SUBSAMPLES as f32;
+        let odd = path.fill_type() == FillType::Odd;
+        let mut crossings: Vec<(f32, i32)> = vec![];
+        for row in first_row..last_row {
+            let line = &mut out[row * width..(row + 1) * width];
+            for sample in 0..SUBSAMPLES {
+                let y = row as f32 + (sample as f32 + 0.5) * weight;
+                crossings.clear();
+                for (a, b) in &edges {
+                    let (top, bottom, direction) = if a.y < b.y { (a, b, 1) } else { (b, a, -1) };
+                    if y < top.y || y >= bottom.y {
+                        continue;
+                    }
+                    let x = top.x + (y - top.y) * (bottom.x - top.x) / (bottom.y - top.y);
+                    crossings.push((x, direction));
+                }
+                crossings.sort_by(|a, b| a.0.total_cmp(&b.0));
+                let mut winding = 0;
+                for i in 0..crossings.len().saturating_sub(1) {
+                    winding += crossings[i].1;
+                    let inside = if odd { winding % 2 != 0 } else { winding != 0 };
+                    if inside {
+                        add_span(line, crossings[i].0, crossings[i + 1].0, weight);
+                    }
+                }
+            }
+        }
+        for c in &mut out {
+            *c = c.min(1.0);
+        }
+        out
+    }
+    /// The clip after clipping with a local path.
+    fn clip(
+        &self,
+        clip: Option<&[f32]>,
+        path: &RecordedPath,
+        transform: &Matrix,
+        op: ClipOperation,
+    ) -> Vec<f32> {
+        let mut coverage = self.coverage(&path.transformed(transform));
+        if op == ClipOperation::Difference {
+            for c in &mut coverage {
+                *c = 1.0 - *c;
+            }
+        }
+        if let Some(clip) = clip {
+            for (c, clip) in coverage.iter_mut().zip(clip) {
+                *c *= clip;
+            }
+        }
+        coverage
+    }
+    fn list(&mut self, list: &RecordedDisplayList, base: Matrix, clip: Option<Rc<Vec<f32>>>) {
+        let base = Save {
+            transform: base,
+            clip,
+            layer: None,
+        };
+        walk(self, list, base);
+    }
+    fn push_clip(&self, top: &mut Save, path: &RecordedPath, op: ClipOperation) {
+        let clip = self.clip(
+            top.clip.as_deref().map(Vec::as_slice),
+            path,
+            &top.transform,
+            op,
+        );
+        top.clip = Some(Rc::new(clip));
+    }
+    /// Blend premultiplied pixels onto the canvas.
+    fn composite(
+        &mut self,
+        content: Vec<Rgba>,
+        alpha: f32,
+        filter: Option<&RecordedColorFilter>,
+        blend_mode: BlendMode,
+        mask: Option<&[f32]>,
+    ) {
+        for (i, (dst, src)) in self.pixels.iter_mut().zip(content).enumerate() {
+            let coverage = mask.map_or(1.0, |mask| mask[i]);
+            if coverage <= 0.0 {
+                continue;
+            }
+            let src = match filter {
+                Some(filter) => color_filter(filter, src),
+                None => src,
+            };
+            let blended = blend(blend_mode, src.map(|c| c * alpha), *dst);
+            *dst = std::array::from_fn(|c| dst[c] + (blended[c] - dst[c]) * coverage);
+        }
+    }
+    fn image_filter(
+        &self,
+        pixels: &mut Vec<Rgba>,
+        filter: &RecordedImageFilter,
+        transform: &Matrix,
+    ) {
+        let (width, height) = (self.width, self.height);
+        let (sx, sy) = scale(transform);
+        match filter {
+            RecordedImageFilter::Blur {
+                x_sigma, y_sigma, ..
+            } => blur(pixels, width, height, (x_sigma * sx, y_sigma * sy)),
+            RecordedImageFilter::Dilate { x_radius, y_radius } => {
+                morphology(pixels, width, height, (x_radius * sx, y_radius * sy), true)
+            }
+            RecordedImageFilter::Erode { x_radius, y_radius } => {
+                morphology(pixels, width, height, (x_radius * sx, y_radius * sy), false)
+            }
+            RecordedImageFilter::Matrix { matrix, sampling } => {
+                // the matrix applies in local coordinates
+                let Some(inverse) = transform
+                    .inverse()
+                    .map(|inverse| inverse.then(matrix).then(transform))
+                    .and_then(|device| device.inverse())
+                else {
+                    pixels.fill([0.0; 4]);
+                    return;
+                };
+                let image = Image {
+                    width,
+                    height,
+                    pixels: std::mem::take(pixels),
+                };
+                let area = Rect::from_size(Size::new(width as f32, height as f32));
+                *pixels = (0..width * height)
+                    .map(|i| {
+                        let device = Point::new((i % width) as f32 + 0.5, (i / width) as f32 + 0.5);
+                        match inverse.transform_point2d(device) {
+                            Some(p) => image.sample(
+                                p,
+                                (TileMode::Decal, TileMode::Decal),
+                                *sampling,
+                                &area,
+                            ),
+                            None => [0.0; 4],
+                        }
+                    })
+                    .collect();
+            }
+            RecordedImageFilter::Compose { outer, inner } => {
+                self.image_filter(pixels, inner, transform);
+                self.image_filter(pixels, outer, transform);
+            }
+            // fragment programs are opaque
+            RecordedImageFilter::FragmentProgram { .. } => {}
+        }
+    }
+    /// The coverage of the stroke of a local path.
+    fn stroke_coverage(
+        &self,
+        path: &RecordedPath,
+        paint: &RecordedPaint,
+        transform: &Matrix,
+    ) -> Vec<f32> {
+        let outline = if paint.stroke_width > 0.0 {
+            path.stroke_to_path(
+                paint.stroke_width,
+                paint.stroke_cap,
+                paint.stroke_join,
+                paint.stroke_miter,
+            )
+            .transformed(transform)
+        } else {
+            // hairlines are a pixel wide, whatever the transform
+            path.transformed(transform).stroke_to_path(
+                1.0,
+                paint.stroke_cap,
+                paint.stroke_join,
+                paint.stroke_miter,
+            )
+        };
+        self.coverage(&outline)
+    }
+    /// Draw a local path with the draw style of the paint.
+    fn shape(
+        &mut self,
+        path: &RecordedPath,
+        paint: &RecordedPaint,
+        transform: &Matrix,
+        clip: Option<&Vec<f32>>,
+    ) {
+        let mut coverage = match paint.draw_style {
+            DrawStyle::Fill | DrawStyle::StrokeAndFill => {
+                self.coverage(&path.transformed(transform))
+            }
+            DrawStyle::Stroke => vec![0.0; self.width * self.height],
+        };
+        if paint.draw_style != DrawStyle::Fill {
+            let stroke = self.stroke_coverage(path, paint, transform);
+            for (c, s) in coverage.iter_mut().zip(stroke) {
+                *c = c.max(s);
+            }
+        }
+        self.fill(coverage, paint, transform, clip);
+    }
+    /// The coverage and shader for drawing part of a texture into a local rectangle.
+    fn texture<'a>(
+        &self,
+        texture: &RecordedTexture,
+        src_rect: &Rect,
+        dst_rect: &Rect,
+        sampling: TextureSampling,
+        paint: &'a RecordedPaint,
+        transform: &Matrix,
+    ) -> Option<(Vec<f32>, Shader<'a>)> {
+        if dst_rect.is_empty() || src_rect.is_empty() {
+            return None;
+        }
+        let local_to_texel = Matrix::translation(-dst_rect.min_x(), -dst_rect.min_y(), 0.0)
+            .then_scale(
+                src_rect.width() / dst_rect.width(),
+                src_rect.height() / dst_rect.height(),
+                1.0,
+            )
+            .then_translate(euclid::vec3(src_rect.min_x(), src_rect.min_y(), 0.0));
+        let inverse = transform.inverse()?;
+        let shader = Shader {
+            source: Source::Image {
+                image: Image::from_texture(texture),
+                inverse: inverse.then(&local_to_texel),
+                tile_modes: (TileMode::Clamp, TileMode::Clamp),
+                sampling,
+                area: *src_rect,
+            },
+            alpha: paint.color.alpha,
+            color_filter: paint.color_filter.as_deref(),
+        };
+        let mut path = RecordedPath::default();
+        path.add_rect(dst_rect);
+        Some((self.coverage(&path.transformed(transform)), shader))
+    }
+    /// Fill coverage with the color source of the paint.
+    fn fill(
+        &mut self,
+        coverage: Vec<f32>,
+        paint: &RecordedPaint,
+        transform: &Matrix,
+        clip: Option<&Vec<f32>>,
+    ) {
+        let source = match paint.color_source.as_deref() {
+            Some(
+                source @ (RecordedColorSource::LinearGradient { transformation, .. }
+                | RecordedColorSource::RadialGradient { transformation, .. }
+                | RecordedColorSource::ConicalGradient { transformation, .. }
+                | RecordedColorSource::SweepGradient { transformation, .. }),
+            ) => {
+                let forward = transformation
+                    .unwrap_or_else(Matrix::identity)
+                    .then(transform);
+                match forward.inverse() {
+                    Some(inverse) => Source::Gradient { source, inverse },
+                    None => return,
+                }
+            }
+            Some(RecordedColorSource::Image {
+                image,
+                horizontal_tile_mode,
+                vertical_tile_mode,
+                sampling,
+                transformation,
+            }) => {
+                let forward = transformation
+                    .unwrap_or_else(Matrix::identity)
+                    .then(transform);
+                let Some(inverse) = forward.inverse() else {
+                    return;
+                };
+                Source::Image {
+                    image: Image::from_texture(image),
+                    inverse,
+                    tile_modes: (*horizontal_tile_mode, *vertical_tile_mode),
+                    sampling: *sampling,
+                    area: Rect::from_size(Size::new(image.width as f32, image.height as f32)),
+                }
+            }
+            // fragment programs are opaque, so they draw with the paint color
+            Some(RecordedColorSource::FragmentProgram { .. }) | None => {
+                let alpha = paint.color.alpha;
+                let shader = Shader {
+                    source: Source::Solid(premultiply(Color {
+                        alpha: 1.0,
+                        ..paint.color
+                    })),
+                    alpha,
+                    color_filter: paint.color_filter.as_deref(),
+                };
+                return self.shade(coverage, &shader, paint, transform, clip);
+            }
+        };
+        let shader = Shader {
+            source,
+            alpha: paint.color.alpha,
+            color_filter: paint.color_filter.as_deref(),
+        };
+        self.shade(coverage, &shader, paint, transform, clip);
+    }
+    /// Apply the mask filter, image filter and blend mode of the paint while filling.
+    fn shade(
+        &mut self,
+        mut coverage: Vec<f32>,
+        shader: &Shader,
+        paint: &RecordedPaint,
+        transform: &Matrix,
+        clip: Option<&Vec<f32>>,
+    ) {
+        let (width, height) = (self.width, self.height);
+        if let Some(filter) = &paint.mask_filter {
+            let RecordedMaskFilter::Blur { style, sigma } = filter.as_ref();
+            let (sx, sy) = scale(transform);
+            let mut blurred: Vec<[f32; 1]> = coverage.iter().map(|c| [*c]).collect();
+            blur(&mut blurred, width, height, (sigma * sx, sigma * sy));
+            for (c, [b]) in coverage.iter_mut().zip(blurred) {
+                *c = match style {
+                    BlurStyle::Normal => b,
+                    BlurStyle::Solid => c.max(b),
+                    BlurStyle::Outer => b * (1.0 - *c),
+                    BlurStyle::Inner => b * *c,
+                };
+            }
+        }
+        if let Some(filter) = &paint.image_filter {
+            let mut content = self.transparent();
+            for (i, (out, c)) in content.iter_mut().zip(&coverage).enumerate() {
+                if *c > 0.0 {
+                    *out = shader.shade(i % width, i / width).map(|v| v * c);
+                }
+            }
+            self.image_filter(&mut content, filter, transform);
+            self.composite(
+                content,
+                1.0,
+                None,
+                paint.blend_mode,
+                clip.map(Vec::as_slice),
+            );
+            return;
+        }
+        for (i, c) in coverage.into_iter().enumerate() {
+            let c = c * clip.map_or(1.0, |clip| clip[i]);
+            if c <= 0.0 {
+                continue;
+            }
+            let dst = self.pixels[i];
+            let blended = blend(paint.blend_mode, shader.shade(i % width, i / width), dst);
+            self.pixels[i] = std::array::from_fn(|k| dst[k] + (blended[k] - dst[k]) * c);
+        }
+    }
+}
+
+impl Pass for Rasterizer {
+    type Level = Save;
+    fn transform(save: &mut Save) -> &mut Matrix {
+        &mut save.transform
+    }
+    fn save(&mut self, top: &Save, op: &DisplayOp) -> Save {
+        let (transform, clip) = (top.transform, top.clip.clone());
+        let DisplayOp::SaveLayer {
+            bounds,
+            paint,
+            backdrop,
+        } = op
+        else {
+            return Save {
+                transform,
+                clip,
+                layer: None,
+            };
+        };
+        let mut path = RecordedPath::default();
+        path.add_rect(bounds);
+        let mask = Rc::new(self.clip(
+            clip.as_deref().map(Vec::as_slice),
+            &path,
+            &transform,
+            ClipOperation::Intersect,
+        ));
+        let mut content = self.transparent();
+        if let Some(backdrop) = backdrop {
+            // the layer starts out with the filtered backdrop
+            let mut filtered = self.pixels.clone();
+            self.image_filter(&mut filtered, backdrop, &transform);
+            for ((out, pixel), m) in content.iter_mut().zip(filtered).zip(mask.iter()) {
+                *out = pixel.map(|c| c * m);
+            }
+        }
+        let parent = std::mem::replace(&mut self.pixels, content);
+        Save {
+            transform,
+            clip: Some(mask.clone()),
+            layer: Some(Layer {
+                parent,
+                paint: paint.clone(),
+                mask,
+                transform,
+            }),
+        }
+    }
+    fn restore(&mut self, save: Save, _parent: &mut Save) {
+        let Some(layer) = save.layer else {
+            return;
+        };
+        let mut content = std::mem::replace(&mut self.pixels, layer.parent);
+        let paint = layer.paint.unwrap_or_default();
+        if let Some(filter) = &paint.image_filter {
+            self.image_filter(&mut content, filter, &layer.transform);
+        }
+        self.composite(
+            content,
+            paint.color.alpha,
+            paint.color_filter.as_deref(),
+            paint.blend_mode,
+            Some(&layer.mask),
+        );
+    }
+    fn op(&mut self, top: &mut Save, _index: usize, op: &DisplayOp) {
+        let (transform, clip) = (top.transform, top.clip.clone());
+        match op {
+            DisplayOp::ClipRect { rect, op } => {
+                let mut path = RecordedPath::default();
+                path.add_rect(rect);
+                self.push_clip(top, &path, *op);
+            }
+            DisplayOp::ClipOval { oval_bounds, op } => {
+                let mut path = RecordedPath::default();
+                path.add_oval(oval_bounds);
+                self.push_clip(top, &path, *op);
+            }
+            DisplayOp::ClipRoundedRect { rect, radii, op } => {
+                let mut path = RecordedPath::default();
+                path.add_rounded_rect(rect, radii);
+                self.push_clip(top, &path, *op);
+            }
+            DisplayOp::ClipPath { path, op } => {
+                self.push_clip(top, path, *op);
+            }
+            DisplayOp::DrawPaint { paint } => {
+                let coverage = vec![1.0; self.width * self.height];
+                self.fill(coverage, paint, &transform, clip.as_deref());
+            }
+            DisplayOp::DrawLine { from, to, paint } => {
+                let mut path = RecordedPath::default();
+                path.move_to(*from).line_to(*to);
+                let coverage = self.stroke_coverage(&path, paint, &transform);
+                self.fill(coverage, paint, &transform, clip.as_deref());
+            }
+            DisplayOp::DrawDashedLine {
+                from,
+                to,
+                on_length,
+                off_length,
+                paint,
+            } => {
+                let mut path = RecordedPath::default();
+                path.move_to(*from).line_to(*to);
+                let path = path.dash(&[*on_length, *off_length], 0.0);
+                let coverage = self.stroke_coverage(&path, paint, &transform);
+                self.fill(coverage, paint, &transform, clip.as_deref());
+            }
+            DisplayOp::DrawRect { rect, paint } => {
+                let mut path = RecordedPath::default();
+                path.add_rect(rect);
+                self.shape(&path, paint, &transform, clip.as_deref());
+            }
+            DisplayOp::DrawOval { oval_bounds, paint } => {
+                let mut path = RecordedPath::default();
+                path.add_oval(oval_bounds);
+                self.shape(&path, paint, &transform, clip.as_deref());
+            }
+            DisplayOp::DrawRoundedRect { rect, radii, paint } => {
+                let mut path = RecordedPath::default();
+                path.add_rounded_rect(rect, radii);
+                self.shape(&path, paint, &transform, clip.as_deref());
+            }
+            DisplayOp::DrawRoundedRectDifference {
+                outer_rect,
+                outer_radii,
+                inner_rect,
+                inner_radii,
+                paint,
+            } => {
+                let mut path = RecordedPath::new(FillType::Odd);
+                path.add_rounded_rect(outer_rect, outer_radii)
+                    .add_rounded_rect(inner_rect, inner_radii);
+                self.shape(&path, paint, &transform, clip.as_deref());
+            }
+            DisplayOp::DrawPath { path, paint } => {
+                self.shape(path, paint, &transform, clip.as_deref());
+            }
+            DisplayOp::DrawDisplayList {
+                display_list: Some(display_list),
+                opacity,
+            } => {
+                let content = self.transparent();
+                let parent = std::mem::replace(&mut self.pixels, content);
+                self.list(display_list, transform, clip.clone());
+                let content = std::mem::replace(&mut self.pixels, parent);
+                self.composite(
+                    content,
+                    *opacity,
+                    None,
+                    BlendMode::SourceOver,
+                    clip.as_deref().map(Vec::as_slice),
+                );
+            }
+            DisplayOp::DrawShadow {
+                path,
+                color,
+                elevation,
+                occluder_is_transparent: _,
+                device_pixel_ratio,
+            } => {
+                let paint = RecordedPaint {
+                    color: *color,
+                    mask_filter: Some(std::sync::Arc::new(RecordedMaskFilter::Blur {
+                        style: BlurStyle::Normal,
+                        sigma: shadow_sigma(*elevation, *device_pixel_ratio),
+                    })),
+                    ..Default::default()
+                };
+                self.shape(path, &paint, &transform, clip.as_deref());
+            }
+            DisplayOp::DrawTexture {
+                texture,
+                point,
+                sampling,
+                paint,
+            } => {
+                let size = Size::new(texture.width as f32, texture.height as f32);
+                let dst_rect = Rect::new(*point, size);
+                let src_rect = Rect::from_size(size);
+                let texture =
+                    self.texture(texture, &src_rect, &dst_rect, *sampling, paint, &transform);
+                if let Some((coverage, shader)) = texture {
+                    self.shade(coverage, &shader, paint, &transform, clip.as_deref());
+                }
+            }
+            DisplayOp::DrawTextureRect {
+                texture,
+                src_rect,
+                dst_rect,
+                sampling,
+                paint,
+            } => {
+                let paint = paint.clone().unwrap_or_default();
+                let texture =
+                    self.texture(texture, src_rect, dst_rect, *sampling, &paint, &transform);
+                if let Some((coverage, shader)) = texture {
+                    self.shade(coverage, &shader, &paint, &transform, clip.as_deref());
+                }
+            }
+            // paragraphs are laid out inside impeller, and there is no font rasterizer here
+            _ => {}
+        }
+    }
+}
+/// Add coverage to the pixels between two x coordinates of a scanline.
+fn add_span(line: &mut [f32], from: f32, to: f32, weight: f32) {
+    let width = line.len() as f32;
+    let (from, to) = (from.clamp(0.0, width), to.clamp(0.0, width));
+    if to <= from {
+        return;
+    }
+    let (first, last) = (from.floor() as usize, to.floor() as usize);
+    if first == last {
+        line[first] += (to - from) * weight;
+        return;
+    }
+    line[first] += (first as f32 + 1.0 - from) * weight;
+    for c in &mut line[first + 1..last] {
+        *c += weight;
+    }
+    if let Some(c) = line.get_mut(last) {
+        *c += (to - last as f32) * weight;
+    }
+}
+
+impl RecordedDisplayList {
+    /// Draw the recording on the CPU, without impeller or a GPU.
+    ///
+    /// This is a reference rasterizer for snapshot tests on machines without a GPU.
+    /// It supports transforms, clips, layers, fills and strokes with anti-aliased edges,
+    /// every blend mode, gradients, textures, color filters, blurs and morphology
+    /// filters. It is not pixel exact with impeller.
+    ///
+    /// Paragraphs are not drawn, as text is laid out inside impeller, and fragment
+    /// programs draw with the paint color. Textures without recorded pixels, like the ones
+    /// created with [Context::create_texture_with_rgba8](crate::Context::create_texture_with_rgba8),
+    /// are drawn as if they were transparent.
+    ///
+    /// - width:  The width of the result in pixels.
+    /// - height: The height of the result in pixels.
+    ///
+    /// @return   The unpremultiplied pixels, on a transparent background.
+    ///           Use [RecordedTexture::to_png] to save them.
+    ///
+    /// @see [DisplayList::rasterize](crate::DisplayList::rasterize)
+    pub fn rasterize(&self, width: u32, height: u32) -> RecordedTexture {
+        let mut rasterizer = Rasterizer {
+            width: width as usize,
+            height: height as usize,
+            pixels: vec![[0.0; 4]; width as usize * height as usize],
+        };
+        rasterizer.list(self, Matrix::identity(), None);
+        let byte = |v: f32| (v.clamp(0.0, 1.0) * 255.0).round() as u8;
+        let pixels: Vec<u8> = rasterizer
+            .pixels
+            .into_iter()
+            .flat_map(|pixel| unpremultiply(pixel).map(byte))
+            .collect();
+        RecordedTexture {
+            width,
+            height,
+            pixels: Some(pixels.into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use super::*;
+
+    fn pixel(texture: &RecordedTexture, x: u32, y: u32) -> [u8; 4] {
+        let i = (y * texture.width + x) as usize * 4;
+        texture.pixels.as_ref().unwrap()[i..i + 4]
+            .try_into()
+            .unwrap()
+    }
+    fn paint(color: Color) -> RecordedPaint {
+        RecordedPaint {
+            color,
+            ..Default::default()
+        }
+    }
+    const RED: Color = Color::new_srgb(1.0, 0.0, 0.0);
+    const BLUE: Color = Color::new_srgb(0.0, 0.0, 1.0);
+
+    #[test]
+    fn fills_are_anti_aliased() {
+        let mut recording = RecordedDisplayList::new(None);
+        recording.push(DisplayOp::DrawRect {
+            rect: Rect::new(Point::new(1.0, 1.0), Size::new(2.5, 2.0)),
+            paint: paint(RED),
+        });
+        let image = recording.rasterize(5, 4);
+        assert_eq!(pixel(&image, 0, 0), [0, 0, 0, 0]);
+        assert_eq!(pixel(&image, 1, 1), [255, 0, 0, 255]);
+        assert_eq!(pixel(&image, 3, 2), [255, 0, 0, 128]);
+        assert_eq!(pixel(&image, 1, 3), [0, 0, 0, 0]);
+
+        // an even-odd ring leaves its middle empty
+        let mut path = RecordedPath::new(FillType::Odd);
+        path.add_rect(&Rect::new(Point::zero(), Size::new(6.0, 6.0)))
+            .add_rect(&Rect::new(Point::new(2.0, 2.0), Size::new(2.0, 2.0)));
+        let mut recording = RecordedDisplayList::new(None);
+        recording.push(DisplayOp::DrawPath {
+            path: Arc::new(path),
+            paint: paint(BLUE),
+        });
+        let image = recording.rasterize(6, 6);
+        assert_eq!(pixel(&image, 1, 1), [0, 0, 255, 255]);
+        assert_eq!(pixel(&image, 2, 2), [0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn transforms_clips_and_strokes() {
+        let mut recording = RecordedDisplayList::new(None);
+        recording
+            .push(DisplayOp::Save)
+            .push(DisplayOp::Translate {
+                x_translation: 2.0,
+                y_translation: 0.0,
+            })
+            .push(DisplayOp::Scale {
+                x_scale: 2.0,
+                y_scale: 2.0,
+            })
+            .push(DisplayOp::ClipRect {
+                rect: Rect::new(Point::zero(), Size::new(2.0, 5.0)),
+                op: ClipOperation::Intersect,
+            })
+            .push(DisplayOp::DrawPaint { paint: paint(RED) })
+            .push(DisplayOp::Restore)
+            .push(DisplayOp::DrawLine {
+                from: Point::new(0.0, 8.0),
+                to: Point::new(10.0, 8.0),
+                paint: RecordedPaint {
+                    stroke_width: 2.0,
+                    ..paint(BLUE)
+                },
+            });
+        let image = recording.rasterize(10, 10);
+        // the clip is at x 2..6 and y 0..10 after the transform
+        assert_eq!(pixel(&image, 1, 1), [0, 0, 0, 0]);
+        assert_eq!(pixel(&image, 2, 1), [255, 0, 0, 255]);
+        assert_eq!(pixel(&image, 5, 5), [255, 0, 0, 255]);
+        assert_eq!(pixel(&image, 6, 5), [0, 0, 0, 0]);
+        // the stroke covers y 7..9, over the clipped fill
+        assert_eq!(pixel(&image, 3, 7), [0, 0, 255, 255]);
+        assert_eq!(pixel(&image, 3, 9), [255, 0, 0, 255]);
+        assert_eq!(pixel(&image, 8, 8), [0, 0, 255, 255]);
+    }
+
+    #[test]
+    fn layers_gradients_and_textures() {
+        let rect = Rect::new(Point::zero(), Size::new(4.0, 4.0));
+        let gradient = RecordedColorSource::LinearGradient {
+            start: Point::zero(),
+            end: Point::new(4.0, 0.0),
+            colors: vec![RED, BLUE],
+            stops: vec![],
+            tile_mode: TileMode::Clamp,
+            transformation: None,
+        };
+        let texture =
+            RecordedTexture::new_rgba8(2, 1, vec![0, 255, 0, 255, 255, 255, 255, 255]).unwrap();
+        let mut recording = RecordedDisplayList::new(None);
+        recording
+            .push(DisplayOp::SaveLayer {
+                bounds: rect,
+                paint: Some(paint(Color::new_srgba(0.0, 0.0, 0.0, 0.5))),
+                backdrop: None,
+            })
+            .push(DisplayOp::DrawRect {
+                rect,
+                paint: RecordedPaint {
+                    color_source: Some(Arc::new(gradient)),
+                    ..Default::default()
+                },
+            })
+            .push(DisplayOp::Restore)
+            .push(DisplayOp::DrawTextureRect {
+                texture: Arc::new(texture),
+                src_rect: Rect::new(Point::zero(), Size::new(2.0, 1.0)),
+                dst_rect: Rect::new(Point::new(0.0, 4.0), Size::new(4.0, 2.0)),
+                sampling: TextureSampling::NearestNeighbor,
+                paint: None,
+            });
+        let image = recording.rasterize(4, 6);
+        // the gradient is sampled at pixel centers, through a half transparent layer
+        assert_eq!(pixel(&image, 0, 0), [223, 0, 32, 128]);
+        assert_eq!(pixel(&image, 3, 0), [32, 0, 223, 128]);
+        // each texel covers 2x2 pixels
+        assert_eq!(pixel(&image, 1, 5), [0, 255, 0, 255]);
+        assert_eq!(pixel(&image, 2, 4), [255, 255, 255, 255]);
+    }
+
+    #[test]
+    fn blurs_spread_coverage() {
+        let mut recording = RecordedDisplayList::new(None);
+        recording.push(DisplayOp::DrawRect {
+            rect: Rect::new(Point::new(4.0, 4.0), Size::new(2.0, 2.0)),
+            paint: RecordedPaint {
+                mask_filter: Some(Arc::new(RecordedMaskFilter::Blur {
+                    style: BlurStyle::Normal,
+                    sigma: 1.0,
+                })),
+                ..paint(RED)
+            },
+        });
+        let image = recording.rasterize(10, 10);
+        let alpha = |x| pixel(&image, x, 4)[3];
+        assert!(alpha(4) < 255 && alpha(4) > alpha(3) && alpha(3) > alpha(2) && alpha(2) > 0);
+        assert_eq!(alpha(4), alpha(5));
+        assert_eq!(alpha(9), 0);
+    }
+
+    #[test]
+    fn huge_filters() {
+        let draw = |mask_filter: Option<RecordedMaskFilter>,
+                    image_filter: Option<RecordedImageFilter>| {
+            let mut recording = RecordedDisplayList::new(None);
+            recording.push(DisplayOp::DrawRect {
+                rect: Rect::new(Point::new(4.0, 4.0), Size::new(2.0, 2.0)),
+                paint: RecordedPaint {
+                    mask_filter: mask_filter.map(Arc::new),
+                    image_filter: image_filter.map(Arc::new),
+                    ..paint(RED)
+                },
+            });
+            recording.rasterize(10, 10)
+        };
+        let blur = |sigma| {
+            Some(RecordedMaskFilter::Blur {
+                style: BlurStyle::Normal,
+                sigma,
+            })
+        };
+        // infinite blurs are ignored
+        let image = draw(blur(f32::INFINITY), None);
+        assert_eq!(pixel(&image, 4, 4)[3], 255);
+        assert_eq!(pixel(&image, 3, 4)[3], 0);
+        // huge ones spread over the whole image
+        let image = draw(blur(1e30), None);
+        assert!(pixel(&image, 9, 9)[3] > 0);
+        let dilate = RecordedImageFilter::Dilate {
+            x_radius: f32::INFINITY,
+            y_radius: 1e30,
+        };
+        let image = draw(None, Some(dilate));
+        assert_eq!(pixel(&image, 0, 0), pixel(&image, 4, 4));
+    }
+}
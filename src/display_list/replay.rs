@@ -0,0 +1,526 @@
+//! Rebuilding impeller objects from recordings, through the regular builder methods.
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use super::{DisplayOp, RecordedDisplayList, Recording, MAX_DEPTH};
+use crate::{
+    ColorFilter, ColorSource, Context, DisplayList, DisplayListBuilder, ImageFilter, MaskFilter,
+    Paint, Paragraph, ParagraphBuilder, ParagraphItem, ParagraphStyle, Path, RecordedColorFilter,
+    RecordedColorSource, RecordedImageFilter, RecordedMaskFilter, RecordedPaint, RecordedParagraph,
+    RecordedParagraphStyle, RecordedPath, RecordedTexture, Texture, TypographyContext,
+};
+
+/// Creates every shared object only once, so a texture that is drawn twice is uploaded once.
+struct Replayer<'a> {
+    context: Option<&'a Context>,
+    typography: Option<&'a TypographyContext>,
+    paths: HashMap<*const RecordedPath, Path>,
+    textures: HashMap<*const RecordedTexture, Texture>,
+    paragraphs: HashMap<*const RecordedParagraph, Paragraph>,
+    display_lists: HashMap<*const RecordedDisplayList, DisplayList>,
+    /// How many composed image filters or nested display lists are being replayed.
+    depth: usize,
+}
+impl Replayer<'_> {
+    /// Replay something one level deeper, failing past [MAX_DEPTH].
+    fn nested<T>(
+        &mut self,
+        replay: impl FnOnce(&mut Self) -> Result<T, &'static str>,
+    ) -> Result<T, &'static str> {
+        if self.depth == MAX_DEPTH {
+            return Err("objects are nested too deeply");
+        }
+        self.depth += 1;
+        let result = replay(self);
+        self.depth -= 1;
+        result
+    }
+    fn path(&mut self, recording: &Arc<RecordedPath>) -> Path {
+        self.paths
+            .entry(Arc::as_ptr(recording))
+            .or_insert_with(|| {
+                let mut path = recording.to_path();
+                path.1 = recording.clone();
+                path
+            })
+            .clone()
+    }
+    /// # Safety
+    /// Textures must be dropped before the context is dropped.
+    unsafe fn texture(
+        &mut self,
+        recording: &Arc<RecordedTexture>,
+    ) -> Result<Texture, &'static str> {
+        if let Some(texture) = self.textures.get(&Arc::as_ptr(recording)) {
+            return Ok(texture.clone());
+        }
+        let context = self
+            .context
+            .ok_or("a context is needed to create textures")?;
+        let texture = unsafe { context.create_texture_from_recording(recording)? };
+        self.textures
+            .insert(Arc::as_ptr(recording), texture.clone());
+        Ok(texture)
+    }
+    fn paragraph(&mut self, recording: &Arc<RecordedParagraph>) -> Result<Paragraph, &'static str> {
+        if let Some(paragraph) = self.paragraphs.get(&Arc::as_ptr(recording)) {
+            return Ok(paragraph.clone());
+        }
+        let typography = self
+            .typography
+            .ok_or("a typography context is needed to create paragraphs")?;
+        let mut builder =
+            ParagraphBuilder::new(typography).ok_or("failed to create a paragraph builder")?;
+        for item in &recording.items {
+            match item {
+                ParagraphItem::PushStyle(style) => {
+                    builder.push_style(&self.paragraph_style(style)?);
+                }
+                ParagraphItem::PopStyle => {
+                    builder.pop_style();
+                }
+                ParagraphItem::Text(text) => {
+                    builder.add_text(text);
+                }
+            }
+        }
+        let paragraph = builder
+            .build(recording.width)
+            .ok_or("failed to build a paragraph")?;
+        self.paragraphs
+            .insert(Arc::as_ptr(recording), paragraph.clone());
+        Ok(paragraph)
+    }
+    fn paragraph_style(
+        &mut self,
+        recording: &RecordedParagraphStyle,
+    ) -> Result<ParagraphStyle, &'static str> {
+        let mut style = ParagraphStyle::default();
+        if let Some(paint) = &recording.foreground {
+            style.set_foreground(&self.paint(paint)?);
+        }
+        if let Some(paint) = &recording.background {
+            style.set_background(&self.paint(paint)?);
+        }
+        if let Some(weight) = recording.font_weight {
+            style.set_font_weight(weight);
+        }
+        if let Some(font_style) = recording.font_style {
+            style.set_font_style(font_style);
+        }
+        if let Some(family) = &recording.font_family {
+            style.set_font_family(family);
+        }
+        if let Some(size) = recording.font_size {
+            style.set_font_size(size);
+        }
+        if let Some(height) = recording.height {
+            style.set_height(height);
+        }
+        if let Some(align) = recording.text_alignment {
+            style.set_text_alignment(align);
+        }
+        if let Some(direction) = recording.text_direction {
+            style.set_text_direction(direction);
+        }
+        if let Some(decoration) = &recording.text_decoration {
+            style.set_text_decoration(
+                decoration.types,
+                &decoration.color,
+                decoration.style,
+                decoration.thickness_multiplier,
+            );
+        }
+        if let Some(max_lines) = recording.max_lines {
+            style.set_max_lines(max_lines);
+        }
+        if let Some(locale) = &recording.locale {
+            style.set_locale(locale);
+        }
+        if let Some(ellipsis) = &recording.ellipsis {
+            style.set_ellipsis(Some(ellipsis));
+        }
+        Ok(style)
+    }
+    fn paint(&mut self, recording: &RecordedPaint) -> Result<Paint, &'static str> {
+        let mut paint = Paint::default();
+        paint
+            .set_color(recording.color)
+            .set_blend_mode(recording.blend_mode)
+            .set_draw_style(recording.draw_style)
+            .set_stroke_cap(recording.stroke_cap)
+            .set_stroke_join(recording.stroke_join)
+            .set_stroke_width(recording.stroke_width)
+            .set_stroke_miter(recording.stroke_miter);
+        if let Some(filter) = &recording.color_filter {
+            paint.set_color_filter(&color_filter(filter));
+        }
+        if let Some(filter) = &recording.image_filter {
+            paint.set_image_filter(&self.image_filter(filter)?);
+        }
+        if let Some(source) = &recording.color_source {
+            paint.set_color_source(&self.color_source(source)?);
+        }
+        if let Some(filter) = &recording.mask_filter {
+            paint.set_mask_filter(&mask_filter(filter));
+        }
+        Ok(paint)
+    }
+    fn color_source(
+        &mut self,
+        recording: &Arc<RecordedColorSource>,
+    ) -> Result<ColorSource, &'static str> {
+        let mut source = match recording.as_ref() {
+            RecordedColorSource::LinearGradient {
+                start,
+                end,
+                colors,
+                stops,
+                tile_mode,
+                transformation,
+            } => ColorSource::new_linear_gradient(
+                *start,
+                *end,
+                colors,
+                stops,
+                *tile_mode,
+                transformation.as_ref(),
+            ),
+            RecordedColorSource::RadialGradient {
+                center,
+                radius,
+                colors,
+                stops,
+                tile_mode,
+                transformation,
+            } => ColorSource::new_radial_gradient(
+                *center,
+                *radius,
+                colors,
+                stops,
+                *tile_mode,
+                transformation.as_ref(),
+            ),
+            RecordedColorSource::ConicalGradient {
+                start_center,
+                start_radius,
+                end_center,
+                end_radius,
+                colors,
+                stops,
+                tile_mode,
+                transformation,
+            } => ColorSource::new_conical_gradient(
+                *start_center,
+                *start_radius,
+                *end_center,
+                *end_radius,
+                colors,
+                stops,
+                *tile_mode,
+                transformation.as_ref(),
+            ),
+            RecordedColorSource::SweepGradient {
+                center,
+                start,
+                end,
+                colors,
+                stops,
+                tile_mode,
+                transformation,
+            } => ColorSource::new_sweep_gradient(
+                *center,
+                *start,
+                *end,
+                colors,
+                stops,
+                *tile_mode,
+                transformation.as_ref(),
+            ),
+            RecordedColorSource::Image {
+                image,
+                horizontal_tile_mode,
+                vertical_tile_mode,
+                sampling,
+                transformation,
+            } => ColorSource::new_image(
+                &unsafe { self.texture(image)? },
+                *horizontal_tile_mode,
+                *vertical_tile_mode,
+                *sampling,
+                transformation.as_ref(),
+            ),
+            RecordedColorSource::FragmentProgram { .. } => {
+                return Err("fragment programs are not recorded, so they can't be replayed")
+            }
+        };
+        source.1 = Recording::new(|| recording.clone());
+        Ok(source)
+    }
+    fn image_filter(
+        &mut self,
+        recording: &Arc<RecordedImageFilter>,
+    ) -> Result<ImageFilter, &'static str> {
+        let mut filter = match recording.as_ref() {
+            RecordedImageFilter::Blur {
+                x_sigma,
+                y_sigma,
+                tile_mode,
+            } => ImageFilter::new_blur(*x_sigma, *y_sigma, *tile_mode),
+            RecordedImageFilter::Dilate { x_radius, y_radius } => {
+                ImageFilter::new_dilate(*x_radius, *y_radius)
+            }
+            RecordedImageFilter::Erode { x_radius, y_radius } => {
+                ImageFilter::new_erode(*x_radius, *y_radius)
+            }
+            RecordedImageFilter::Matrix { matrix, sampling } => {
+                ImageFilter::new_matrix(matrix, *sampling)
+            }
+            RecordedImageFilter::Compose { outer, inner } => self.nested(|replayer| {
+                Ok(ImageFilter::new_compose(
+                    &replayer.image_filter(outer)?,
+                    &replayer.image_filter(inner)?,
+                ))
+            })?,
+            RecordedImageFilter::FragmentProgram { .. } => {
+                return Err("fragment programs are not recorded, so they can't be replayed")
+            }
+        };
+        filter.1 = Recording::new(|| recording.clone());
+        Ok(filter)
+    }
+    fn display_list(
+        &mut self,
+        recording: &Arc<RecordedDisplayList>,
+    ) -> Result<DisplayList, &'static str> {
+        if let Some(display_list) = self.display_lists.get(&Arc::as_ptr(recording)) {
+            return Ok(display_list.clone());
+        }
+        let display_list = self.nested(|replayer| replayer.replay(recording))?;
+        self.display_lists
+            .insert(Arc::as_ptr(recording), display_list.clone());
+        Ok(display_list)
+    }
+    fn replay(&mut self, recording: &RecordedDisplayList) -> Result<DisplayList, &'static str> {
+        #[cfg(feature = "recording")]
+        let mut builder = DisplayListBuilder::new_recording(recording.cull_rect().as_ref());
+        #[cfg(not(feature = "recording"))]
+        let mut builder = DisplayListBuilder::new(recording.cull_rect().as_ref());
+        for op in recording.ops() {
+            match op {
+                DisplayOp::Save => {
+                    builder.save();
+                }
+                DisplayOp::SaveLayer {
+                    bounds,
+                    paint,
+                    backdrop,
+                } => {
+                    let paint = paint.as_ref().map(|p| self.paint(p)).transpose()?;
+                    let backdrop = backdrop
+                        .as_ref()
+                        .map(|b| self.image_filter(b))
+                        .transpose()?;
+                    builder.save_layer(bounds, paint.as_ref(), backdrop.as_ref());
+                }
+                DisplayOp::Restore => {
+                    builder.restore();
+                }
+                DisplayOp::RestoreToCount(count) => {
+                    builder.restore_to_count(*count);
+                }
+                DisplayOp::Scale { x_scale, y_scale } => {
+                    builder.scale(*x_scale, *y_scale);
+                }
+                DisplayOp::Rotate { angle_degrees } => {
+                    builder.rotate(*angle_degrees);
+                }
+                DisplayOp::Translate {
+                    x_translation,
+                    y_translation,
+                } => {
+                    builder.translate(*x_translation, *y_translation);
+                }
+                DisplayOp::Transform(transform) => {
+                    builder.transform(transform);
+                }
+                DisplayOp::SetTransform(transform) => {
+                    builder.set_transform(transform);
+                }
+                DisplayOp::ResetTransform => {
+                    builder.reset_transform();
+                }
+                DisplayOp::ClipRect { rect, op } => {
+                    builder.clip_rect(rect, *op);
+                }
+                DisplayOp::ClipOval { oval_bounds, op } => {
+                    builder.clip_oval(oval_bounds, *op);
+                }
+                DisplayOp::ClipRoundedRect { rect, radii, op } => {
+                    builder.clip_rounded_rect(rect, radii, *op);
+                }
+                DisplayOp::ClipPath { path, op } => {
+                    builder.clip_path(&self.path(path), *op);
+                }
+                DisplayOp::DrawPaint { paint } => {
+                    builder.draw_paint(&self.paint(paint)?);
+                }
+                DisplayOp::DrawLine { from, to, paint } => {
+                    builder.draw_line(*from, *to, &self.paint(paint)?);
+                }
+                DisplayOp::DrawDashedLine {
+                    from,
+                    to,
+                    on_length,
+                    off_length,
+                    paint,
+                } => {
+                    builder.draw_dashed_line(
+                        *from,
+                        *to,
+                        *on_length,
+                        *off_length,
+                        &self.paint(paint)?,
+                    );
+                }
+                DisplayOp::DrawRect { rect, paint } => {
+                    builder.draw_rect(rect, &self.paint(paint)?);
+                }
+                DisplayOp::DrawOval { oval_bounds, paint } => {
+                    builder.draw_oval(oval_bounds, &self.paint(paint)?);
+                }
+                DisplayOp::DrawRoundedRect { rect, radii, paint } => {
+                    builder.draw_rounded_rect(rect, radii, &self.paint(paint)?);
+                }
+                DisplayOp::DrawRoundedRectDifference {
+                    outer_rect,
+                    outer_radii,
+                    inner_rect,
+                    inner_radii,
+                    paint,
+                } => {
+                    builder.draw_rounded_rect_difference(
+                        outer_rect,
+                        outer_radii,
+                        inner_rect,
+                        inner_radii,
+                        &self.paint(paint)?,
+                    );
+                }
+                DisplayOp::DrawPath { path, paint } => {
+                    builder.draw_path(&self.path(path), &self.paint(paint)?);
+                }
+                DisplayOp::DrawDisplayList {
+                    display_list,
+                    opacity,
+                } => {
+                    let display_list = display_list
+                        .as_ref()
+                        .ok_or("the nested display list was not recorded")?;
+                    builder.draw_display_list(&self.display_list(display_list)?, *opacity);
+                }
+                DisplayOp::DrawParagraph { paragraph, point } => {
+                    builder.draw_paragraph(&self.paragraph(paragraph)?, *point);
+                }
+                DisplayOp::DrawShadow {
+                    path,
+                    color,
+                    elevation,
+                    occluder_is_transparent,
+                    device_pixel_ratio,
+                } => {
+                    builder.draw_shadow(
+                        &self.path(path),
+                        color,
+                        *elevation,
+                        *occluder_is_transparent,
+                        *device_pixel_ratio,
+                    );
+                }
+                DisplayOp::DrawTexture {
+                    texture,
+                    point,
+                    sampling,
+                    paint,
+                } => {
+                    let texture = unsafe { self.texture(texture)? };
+                    builder.draw_texture(&texture, *point, *sampling, &self.paint(paint)?);
+                }
+                DisplayOp::DrawTextureRect {
+                    texture,
+                    src_rect,
+                    dst_rect,
+                    sampling,
+                    paint,
+                } => {
+                    let texture = unsafe { self.texture(texture)? };
+                    let paint = paint.as_ref().map(|p| self.paint(p)).transpose()?;
+                    builder.draw_texture_rect(
+                        &texture,
+                        src_rect,
+                        dst_rect,
+                        *sampling,
+                        paint.as_ref(),
+                    );
+                }
+                DisplayOp::SetTag(tag) => {
+                    builder.set_tag(*tag);
+                }
+            }
+        }
+        builder.build().ok_or("failed to build the display list")
+    }
+}
+fn color_filter(recording: &Arc<RecordedColorFilter>) -> ColorFilter {
+    let mut filter = match recording.as_ref() {
+        RecordedColorFilter::Blend { color, blend_mode } => {
+            ColorFilter::new_blend(*color, *blend_mode)
+        }
+        RecordedColorFilter::Matrix(matrix) => ColorFilter::new_matrix(*matrix),
+    };
+    filter.1 = Recording::new(|| recording.clone());
+    filter
+}
+fn mask_filter(recording: &Arc<RecordedMaskFilter>) -> MaskFilter {
+    let mut filter = match recording.as_ref() {
+        RecordedMaskFilter::Blur { style, sigma } => MaskFilter::new_blur(*style, *sigma),
+    };
+    filter.1 = Recording::new(|| recording.clone());
+    filter
+}
+
+impl RecordedDisplayList {
+    /// Rebuild an impeller [DisplayList] by replaying the recorded ops into a
+    /// [DisplayListBuilder], which records them again with the `recording` feature.
+    ///
+    /// Shared objects like textures are only created once.
+    ///
+    /// - context:     Needed if the recording has textures in it.
+    /// - typography:  Needed if the recording has paragraphs in it. The fonts that the
+    ///   paragraphs use must be registered with it.
+    ///
+    /// Fails if an object that is needed is missing, or the recording contains
+    /// objects that can't be recreated, like fragment programs.
+    ///
+    /// @see [Self::from_bytes]
+    ///
+    /// # Safety
+    ///
+    /// * If textures are created, the display list must be dropped before the context is dropped
+    pub unsafe fn to_display_list(
+        &self,
+        context: Option<&Context>,
+        typography: Option<&TypographyContext>,
+    ) -> Result<DisplayList, &'static str> {
+        Replayer {
+            context,
+            typography,
+            paths: HashMap::new(),
+            textures: HashMap::new(),
+            paragraphs: HashMap::new(),
+            display_lists: HashMap::new(),
+            depth: 0,
+        }
+        .replay(self)
+    }
+}
@@ -0,0 +1,344 @@
+//! Op counts and a rough cost estimate for recorded display lists.
+//!
+//! The cost is measured in "filled rectangles": drawing a solid rect costs 1, and
+//! everything else is weighted by how much more work impeller usually does for it.
+//! It is only meant for comparing recordings against each other or against a budget.
+use std::collections::{BTreeMap, HashMap};
+
+use super::{DisplayOp, RecordedDisplayList};
+use crate::{
+    DrawStyle, PathVerb, RecordedImageFilter, RecordedMaskFilter, RecordedPaint, RecordedPath,
+};
+
+/// Any draw call.
+const DRAW: f32 = 1.0;
+/// Non rectangular clips need a stencil pass.
+const CLIP: f32 = 1.0;
+/// Lines and moves of a path that has to be tessellated.
+const VERB: f32 = 0.1;
+/// Curves are subdivided into many lines.
+const CURVE: f32 = 0.25;
+/// Strokes are tessellated again for the outline.
+const STROKE: f32 = 0.5;
+/// An offscreen render target, and compositing it back.
+const LAYER: f32 = 10.0;
+/// Reading back what was drawn behind a layer.
+const BACKDROP: f32 = 10.0;
+/// Impeller downsamples large blurs, so the cost doesn't grow much with sigma.
+const BLUR: f32 = 20.0;
+/// Morphology, matrix and fragment program filters.
+const FILTER: f32 = 5.0;
+/// Per character of a paragraph.
+const GLYPH: f32 = 0.05;
+/// Sampling a texture.
+const TEXTURE: f32 = 2.0;
+
+/// What a [RecordedDisplayList] draws, in numbers.
+///
+/// Nested display lists are counted as if their ops were drawn in place, as many times
+/// as they are drawn.
+///
+/// @see [RecordedDisplayList::stats]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DisplayListStats {
+    /// The number of ops, keyed by the name of the [DisplayListBuilder](crate::DisplayListBuilder)
+    /// method that recorded them, like `"draw_rect"`.
+    pub ops: BTreeMap<&'static str, usize>,
+    /// Save layers, including the implicit ones of nested display lists drawn with an
+    /// opacity below 1.
+    pub save_layers: usize,
+    /// Save layers with a blur in their paint's image filter or in their backdrop filter.
+    ///
+    /// These are usually the most expensive ops of a frame.
+    pub blurred_layers: usize,
+    /// Blur image filters and blur mask filters, wherever they are used.
+    pub blur_filters: usize,
+    /// The verbs of all the paths that are drawn, clipped to or used for shadows.
+    pub path_verbs: HashMap<PathVerb, usize>,
+    /// [DisplayOp::DrawTexture] and [DisplayOp::DrawTextureRect] ops.
+    pub texture_draws: usize,
+    /// [DisplayOp::DrawDisplayList] ops, at any depth.
+    pub nested_display_lists: usize,
+    /// Nested display lists that weren't recorded, so their contents are missing from
+    /// these stats.
+    pub unrecorded_display_lists: usize,
+    /// A rough estimate of the rendering cost, where a solid rect costs 1.
+    pub cost: f32,
+}
+impl DisplayListStats {
+    /// The total number of ops.
+    pub fn op_count(&self) -> usize {
+        self.ops.values().sum()
+    }
+    fn path(&mut self, path: &RecordedPath) -> f32 {
+        let mut cost = 0.0;
+        for verb in path.verbs() {
+            *self.path_verbs.entry(*verb).or_default() += 1;
+            cost += match verb {
+                PathVerb::Quad | PathVerb::Cubic => CURVE,
+                _ => VERB,
+            };
+        }
+        cost
+    }
+    /// The cost of an image filter, and whether it blurs.
+    fn image_filter(&mut self, filter: &RecordedImageFilter) -> (f32, bool) {
+        match filter {
+            RecordedImageFilter::Blur { .. } => {
+                self.blur_filters += 1;
+                (BLUR, true)
+            }
+            RecordedImageFilter::Compose { outer, inner } => {
+                let (outer, outer_blurs) = self.image_filter(outer);
+                let (inner, inner_blurs) = self.image_filter(inner);
+                (outer + inner, outer_blurs || inner_blurs)
+            }
+            _ => (FILTER, false),
+        }
+    }
+    /// The extra cost of drawing with a paint, and whether its image filter blurs.
+    fn paint(&mut self, paint: &RecordedPaint) -> (f32, bool) {
+        let mut cost = 0.0;
+        if paint.draw_style != DrawStyle::Fill {
+            cost += STROKE;
+        }
+        if let Some(RecordedMaskFilter::Blur { .. }) = paint.mask_filter.as_deref() {
+            self.blur_filters += 1;
+            cost += BLUR;
+        }
+        match &paint.image_filter {
+            Some(filter) => {
+                let (filter_cost, blurs) = self.image_filter(filter);
+                (cost + filter_cost, blurs)
+            }
+            None => (cost, false),
+        }
+    }
+    fn list(&mut self, list: &RecordedDisplayList) {
+        for op in list.ops() {
+            *self.ops.entry(name(op)).or_default() += 1;
+            let cost = match op {
+                DisplayOp::SaveLayer {
+                    paint, backdrop, ..
+                } => {
+                    self.save_layers += 1;
+                    let (mut cost, mut blurs) = match paint {
+                        Some(paint) => self.paint(paint),
+                        None => (0.0, false),
+                    };
+                    if let Some(backdrop) = backdrop {
+                        let (backdrop_cost, backdrop_blurs) = self.image_filter(backdrop);
+                        cost += BACKDROP + backdrop_cost;
+                        blurs |= backdrop_blurs;
+                    }
+                    if blurs {
+                        self.blurred_layers += 1;
+                    }
+                    LAYER + cost
+                }
+                DisplayOp::ClipRect { .. } => 0.0,
+                DisplayOp::ClipOval { .. } | DisplayOp::ClipRoundedRect { .. } => CLIP,
+                DisplayOp::ClipPath { path, .. } => CLIP + self.path(path),
+                DisplayOp::DrawPath { path, paint } => DRAW + self.path(path) + self.paint(paint).0,
+                DisplayOp::DrawShadow { path, .. } => DRAW + BLUR + self.path(path),
+                DisplayOp::DrawTexture { paint, .. } => {
+                    self.texture_draws += 1;
+                    DRAW + TEXTURE + self.paint(paint).0
+                }
+                DisplayOp::DrawTextureRect { paint, .. } => {
+                    self.texture_draws += 1;
+                    DRAW + TEXTURE + paint.as_ref().map_or(0.0, |paint| self.paint(paint).0)
+                }
+                DisplayOp::DrawParagraph { paragraph, .. } => {
+                    DRAW + GLYPH * paragraph.text().chars().count() as f32
+                }
+                DisplayOp::DrawDisplayList {
+                    display_list,
+                    opacity,
+                } => {
+                    self.nested_display_lists += 1;
+                    match display_list {
+                        Some(nested) => self.list(nested),
+                        None => self.unrecorded_display_lists += 1,
+                    }
+                    if *opacity < 1.0 {
+                        self.save_layers += 1;
+                        LAYER
+                    } else {
+                        0.0
+                    }
+                }
+                op => match op.paint() {
+                    Some(paint) => DRAW + self.paint(paint).0,
+                    None => 0.0,
+                },
+            };
+            self.cost += cost;
+        }
+    }
+}
+/// The name of the builder method that records the op.
+fn name(op: &DisplayOp) -> &'static str {
+    match op {
+        DisplayOp::Save => "save",
+        DisplayOp::SaveLayer { .. } => "save_layer",
+        DisplayOp::Restore => "restore",
+        DisplayOp::RestoreToCount(_) => "restore_to_count",
+        DisplayOp::Scale { .. } => "scale",
+        DisplayOp::Rotate { .. } => "rotate",
+        DisplayOp::Translate { .. } => "translate",
+        DisplayOp::Transform(_) => "transform",
+        DisplayOp::SetTransform(_) => "set_transform",
+        DisplayOp::ResetTransform => "reset_transform",
+        DisplayOp::ClipRect { .. } => "clip_rect",
+        DisplayOp::ClipOval { .. } => "clip_oval",
+        DisplayOp::ClipRoundedRect { .. } => "clip_rounded_rect",
+        DisplayOp::ClipPath { .. } => "clip_path",
+        DisplayOp::DrawPaint { .. } => "draw_paint",
+        DisplayOp::DrawLine { .. } => "draw_line",
+        DisplayOp::DrawDashedLine { .. } => "draw_dashed_line",
+        DisplayOp::DrawRect { .. } => "draw_rect",
+        DisplayOp::DrawOval { .. } => "draw_oval",
+        DisplayOp::DrawRoundedRect { .. } => "draw_rounded_rect",
+        DisplayOp::DrawRoundedRectDifference { .. } => "draw_rounded_rect_difference",
+        DisplayOp::DrawPath { .. } => "draw_path",
+        DisplayOp::DrawDisplayList { .. } => "draw_display_list",
+        DisplayOp::DrawParagraph { .. } => "draw_paragraph",
+        DisplayOp::DrawShadow { .. } => "draw_shadow",
+        DisplayOp::DrawTexture { .. } => "draw_texture",
+        DisplayOp::DrawTextureRect { .. } => "draw_texture_rect",
+        DisplayOp::SetTag(_) => "set_tag",
+    }
+}
+
+impl RecordedDisplayList {
+    /// Count the ops of the recording, and estimate how expensive it is to render.
+    ///
+    /// Useful to keep an eye on display lists that grow over time, or to find blurred
+    /// save layers.
+    ///
+    /// ```rust
+    /// # use impellers::*;
+    /// # use std::sync::Arc;
+    /// let mut list = RecordedDisplayList::new(None);
+    /// let blur = RecordedImageFilter::Blur {
+    ///     x_sigma: 8.0,
+    ///     y_sigma: 8.0,
+    ///     tile_mode: TileMode::Clamp,
+    /// };
+    /// list.push(DisplayOp::SaveLayer {
+    ///     bounds: Rect::new(Point::zero(), Size::new(100.0, 100.0)),
+    ///     paint: None,
+    ///     backdrop: Some(Arc::new(blur)),
+    /// })
+    /// .push(DisplayOp::Restore);
+    /// let stats = list.stats();
+    /// assert_eq!(stats.ops["save_layer"], 1);
+    /// assert_eq!(stats.blurred_layers, 1);
+    /// assert!(stats.cost > 1.0);
+    /// ```
+    pub fn stats(&self) -> DisplayListStats {
+        let mut stats = DisplayListStats::default();
+        stats.list(self);
+        stats
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::{Point, RecordedTexture, Rect, Size, TextureSampling, TileMode};
+
+    #[test]
+    fn stats_of_ops() {
+        let rect = Rect::new(Point::zero(), Size::new(10.0, 10.0));
+        let mut path = RecordedPath::default();
+        path.move_to(Point::zero())
+            .line_to(Point::new(10.0, 0.0))
+            .cubic_curve_to(
+                Point::new(10.0, 5.0),
+                Point::new(5.0, 10.0),
+                Point::new(0.0, 10.0),
+            )
+            .close();
+        let path = Arc::new(path);
+        let mut nested = RecordedDisplayList::new(None);
+        nested
+            .push(DisplayOp::DrawRect {
+                rect,
+                paint: RecordedPaint::default(),
+            })
+            .push(DisplayOp::DrawPath {
+                path: path.clone(),
+                paint: RecordedPaint::default(),
+            });
+        let nested = Arc::new(nested);
+        let blurred = RecordedPaint {
+            image_filter: Some(Arc::new(RecordedImageFilter::Compose {
+                outer: Arc::new(RecordedImageFilter::Dilate {
+                    x_radius: 1.0,
+                    y_radius: 1.0,
+                }),
+                inner: Arc::new(RecordedImageFilter::Blur {
+                    x_sigma: 4.0,
+                    y_sigma: 4.0,
+                    tile_mode: TileMode::Decal,
+                }),
+            })),
+            ..Default::default()
+        };
+
+        let mut list = RecordedDisplayList::new(None);
+        list.push(DisplayOp::SaveLayer {
+            bounds: rect,
+            paint: Some(blurred),
+            backdrop: None,
+        })
+        .push(DisplayOp::ClipPath {
+            path,
+            op: crate::ClipOperation::Intersect,
+        })
+        .push(DisplayOp::DrawDisplayList {
+            display_list: Some(nested.clone()),
+            opacity: 1.0,
+        })
+        .push(DisplayOp::DrawDisplayList {
+            display_list: Some(nested),
+            opacity: 0.5,
+        })
+        .push(DisplayOp::DrawDisplayList {
+            display_list: None,
+            opacity: 1.0,
+        })
+        .push(DisplayOp::DrawTexture {
+            texture: Arc::new(RecordedTexture {
+                width: 4,
+                height: 4,
+                pixels: None,
+            }),
+            point: Point::zero(),
+            sampling: TextureSampling::Linear,
+            paint: RecordedPaint::default(),
+        })
+        .push(DisplayOp::Restore);
+
+        let stats = list.stats();
+        assert_eq!(stats.op_count(), 11);
+        assert_eq!(stats.ops["draw_rect"], 2);
+        assert_eq!(stats.ops["draw_path"], 2);
+        assert_eq!(stats.ops["draw_display_list"], 3);
+        assert_eq!(stats.save_layers, 2);
+        assert_eq!(stats.blurred_layers, 1);
+        assert_eq!(stats.blur_filters, 1);
+        assert_eq!(stats.path_verbs[&PathVerb::Move], 3);
+        assert_eq!(stats.path_verbs[&PathVerb::Cubic], 3);
+        assert_eq!(stats.texture_draws, 1);
+        assert_eq!(stats.nested_display_lists, 3);
+        assert_eq!(stats.unrecorded_display_lists, 1);
+        // the blurred layer is 35 of it
+        assert!((stats.cost - 54.65).abs() < 1e-3);
+        assert_eq!(RecordedDisplayList::new(None).stats(), Default::default());
+    }
+}
@@ -0,0 +1,1250 @@
+//! Exporting recorded display lists as svg documents.
+//!
+//! Shapes are written in their local coordinates with a `transform` attribute. Groups
+//! never have transforms, so clips, masks and filters are all in document coordinates.
+//!
+//! <https://www.w3.org/TR/SVG2/>
+use std::fmt::Write;
+
+use super::{shadow_sigma, walk, DisplayOp, Pass, RecordedDisplayList};
+use crate::paint::sample_gradient;
+use crate::{
+    BlendMode, BlurStyle, ClipOperation, Color, DrawStyle, FillType, FontStyle, FontWeight, Matrix,
+    Point, RecordedColorFilter, RecordedColorSource, RecordedImageFilter, RecordedMaskFilter,
+    RecordedPaint, RecordedParagraph, RecordedPath, RecordedTexture, Rect, Size, StrokeCap,
+    StrokeJoin, TextAlignment, TextDecorationStyle, TextDecorationType, TextDirection,
+    TextureSampling, TileMode,
+};
+
+/// Sweep gradients are drawn as this many wedges.
+const SWEEP_WEDGES: usize = 180;
+
+/// Geometry that can be written as an svg element.
+enum Shape {
+    Rect(Rect),
+    Oval(Rect),
+    Line(Point, Point),
+    Path(RecordedPath),
+}
+impl Shape {
+    /// The element name and geometry attributes.
+    fn element(&self) -> String {
+        match self {
+            Shape::Rect(rect) => format!(
+                r#"<rect x="{}" y="{}" width="{}" height="{}""#,
+                n(rect.min_x()),
+                n(rect.min_y()),
+                n(rect.width()),
+                n(rect.height())
+            ),
+            Shape::Oval(rect) => format!(
+                r#"<ellipse cx="{}" cy="{}" rx="{}" ry="{}""#,
+                n(rect.center().x),
+                n(rect.center().y),
+                n(rect.width() / 2.0),
+                n(rect.height() / 2.0)
+            ),
+            Shape::Line(from, to) => format!(
+                r#"<line x1="{}" y1="{}" x2="{}" y2="{}""#,
+                n(from.x),
+                n(from.y),
+                n(to.x),
+                n(to.y)
+            ),
+            Shape::Path(path) => {
+                let mut element = format!(r#"<path d="{}""#, path.to_svg_path_data());
+                if path.fill_type() == FillType::Odd {
+                    element.push_str(r#" fill-rule="evenodd" clip-rule="evenodd""#);
+                }
+                element
+            }
+        }
+    }
+    fn bounds(&self) -> Rect {
+        match self {
+            Shape::Rect(rect) | Shape::Oval(rect) => *rect,
+            Shape::Line(from, to) => Rect::from_points([*from, *to]),
+            Shape::Path(path) => path.tight_bounds(),
+        }
+    }
+}
+
+/// The transform of a save, and the svg groups to close when it is restored.
+struct Save {
+    transform: Matrix,
+    /// The number of `<g>` elements that were opened since the save.
+    groups: usize,
+}
+
+struct Writer {
+    out: String,
+    size: Size,
+    next_id: u32,
+}
+impl Writer {
+    fn id(&mut self) -> u32 {
+        self.next_id += 1;
+        self.next_id
+    }
+    fn list(&mut self, list: &RecordedDisplayList, base: Matrix) {
+        let base = Save {
+            transform: base,
+            groups: 0,
+        };
+        let base = walk(self, list, base);
+        self.close(base.groups);
+    }
+    fn close(&mut self, groups: usize) {
+        for _ in 0..groups {
+            self.out.push_str("</g>\n");
+        }
+    }
+    /// Open a group that is clipped by the shape. Returns the number of groups opened.
+    fn clip(&mut self, shape: &Shape, transform: &Matrix, op: ClipOperation) -> usize {
+        let id = self.id();
+        let element = shape.element();
+        let transform = transform_attribute(transform);
+        match op {
+            ClipOperation::Intersect => {
+                let _ = writeln!(
+                    self.out,
+                    r#"<clipPath id="c{id}">{element}{transform}/></clipPath>"#
+                );
+                let _ = writeln!(self.out, r#"<g clip-path="url(#c{id})">"#);
+            }
+            ClipOperation::Difference => {
+                let (width, height) = (n(self.size.width), n(self.size.height));
+                let _ = writeln!(
+                    self.out,
+                    r#"<mask id="c{id}" maskUnits="userSpaceOnUse" x="0" y="0" width="{width}" height="{height}"><rect width="{width}" height="{height}" fill="white"/>{element}{transform} fill="black"/></mask>"#
+                );
+                let _ = writeln!(self.out, r#"<g mask="url(#c{id})">"#);
+            }
+        }
+        1
+    }
+    /// Write a shape with a paint. `extra` is added to the attributes of the element.
+    fn draw(&mut self, shape: &Shape, transform: &Matrix, paint: &RecordedPaint, extra: &str) {
+        let filter = self.filter(paint, transform);
+        if !filter.is_empty() {
+            let _ = writeln!(self.out, "<g{filter}>");
+        }
+        let mut bounds = shape.bounds();
+        if paint.draw_style != DrawStyle::Fill {
+            let outset = paint.stroke_width.max(1.0) * paint.stroke_miter.max(1.0);
+            bounds = bounds.inflate(outset, outset);
+        }
+        let color = self.paint_color(paint, &bounds);
+        let mut attributes = String::new();
+        let (fill, stroke) = match paint.draw_style {
+            DrawStyle::Fill => (true, false),
+            DrawStyle::Stroke => (false, true),
+            DrawStyle::StrokeAndFill => (true, true),
+        };
+        if fill {
+            let _ = write!(attributes, r#" fill="{}""#, color.0);
+            if color.1 < 1.0 {
+                let _ = write!(attributes, r#" fill-opacity="{}""#, n(color.1));
+            }
+        } else {
+            attributes.push_str(r#" fill="none""#);
+        }
+        if stroke {
+            let _ = write!(attributes, r#" stroke="{}""#, color.0);
+            if color.1 < 1.0 {
+                let _ = write!(attributes, r#" stroke-opacity="{}""#, n(color.1));
+            }
+            if paint.stroke_width > 0.0 {
+                let _ = write!(attributes, r#" stroke-width="{}""#, n(paint.stroke_width));
+            } else {
+                // a hairline
+                attributes.push_str(r#" stroke-width="1" vector-effect="non-scaling-stroke""#);
+            }
+            attributes.push_str(match paint.stroke_cap {
+                StrokeCap::Butt => "",
+                StrokeCap::Round => r#" stroke-linecap="round""#,
+                StrokeCap::Square => r#" stroke-linecap="square""#,
+            });
+            match paint.stroke_join {
+                StrokeJoin::Miter => {
+                    let _ = write!(
+                        attributes,
+                        r#" stroke-miterlimit="{}""#,
+                        n(paint.stroke_miter)
+                    );
+                }
+                StrokeJoin::Round => attributes.push_str(r#" stroke-linejoin="round""#),
+                StrokeJoin::Bevel => attributes.push_str(r#" stroke-linejoin="bevel""#),
+            }
+        }
+        attributes.push_str(&blend_attribute(paint.blend_mode));
+        let _ = writeln!(
+            self.out,
+            "{}{}{attributes}{extra}/>",
+            shape.element(),
+            transform_attribute(transform)
+        );
+        if !filter.is_empty() {
+            self.close(1);
+        }
+    }
+    /// The fill or stroke of a paint and its opacity. Gradients are added as defs.
+    ///
+    /// - bounds: The local bounds of what is painted. Sweep gradients only cover them.
+    fn paint_color(&mut self, paint: &RecordedPaint, bounds: &Rect) -> (String, f32) {
+        let Some(source) = &paint.color_source else {
+            return (hex(&paint.color), paint.color.alpha);
+        };
+        let id = self.id();
+        let (colors, stops, tile_mode, transformation) = match source.as_ref() {
+            RecordedColorSource::LinearGradient {
+                start,
+                end,
+                colors,
+                stops,
+                tile_mode,
+                transformation,
+            } => {
+                let _ = write!(
+                    self.out,
+                    r#"<linearGradient id="g{id}" gradientUnits="userSpaceOnUse" x1="{}" y1="{}" x2="{}" y2="{}""#,
+                    n(start.x),
+                    n(start.y),
+                    n(end.x),
+                    n(end.y)
+                );
+                (colors, stops, *tile_mode, transformation)
+            }
+            RecordedColorSource::RadialGradient {
+                center,
+                radius,
+                colors,
+                stops,
+                tile_mode,
+                transformation,
+            } => {
+                let _ = write!(
+                    self.out,
+                    r#"<radialGradient id="g{id}" gradientUnits="userSpaceOnUse" cx="{}" cy="{}" r="{}""#,
+                    n(center.x),
+                    n(center.y),
+                    n(*radius)
+                );
+                (colors, stops, *tile_mode, transformation)
+            }
+            RecordedColorSource::ConicalGradient {
+                start_center,
+                start_radius,
+                end_center,
+                end_radius,
+                colors,
+                stops,
+                tile_mode,
+                transformation,
+            } => {
+                let _ = write!(
+                    self.out,
+                    r#"<radialGradient id="g{id}" gradientUnits="userSpaceOnUse" cx="{}" cy="{}" r="{}" fx="{}" fy="{}" fr="{}""#,
+                    n(end_center.x),
+                    n(end_center.y),
+                    n(*end_radius),
+                    n(start_center.x),
+                    n(start_center.y),
+                    n(*start_radius)
+                );
+                (colors, stops, *tile_mode, transformation)
+            }
+            RecordedColorSource::SweepGradient {
+                center,
+                start,
+                end,
+                colors,
+                stops,
+                tile_mode,
+                transformation,
+            } => {
+                self.sweep_pattern(
+                    id,
+                    *center,
+                    *start,
+                    *end,
+                    colors,
+                    stops,
+                    *tile_mode,
+                    transformation.as_ref(),
+                    bounds,
+                );
+                return (format!("url(#g{id})"), paint.color.alpha);
+            }
+            RecordedColorSource::Image {
+                image,
+                horizontal_tile_mode: _,
+                vertical_tile_mode: _,
+                sampling,
+                transformation,
+            } => {
+                let Some(href) = data_uri(image) else {
+                    return (hex(&paint.color), paint.color.alpha);
+                };
+                let _ = write!(
+                    self.out,
+                    r#"<pattern id="g{id}" patternUnits="userSpaceOnUse" width="{}" height="{}""#,
+                    image.width, image.height
+                );
+                if let Some(transformation) = transformation {
+                    let _ = write!(
+                        self.out,
+                        r#" patternTransform="{}""#,
+                        matrix(transformation)
+                    );
+                }
+                let _ = writeln!(
+                    self.out,
+                    r#"><image width="{}" height="{}" xlink:href="{href}"{}/></pattern>"#,
+                    image.width,
+                    image.height,
+                    rendering_attribute(*sampling)
+                );
+                return (format!("url(#g{id})"), paint.color.alpha);
+            }
+            // fragment programs are opaque
+            RecordedColorSource::FragmentProgram { .. } => {
+                return (hex(&paint.color), paint.color.alpha);
+            }
+        };
+        if let Some(transformation) = transformation {
+            let _ = write!(
+                self.out,
+                r#" gradientTransform="{}""#,
+                matrix(transformation)
+            );
+        }
+        self.out.push_str(match tile_mode {
+            // svg has no decal spread, but it only differs outside the gradient
+            TileMode::Clamp | TileMode::Decal => "",
+            TileMode::Repeat => r#" spreadMethod="repeat""#,
+            TileMode::Mirror => r#" spreadMethod="reflect""#,
+        });
+        self.out.push('>');
+        for (i, color) in colors.iter().enumerate() {
+            let offset = match stops.get(i) {
+                Some(stop) => *stop,
+                None => i as f32 / (colors.len() - 1).max(1) as f32,
+            };
+            let _ = write!(
+                self.out,
+                r#"<stop offset="{}" stop-color="{}""#,
+                n(offset),
+                hex(color)
+            );
+            if color.alpha < 1.0 {
+                let _ = write!(self.out, r#" stop-opacity="{}""#, n(color.alpha));
+            }
+            self.out.push_str("/>");
+        }
+        let tag = match source.as_ref() {
+            RecordedColorSource::LinearGradient { .. } => "linearGradient",
+            _ => "radialGradient",
+        };
+        let _ = writeln!(self.out, "</{tag}>");
+        (format!("url(#g{id})"), paint.color.alpha)
+    }
+    /// Svg has no sweep gradients, so they are approximated by a pattern of wedges that
+    /// is large enough to cover the bounds.
+    #[allow(clippy::too_many_arguments)]
+    fn sweep_pattern(
+        &mut self,
+        id: u32,
+        center: Point,
+        start: f32,
+        end: f32,
+        colors: &[Color],
+        stops: &[f32],
+        tile_mode: TileMode,
+        transformation: Option<&Matrix>,
+        bounds: &Rect,
+    ) {
+        let inverse = transformation
+            .and_then(Matrix::inverse)
+            .unwrap_or_else(Matrix::identity);
+        let corners = [
+            bounds.min(),
+            Point::new(bounds.max_x(), bounds.min_y()),
+            bounds.max(),
+            Point::new(bounds.min_x(), bounds.max_y()),
+        ];
+        let radius = corners
+            .iter()
+            .filter_map(|p| inverse.transform_point2d(*p))
+            .map(|p| (p - center).length())
+            .fold(1.0, f32::max)
+            + 1.0;
+        let _ = write!(
+            self.out,
+            r#"<pattern id="g{id}" patternUnits="userSpaceOnUse" x="{}" y="{}" width="{}" height="{}""#,
+            n(center.x - radius),
+            n(center.y - radius),
+            n(radius * 2.0),
+            n(radius * 2.0)
+        );
+        if let Some(transformation) = transformation {
+            let _ = write!(
+                self.out,
+                r#" patternTransform="{}""#,
+                matrix(transformation)
+            );
+        }
+        self.out.push('>');
+        let step = 360.0 / SWEEP_WEDGES as f32;
+        let point = |degrees: f32| {
+            let (sin, cos) = degrees.to_radians().sin_cos();
+            Point::new(center.x + cos * radius, center.y + sin * radius)
+        };
+        // a sweep without width draws nothing, like when rasterizing
+        let wedges = if end == start { 0 } else { SWEEP_WEDGES };
+        for i in 0..wedges {
+            let from = i as f32 * step;
+            let t = (from + step / 2.0 - start) / (end - start);
+            let Some(color) = sample_gradient(colors, stops, tile_mode, t) else {
+                continue;
+            };
+            // overlap the next wedge a little, so there are no hairline gaps
+            let (a, b) = (point(from), point(from + step * 1.5));
+            let _ = write!(
+                self.out,
+                r#"<path d="M{} {} L{} {} L{} {} Z" fill="{}""#,
+                n(center.x),
+                n(center.y),
+                n(a.x),
+                n(a.y),
+                n(b.x),
+                n(b.y),
+                hex(&color)
+            );
+            if color.alpha < 1.0 {
+                let _ = write!(self.out, r#" fill-opacity="{}""#, n(color.alpha));
+            }
+            self.out.push_str("/>");
+        }
+        self.out.push_str("</pattern>\n");
+    }
+    /// A `filter` attribute for the color filter, mask filter and image filter of a paint.
+    /// The filter is added as a def.
+    ///
+    /// Filters are applied in document coordinates, so sigmas and radii are scaled
+    /// by the transform.
+    fn filter(&mut self, paint: &RecordedPaint, transform: &Matrix) -> String {
+        let scale = (
+            transform.m11.hypot(transform.m12),
+            transform.m21.hypot(transform.m22),
+        );
+        let mut primitives = String::new();
+        if let Some(filter) = &paint.color_filter {
+            color_filter(&mut primitives, filter);
+        }
+        if let Some(filter) = &paint.mask_filter {
+            let RecordedMaskFilter::Blur { style, sigma } = filter.as_ref();
+            let _ = write!(
+                primitives,
+                r#"<feGaussianBlur stdDeviation="{} {}" result="blur"/>"#,
+                n(sigma * scale.0),
+                n(sigma * scale.1)
+            );
+            primitives.push_str(match style {
+                BlurStyle::Normal => "",
+                BlurStyle::Solid => {
+                    r#"<feMerge><feMergeNode in="blur"/><feMergeNode in="SourceGraphic"/></feMerge>"#
+                }
+                BlurStyle::Outer => r#"<feComposite in="blur" in2="SourceGraphic" operator="out"/>"#,
+                BlurStyle::Inner => r#"<feComposite in="blur" in2="SourceGraphic" operator="in"/>"#,
+            });
+        }
+        if let Some(filter) = &paint.image_filter {
+            image_filter(&mut primitives, filter, scale);
+        }
+        if primitives.is_empty() {
+            return String::new();
+        }
+        let id = self.id();
+        let _ = writeln!(
+            self.out,
+            r#"<filter id="f{id}" filterUnits="userSpaceOnUse" x="0" y="0" width="{}" height="{}">{primitives}</filter>"#,
+            n(self.size.width),
+            n(self.size.height)
+        );
+        format!(r#" filter="url(#f{id})""#)
+    }
+    fn paragraph(&mut self, paragraph: &RecordedParagraph, point: Point, transform: &Matrix) {
+        let mut runs = paragraph.runs().peekable();
+        if runs.peek().is_none() {
+            return;
+        }
+        let first = runs.peek().and_then(|(style, _)| *style);
+        let direction = first.and_then(|style| style.text_direction);
+        let alignment = first.and_then(|style| style.text_alignment);
+        let (x, anchor) = match (alignment, direction) {
+            (Some(TextAlignment::Center), _) => (point.x + paragraph.width / 2.0, "middle"),
+            (Some(TextAlignment::Right), _)
+            | (Some(TextAlignment::End), Some(TextDirection::LTR) | None)
+            | (Some(TextAlignment::Start) | None, Some(TextDirection::RTL)) => {
+                (point.x + paragraph.width, "end")
+            }
+            _ => (point.x, "start"),
+        };
+        let _ = write!(
+            self.out,
+            r#"<text x="{}" y="{}" text-anchor="{anchor}" xml:space="preserve"{}>"#,
+            n(x),
+            n(point.y + paragraph.alphabetic_baseline),
+            transform_attribute(transform)
+        );
+        for (style, text) in runs {
+            let mut attributes = String::new();
+            let style = style.cloned().unwrap_or_default();
+            let color = style
+                .foreground
+                .map_or(Color::new_srgb(0.0, 0.0, 0.0), |paint| paint.color);
+            let _ = write!(attributes, r#" fill="{}""#, hex(&color));
+            if color.alpha < 1.0 {
+                let _ = write!(attributes, r#" fill-opacity="{}""#, n(color.alpha));
+            }
+            if let Some(family) = &style.font_family {
+                let _ = write!(attributes, r#" font-family="{}""#, escape(family));
+            }
+            // https://github.com/flutter/engine/blob/main/lib/ui/text/paragraph_builder.cc
+            let _ = write!(
+                attributes,
+                r#" font-size="{}""#,
+                n(style.font_size.unwrap_or(14.0))
+            );
+            if let Some(weight) = style.font_weight {
+                let weight = match weight {
+                    FontWeight::Thin => 100,
+                    FontWeight::ExtraLight => 200,
+                    FontWeight::Light => 300,
+                    FontWeight::Regular => 400,
+                    FontWeight::Medium => 500,
+                    FontWeight::SemiBold => 600,
+                    FontWeight::Bold => 700,
+                    FontWeight::ExtraBold => 800,
+                    FontWeight::Black => 900,
+                };
+                let _ = write!(attributes, r#" font-weight="{weight}""#);
+            }
+            if style.font_style == Some(FontStyle::Italic) {
+                attributes.push_str(r#" font-style="italic""#);
+            }
+            if let Some(decoration) = &style.text_decoration {
+                let lines: Vec<_> = [
+                    (TextDecorationType::UNDERLINE, "underline"),
+                    (TextDecorationType::OVERLINE, "overline"),
+                    (TextDecorationType::LINETHROUGH, "line-through"),
+                ]
+                .into_iter()
+                .filter(|(kind, _)| decoration.types.contains(*kind))
+                .map(|(_, name)| name)
+                .collect();
+                if !lines.is_empty() {
+                    let line_style = match decoration.style {
+                        TextDecorationStyle::Solid => "solid",
+                        TextDecorationStyle::Double => "double",
+                        TextDecorationStyle::Dotted => "dotted",
+                        TextDecorationStyle::Dashed => "dashed",
+                        TextDecorationStyle::Wavy => "wavy",
+                    };
+                    let _ = write!(
+                        attributes,
+                        r#" style="text-decoration: {} {line_style} {}""#,
+                        lines.join(" "),
+                        hex(&decoration.color)
+                    );
+                }
+            }
+            let _ = write!(self.out, "<tspan{attributes}>{}</tspan>", escape(text));
+        }
+        self.out.push_str("</text>\n");
+    }
+    fn texture(
+        &mut self,
+        texture: &RecordedTexture,
+        src_rect: &Rect,
+        dst_rect: &Rect,
+        sampling: TextureSampling,
+        paint: Option<&RecordedPaint>,
+        transform: &Matrix,
+    ) {
+        let Some(href) = data_uri(texture) else {
+            self.out
+                .push_str("<!-- the texture pixels were not recorded -->\n");
+            return;
+        };
+        let mut attributes = String::new();
+        let mut filter = String::new();
+        if let Some(paint) = paint {
+            if paint.color.alpha < 1.0 {
+                let _ = write!(attributes, r#" opacity="{}""#, n(paint.color.alpha));
+            }
+            attributes.push_str(&blend_attribute(paint.blend_mode));
+            filter = self.filter(paint, transform);
+        }
+        let _ = writeln!(
+            self.out,
+            r#"<g{filter}{attributes}><g{}><svg x="{}" y="{}" width="{}" height="{}" viewBox="{} {} {} {}" preserveAspectRatio="none"><image width="{}" height="{}" xlink:href="{href}"{}/></svg></g></g>"#,
+            transform_attribute(transform),
+            n(dst_rect.min_x()),
+            n(dst_rect.min_y()),
+            n(dst_rect.width()),
+            n(dst_rect.height()),
+            n(src_rect.min_x()),
+            n(src_rect.min_y()),
+            n(src_rect.width()),
+            n(src_rect.height()),
+            texture.width,
+            texture.height,
+            rendering_attribute(sampling)
+        );
+    }
+}
+
+impl Pass for Writer {
+    type Level = Save;
+    fn transform(save: &mut Save) -> &mut Matrix {
+        &mut save.transform
+    }
+    fn save(&mut self, top: &Save, op: &DisplayOp) -> Save {
+        let transform = top.transform;
+        let DisplayOp::SaveLayer {
+            bounds,
+            paint,
+            backdrop: _,
+        } = op
+        else {
+            return Save {
+                transform,
+                groups: 0,
+            };
+        };
+        // backdrop filters read what is behind the layer, which svg can't express
+        let mut groups = self.clip(&Shape::Rect(*bounds), &transform, ClipOperation::Intersect);
+        if let Some(paint) = paint {
+            let mut attributes = String::new();
+            if paint.color.alpha < 1.0 {
+                let _ = write!(attributes, r#" opacity="{}""#, n(paint.color.alpha));
+            }
+            attributes.push_str(&blend_attribute(paint.blend_mode));
+            attributes.push_str(&self.filter(paint, &transform));
+            if !attributes.is_empty() {
+                let _ = writeln!(self.out, "<g{attributes}>");
+                groups += 1;
+            }
+        }
+        Save { transform, groups }
+    }
+    fn restore(&mut self, save: Save, _parent: &mut Save) {
+        self.close(save.groups);
+    }
+    fn op(&mut self, top: &mut Save, _index: usize, op: &DisplayOp) {
+        let transform = top.transform;
+        match op {
+            DisplayOp::ClipRect { rect, op } => {
+                top.groups += self.clip(&Shape::Rect(*rect), &transform, *op);
+            }
+            DisplayOp::ClipOval { oval_bounds, op } => {
+                top.groups += self.clip(&Shape::Oval(*oval_bounds), &transform, *op);
+            }
+            DisplayOp::ClipRoundedRect { rect, radii, op } => {
+                let mut path = RecordedPath::default();
+                path.add_rounded_rect(rect, radii);
+                top.groups += self.clip(&Shape::Path(path), &transform, *op);
+            }
+            DisplayOp::ClipPath { path, op } => {
+                top.groups += self.clip(&Shape::Path(path.as_ref().clone()), &transform, *op);
+            }
+            DisplayOp::DrawPaint { paint } => {
+                let canvas = Rect::from_size(self.size);
+                let Some(inverse) = transform.inverse() else {
+                    return;
+                };
+                let corners = [
+                    canvas.min(),
+                    Point::new(canvas.max_x(), canvas.min_y()),
+                    canvas.max(),
+                    Point::new(canvas.min_x(), canvas.max_y()),
+                ];
+                let local =
+                    Rect::from_points(corners.iter().filter_map(|p| inverse.transform_point2d(*p)));
+                let fill = RecordedPaint {
+                    draw_style: DrawStyle::Fill,
+                    ..paint.clone()
+                };
+                self.draw(&Shape::Rect(local), &transform, &fill, "");
+            }
+            DisplayOp::DrawLine { from, to, paint } => {
+                let stroke = RecordedPaint {
+                    draw_style: DrawStyle::Stroke,
+                    ..paint.clone()
+                };
+                self.draw(&Shape::Line(*from, *to), &transform, &stroke, "");
+            }
+            DisplayOp::DrawDashedLine {
+                from,
+                to,
+                on_length,
+                off_length,
+                paint,
+            } => {
+                let stroke = RecordedPaint {
+                    draw_style: DrawStyle::Stroke,
+                    ..paint.clone()
+                };
+                let dashes = format!(
+                    r#" stroke-dasharray="{} {}""#,
+                    n(*on_length),
+                    n(*off_length)
+                );
+                self.draw(&Shape::Line(*from, *to), &transform, &stroke, &dashes);
+            }
+            DisplayOp::DrawRect { rect, paint } => {
+                self.draw(&Shape::Rect(*rect), &transform, paint, "");
+            }
+            DisplayOp::DrawOval { oval_bounds, paint } => {
+                self.draw(&Shape::Oval(*oval_bounds), &transform, paint, "");
+            }
+            DisplayOp::DrawRoundedRect { rect, radii, paint } => {
+                let mut path = RecordedPath::default();
+                path.add_rounded_rect(rect, radii);
+                self.draw(&Shape::Path(path), &transform, paint, "");
+            }
+            DisplayOp::DrawRoundedRectDifference {
+                outer_rect,
+                outer_radii,
+                inner_rect,
+                inner_radii,
+                paint,
+            } => {
+                let mut path = RecordedPath::new(FillType::Odd);
+                path.add_rounded_rect(outer_rect, outer_radii)
+                    .add_rounded_rect(inner_rect, inner_radii);
+                self.draw(&Shape::Path(path), &transform, paint, "");
+            }
+            DisplayOp::DrawPath { path, paint } => {
+                self.draw(&Shape::Path(path.as_ref().clone()), &transform, paint, "");
+            }
+            DisplayOp::DrawDisplayList {
+                display_list,
+                opacity,
+            } => {
+                let Some(display_list) = display_list else {
+                    self.out
+                        .push_str("<!-- the display list was not recorded -->\n");
+                    return;
+                };
+                let _ = writeln!(self.out, r#"<g opacity="{}">"#, n(*opacity));
+                self.list(display_list, transform);
+                self.close(1);
+            }
+            DisplayOp::DrawParagraph { paragraph, point } => {
+                self.paragraph(paragraph, *point, &transform);
+            }
+            DisplayOp::DrawShadow {
+                path,
+                color,
+                elevation,
+                occluder_is_transparent: _,
+                device_pixel_ratio,
+            } => {
+                let paint = RecordedPaint {
+                    color: *color,
+                    mask_filter: Some(std::sync::Arc::new(RecordedMaskFilter::Blur {
+                        style: BlurStyle::Normal,
+                        sigma: shadow_sigma(*elevation, *device_pixel_ratio),
+                    })),
+                    ..Default::default()
+                };
+                self.draw(&Shape::Path(path.as_ref().clone()), &transform, &paint, "");
+            }
+            DisplayOp::DrawTexture {
+                texture,
+                point,
+                sampling,
+                paint,
+            } => {
+                let size = Size::new(texture.width as f32, texture.height as f32);
+                let rect = Rect::new(*point, size);
+                self.texture(
+                    texture,
+                    &Rect::from_size(size),
+                    &rect,
+                    *sampling,
+                    Some(paint),
+                    &transform,
+                );
+            }
+            DisplayOp::DrawTextureRect {
+                texture,
+                src_rect,
+                dst_rect,
+                sampling,
+                paint,
+            } => {
+                self.texture(
+                    texture,
+                    src_rect,
+                    dst_rect,
+                    *sampling,
+                    paint.as_ref(),
+                    &transform,
+                );
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Filter primitives for a color filter, applied to the previous result.
+fn color_filter(out: &mut String, filter: &RecordedColorFilter) {
+    match filter {
+        RecordedColorFilter::Matrix(matrix) => {
+            // impeller takes the offsets in 0..255, svg in 0..1
+            let values: Vec<_> = matrix
+                .m
+                .iter()
+                .enumerate()
+                .map(|(i, v)| n(if i % 5 == 4 { v / 255.0 } else { *v }).to_string())
+                .collect();
+            let _ = write!(
+                out,
+                r#"<feColorMatrix type="matrix" values="{}"/>"#,
+                values.join(" ")
+            );
+        }
+        RecordedColorFilter::Blend { color, blend_mode } => {
+            let _ = write!(
+                out,
+                r#"<feFlood flood-color="{}" flood-opacity="{}" result="flood"/>"#,
+                hex(color),
+                n(color.alpha)
+            );
+            // the flood covers everything, so some modes are masked back to what was drawn
+            let (primitive, mask) = match blend_mode {
+                BlendMode::Clear => (r#"<feFlood flood-opacity="0"/>"#.to_string(), false),
+                BlendMode::Destination => (
+                    r#"<feComposite in="SourceGraphic" in2="SourceGraphic" operator="over"/>"#
+                        .to_string(),
+                    false,
+                ),
+                BlendMode::Source => (String::new(), true),
+                BlendMode::SourceOver => (composite("flood", "SourceGraphic", "over"), true),
+                BlendMode::SourceIn => (composite("flood", "SourceGraphic", "in"), false),
+                BlendMode::SourceOut => (composite("flood", "SourceGraphic", "out"), false),
+                BlendMode::SourceATop => (composite("flood", "SourceGraphic", "atop"), false),
+                BlendMode::DestinationOver => (composite("SourceGraphic", "flood", "over"), true),
+                BlendMode::DestinationIn => (composite("SourceGraphic", "flood", "in"), false),
+                BlendMode::DestinationOut => (composite("SourceGraphic", "flood", "out"), false),
+                BlendMode::DestinationATop => (composite("SourceGraphic", "flood", "atop"), true),
+                BlendMode::Xor => (composite("flood", "SourceGraphic", "xor"), true),
+                BlendMode::Plus => (
+                    r#"<feComposite in="flood" in2="SourceGraphic" operator="arithmetic" k2="1" k3="1"/>"#
+                        .to_string(),
+                    true,
+                ),
+                mode => (
+                    format!(
+                        r#"<feBlend in="flood" in2="SourceGraphic" mode="{}"/>"#,
+                        css_blend_mode(*mode).unwrap_or("normal")
+                    ),
+                    true,
+                ),
+            };
+            out.push_str(&primitive);
+            if mask {
+                out.push_str(r#"<feComposite in2="SourceAlpha" operator="in"/>"#);
+            }
+        }
+    }
+}
+fn composite(a: &str, b: &str, operator: &str) -> String {
+    format!(r#"<feComposite in="{a}" in2="{b}" operator="{operator}"/>"#)
+}
+/// Filter primitives for an image filter, applied to the previous result.
+fn image_filter(out: &mut String, filter: &RecordedImageFilter, scale: (f32, f32)) {
+    match filter {
+        RecordedImageFilter::Blur {
+            x_sigma,
+            y_sigma,
+            tile_mode,
+        } => {
+            let edge = match tile_mode {
+                TileMode::Clamp | TileMode::Mirror => "duplicate",
+                TileMode::Repeat => "wrap",
+                TileMode::Decal => "none",
+            };
+            let _ = write!(
+                out,
+                r#"<feGaussianBlur stdDeviation="{} {}" edgeMode="{edge}"/>"#,
+                n(x_sigma * scale.0),
+                n(y_sigma * scale.1)
+            );
+        }
+        RecordedImageFilter::Dilate { x_radius, y_radius } => {
+            let _ = write!(
+                out,
+                r#"<feMorphology operator="dilate" radius="{} {}"/>"#,
+                n(x_radius * scale.0),
+                n(y_radius * scale.1)
+            );
+        }
+        RecordedImageFilter::Erode { x_radius, y_radius } => {
+            let _ = write!(
+                out,
+                r#"<feMorphology operator="erode" radius="{} {}"/>"#,
+                n(x_radius * scale.0),
+                n(y_radius * scale.1)
+            );
+        }
+        RecordedImageFilter::Compose { outer, inner } => {
+            image_filter(out, inner, scale);
+            image_filter(out, outer, scale);
+        }
+        // svg filters can't transform their input, and fragment programs are opaque
+        RecordedImageFilter::Matrix { .. } | RecordedImageFilter::FragmentProgram { .. } => {}
+    }
+}
+/// <https://www.w3.org/TR/compositing-1/#ltblendmodegt>
+fn css_blend_mode(mode: BlendMode) -> Option<&'static str> {
+    Some(match mode {
+        BlendMode::Modulate | BlendMode::Multiply => "multiply",
+        BlendMode::Screen => "screen",
+        BlendMode::Overlay => "overlay",
+        BlendMode::Darken => "darken",
+        BlendMode::Lighten => "lighten",
+        BlendMode::ColorDodge => "color-dodge",
+        BlendMode::ColorBurn => "color-burn",
+        BlendMode::HardLight => "hard-light",
+        BlendMode::SoftLight => "soft-light",
+        BlendMode::Difference => "difference",
+        BlendMode::Exclusion => "exclusion",
+        BlendMode::Hue => "hue",
+        BlendMode::Saturation => "saturation",
+        BlendMode::Color => "color",
+        BlendMode::Luminosity => "luminosity",
+        BlendMode::Plus => "plus-lighter",
+        _ => return None,
+    })
+}
+fn blend_attribute(mode: BlendMode) -> String {
+    match css_blend_mode(mode) {
+        Some(mode) => format!(r#" style="mix-blend-mode: {mode}""#),
+        None => String::new(),
+    }
+}
+fn rendering_attribute(sampling: TextureSampling) -> &'static str {
+    match sampling {
+        TextureSampling::NearestNeighbor => r#" style="image-rendering: pixelated""#,
+        TextureSampling::Linear => "",
+    }
+}
+/// The 2D part of the matrix, as an svg transform. Perspective is dropped.
+fn matrix(m: &Matrix) -> String {
+    format!(
+        "matrix({} {} {} {} {} {})",
+        n(m.m11),
+        n(m.m12),
+        n(m.m21),
+        n(m.m22),
+        n(m.m41),
+        n(m.m42)
+    )
+}
+fn transform_attribute(m: &Matrix) -> String {
+    if *m == Matrix::identity() {
+        String::new()
+    } else {
+        format!(r#" transform="{}""#, matrix(m))
+    }
+}
+fn hex(color: &Color) -> String {
+    let byte = |v: f32| (v.clamp(0.0, 1.0) * 255.0).round() as u8;
+    format!(
+        "#{:02x}{:02x}{:02x}",
+        byte(color.red),
+        byte(color.green),
+        byte(color.blue)
+    )
+}
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+fn data_uri(texture: &RecordedTexture) -> Option<String> {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let png = texture.to_png()?;
+    let mut out = String::from("data:image/png;base64,");
+    for chunk in png.chunks(3) {
+        let bytes = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let bits = u32::from_be_bytes([0, bytes[0], bytes[1], bytes[2]]);
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(bits >> (18 - i * 6)) as usize & 63] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    Some(out)
+}
+/// Avoids writing `-0`.
+fn n(v: f32) -> f32 {
+    if v == 0.0 {
+        0.0
+    } else {
+        v
+    }
+}
+
+impl RecordedDisplayList {
+    /// Export the recording as an svg document of the given size.
+    ///
+    /// Shapes, clips, layers, gradients and blurs map to their svg equivalents, so the
+    /// result can be edited in vector graphics tools. Some things are approximated:
+    ///
+    /// - Sweep gradients are drawn as a pattern of wedges, as svg has none.
+    /// - Paragraphs are written as a single line of `<text>`, and use whatever fonts
+    ///   the svg viewer has.
+    /// - Shadows are drawn as blurred paths.
+    ///
+    /// Backdrop filters, matrix image filters, perspective and fragment programs are
+    /// left out. Textures are embedded as png images if their pixels were recorded.
+    ///
+    /// @see [DisplayList::to_svg](crate::DisplayList::to_svg)
+    pub fn to_svg(&self, size: Size) -> String {
+        let mut writer = Writer {
+            out: String::new(),
+            size,
+            next_id: 0,
+        };
+        let _ = writeln!(
+            writer.out,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" xmlns:xlink="http://www.w3.org/1999/xlink" width="{0}" height="{1}" viewBox="0 0 {0} {1}">"#,
+            n(size.width),
+            n(size.height)
+        );
+        writer.list(self, Matrix::identity());
+        writer.out.push_str("</svg>\n");
+        writer.out
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use super::*;
+
+    fn paint(color: Color) -> RecordedPaint {
+        RecordedPaint {
+            color,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn shapes_clips_and_layers() {
+        let red = Color::new_srgb(1.0, 0.0, 0.0);
+        let rect = Rect::new(Point::new(10.0, 10.0), Size::new(20.0, 30.0));
+        let mut recording = RecordedDisplayList::new(None);
+        recording
+            .push(DisplayOp::Save)
+            .push(DisplayOp::Translate {
+                x_translation: 5.0,
+                y_translation: 0.0,
+            })
+            .push(DisplayOp::ClipOval {
+                oval_bounds: rect,
+                op: ClipOperation::Intersect,
+            })
+            .push(DisplayOp::DrawRect {
+                rect,
+                paint: paint(red),
+            })
+            .push(DisplayOp::Restore)
+            .push(DisplayOp::SaveLayer {
+                bounds: rect,
+                paint: Some(paint(Color::new_srgba(0.0, 0.0, 0.0, 0.5))),
+                backdrop: None,
+            })
+            .push(DisplayOp::DrawOval {
+                oval_bounds: rect,
+                paint: RecordedPaint {
+                    draw_style: DrawStyle::Stroke,
+                    stroke_width: 2.0,
+                    ..paint(red)
+                },
+            })
+            .push(DisplayOp::ClipRect {
+                rect,
+                op: ClipOperation::Difference,
+            });
+        let svg = recording.to_svg(Size::new(100.0, 50.0));
+        assert_eq!(
+            svg,
+            r##"<svg xmlns="http://www.w3.org/2000/svg" xmlns:xlink="http://www.w3.org/1999/xlink" width="100" height="50" viewBox="0 0 100 50">
+<clipPath id="c1"><ellipse cx="20" cy="25" rx="10" ry="15" transform="matrix(1 0 0 1 5 0)"/></clipPath>
+<g clip-path="url(#c1)">
+<rect x="10" y="10" width="20" height="30" transform="matrix(1 0 0 1 5 0)" fill="#ff0000"/>
+</g>
+<clipPath id="c2"><rect x="10" y="10" width="20" height="30"/></clipPath>
+<g clip-path="url(#c2)">
+<g opacity="0.5">
+<ellipse cx="20" cy="25" rx="10" ry="15" fill="none" stroke="#ff0000" stroke-width="2" stroke-miterlimit="4"/>
+<mask id="c3" maskUnits="userSpaceOnUse" x="0" y="0" width="100" height="50"><rect width="100" height="50" fill="white"/><rect x="10" y="10" width="20" height="30" fill="black"/></mask>
+<g mask="url(#c3)">
+</g>
+</g>
+</g>
+</svg>
+"##
+        );
+    }
+
+    #[test]
+    fn gradients_and_filters() {
+        let colors = vec![
+            Color::new_srgb(1.0, 0.0, 0.0),
+            Color::new_srgba(0.0, 0.0, 1.0, 0.5),
+        ];
+        let linear = RecordedColorSource::LinearGradient {
+            start: Point::zero(),
+            end: Point::new(10.0, 0.0),
+            colors: colors.clone(),
+            stops: vec![],
+            tile_mode: TileMode::Mirror,
+            transformation: None,
+        };
+        let sweep = RecordedColorSource::SweepGradient {
+            center: Point::new(5.0, 5.0),
+            start: 0.0,
+            end: 360.0,
+            colors: colors.clone(),
+            stops: vec![0.0, 1.0],
+            tile_mode: TileMode::Clamp,
+            transformation: None,
+        };
+        // draws nothing
+        let degenerate_sweep = RecordedColorSource::SweepGradient {
+            center: Point::new(5.0, 5.0),
+            start: 90.0,
+            end: 90.0,
+            colors,
+            stops: vec![0.0, 1.0],
+            tile_mode: TileMode::Repeat,
+            transformation: None,
+        };
+        let rect = Rect::new(Point::zero(), Size::new(10.0, 10.0));
+        let mut recording = RecordedDisplayList::new(None);
+        recording
+            .push(DisplayOp::Scale {
+                x_scale: 2.0,
+                y_scale: 2.0,
+            })
+            .push(DisplayOp::DrawRect {
+                rect,
+                paint: RecordedPaint {
+                    color_source: Some(Arc::new(linear)),
+                    image_filter: Some(Arc::new(RecordedImageFilter::Blur {
+                        x_sigma: 1.0,
+                        y_sigma: 3.0,
+                        tile_mode: TileMode::Decal,
+                    })),
+                    ..Default::default()
+                },
+            })
+            .push(DisplayOp::DrawRect {
+                rect,
+                paint: RecordedPaint {
+                    color_source: Some(Arc::new(sweep)),
+                    ..Default::default()
+                },
+            })
+            .push(DisplayOp::DrawRect {
+                rect,
+                paint: RecordedPaint {
+                    color_source: Some(Arc::new(degenerate_sweep)),
+                    ..Default::default()
+                },
+            });
+        let svg = recording.to_svg(Size::new(20.0, 20.0));
+        assert!(svg.contains(r##"<filter id="f1" filterUnits="userSpaceOnUse" x="0" y="0" width="20" height="20"><feGaussianBlur stdDeviation="2 6" edgeMode="none"/></filter>
+<g filter="url(#f1)">
+<linearGradient id="g2" gradientUnits="userSpaceOnUse" x1="0" y1="0" x2="10" y2="0" spreadMethod="reflect"><stop offset="0" stop-color="#ff0000"/><stop offset="1" stop-color="#0000ff" stop-opacity="0.5"/></linearGradient>
+<rect x="0" y="0" width="10" height="10" transform="matrix(2 0 0 2 0 0)" fill="url(#g2)"/>
+</g>"##));
+        assert!(svg.contains(r#"<pattern id="g3" patternUnits="userSpaceOnUse""#));
+        assert_eq!(svg.matches("<path d=").count(), SWEEP_WEDGES);
+        assert!(!svg.contains("NaN") && !svg.contains("inf"));
+        // the wedges go from red at 0 degrees to blue
+        let first = svg.find("<path d=").unwrap();
+        assert!(svg[first..].starts_with(r##"<path d="M5 5 L"##));
+        assert!(svg[first..]
+            .split("/>")
+            .next()
+            .unwrap()
+            .contains(r##"fill="#fe0001""##));
+    }
+
+    #[test]
+    fn nested_lists_text_and_textures() {
+        let mut nested = RecordedDisplayList::new(None);
+        nested
+            .push(DisplayOp::ResetTransform)
+            .push(DisplayOp::DrawTexture {
+                texture: Arc::new(RecordedTexture::new_rgba8(1, 1, vec![255; 4]).unwrap()),
+                point: Point::new(1.0, 2.0),
+                sampling: TextureSampling::NearestNeighbor,
+                paint: RecordedPaint::default(),
+            });
+        let paragraph = RecordedParagraph {
+            items: vec![crate::ParagraphItem::Text("a < b".into())],
+            alphabetic_baseline: 12.0,
+            ..Default::default()
+        };
+        let mut recording = RecordedDisplayList::new(None);
+        recording
+            .push(DisplayOp::Translate {
+                x_translation: 3.0,
+                y_translation: 4.0,
+            })
+            .push(DisplayOp::DrawDisplayList {
+                display_list: Some(Arc::new(nested)),
+                opacity: 0.25,
+            })
+            .push(DisplayOp::DrawDisplayList {
+                display_list: None,
+                opacity: 1.0,
+            })
+            .push(DisplayOp::DrawParagraph {
+                paragraph: Arc::new(paragraph),
+                point: Point::zero(),
+            });
+        let svg = recording.to_svg(Size::new(10.0, 10.0));
+        // the nested reset goes back to the transform the list was drawn with
+        assert!(svg.contains(r#"<g opacity="0.25">
+<g><g transform="matrix(1 0 0 1 3 4)"><svg x="1" y="2" width="1" height="1" viewBox="0 0 1 1" preserveAspectRatio="none"><image width="1" height="1" xlink:href="data:image/png;base64,iVBORw0KGgo"#));
+        assert!(svg.contains("<!-- the display list was not recorded -->"));
+        assert!(svg.contains(r##"<text x="0" y="12" text-anchor="start" xml:space="preserve" transform="matrix(1 0 0 1 3 4)"><tspan fill="#000000" font-size="14">a &lt; b</tspan></text>"##));
+    }
+}
@@ -0,0 +1,15 @@
+//! Helpers shared by the unit tests.
+use crate::{Color, Point, Rect, Size};
+
+/// The rect at `x`, `y` with the size.
+pub(crate) fn rect(x: f32, y: f32, width: f32, height: f32) -> Rect {
+    Rect::new(Point::new(x, y), Size::new(width, height))
+}
+/// Whether all components of two colors, and their alpha, are within 1e-3 of each other.
+///
+/// The color spaces are not compared.
+pub(crate) fn close(a: Color, b: Color) -> bool {
+    let a = [a.red, a.green, a.blue, a.alpha];
+    let b = [b.red, b.green, b.blue, b.alpha];
+    a.iter().zip(b).all(|(a, b)| (a - b).abs() < 1e-3)
+}
@@ -18,6 +18,8 @@
 //!
 //! [DisplayListBuilder] acts like a canvas on to which we "draw" using methods like [DisplayListBuilder::draw_rect].
 //! Internally, it builds up a list of draw commands, so that we can execute those commands later.
+//! Drawing code can also be generic over the [Canvas] trait, which [RecordedDisplayList] implements
+//! too, so that it can be tested without impeller.
 //!
 //! The draw command is modified by three things before being added to the list:
 //! 1. **Transformation**: decides the final position/size/shape of the draw command.
@@ -108,7 +110,14 @@
 #![warn(missing_docs)]
 #![warn(clippy::missing_safety_doc)]
 
+mod canvas;
 mod color;
+mod display_list;
+#[cfg(test)]
+mod fixtures;
+mod paint;
+mod paragraph;
+mod path;
 #[cfg(all(feature = "sys", not(target_os = "windows")))]
 #[allow(missing_docs)]
 pub mod sys;
@@ -128,6 +137,7 @@ pub mod sys {
 }
 
 use std::borrow::Cow;
+use std::sync::Arc;
 
 use bytemuck::cast;
 use bytemuck::cast_ref;
@@ -190,6 +200,23 @@ pub use sys::TextureSampling;
 /// <https://api.flutter.dev/flutter/dart-ui/TileMode.html>
 pub use sys::TileMode;
 
+pub use canvas::Canvas;
+pub use color::{Hsl, Hsv, InterpolationSpace, Lab, LinearSrgb, Oklab, Oklch};
+use display_list::Recording;
+pub use display_list::{
+    DamageTracker, DisplayListStats, DisplayOp, HitResult, RecordedDisplayList,
+};
+pub use paint::{
+    RecordedColorFilter, RecordedColorSource, RecordedImageFilter, RecordedMaskFilter,
+    RecordedPaint, RecordedTexture,
+};
+pub use paragraph::{
+    ParagraphItem, RecordedParagraph, RecordedParagraphStyle, RecordedTextDecoration,
+};
+pub use path::{
+    ContourMeasure, ParseError, PathIter, PathMeasure, PathOp, PathSegment, PathVerb, Polyline,
+    RecordedPath,
+};
 pub use sys::{
     ImpellerColor as Color, ImpellerColorMatrix as ColorMatrix,
     ImpellerContextVulkanInfo as VulkanInfo, ImpellerRange as Range,
@@ -199,6 +226,8 @@ pub type Rect = euclid::Rect<f32, euclid::UnknownUnit>;
 #[allow(missing_docs)]
 pub type Point = euclid::Point2D<f32, euclid::UnknownUnit>;
 #[allow(missing_docs)]
+pub type Vector = euclid::Vector2D<f32, euclid::UnknownUnit>;
+#[allow(missing_docs)]
 pub type ISize = euclid::Size2D<i64, euclid::UnknownUnit>;
 #[allow(missing_docs)]
 pub type Size = euclid::Size2D<f32, euclid::UnknownUnit>;
@@ -500,11 +529,79 @@ impl Context {
         if contents.len() != total_bytes {
             return Err("provided buffer size does not match expected size");
         }
-        let mip_count = flutter_mip_count(width as f32, height as f32);
-
         let t = unsafe {
             // SAFETY: pass the mapping with the right user_data returned from the function.
             let (mapping, user_data) = sys::ImpellerMapping::from_cow(contents);
+            self.create_texture_with_mapping(&mapping, user_data, width, height)
+        };
+        if t.is_null() {
+            Err("ImpellerTextureCreateWithContentsNew returned null")
+        } else {
+            Ok(Texture(
+                t,
+                Recording::new(|| {
+                    Arc::new(RecordedTexture {
+                        width,
+                        height,
+                        pixels: None,
+                    })
+                }),
+            ))
+        }
+    }
+    /// Create a texture from a [RecordedTexture] that has its pixels.
+    ///
+    /// Unlike [Self::create_texture_with_rgba8], the pixels are kept alive by the
+    /// [Texture::recording] of the returned texture, so that display lists which draw
+    /// it can be [encoded](RecordedDisplayList::to_bytes) along with their textures.
+    ///
+    /// @return     The texture if one can be created using the provided data, an error
+    ///             otherwise.
+    ///
+    /// # Safety
+    ///
+    /// * The texture must be dropped before the context is dropped
+    ///
+    #[doc(alias = "ImpellerTextureCreateWithContentsNew")]
+    pub unsafe fn create_texture_from_recording(
+        &self,
+        texture: &Arc<RecordedTexture>,
+    ) -> Result<Texture, &'static str> {
+        let RecordedTexture {
+            width,
+            height,
+            pixels,
+        } = texture.as_ref();
+        let (width, height) = (*width, *height);
+        let pixels = pixels.as_ref().ok_or("texture pixels were not recorded")?;
+        if width == 0 || height == 0 {
+            return Err("width and height must be greater than zero");
+        }
+        if pixels.len() != width as usize * height as usize * 4 {
+            return Err("provided buffer size does not match expected size");
+        }
+        let t = unsafe {
+            // SAFETY: pass the mapping with the right user_data returned from the function.
+            let (mapping, user_data) = sys::ImpellerMapping::from_arc(pixels.clone());
+            self.create_texture_with_mapping(&mapping, user_data, width, height)
+        };
+        if t.is_null() {
+            Err("ImpellerTextureCreateWithContentsNew returned null")
+        } else {
+            Ok(Texture(t, Recording::new(|| texture.clone())))
+        }
+    }
+    /// # Safety
+    /// `mapping` and `user_data` must come from the same `sys::ImpellerMapping` helper.
+    unsafe fn create_texture_with_mapping(
+        &self,
+        mapping: &sys::ImpellerMapping,
+        user_data: *mut std::ffi::c_void,
+        width: u32,
+        height: u32,
+    ) -> sys::ImpellerTexture {
+        let mip_count = flutter_mip_count(width as f32, height as f32);
+        unsafe {
             sys::ImpellerTextureCreateWithContentsNew(
                 self.0,
                 &sys::ImpellerTextureDescriptor {
@@ -512,14 +609,9 @@ impl Context {
                     pixel_format: PixelFormat::RGBA8888,
                     mip_count,
                 },
-                &mapping,
+                mapping,
                 user_data,
             )
-        };
-        if t.is_null() {
-            Err("ImpellerTextureCreateWithContentsNew returned null")
-        } else {
-            Ok(Texture(t))
         }
     }
 
@@ -572,7 +664,16 @@ impl Context {
         if t.is_null() {
             None
         } else {
-            Some(Texture(t))
+            Some(Texture(
+                t,
+                Recording::new(|| {
+                    Arc::new(RecordedTexture {
+                        width,
+                        height,
+                        pixels: None,
+                    })
+                }),
+            ))
         }
     }
     //------------------------------------------------------------------------------
@@ -726,6 +827,12 @@ impl Context {
         samplers: &[Texture],
         uniform_data: &[u8],
     ) -> ColorSource {
+        let recording = Recording::new(|| {
+            Arc::new(RecordedColorSource::FragmentProgram {
+                samplers: samplers.iter().map(|t| t.1.get().clone()).collect(),
+                uniform_data: uniform_data.to_vec(),
+            })
+        });
         let samplers_len = samplers.len();
         let mut samplers: Vec<sys::ImpellerTexture> = samplers.iter().map(|t| t.0).collect();
         let cs = unsafe {
@@ -743,7 +850,7 @@ impl Context {
             )
         };
         assert!(!cs.is_null());
-        ColorSource(cs)
+        ColorSource(cs, recording)
     }
     /// Create an image filter where each pixel is shaded by a fragment program.
     ///
@@ -759,6 +866,12 @@ impl Context {
         samplers: &[Texture],
         uniform_data: &[u8],
     ) -> ImageFilter {
+        let recording = Recording::new(|| {
+            Arc::new(RecordedImageFilter::FragmentProgram {
+                samplers: samplers.iter().map(|t| t.1.get().clone()).collect(),
+                uniform_data: uniform_data.to_vec(),
+            })
+        });
         let samplers_len = samplers.len();
         let mut samplers: Vec<sys::ImpellerTexture> = samplers.iter().map(|t| t.0).collect();
         let cs = unsafe {
@@ -776,7 +889,7 @@ impl Context {
             )
         };
         assert!(!cs.is_null());
-        ImageFilter(cs)
+        ImageFilter(cs, recording)
     }
 }
 
@@ -786,10 +899,12 @@ impl Context {
 /// While it is perfectly fine to create new display lists per frame, there may
 /// be opportunities for optimization when display lists are reused multiple
 /// times.
+///
+/// Display lists built by a [recording](DisplayListBuilder::new_recording) builder
+/// also carry the [DisplayOp]s that were recorded, which can be read back with [Self::ops].
 #[derive(Debug)]
-#[repr(transparent)]
 #[doc(alias = "ImpellerDisplayList")]
-pub struct DisplayList(sys::ImpellerDisplayList);
+pub struct DisplayList(sys::ImpellerDisplayList, Option<Arc<RecordedDisplayList>>);
 unsafe impl Send for DisplayList {}
 unsafe impl Sync for DisplayList {}
 impl Clone for DisplayList {
@@ -798,7 +913,7 @@ impl Clone for DisplayList {
         unsafe {
             sys::ImpellerDisplayListRetain(self.0);
         }
-        Self(self.0)
+        Self(self.0, self.1.clone())
     }
 }
 
@@ -809,6 +924,157 @@ impl Drop for DisplayList {
         }
     }
 }
+impl DisplayList {
+    /// The ops that were recorded while building this display list.
+    ///
+    /// Returns `None` if the builder was not in recording mode.
+    ///
+    /// ```rust,no_run
+    /// # use impellers::*;
+    /// let mut builder = DisplayListBuilder::new_recording(None);
+    /// let mut paint = Paint::default();
+    /// paint.set_color(Color::new_srgb(1.0, 0.0, 0.0));
+    /// let rect = Rect::new(Point::new(10.0, 10.0), Size::new(50.0, 50.0));
+    /// builder.draw_rect(&rect, &paint);
+    /// let display_list = builder.build().unwrap();
+    /// let ops = display_list.ops().unwrap();
+    /// assert!(matches!(&ops[0], DisplayOp::DrawRect { rect: r, paint } if *r == rect && paint.color.red == 1.0));
+    /// ```
+    #[cfg(feature = "recording")]
+    pub fn ops(&self) -> Option<&[DisplayOp]> {
+        self.1.as_ref().map(|recording| recording.ops())
+    }
+    /// The recording of this display list, if it was built by a recording builder.
+    ///
+    /// @see [Self::ops]
+    #[cfg(feature = "recording")]
+    pub fn recording(&self) -> Option<&Arc<RecordedDisplayList>> {
+        self.1.as_ref()
+    }
+    /// Encode the recording of this display list.
+    ///
+    /// Fails if the builder was not in recording mode.
+    ///
+    /// @see [RecordedDisplayList::to_bytes]
+    #[cfg(feature = "recording")]
+    pub fn to_bytes(&self) -> Result<Vec<u8>, &'static str> {
+        self.1
+            .as_ref()
+            .ok_or("the display list was not recorded")?
+            .to_bytes()
+    }
+    /// Export the recording of this display list as an svg document of the given size.
+    ///
+    /// Fails if the builder was not in recording mode.
+    ///
+    /// @see [RecordedDisplayList::to_svg]
+    #[cfg(feature = "recording")]
+    pub fn to_svg(&self, size: Size) -> Result<String, &'static str> {
+        Ok(self
+            .1
+            .as_ref()
+            .ok_or("the display list was not recorded")?
+            .to_svg(size))
+    }
+    /// Draw the recording of this display list on the CPU.
+    ///
+    /// Fails if the builder was not in recording mode.
+    ///
+    /// @see [RecordedDisplayList::rasterize]
+    #[cfg(feature = "recording")]
+    pub fn rasterize(&self, width: u32, height: u32) -> Result<RecordedTexture, &'static str> {
+        Ok(self
+            .1
+            .as_ref()
+            .ok_or("the display list was not recorded")?
+            .rasterize(width, height))
+    }
+    /// A readable listing of the recorded ops, indented by save depth.
+    ///
+    /// Fails if the builder was not in recording mode.
+    ///
+    /// @see [RecordedDisplayList]'s [Display](std::fmt::Display) implementation
+    #[cfg(feature = "recording")]
+    pub fn dump(&self) -> Result<String, &'static str> {
+        Ok(self
+            .1
+            .as_ref()
+            .ok_or("the display list was not recorded")?
+            .to_string())
+    }
+    /// Conservative bounds of what the recording of this display list draws.
+    ///
+    /// Fails if the builder was not in recording mode.
+    ///
+    /// @see [RecordedDisplayList::bounds]
+    #[cfg(feature = "recording")]
+    pub fn bounds(&self) -> Result<Option<Rect>, &'static str> {
+        Ok(self
+            .1
+            .as_ref()
+            .ok_or("the display list was not recorded")?
+            .bounds())
+    }
+    /// A new display list without the ops that can't change what is drawn inside
+    /// the viewport.
+    ///
+    /// The culled recording is replayed into a new display list, which is recorded too.
+    ///
+    /// - viewport:    The area that will be shown, in the coordinates of this display list.
+    /// - context:     Needed if the recording has textures in it.
+    /// - typography:  Needed if the recording has paragraphs in it. The fonts that the
+    ///   paragraphs use must be registered with it.
+    ///
+    /// Fails if the builder was not in recording mode, or the culled recording can't be
+    /// replayed.
+    ///
+    /// @see [RecordedDisplayList::culled]
+    /// @see [RecordedDisplayList::to_display_list]
+    ///
+    /// # Safety
+    ///
+    /// * If textures are created, the display list must be dropped before the context is dropped
+    #[cfg(feature = "recording")]
+    pub unsafe fn culled(
+        &self,
+        viewport: &Rect,
+        context: Option<&Context>,
+        typography: Option<&TypographyContext>,
+    ) -> Result<DisplayList, &'static str> {
+        let recording = self.1.as_ref().ok_or("the display list was not recorded")?;
+        unsafe {
+            recording
+                .culled(viewport)
+                .to_display_list(context, typography)
+        }
+    }
+    /// The recorded ops that draw at a point, topmost first.
+    ///
+    /// Fails if the builder was not in recording mode.
+    ///
+    /// @see [RecordedDisplayList::hit_test] and [DisplayListBuilder::set_tag]
+    #[cfg(feature = "recording")]
+    pub fn hit_test(&self, point: Point) -> Result<Vec<HitResult>, &'static str> {
+        Ok(self
+            .1
+            .as_ref()
+            .ok_or("the display list was not recorded")?
+            .hit_test(point))
+    }
+    /// Op counts and a rough cost estimate of the recording of this display list.
+    ///
+    /// Fails if the builder was not in recording mode.
+    ///
+    /// @see [RecordedDisplayList::stats]
+    #[cfg(feature = "recording")]
+    pub fn stats(&self) -> Result<DisplayListStats, &'static str> {
+        Ok(self
+            .1
+            .as_ref()
+            .ok_or("the display list was not recorded")?
+            .stats())
+    }
+}
 /// Display list builders allow for the incremental creation of display lists.
 ///
 /// Display list builders are context-agnostic.
@@ -878,10 +1144,17 @@ impl Drop for DisplayList {
 /// For example, [Self::draw_rect] draws a rectangle. But whether it is a filled rect or just
 /// a stroked (bordered) rect is decided by the paint's [Paint::set_draw_style].
 ///
+/// ### Recording
+///
+/// With the `recording` feature, a builder created with [Self::new_recording] also records
+/// every call as a [DisplayOp], with the paints, paths and other objects captured at the time
+/// of the call.
+/// The ops end up in the built [DisplayList] (see [DisplayList::ops]), so you can check
+/// what was drawn without a GPU context. Getters like [Self::get_transform] are not recorded.
+///
 #[derive(Debug)]
-#[repr(transparent)]
 #[doc(alias = "ImpellerDisplayListBuilder")]
-pub struct DisplayListBuilder(sys::ImpellerDisplayListBuilder);
+pub struct DisplayListBuilder(sys::ImpellerDisplayListBuilder, Option<RecordedDisplayList>);
 
 unsafe impl Send for DisplayListBuilder {}
 unsafe impl Sync for DisplayListBuilder {}
@@ -909,12 +1182,41 @@ impl DisplayListBuilder {
             sys::ImpellerDisplayListBuilderNew(cull_rect.map_or(std::ptr::null(), |r| cast_ref(r)))
         };
         assert!(!result.is_null(), "Failed to create display list builder");
-        Self(result)
+        Self(result, None)
+    }
+    /// Create a new display list builder that also records every call as a [DisplayOp].
+    ///
+    /// Needs the `recording` feature, so that paints and other objects keep a copy of their
+    /// state to record.
+    ///
+    /// @see [Self::new] and [DisplayList::ops]
+    ///
+    /// - cull_rect:    The cull rectangle or NULL.
+    ///
+    /// @return         The recording display list builder.
+    #[cfg(feature = "recording")]
+    pub fn new_recording(cull_rect: Option<&Rect>) -> Self {
+        let mut builder = Self::new(cull_rect);
+        builder.1 = Some(RecordedDisplayList::new(cull_rect.copied()));
+        builder
+    }
+    /// Whether this builder was created with [Self::new_recording].
+    pub fn is_recording(&self) -> bool {
+        self.1.is_some()
+    }
+    /// Record an op if this builder is recording.
+    fn record(&mut self, op: impl FnOnce() -> DisplayOp) {
+        if let Some(recording) = &mut self.1 {
+            recording.push(op());
+        }
     }
     //------------------------------------------------------------------------------
     /// Create a new display list using the rendering intent already
     /// encoded in the builder. The builder is reset after this call.
     ///
+    /// If the builder is recording, the recorded ops move into the display list
+    /// and the builder keeps recording from scratch.
+    ///
     /// @return     The display list.
     #[must_use]
     #[doc(alias = "ImpellerDisplayListBuilderCreateDisplayListNew")]
@@ -923,7 +1225,8 @@ impl DisplayListBuilder {
         if d.is_null() {
             None
         } else {
-            Some(DisplayList(d))
+            let recording = self.1.as_mut().map(|r| Arc::new(r.take()));
+            Some(DisplayList(d, recording))
         }
     }
     //------------------------------------------------------------------------------
@@ -938,6 +1241,7 @@ impl DisplayListBuilder {
         unsafe {
             sys::ImpellerDisplayListBuilderSave(self.0);
         }
+        self.record(|| DisplayOp::Save);
         self
     }
     //------------------------------------------------------------------------------
@@ -972,6 +1276,11 @@ impl DisplayListBuilder {
                 backdrop.map_or(std::ptr::null_mut(), |b| b.0),
             );
         }
+        self.record(|| DisplayOp::SaveLayer {
+            bounds: *bounds,
+            paint: paint.map(|p| p.1.get().clone()),
+            backdrop: backdrop.map(|b| b.1.get().clone()),
+        });
         self
     }
 
@@ -983,6 +1292,7 @@ impl DisplayListBuilder {
         unsafe {
             sys::ImpellerDisplayListBuilderRestore(self.0);
         }
+        self.record(|| DisplayOp::Restore);
         self
     }
     //------------------------------------------------------------------------------
@@ -998,6 +1308,7 @@ impl DisplayListBuilder {
         unsafe {
             sys::ImpellerDisplayListBuilderScale(self.0, x_scale, y_scale);
         }
+        self.record(|| DisplayOp::Scale { x_scale, y_scale });
         self
     }
     //------------------------------------------------------------------------------
@@ -1012,6 +1323,7 @@ impl DisplayListBuilder {
         unsafe {
             sys::ImpellerDisplayListBuilderRotate(self.0, angle_degrees);
         }
+        self.record(|| DisplayOp::Rotate { angle_degrees });
         self
     }
     /// Apply a translation to the transformation matrix currently on
@@ -1026,6 +1338,10 @@ impl DisplayListBuilder {
         unsafe {
             sys::ImpellerDisplayListBuilderTranslate(self.0, x_translation, y_translation);
         }
+        self.record(|| DisplayOp::Translate {
+            x_translation,
+            y_translation,
+        });
         self
     }
     //------------------------------------------------------------------------------
@@ -1040,6 +1356,7 @@ impl DisplayListBuilder {
         unsafe {
             sys::ImpellerDisplayListBuilderTransform(self.0, cast_ref(transform));
         }
+        self.record(|| DisplayOp::Transform(*transform));
         self
     }
 
@@ -1057,6 +1374,7 @@ impl DisplayListBuilder {
         unsafe {
             sys::ImpellerDisplayListBuilderSetTransform(self.0, cast_ref(transform));
         }
+        self.record(|| DisplayOp::SetTransform(*transform));
         self
     }
     //------------------------------------------------------------------------------
@@ -1089,6 +1407,7 @@ impl DisplayListBuilder {
         unsafe {
             sys::ImpellerDisplayListBuilderResetTransform(self.0);
         }
+        self.record(|| DisplayOp::ResetTransform);
         self
     }
     //------------------------------------------------------------------------------
@@ -1113,6 +1432,23 @@ impl DisplayListBuilder {
         unsafe {
             sys::ImpellerDisplayListBuilderRestoreToCount(self.0, count);
         }
+        self.record(|| DisplayOp::RestoreToCount(count));
+        self
+    }
+    //------------------------------------------------------------------------------
+    /// Tag the ops that follow with an id of your own, like the id of a widget.
+    ///
+    /// Tags only exist in the recording, impeller never sees them. They are saved
+    /// and restored along with the transform, and nested display lists start out
+    /// with the tag they are drawn with.
+    ///
+    /// Does nothing if the builder is not in recording mode.
+    ///
+    /// @see [DisplayList::hit_test]
+    ///
+    /// - tag    The tag.
+    pub fn set_tag(&mut self, tag: u64) -> &mut Self {
+        self.record(|| DisplayOp::SetTag(tag));
         self
     }
     //------------------------------------------------------------------------------
@@ -1134,6 +1470,7 @@ impl DisplayListBuilder {
         unsafe {
             sys::ImpellerDisplayListBuilderClipRect(self.0, cast_ref(rect), op);
         }
+        self.record(|| DisplayOp::ClipRect { rect: *rect, op });
         self
     }
 
@@ -1148,6 +1485,10 @@ impl DisplayListBuilder {
         unsafe {
             sys::ImpellerDisplayListBuilderClipOval(self.0, cast_ref(oval_bounds), op);
         }
+        self.record(|| DisplayOp::ClipOval {
+            oval_bounds: *oval_bounds,
+            op,
+        });
         self
     }
     //------------------------------------------------------------------------------
@@ -1177,6 +1518,11 @@ impl DisplayListBuilder {
                 op,
             );
         }
+        self.record(|| DisplayOp::ClipRoundedRect {
+            rect: *rect,
+            radii: *radii,
+            op,
+        });
         self
     }
     //------------------------------------------------------------------------------
@@ -1196,6 +1542,10 @@ impl DisplayListBuilder {
         unsafe {
             sys::ImpellerDisplayListBuilderClipPath(self.0, path.0, op);
         }
+        self.record(|| DisplayOp::ClipPath {
+            path: path.1.clone(),
+            op,
+        });
         self
     }
     //------------------------------------------------------------------------------
@@ -1211,6 +1561,9 @@ impl DisplayListBuilder {
         unsafe {
             sys::ImpellerDisplayListBuilderDrawPaint(self.0, paint.0);
         }
+        self.record(|| DisplayOp::DrawPaint {
+            paint: paint.1.get().clone(),
+        });
         self
     }
     //------------------------------------------------------------------------------
@@ -1233,6 +1586,11 @@ impl DisplayListBuilder {
                 paint.0,
             );
         }
+        self.record(|| DisplayOp::DrawLine {
+            from,
+            to,
+            paint: paint.1.get().clone(),
+        });
         self
     }
 
@@ -1263,6 +1621,13 @@ impl DisplayListBuilder {
                 paint.0,
             );
         }
+        self.record(|| DisplayOp::DrawDashedLine {
+            from,
+            to,
+            on_length,
+            off_length,
+            paint: paint.1.get().clone(),
+        });
         self
     }
 
@@ -1278,6 +1643,10 @@ impl DisplayListBuilder {
         unsafe {
             sys::ImpellerDisplayListBuilderDrawRect(self.0, cast_ref(rect), paint.0);
         }
+        self.record(|| DisplayOp::DrawRect {
+            rect: *rect,
+            paint: paint.1.get().clone(),
+        });
         self
     }
     //------------------------------------------------------------------------------
@@ -1294,6 +1663,10 @@ impl DisplayListBuilder {
         unsafe {
             sys::ImpellerDisplayListBuilderDrawOval(self.0, cast_ref(oval_bounds), paint.0);
         }
+        self.record(|| DisplayOp::DrawOval {
+            oval_bounds: *oval_bounds,
+            paint: paint.1.get().clone(),
+        });
         self
     }
     //------------------------------------------------------------------------------
@@ -1321,6 +1694,11 @@ impl DisplayListBuilder {
                 paint.0,
             );
         }
+        self.record(|| DisplayOp::DrawRoundedRect {
+            rect: *rect,
+            radii: *radii,
+            paint: paint.1.get().clone(),
+        });
         self
     }
     //------------------------------------------------------------------------------
@@ -1353,6 +1731,13 @@ impl DisplayListBuilder {
                 paint.0,
             );
         }
+        self.record(|| DisplayOp::DrawRoundedRectDifference {
+            outer_rect: *outer_rect,
+            outer_radii: *outer_radii,
+            inner_rect: *inner_rect,
+            inner_radii: *inner_radii,
+            paint: paint.1.get().clone(),
+        });
         self
     }
     //------------------------------------------------------------------------------
@@ -1367,6 +1752,10 @@ impl DisplayListBuilder {
         unsafe {
             sys::ImpellerDisplayListBuilderDrawPath(self.0, path.0, paint.0);
         }
+        self.record(|| DisplayOp::DrawPath {
+            path: path.1.clone(),
+            paint: paint.1.get().clone(),
+        });
         self
     }
     //------------------------------------------------------------------------------
@@ -1384,6 +1773,10 @@ impl DisplayListBuilder {
         unsafe {
             sys::ImpellerDisplayListBuilderDrawDisplayList(self.0, display_list.0, opacity);
         }
+        self.record(|| DisplayOp::DrawDisplayList {
+            display_list: display_list.1.clone(),
+            opacity,
+        });
         self
     }
     //------------------------------------------------------------------------------
@@ -1400,6 +1793,10 @@ impl DisplayListBuilder {
         unsafe {
             sys::ImpellerDisplayListBuilderDrawParagraph(self.0, paragraph.0, cast_ref(&point));
         }
+        self.record(|| DisplayOp::DrawParagraph {
+            paragraph: paragraph.1.get().clone(),
+            point,
+        });
         self
     }
 
@@ -1433,6 +1830,13 @@ impl DisplayListBuilder {
                 device_pixel_ratio,
             );
         }
+        self.record(|| DisplayOp::DrawShadow {
+            path: path.1.clone(),
+            color: *color,
+            elevation,
+            occluder_is_transparent,
+            device_pixel_ratio,
+        });
         self
     }
     //------------------------------------------------------------------------------
@@ -1471,6 +1875,12 @@ impl DisplayListBuilder {
                 paint.0,
             );
         }
+        self.record(|| DisplayOp::DrawTexture {
+            texture: texture.1.get().clone(),
+            point,
+            sampling,
+            paint: paint.1.get().clone(),
+        });
         self
     }
     //------------------------------------------------------------------------------
@@ -1507,6 +1917,13 @@ impl DisplayListBuilder {
                 paint.map_or(std::ptr::null_mut(), |p| p.0),
             );
         }
+        self.record(|| DisplayOp::DrawTextureRect {
+            texture: texture.1.get().clone(),
+            src_rect: *src_rect,
+            dst_rect: *dst_rect,
+            sampling,
+            paint: paint.map(|p| p.1.get().clone()),
+        });
         self
     }
 }
@@ -1523,10 +1940,12 @@ impl DisplayListBuilder {
 /// <https://shopify.github.io/react-native-skia/docs/paint/properties>
 ///
 /// <https://learn.microsoft.com/en-us/dotnet/api/skiasharp.skpaint?view=skiasharp-2.88>
+///
+/// With the `recording` feature, every setter is also recorded in a [RecordedPaint]
+/// (see [Self::recording]).
 #[derive(Debug)]
-#[repr(transparent)]
 #[doc(alias = "ImpellerPaint")]
-pub struct Paint(sys::ImpellerPaint);
+pub struct Paint(sys::ImpellerPaint, Recording<RecordedPaint>);
 
 unsafe impl Send for Paint {}
 unsafe impl Sync for Paint {}
@@ -1542,10 +1961,15 @@ impl Default for Paint {
     fn default() -> Self {
         let p = unsafe { sys::ImpellerPaintNew() };
         assert!(!p.is_null());
-        Self(p)
+        Self(p, Recording::new(RecordedPaint::default))
     }
 }
 impl Paint {
+    /// The state of this paint, as set by its setters.
+    #[cfg(feature = "recording")]
+    pub fn recording(&self) -> &RecordedPaint {
+        self.1.get()
+    }
     /// Set the paint color for stroking or filling.
     ///
     /// <https://api.flutter.dev/flutter/dart-ui/Paint/color.html>
@@ -1556,6 +1980,7 @@ impl Paint {
         unsafe {
             sys::ImpellerPaintSetColor(self.0, &color);
         }
+        self.1.update(|paint| paint.color = color);
         self
     }
 
@@ -1569,6 +1994,7 @@ impl Paint {
         unsafe {
             sys::ImpellerPaintSetBlendMode(self.0, mode);
         }
+        self.1.update(|paint| paint.blend_mode = mode);
         self
     }
 
@@ -1581,6 +2007,7 @@ impl Paint {
         unsafe {
             sys::ImpellerPaintSetDrawStyle(self.0, style);
         }
+        self.1.update(|paint| paint.draw_style = style);
         self
     }
 
@@ -1592,6 +2019,7 @@ impl Paint {
         unsafe {
             sys::ImpellerPaintSetStrokeCap(self.0, cap);
         }
+        self.1.update(|paint| paint.stroke_cap = cap);
         self
     }
 
@@ -1605,6 +2033,7 @@ impl Paint {
         unsafe {
             sys::ImpellerPaintSetStrokeJoin(self.0, join);
         }
+        self.1.update(|paint| paint.stroke_join = join);
         self
     }
 
@@ -1616,6 +2045,7 @@ impl Paint {
         unsafe {
             sys::ImpellerPaintSetStrokeWidth(self.0, width);
         }
+        self.1.update(|paint| paint.stroke_width = width);
         self
     }
 
@@ -1629,6 +2059,7 @@ impl Paint {
         unsafe {
             sys::ImpellerPaintSetStrokeMiter(self.0, miter);
         }
+        self.1.update(|paint| paint.stroke_miter = miter);
         self
     }
 
@@ -1644,6 +2075,8 @@ impl Paint {
         unsafe {
             sys::ImpellerPaintSetColorFilter(self.0, color_filter.0);
         }
+        self.1
+            .update(|paint| paint.color_filter = Some(color_filter.1.get().clone()));
         self
     }
 
@@ -1658,6 +2091,8 @@ impl Paint {
         unsafe {
             sys::ImpellerPaintSetImageFilter(self.0, image_filter.0);
         }
+        self.1
+            .update(|paint| paint.image_filter = Some(image_filter.1.get().clone()));
         self
     }
     /// Set the color source of the paint.
@@ -1671,6 +2106,8 @@ impl Paint {
         unsafe {
             sys::ImpellerPaintSetColorSource(self.0, color_source.0);
         }
+        self.1
+            .update(|paint| paint.color_source = Some(color_source.1.get().clone()));
         self
     }
     /// Set the mask filter of a paint.
@@ -1685,6 +2122,8 @@ impl Paint {
         unsafe {
             sys::ImpellerPaintSetMaskFilter(self.0, mask_filter.0);
         }
+        self.1
+            .update(|paint| paint.mask_filter = Some(mask_filter.1.get().clone()));
         self
     }
 }
@@ -1697,10 +2136,15 @@ impl Paint {
 /// <https://api.flutter.dev/flutter/dart-ui/ColorFilter-class.html>
 ///
 /// <https://shopify.github.io/react-native-skia/docs/color-filters>
+///
+/// With the `recording` feature, the arguments it was created with are recorded in a
+/// [RecordedColorFilter] (see [Self::recording]).
 #[derive(Debug)]
-#[repr(transparent)]
 #[doc(alias = "ImpellerColorFilter")]
-pub struct ColorFilter(sys::ImpellerColorFilter);
+pub struct ColorFilter(
+    sys::ImpellerColorFilter,
+    Recording<Arc<RecordedColorFilter>>,
+);
 unsafe impl Send for ColorFilter {}
 unsafe impl Sync for ColorFilter {}
 impl Clone for ColorFilter {
@@ -1708,7 +2152,7 @@ impl Clone for ColorFilter {
         unsafe {
             sys::ImpellerColorFilterRetain(self.0);
         }
-        Self(self.0)
+        Self(self.0, self.1.clone())
     }
 }
 
@@ -1720,6 +2164,11 @@ impl Drop for ColorFilter {
     }
 }
 impl ColorFilter {
+    /// The arguments this color filter was created with.
+    #[cfg(feature = "recording")]
+    pub fn recording(&self) -> &RecordedColorFilter {
+        self.1.get()
+    }
     /// Create a color filter that performs blending of pixel values
     /// independently.
     ///
@@ -1735,7 +2184,11 @@ impl ColorFilter {
 
     #[doc(alias = "ImpellerColorFilterCreateBlendNew")]
     pub fn new_blend(color: Color, blend_mode: BlendMode) -> Self {
-        unsafe { Self(sys::ImpellerColorFilterCreateBlendNew(&color, blend_mode)) }
+        let result = unsafe { sys::ImpellerColorFilterCreateBlendNew(&color, blend_mode) };
+        Self(
+            result,
+            Recording::new(|| Arc::new(RecordedColorFilter::Blend { color, blend_mode })),
+        )
     }
 
     /// Create a color filter that transforms pixel color values
@@ -1748,10 +2201,17 @@ impl ColorFilter {
     /// playground to play with matrices: <https://fecolormatrix.com/>
     ///
     /// read more in struct docs [ColorFilter]
+    ///
+    /// the constructors of [ColorMatrix], like [ColorMatrix::sepia], build the matrices
+    /// of the css filter functions.
 
     #[doc(alias = "ImpellerColorFilterCreateColorMatrixNew")]
     pub fn new_matrix(color_matrix: ColorMatrix) -> Self {
-        unsafe { Self(sys::ImpellerColorFilterCreateColorMatrixNew(&color_matrix)) }
+        let result = unsafe { sys::ImpellerColorFilterCreateColorMatrixNew(&color_matrix) };
+        Self(
+            result,
+            Recording::new(|| Arc::new(RecordedColorFilter::Matrix(color_matrix))),
+        )
     }
 }
 /// Color sources are functions that generate colors for each texture element
@@ -1762,10 +2222,15 @@ impl ColorFilter {
 /// <https://api.flutter.dev/flutter/dart-ui/Gradient-class.html>
 ///
 /// <https://learn.microsoft.com/en-us/previous-versions/xamarin/xamarin-forms/user-interface/graphics/skiasharp/effects/shaders/>
+///
+/// With the `recording` feature, the arguments it was created with are recorded in a
+/// [RecordedColorSource] (see [Self::recording]).
 #[derive(Debug)]
-#[repr(transparent)]
 #[doc(alias = "ImpellerColorSource")]
-pub struct ColorSource(sys::ImpellerColorSource);
+pub struct ColorSource(
+    sys::ImpellerColorSource,
+    Recording<Arc<RecordedColorSource>>,
+);
 unsafe impl Send for ColorSource {}
 unsafe impl Sync for ColorSource {}
 impl Clone for ColorSource {
@@ -1774,7 +2239,7 @@ impl Clone for ColorSource {
         unsafe {
             sys::ImpellerColorSourceRetain(self.0);
         }
-        Self(self.0)
+        Self(self.0, self.1.clone())
     }
 }
 
@@ -1786,6 +2251,11 @@ impl Drop for ColorSource {
     }
 }
 impl ColorSource {
+    /// The arguments this color source was created with.
+    #[cfg(feature = "recording")]
+    pub fn recording(&self) -> &RecordedColorSource {
+        self.1.get()
+    }
     //------------------------------------------------------------------------------
     /// Create a color source that forms a linear gradient.
     ///
@@ -1829,7 +2299,19 @@ impl ColorSource {
             )
         };
         assert!(!result.is_null());
-        Self(result)
+        Self(
+            result,
+            Recording::new(|| {
+                Arc::new(RecordedColorSource::LinearGradient {
+                    start,
+                    end,
+                    colors: colors.to_vec(),
+                    stops: stops.to_vec(),
+                    tile_mode,
+                    transformation: transformation.copied(),
+                })
+            }),
+        )
     }
 
     //------------------------------------------------------------------------------
@@ -1876,7 +2358,19 @@ impl ColorSource {
             )
         };
         assert!(!result.is_null());
-        Self(result)
+        Self(
+            result,
+            Recording::new(|| {
+                Arc::new(RecordedColorSource::RadialGradient {
+                    center,
+                    radius,
+                    colors: colors.to_vec(),
+                    stops: stops.to_vec(),
+                    tile_mode,
+                    transformation: transformation.copied(),
+                })
+            }),
+        )
     }
 
     //------------------------------------------------------------------------------
@@ -1925,7 +2419,21 @@ impl ColorSource {
             )
         };
         assert!(!result.is_null());
-        Self(result)
+        Self(
+            result,
+            Recording::new(|| {
+                Arc::new(RecordedColorSource::ConicalGradient {
+                    start_center,
+                    start_radius,
+                    end_center,
+                    end_radius,
+                    colors: colors.to_vec(),
+                    stops: stops.to_vec(),
+                    tile_mode,
+                    transformation: transformation.copied(),
+                })
+            }),
+        )
     }
 
     //------------------------------------------------------------------------------
@@ -1975,7 +2483,20 @@ impl ColorSource {
             )
         };
         assert!(!result.is_null());
-        Self(result)
+        Self(
+            result,
+            Recording::new(|| {
+                Arc::new(RecordedColorSource::SweepGradient {
+                    center,
+                    start,
+                    end,
+                    colors: colors.to_vec(),
+                    stops: stops.to_vec(),
+                    tile_mode,
+                    transformation: transformation.copied(),
+                })
+            }),
+        )
     }
     /// Create a color source that samples from an image.
     ///
@@ -2010,7 +2531,18 @@ impl ColorSource {
             )
         };
         assert!(!result.is_null());
-        Self(result)
+        Self(
+            result,
+            Recording::new(|| {
+                Arc::new(RecordedColorSource::Image {
+                    image: image.1.get().clone(),
+                    horizontal_tile_mode,
+                    vertical_tile_mode,
+                    sampling,
+                    transformation: transformation.copied(),
+                })
+            }),
+        )
     }
 }
 /// Image filters are functions that are applied regions of a texture to produce
@@ -2023,10 +2555,15 @@ impl ColorSource {
 /// <https://shopify.github.io/react-native-skia/docs/image-filters/overview>
 ///
 /// <https://learn.microsoft.com/en-us/previous-versions/xamarin/xamarin-forms/user-interface/graphics/skiasharp/effects/image-filters>
+///
+/// With the `recording` feature, the arguments it was created with are recorded in a
+/// [RecordedImageFilter] (see [Self::recording]).
 #[derive(Debug)]
-#[repr(transparent)]
 #[doc(alias = "ImpellerImageFilter")]
-pub struct ImageFilter(sys::ImpellerImageFilter);
+pub struct ImageFilter(
+    sys::ImpellerImageFilter,
+    Recording<Arc<RecordedImageFilter>>,
+);
 unsafe impl Send for ImageFilter {}
 unsafe impl Sync for ImageFilter {}
 impl Clone for ImageFilter {
@@ -2034,7 +2571,7 @@ impl Clone for ImageFilter {
         unsafe {
             sys::ImpellerImageFilterRetain(self.0);
         }
-        Self(self.0)
+        Self(self.0, self.1.clone())
     }
 }
 impl Drop for ImageFilter {
@@ -2045,6 +2582,11 @@ impl Drop for ImageFilter {
     }
 }
 impl ImageFilter {
+    /// The arguments this image filter was created with.
+    #[cfg(feature = "recording")]
+    pub fn recording(&self) -> &RecordedImageFilter {
+        self.1.get()
+    }
     /// Creates an image filter that applies a Gaussian blur.
     ///
     /// The Gaussian blur applied may be an approximation for
@@ -2063,7 +2605,16 @@ impl ImageFilter {
     pub fn new_blur(x_sigma: f32, y_sigma: f32, tile_mode: TileMode) -> Self {
         let result = unsafe { sys::ImpellerImageFilterCreateBlurNew(x_sigma, y_sigma, tile_mode) };
         assert!(!result.is_null());
-        Self(result)
+        Self(
+            result,
+            Recording::new(|| {
+                Arc::new(RecordedImageFilter::Blur {
+                    x_sigma,
+                    y_sigma,
+                    tile_mode,
+                })
+            }),
+        )
     }
     /// Creates an image filter that enhances the per-channel pixel
     /// values to the maximum value in a circle around the pixel.
@@ -2078,7 +2629,10 @@ impl ImageFilter {
     pub fn new_dilate(x_radius: f32, y_radius: f32) -> Self {
         let result = unsafe { sys::ImpellerImageFilterCreateDilateNew(x_radius, y_radius) };
         assert!(!result.is_null());
-        Self(result)
+        Self(
+            result,
+            Recording::new(|| Arc::new(RecordedImageFilter::Dilate { x_radius, y_radius })),
+        )
     }
     /// Creates an image filter that dampens the per-channel pixel
     /// values to the minimum value in a circle around the pixel.
@@ -2093,7 +2647,10 @@ impl ImageFilter {
     pub fn new_erode(x_radius: f32, y_radius: f32) -> Self {
         let result = unsafe { sys::ImpellerImageFilterCreateErodeNew(x_radius, y_radius) };
         assert!(!result.is_null());
-        Self(result)
+        Self(
+            result,
+            Recording::new(|| Arc::new(RecordedImageFilter::Erode { x_radius, y_radius })),
+        )
     }
     /// Creates an image filter that applies a transformation matrix to
     /// the underlying image.
@@ -2106,7 +2663,15 @@ impl ImageFilter {
     pub fn new_matrix(matrix: &Matrix, sampling: TextureSampling) -> Self {
         let result = unsafe { sys::ImpellerImageFilterCreateMatrixNew(cast_ref(matrix), sampling) };
         assert!(!result.is_null());
-        Self(result)
+        Self(
+            result,
+            Recording::new(|| {
+                Arc::new(RecordedImageFilter::Matrix {
+                    matrix: *matrix,
+                    sampling,
+                })
+            }),
+        )
     }
 
     //------------------------------------------------------------------------------
@@ -2127,7 +2692,15 @@ impl ImageFilter {
     pub fn new_compose(outer: &Self, inner: &Self) -> Self {
         let result = unsafe { sys::ImpellerImageFilterCreateComposeNew(outer.0, inner.0) };
         assert!(!result.is_null());
-        Self(result)
+        Self(
+            result,
+            Recording::new(|| {
+                Arc::new(RecordedImageFilter::Compose {
+                    outer: outer.1.get().clone(),
+                    inner: inner.1.get().clone(),
+                })
+            }),
+        )
     }
 }
 /// Mask filters are functions that are applied over a shape after it has been
@@ -2138,10 +2711,12 @@ impl ImageFilter {
 /// <https://shopify.github.io/react-native-skia/docs/mask-filters>
 ///
 /// <https://learn.microsoft.com/en-us/previous-versions/xamarin/xamarin-forms/user-interface/graphics/skiasharp/effects/mask-filters>
+///
+/// With the `recording` feature, the arguments it was created with are recorded in a
+/// [RecordedMaskFilter] (see [Self::recording]).
 #[derive(Debug)]
-#[repr(transparent)]
 #[doc(alias = "ImpellerMaskFilter")]
-pub struct MaskFilter(sys::ImpellerMaskFilter);
+pub struct MaskFilter(sys::ImpellerMaskFilter, Recording<Arc<RecordedMaskFilter>>);
 unsafe impl Send for MaskFilter {}
 unsafe impl Sync for MaskFilter {}
 impl Clone for MaskFilter {
@@ -2149,7 +2724,7 @@ impl Clone for MaskFilter {
         unsafe {
             sys::ImpellerMaskFilterRetain(self.0);
         }
-        Self(self.0)
+        Self(self.0, self.1.clone())
     }
 }
 impl Drop for MaskFilter {
@@ -2160,6 +2735,11 @@ impl Drop for MaskFilter {
     }
 }
 impl MaskFilter {
+    /// The arguments this mask filter was created with.
+    #[cfg(feature = "recording")]
+    pub fn recording(&self) -> &RecordedMaskFilter {
+        self.1.get()
+    }
     //------------------------------------------------------------------------------
     /// Create a mask filter that blurs contents in the masked shape.
     ///
@@ -2175,7 +2755,10 @@ impl MaskFilter {
     pub fn new_blur(style: BlurStyle, sigma: f32) -> Self {
         let result = unsafe { sys::ImpellerMaskFilterCreateBlurNew(style, sigma) };
         assert!(!result.is_null());
-        Self(result)
+        Self(
+            result,
+            Recording::new(|| Arc::new(RecordedMaskFilter::Blur { style, sigma })),
+        )
     }
 }
 /// A fragment shader is a small program that is authored in GLSL and compiled using impellerc that runs on each pixel covered by a polygon and allows the user to configure how it is shaded.
@@ -2317,15 +2900,15 @@ impl TypographyContext {
 
 /// An immutable, fully laid out paragraph.
 ///
-///
 /// <https://shopify.github.io/react-native-skia/docs/text/paragraph>
 ///
 /// @see [ParagraphStyle] and [ParagraphBuilder]
 ///
+/// With the `recording` feature, the styled text that the paragraph was built from is
+/// recorded in a [RecordedParagraph] (see [Self::recording]).
 #[derive(Debug)]
-#[repr(transparent)]
 #[doc(alias = "ImpellerParagraph")]
-pub struct Paragraph(sys::ImpellerParagraph);
+pub struct Paragraph(sys::ImpellerParagraph, Recording<Arc<RecordedParagraph>>);
 unsafe impl Send for Paragraph {}
 unsafe impl Sync for Paragraph {}
 impl Clone for Paragraph {
@@ -2333,7 +2916,7 @@ impl Clone for Paragraph {
         unsafe {
             sys::ImpellerParagraphRetain(self.0);
         }
-        Self(self.0)
+        Self(self.0, self.1.clone())
     }
 }
 impl Drop for Paragraph {
@@ -2344,6 +2927,11 @@ impl Drop for Paragraph {
     }
 }
 impl Paragraph {
+    /// The styled text of this paragraph and its layout metrics.
+    #[cfg(feature = "recording")]
+    pub fn recording(&self) -> &RecordedParagraph {
+        self.1.get()
+    }
     //------------------------------------------------------------------------------
     /// @see        [Self::get_min_intrinsic_width]
     ///
@@ -2748,9 +3336,8 @@ impl GlyphInfo {
 /// let paragraph = builder.build(100.0).expect("building paragraph failed");
 /// ```
 #[derive(Debug)]
-#[repr(transparent)]
 #[doc(alias = "ImpellerParagraphBuilder")]
-pub struct ParagraphBuilder(sys::ImpellerParagraphBuilder);
+pub struct ParagraphBuilder(sys::ImpellerParagraphBuilder, Recording<Vec<ParagraphItem>>);
 impl Drop for ParagraphBuilder {
     #[doc(alias = "ImpellerParagraphBuilderRelease")]
     fn drop(&mut self) {
@@ -2768,7 +3355,7 @@ impl ParagraphBuilder {
     #[doc(alias = "ImpellerParagraphBuilderNew")]
     pub fn new(context: &TypographyContext) -> Option<ParagraphBuilder> {
         let result = unsafe { sys::ImpellerParagraphBuilderNew(context.0) };
-        (!result.is_null()).then_some(ParagraphBuilder(result))
+        (!result.is_null()).then(|| ParagraphBuilder(result, Recording::new(Vec::new)))
     }
     //------------------------------------------------------------------------------
     /// Push a new paragraph style onto the paragraph style stack
@@ -2792,6 +3379,8 @@ impl ParagraphBuilder {
         unsafe {
             sys::ImpellerParagraphBuilderPushStyle(self.0, style.0);
         }
+        self.1
+            .update(|items| items.push(ParagraphItem::PushStyle(Arc::new(style.1.get().clone()))));
         self
     }
     //------------------------------------------------------------------------------
@@ -2803,6 +3392,7 @@ impl ParagraphBuilder {
         unsafe {
             sys::ImpellerParagraphBuilderPopStyle(self.0);
         }
+        self.1.update(|items| items.push(ParagraphItem::PopStyle));
         self
     }
     //------------------------------------------------------------------------------
@@ -2818,6 +3408,8 @@ impl ParagraphBuilder {
                 text.len().try_into().unwrap(),
             );
         }
+        self.1
+            .update(|items| items.push(ParagraphItem::Text(text.to_owned())));
         self
     }
 
@@ -2831,9 +3423,22 @@ impl ParagraphBuilder {
     /// @return     The paragraph if one can be created, NULL otherwise.
     #[must_use]
     #[doc(alias = "ImpellerParagraphBuilderBuildParagraphNew")]
-    pub fn build(self, width: f32) -> Option<Paragraph> {
+    pub fn build(mut self, width: f32) -> Option<Paragraph> {
         let result = unsafe { sys::ImpellerParagraphBuilderBuildParagraphNew(self.0, width) };
-        (!result.is_null()).then_some(Paragraph(result))
+        if result.is_null() {
+            return None;
+        }
+        let mut paragraph = Paragraph(result, Default::default());
+        paragraph.1 = std::mem::take(&mut self.1).map(|items| {
+            Arc::new(RecordedParagraph {
+                items,
+                width,
+                height: paragraph.get_height(),
+                longest_line_width: paragraph.get_longest_line_width(),
+                alphabetic_baseline: paragraph.get_alphabetic_baseline(),
+            })
+        });
+        Some(paragraph)
     }
 }
 
@@ -2848,9 +3453,11 @@ impl ParagraphBuilder {
 /// @see [Paragraph] and [ParagraphBuilder]
 ///
 #[derive(Debug)]
-#[repr(transparent)]
 #[doc = "ImpellerParagraphStyle"]
-pub struct ParagraphStyle(sys::ImpellerParagraphStyle);
+pub struct ParagraphStyle(
+    sys::ImpellerParagraphStyle,
+    Recording<RecordedParagraphStyle>,
+);
 unsafe impl Send for ParagraphStyle {}
 unsafe impl Sync for ParagraphStyle {}
 impl Drop for ParagraphStyle {
@@ -2864,10 +3471,15 @@ impl Default for ParagraphStyle {
     fn default() -> Self {
         let result = unsafe { sys::ImpellerParagraphStyleNew() };
         assert!(!result.is_null());
-        Self(result)
+        Self(result, Recording::new(RecordedParagraphStyle::default))
     }
 }
 impl ParagraphStyle {
+    /// The setters that were called on this style.
+    #[cfg(feature = "recording")]
+    pub fn recording(&self) -> &RecordedParagraphStyle {
+        self.1.get()
+    }
     /// Set the paint used to render the text glyph contents.
     ///
     /// - paint            The paint.
@@ -2876,6 +3488,8 @@ impl ParagraphStyle {
         unsafe {
             sys::ImpellerParagraphStyleSetForeground(self.0, paint.0);
         }
+        self.1
+            .update(|style| style.foreground = Some(paint.1.get().clone()));
         self
     }
     //------------------------------------------------------------------------------
@@ -2887,6 +3501,8 @@ impl ParagraphStyle {
         unsafe {
             sys::ImpellerParagraphStyleSetBackground(self.0, paint.0);
         }
+        self.1
+            .update(|style| style.background = Some(paint.1.get().clone()));
         self
     }
     /// Set the weight of the font to select when rendering glyphs.
@@ -2897,6 +3513,8 @@ impl ParagraphStyle {
         unsafe {
             sys::ImpellerParagraphStyleSetFontWeight(self.0, weight);
         }
+        self.1
+            .update(|recording| recording.font_weight = Some(weight));
         self
     }
     /// Set whether the glyphs should be bolded or italicized.
@@ -2907,6 +3525,8 @@ impl ParagraphStyle {
         unsafe {
             sys::ImpellerParagraphStyleSetFontStyle(self.0, style);
         }
+        self.1
+            .update(|recording| recording.font_style = Some(style));
         self
     }
     /// Set the font family.
@@ -2916,6 +3536,8 @@ impl ParagraphStyle {
     /// - family_name      The family name.
     #[doc(alias = "ImpellerParagraphStyleSetFontFamily")]
     pub fn set_font_family(&mut self, family_name: &str) -> &mut Self {
+        self.1
+            .update(|style| style.font_family = Some(family_name.to_owned()));
         let family_name =
             std::ffi::CString::new(family_name).expect("failed to create Cstring from family name");
         unsafe {
@@ -2934,6 +3556,7 @@ impl ParagraphStyle {
         unsafe {
             sys::ImpellerParagraphStyleSetFontSize(self.0, size);
         }
+        self.1.update(|recording| recording.font_size = Some(size));
         self
     }
     /// The height of the text as a multiple of text size.
@@ -2951,6 +3574,7 @@ impl ParagraphStyle {
         unsafe {
             sys::ImpellerParagraphStyleSetHeight(self.0, height);
         }
+        self.1.update(|recording| recording.height = Some(height));
         self
     }
     //------------------------------------------------------------------------------
@@ -2962,6 +3586,8 @@ impl ParagraphStyle {
         unsafe {
             sys::ImpellerParagraphStyleSetTextAlignment(self.0, align);
         }
+        self.1
+            .update(|recording| recording.text_alignment = Some(align));
         self
     }
     //------------------------------------------------------------------------------
@@ -2973,6 +3599,8 @@ impl ParagraphStyle {
         unsafe {
             sys::ImpellerParagraphStyleSetTextDirection(self.0, direction);
         }
+        self.1
+            .update(|recording| recording.text_direction = Some(direction));
         self
     }
     /// Set one of more text decorations on the paragraph. Decorations
@@ -3005,6 +3633,14 @@ impl ParagraphStyle {
         unsafe {
             sys::ImpellerParagraphStyleSetTextDecoration(self.0, &raw const decoration);
         }
+        self.1.update(|recording| {
+            recording.text_decoration = Some(RecordedTextDecoration {
+                types: decoration_type,
+                color: *color,
+                style,
+                thickness_multiplier,
+            })
+        });
         self
     }
     //------------------------------------------------------------------------------
@@ -3016,6 +3652,8 @@ impl ParagraphStyle {
         unsafe {
             sys::ImpellerParagraphStyleSetMaxLines(self.0, max_lines);
         }
+        self.1
+            .update(|recording| recording.max_lines = Some(max_lines));
         self
     }
     //------------------------------------------------------------------------------
@@ -3026,6 +3664,8 @@ impl ParagraphStyle {
     /// - locale           The locale.
     #[doc(alias = "ImpellerParagraphStyleSetLocale")]
     pub fn set_locale(&mut self, locale: &str) -> &mut Self {
+        self.1
+            .update(|style| style.locale = Some(locale.to_owned()));
         let locale = std::ffi::CString::new(locale).expect("failed to create Cstring from locale");
         unsafe {
             sys::ImpellerParagraphStyleSetLocale(self.0, locale.as_ptr());
@@ -3041,6 +3681,8 @@ impl ParagraphStyle {
     /// - ellipsis         The ellipsis string UTF-8 data, or null.
     #[doc(alias = "ImpellerParagraphStyleSetEllipsis")]
     pub fn set_ellipsis(&mut self, ellipsis: Option<&str>) -> &mut Self {
+        self.1
+            .update(|style| style.ellipsis = ellipsis.map(str::to_owned));
         let ellipsis = ellipsis.map(|ellipsis| {
             std::ffi::CString::new(ellipsis).expect("failed to create cstr from ellipsis str")
         });
@@ -3072,10 +3714,13 @@ impl ParagraphStyle {
 ///
 /// Paths are created using path builder that allow for the configuration of the
 /// path segments, how they are filled, and/or stroked.
+///
+/// Along with the impeller handle, a path also carries the [RecordedPath] that
+/// it was built from. So, you can [iterate](Self::iter) over its segments or
+/// read back its [FillType] on the rust side.
 #[derive(Debug)]
-#[repr(transparent)]
 #[doc(alias = "ImpellerPath")]
-pub struct Path(sys::ImpellerPath);
+pub struct Path(sys::ImpellerPath, Arc<RecordedPath>);
 unsafe impl Send for Path {}
 unsafe impl Sync for Path {}
 impl Clone for Path {
@@ -3083,7 +3728,7 @@ impl Clone for Path {
         unsafe {
             sys::ImpellerPathRetain(self.0);
         }
-        Self(self.0)
+        Self(self.0, self.1.clone())
     }
 }
 impl Drop for Path {
@@ -3106,14 +3751,279 @@ impl Path {
         }
         cast(rect)
     }
+    /// Get the smallest rect containing the path.
+    ///
+    /// Unlike [Path::get_bounds], this is computed on the rust side from the extrema
+    /// of the curves, so control points and isolated moves are not included.
+    /// Empty paths have zero bounds.
+    pub fn tight_bounds(&self) -> Rect {
+        self.1.tight_bounds()
+    }
+    /// Approximate the curves of this path with straight lines, one [Polyline]
+    /// per contour.
+    ///
+    /// - tolerance: The furthest the lines may be from the curves.
+    ///
+    /// ```rust,no_run
+    /// # use impellers::*;
+    /// let mut builder = PathBuilder::default();
+    /// let icon = builder
+    ///     .add_oval(&Rect::new(Point::new(0.0, 0.0), Size::new(24.0, 24.0)))
+    ///     .move_to(Point::new(6.0, 12.0))
+    ///     .quadratic_curve_to(Point::new(12.0, 20.0), Point::new(18.0, 12.0))
+    ///     .take_path_new(FillType::NonZero);
+    /// for polyline in icon.flatten(0.25) {
+    ///     println!("{} points, closed: {}", polyline.points.len(), polyline.closed);
+    /// }
+    /// ```
+    pub fn flatten(&self, tolerance: f32) -> impl Iterator<Item = Polyline> {
+        self.1.flatten(tolerance)
+    }
+    /// The fill type that was used to create this path.
+    ///
+    /// @see [PathBuilder::take_path_new] and [PathBuilder::copy_path_new]
+    pub fn fill_type(&self) -> FillType {
+        self.1.fill_type()
+    }
+    /// Iterate over the segments (verbs with their points) of this path.
+    ///
+    /// Shapes like rects, ovals and arcs show up as the lines and curves that approximate them.
+    ///
+    /// ```rust,no_run
+    /// # use impellers::*;
+    /// let mut builder = PathBuilder::default();
+    /// builder.move_to(Point::new(0.0, 0.0)).line_to(Point::new(10.0, 0.0)).close();
+    /// let path = builder.take_path_new(FillType::NonZero);
+    /// for segment in path.iter() {
+    ///     match segment {
+    ///         PathSegment::MoveTo(p) => println!("move to {p:?}"),
+    ///         PathSegment::LineTo(p) => println!("line to {p:?}"),
+    ///         _ => {}
+    ///     }
+    /// }
+    /// ```
+    pub fn iter(&self) -> PathIter<'_> {
+        self.1.iter()
+    }
+    /// The rust side copy of the verbs, points and fill type of this path.
+    pub fn recording(&self) -> &RecordedPath {
+        &self.1
+    }
+    /// Write the path as svg path data (the `d` attribute of a `<path>` element).
+    ///
+    /// Only absolute `M`, `L`, `Q`, `C` and `Z` commands are used.
+    /// The [FillType] maps to the `fill-rule` attribute, which is not part of the path data.
+    /// Segments with non-finite points are left out, see [RecordedPath::to_svg_path_data].
+    pub fn to_svg_path_data(&self) -> String {
+        self.1.to_svg_path_data()
+    }
+    /// Combine this path with another path using a boolean operation.
+    ///
+    /// This is computed on the rust side, as impeller doesn't support path operations.
+    /// The [FillType] of each path decides its inside. Curves are flattened, so the
+    /// result is made of straight lines and uses [FillType::NonZero].
+    ///
+    /// <https://api.flutter.dev/flutter/dart-ui/Path/combine.html>
+    ///
+    /// ```rust,no_run
+    /// # use impellers::*;
+    /// let mut builder = PathBuilder::default();
+    /// let square = builder
+    ///     .add_rect(&Rect::new(Point::new(0.0, 0.0), Size::new(100.0, 100.0)))
+    ///     .take_path_new(FillType::NonZero);
+    /// let circle = builder
+    ///     .add_oval(&Rect::new(Point::new(25.0, 25.0), Size::new(50.0, 50.0)))
+    ///     .take_path_new(FillType::NonZero);
+    /// // a square with a round hole
+    /// let frame = square.op(&circle, PathOp::Difference);
+    /// ```
+    pub fn op(&self, other: &Path, op: PathOp) -> Path {
+        self.1.op(&other.1, op).to_path()
+    }
+    /// Convert the stroke of this path into a path, which can be filled to get
+    /// the same result as drawing this path with a [DrawStyle::Stroke] paint.
+    ///
+    /// This is useful for combining strokes with [Path::op] or hit testing them.
+    ///
+    /// - width: The stroke width. Zero or negative widths produce an empty path.
+    /// - cap: The [StrokeCap] of open contours.
+    /// - join: The [StrokeJoin] between segments.
+    /// - miter_limit: The miter limit, as in [Paint::set_stroke_miter]. Impeller's default is `4.0`.
+    ///
+    /// @return The outline, using [FillType::NonZero].
+    ///
+    /// ```rust,no_run
+    /// # use impellers::*;
+    /// let mut builder = PathBuilder::default();
+    /// let line = builder
+    ///     .move_to(Point::new(10.0, 10.0))
+    ///     .line_to(Point::new(90.0, 50.0))
+    ///     .take_path_new(FillType::NonZero);
+    /// let outline = line.stroke_to_path(8.0, StrokeCap::Round, StrokeJoin::Round, 4.0);
+    /// ```
+    pub fn stroke_to_path(
+        &self,
+        width: f32,
+        cap: StrokeCap,
+        join: StrokeJoin,
+        miter_limit: f32,
+    ) -> Path {
+        self.1
+            .stroke_to_path(width, cap, join, miter_limit)
+            .to_path()
+    }
+    /// Measure the lengths of the contours of this path, to find points and
+    /// tangents along them or extract parts of them.
+    ///
+    /// - force_closed: Measure open contours as if they were closed.
+    ///
+    /// <https://api.flutter.dev/flutter/dart-ui/Path/computeMetrics.html>
+    ///
+    /// ```rust,no_run
+    /// # use impellers::*;
+    /// let mut builder = PathBuilder::default();
+    /// let signature = builder
+    ///     .move_to(Point::new(10.0, 80.0))
+    ///     .cubic_curve_to(Point::new(40.0, 10.0), Point::new(65.0, 10.0), Point::new(95.0, 80.0))
+    ///     .quadratic_curve_to(Point::new(150.0, 150.0), Point::new(180.0, 80.0))
+    ///     .take_path_new(FillType::NonZero);
+    /// let measure = signature.measure(false);
+    /// // draw the first half of the signature
+    /// let half = measure.get_segment(0.0, measure.length() / 2.0);
+    /// ```
+    pub fn measure(&self, force_closed: bool) -> PathMeasure {
+        PathMeasure::new(&self.1, force_closed)
+    }
+    /// Split the contours of this path into dashes, which can then be drawn
+    /// with a [DrawStyle::Stroke] paint.
+    ///
+    /// Unlike [DisplayListBuilder::draw_dashed_line], this works for any path
+    /// and any number of intervals.
+    ///
+    /// - intervals: Alternating lengths of dashes and gaps, like svg's `stroke-dasharray`.
+    /// - phase: The offset into the intervals, like svg's `stroke-dashoffset`.
+    ///
+    /// @see [RecordedPath::dash] for the details.
+    ///
+    /// ```rust,no_run
+    /// # use impellers::*;
+    /// let mut builder = PathBuilder::default();
+    /// let border = builder
+    ///     .add_rounded_rect(
+    ///         &Rect::new(Point::new(10.0, 10.0), Size::new(200.0, 100.0)),
+    ///         &RoundingRadii {
+    ///             top_left: Point::new(16.0, 16.0),
+    ///             top_right: Point::new(16.0, 16.0),
+    ///             bottom_left: Point::new(16.0, 16.0),
+    ///             bottom_right: Point::new(16.0, 16.0),
+    ///         },
+    ///     )
+    ///     .take_path_new(FillType::NonZero);
+    /// let dashed_border = border.dash(&[12.0, 4.0, 2.0, 4.0], 0.0);
+    /// ```
+    pub fn dash(&self, intervals: &[f32], phase: f32) -> Path {
+        self.1.dash(intervals, phase).to_path()
+    }
+    /// Whether the point is inside the fill of this path.
+    ///
+    /// This honors the [FillType] the path was created with (see [PathBuilder::take_path_new]).
+    /// Open contours are implicitly closed, just like when the path is filled.
+    ///
+    /// <https://api.flutter.dev/flutter/dart-ui/Path/contains.html>
+    pub fn contains(&self, point: Point) -> bool {
+        self.1.contains(point)
+    }
+    /// Whether the point is on the stroke of this path, when drawn with the stroke width.
+    ///
+    /// Caps and joins are treated as round. Use [Path::stroke_to_path] with
+    /// [Path::contains] for an exact test.
+    ///
+    /// @see [RecordedPath::distance_to_stroke]
+    pub fn hit_stroke(&self, point: Point, stroke_width: f32) -> bool {
+        self.1.hit_stroke(point, stroke_width)
+    }
+    /// This path with every point transformed by the matrix.
+    ///
+    /// Unlike [DisplayListBuilder::transform], this changes the geometry itself,
+    /// which is useful for clipping, bounds or hit testing.
+    ///
+    /// @see [RecordedPath::transformed] for how perspective is handled.
+    ///
+    /// ```rust,no_run
+    /// # use impellers::*;
+    /// let mut builder = PathBuilder::default();
+    /// let glyph = builder
+    ///     .move_to(Point::new(0.0, -40.0))
+    ///     .line_to(Point::new(10.0, 0.0))
+    ///     .line_to(Point::new(-10.0, 0.0))
+    ///     .close()
+    ///     .take_path_new(FillType::NonZero);
+    /// for i in 0..10 {
+    ///     let stamp = Matrix::rotation(0.0, 0.0, 1.0, euclid::Angle::degrees(i as f32 * 36.0))
+    ///         .then_translate(euclid::vec3(200.0, 200.0, 0.0));
+    ///     builder.add_path(&glyph, Some(&stamp));
+    /// }
+    /// let stamped = builder.take_path_new(FillType::NonZero);
+    /// ```
+    pub fn transformed(&self, transform: &Matrix) -> Path {
+        self.1.transformed(transform).to_path()
+    }
+    /// This path with the direction of every contour reversed.
+    ///
+    /// Reversing contours changes their winding, which matters for [FillType::NonZero].
+    pub fn reversed(&self) -> Path {
+        self.1.reversed().to_path()
+    }
+}
+impl<'a> IntoIterator for &'a Path {
+    type Item = PathSegment;
+    type IntoIter = PathIter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+impl From<&RecordedPath> for Path {
+    fn from(recording: &RecordedPath) -> Self {
+        recording.to_path()
+    }
+}
+impl RecordedPath {
+    /// Create an impeller [Path] by replaying the recorded verbs into a [PathBuilder].
+    pub fn to_path(&self) -> Path {
+        let mut builder = PathBuilder::default();
+        builder.push_segments(self);
+        builder.take_path_new(self.fill_type())
+    }
+}
+impl PathMeasure {
+    /// The part of the path between two distances along all of its contours,
+    /// as an impeller [Path].
+    ///
+    /// @see [PathMeasure::extract_path]
+    pub fn get_segment(&self, start: f32, end: f32) -> Path {
+        self.extract_path(start, end).to_path()
+    }
+}
+impl ContourMeasure {
+    /// The part of the contour between two distances, as an impeller [Path].
+    ///
+    /// @see [ContourMeasure::extract_path]
+    pub fn get_segment(&self, start: f32, end: f32) -> Path {
+        let mut path = RecordedPath::default();
+        self.extract_path(start, end, true, &mut path);
+        path.to_path()
+    }
 }
 /// Path builders allow for the incremental building up of paths.
 ///
+/// Every call is also recorded on the rust side, which ends up in the [Path]
+/// (see [Path::iter]).
+///
 /// @see docs of [Path]
 #[derive(Debug)]
-#[repr(transparent)]
 #[doc = "ImpellerPathBuilder"]
-pub struct PathBuilder(sys::ImpellerPathBuilder);
+pub struct PathBuilder(sys::ImpellerPathBuilder, RecordedPath);
 unsafe impl Send for PathBuilder {}
 unsafe impl Sync for PathBuilder {}
 impl Drop for PathBuilder {
@@ -3131,7 +4041,7 @@ impl Default for PathBuilder {
     fn default() -> Self {
         let p = unsafe { sys::ImpellerPathBuilderNew() };
         assert!(!p.is_null());
-        Self(p)
+        Self(p, RecordedPath::default())
     }
 }
 impl PathBuilder {
@@ -3143,6 +4053,7 @@ impl PathBuilder {
         unsafe {
             sys::ImpellerPathBuilderMoveTo(self.0, cast_ref(&location));
         }
+        self.1.move_to(location);
         self
     }
     /// Add a line segment from the current cursor location to the given
@@ -3154,6 +4065,7 @@ impl PathBuilder {
         unsafe {
             sys::ImpellerPathBuilderLineTo(self.0, cast_ref(&location));
         }
+        self.1.line_to(location);
         self
     }
 
@@ -3173,6 +4085,7 @@ impl PathBuilder {
                 cast_ref(&end_point),
             );
         }
+        self.1.quadratic_curve_to(control_point, end_point);
         self
     }
     /// Add a cubic curve whose start point is current cursor location
@@ -3200,6 +4113,8 @@ impl PathBuilder {
                 cast_ref(&end_point),
             );
         }
+        self.1
+            .cubic_curve_to(control_point_1, control_point_2, end_point);
         self
     }
     /// Adds a rectangle to the path.
@@ -3210,6 +4125,7 @@ impl PathBuilder {
         unsafe {
             sys::ImpellerPathBuilderAddRect(self.0, cast_ref(rect));
         }
+        self.1.add_rect(rect);
         self
     }
     /// Add an arc to the path.
@@ -3232,6 +4148,8 @@ impl PathBuilder {
                 end_angle_degrees,
             );
         }
+        self.1
+            .add_arc(oval_bounds, start_angle_degrees, end_angle_degrees);
         self
     }
 
@@ -3243,6 +4161,7 @@ impl PathBuilder {
         unsafe {
             sys::ImpellerPathBuilderAddOval(self.0, cast_ref(oval_bounds));
         }
+        self.1.add_oval(oval_bounds);
         self
     }
     /// Add a rounded rect with potentially non-uniform radii to the path.
@@ -3262,6 +4181,23 @@ impl PathBuilder {
                 &rounding_radii.into(),
             );
         }
+        self.1.add_rounded_rect(oval_bounds, rounding_radii);
+        self
+    }
+    /// Append the contours of a path, optionally transformed.
+    ///
+    /// Impeller has no way to add a path to a builder, so the recorded
+    /// segments of the path are replayed into this builder.
+    ///
+    /// - path       The path to append.
+    /// - transform  The transformation applied to the path first.
+    ///
+    /// @see [Path::transformed]
+    pub fn add_path(&mut self, path: &Path, transform: Option<&Matrix>) -> &mut Self {
+        match transform {
+            Some(transform) => self.push_segments(&path.1.transformed(transform)),
+            None => self.push_segments(path.1.as_ref()),
+        }
         self
     }
     /// Close the path.
@@ -3270,8 +4206,42 @@ impl PathBuilder {
         unsafe {
             sys::ImpellerPathBuilderClose(self.0);
         }
+        self.1.close();
         self
     }
+    /// The rust side copy of the path built up so far.
+    pub fn recording(&self) -> &RecordedPath {
+        &self.1
+    }
+    fn push_segments(&mut self, segments: impl IntoIterator<Item = PathSegment>) {
+        for segment in segments {
+            match segment {
+                PathSegment::MoveTo(p) => self.move_to(p),
+                PathSegment::LineTo(p) => self.line_to(p),
+                PathSegment::QuadTo(c, p) => self.quadratic_curve_to(c, p),
+                PathSegment::CubicTo(c1, c2, p) => self.cubic_curve_to(c1, c2, p),
+                PathSegment::Close => self.close(),
+            };
+        }
+    }
+    /// Create a path builder from svg path data (the `d` attribute of a `<path>` element).
+    ///
+    /// Supports `M`, `L`, `H`, `V`, `C`, `S`, `Q`, `T`, `A` and `Z` commands,
+    /// both absolute and relative. Arcs are converted to cubic curves.
+    ///
+    /// ```rust,no_run
+    /// # use impellers::*;
+    /// let mut builder = PathBuilder::from_svg_path_data("M2 2 h20 v20 h-20 z").unwrap();
+    /// let path = builder.take_path_new(FillType::NonZero);
+    /// ```
+    ///
+    /// @see [Path::to_svg_path_data] for the reverse.
+    pub fn from_svg_path_data(data: &str) -> Result<PathBuilder, ParseError> {
+        let recording = RecordedPath::from_svg_path_data(data)?;
+        let mut builder = Self::default();
+        builder.push_segments(&recording);
+        Ok(builder)
+    }
 
     /// Create a new path by copying the existing built-up path. The
     /// existing path can continue being added to.
@@ -3283,7 +4253,9 @@ impl PathBuilder {
     pub fn copy_path_new(&mut self, fill: FillType) -> Path {
         let p = unsafe { sys::ImpellerPathBuilderCopyPathNew(self.0, fill) };
         assert!(!p.is_null());
-        Path(p)
+        let mut recording = self.1.clone();
+        recording.set_fill_type(fill);
+        Path(p, Arc::new(recording))
     }
     /// Create a new path using the existing built-up path. The existing
     /// path builder now contains an empty path.
@@ -3295,7 +4267,9 @@ impl PathBuilder {
     pub fn take_path_new(&mut self, fill: FillType) -> Path {
         let p = unsafe { sys::ImpellerPathBuilderTakePathNew(self.0, fill) };
         assert!(!p.is_null());
-        Path(p)
+        let mut recording = std::mem::take(&mut self.1);
+        recording.set_fill_type(fill);
+        Path(p, Arc::new(recording))
     }
 }
 /// A surface represents a render target for Impeller to direct the rendering
@@ -3374,10 +4348,12 @@ impl Surface {
 /// @warning    While textures themselves are thread safe, some context types
 ///             (like OpenGL) may need extra configuration to be able to operate
 ///             from multiple threads.
+///
+/// With the `recording` feature, the size of the texture is recorded in a [RecordedTexture]
+/// (see [Self::recording]).
 #[derive(Debug)]
-#[repr(transparent)]
 #[doc = "ImpellerTexture"]
-pub struct Texture(sys::ImpellerTexture);
+pub struct Texture(sys::ImpellerTexture, Recording<Arc<RecordedTexture>>);
 unsafe impl Sync for Texture {}
 unsafe impl Send for Texture {}
 impl Clone for Texture {
@@ -3386,7 +4362,7 @@ impl Clone for Texture {
         unsafe {
             sys::ImpellerTextureRetain(self.0);
         }
-        Self(self.0)
+        Self(self.0, self.1.clone())
     }
 }
 impl Drop for Texture {
@@ -3398,6 +4374,11 @@ impl Drop for Texture {
     }
 }
 impl Texture {
+    /// What is known about this texture on the rust side.
+    #[cfg(feature = "recording")]
+    pub fn recording(&self) -> &RecordedTexture {
+        self.1.get()
+    }
     /// Get the OpenGL handle associated with this texture. If this is
     /// not an OpenGL texture, this method will always return 0.
     ///
@@ -3457,8 +4438,23 @@ impl Color {
         Self { alpha, ..self }
     }
 }
+impl PartialEq for Color {
+    fn eq(&self, other: &Self) -> bool {
+        self.red == other.red
+            && self.green == other.green
+            && self.blue == other.blue
+            && self.alpha == other.alpha
+            && self.color_space == other.color_space
+    }
+}
+impl PartialEq for ColorMatrix {
+    fn eq(&self, other: &Self) -> bool {
+        self.m == other.m
+    }
+}
 bitflags::bitflags! {
     /// The types of text decoration to apply to text.
+    #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
     pub struct TextDecorationType: std::ffi::c_int {
         /// No text decoration.
         const NONE = sys::TextDecorationType::None as std::ffi::c_int;
@@ -3474,7 +4470,7 @@ bitflags::bitflags! {
 ///
 /// Each point represents the X and Y radius of a corner.
 #[repr(C)]
-#[derive(Debug, Copy, Clone, Default)]
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
 pub struct RoundingRadii {
     /// x and y radius of the top left corner.
     pub top_left: Point,
@@ -3539,6 +4535,29 @@ impl sys::ImpellerMapping {
             user_data.cast(),
         )
     }
+    /// Like [Self::from_cow], but keeps a clone of the [Arc] alive instead, so the
+    /// bytes can be shared with a [RecordedTexture].
+    ///
+    /// # Safety
+    /// - The returned Self's on_release callback MUST be called with only the returned userdata pointer.
+    unsafe fn from_arc(contents: Arc<[u8]>) -> (Self, *mut std::ffi::c_void) {
+        let contents: Box<Arc<[u8]>> = Box::new(contents);
+        let data: *const u8 = contents.as_ptr();
+        let length = contents.len() as u64;
+        let user_data: *mut Arc<[u8]> = Box::leak(contents);
+        extern "C" fn boxed_arc_slice_dropper(on_release_user_data: *mut std::ffi::c_void) {
+            let contents: Box<Arc<[u8]>> = unsafe { Box::from_raw(on_release_user_data as *mut _) };
+            drop(contents);
+        }
+        (
+            sys::ImpellerMapping {
+                data,
+                length,
+                on_release: Some(boxed_arc_slice_dropper),
+            },
+            user_data.cast(),
+        )
+    }
 }
 unsafe impl bytemuck::Zeroable for sys::ImpellerISize {}
 unsafe impl bytemuck::Pod for sys::ImpellerISize {}
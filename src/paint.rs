@@ -0,0 +1,293 @@
+//! Rust-side mirrors of [Paint](crate::Paint) and the objects that can be attached to it.
+//!
+//! Like [RecordedPath](crate::RecordedPath), these are plain data that can be inspected
+//! without a GPU context. A recording [DisplayListBuilder](crate::DisplayListBuilder)
+//! stores them in its [DisplayOp](crate::DisplayOp)s.
+use std::sync::Arc;
+
+pub(crate) mod blend;
+mod png;
+
+use crate::{
+    BlendMode, BlurStyle, Color, ColorMatrix, DrawStyle, Matrix, Point, StrokeCap, StrokeJoin,
+    TextureSampling, TileMode,
+};
+
+/// The state of a [Paint](crate::Paint), as set by its setters.
+///
+/// [Default] matches the defaults of a new impeller paint.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordedPaint {
+    /// @see [Paint::set_color](crate::Paint::set_color)
+    pub color: Color,
+    /// @see [Paint::set_blend_mode](crate::Paint::set_blend_mode)
+    pub blend_mode: BlendMode,
+    /// @see [Paint::set_draw_style](crate::Paint::set_draw_style)
+    pub draw_style: DrawStyle,
+    /// @see [Paint::set_stroke_cap](crate::Paint::set_stroke_cap)
+    pub stroke_cap: StrokeCap,
+    /// @see [Paint::set_stroke_join](crate::Paint::set_stroke_join)
+    pub stroke_join: StrokeJoin,
+    /// @see [Paint::set_stroke_width](crate::Paint::set_stroke_width)
+    pub stroke_width: f32,
+    /// @see [Paint::set_stroke_miter](crate::Paint::set_stroke_miter)
+    pub stroke_miter: f32,
+    /// @see [Paint::set_color_filter](crate::Paint::set_color_filter)
+    pub color_filter: Option<Arc<RecordedColorFilter>>,
+    /// @see [Paint::set_image_filter](crate::Paint::set_image_filter)
+    pub image_filter: Option<Arc<RecordedImageFilter>>,
+    /// @see [Paint::set_color_source](crate::Paint::set_color_source)
+    pub color_source: Option<Arc<RecordedColorSource>>,
+    /// @see [Paint::set_mask_filter](crate::Paint::set_mask_filter)
+    pub mask_filter: Option<Arc<RecordedMaskFilter>>,
+}
+impl Default for RecordedPaint {
+    fn default() -> Self {
+        // https://github.com/flutter/engine/blob/main/display_list/dl_paint.h
+        Self {
+            color: Color::new_srgb(0.0, 0.0, 0.0),
+            blend_mode: BlendMode::SourceOver,
+            draw_style: DrawStyle::Fill,
+            stroke_cap: StrokeCap::Butt,
+            stroke_join: StrokeJoin::Miter,
+            stroke_width: 0.0,
+            stroke_miter: 4.0,
+            color_filter: None,
+            image_filter: None,
+            color_source: None,
+            mask_filter: None,
+        }
+    }
+}
+/// The arguments a [ColorFilter](crate::ColorFilter) was created with.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RecordedColorFilter {
+    /// @see [ColorFilter::new_blend](crate::ColorFilter::new_blend)
+    Blend {
+        /// The color.
+        color: Color,
+        /// The blend mode.
+        blend_mode: BlendMode,
+    },
+    /// @see [ColorFilter::new_matrix](crate::ColorFilter::new_matrix)
+    Matrix(ColorMatrix),
+}
+/// The arguments a [ColorSource](crate::ColorSource) was created with.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RecordedColorSource {
+    /// @see [ColorSource::new_linear_gradient](crate::ColorSource::new_linear_gradient)
+    #[allow(missing_docs)]
+    LinearGradient {
+        start: Point,
+        end: Point,
+        colors: Vec<Color>,
+        stops: Vec<f32>,
+        tile_mode: TileMode,
+        transformation: Option<Matrix>,
+    },
+    /// @see [ColorSource::new_radial_gradient](crate::ColorSource::new_radial_gradient)
+    #[allow(missing_docs)]
+    RadialGradient {
+        center: Point,
+        radius: f32,
+        colors: Vec<Color>,
+        stops: Vec<f32>,
+        tile_mode: TileMode,
+        transformation: Option<Matrix>,
+    },
+    /// @see [ColorSource::new_conical_gradient](crate::ColorSource::new_conical_gradient)
+    #[allow(missing_docs)]
+    ConicalGradient {
+        start_center: Point,
+        start_radius: f32,
+        end_center: Point,
+        end_radius: f32,
+        colors: Vec<Color>,
+        stops: Vec<f32>,
+        tile_mode: TileMode,
+        transformation: Option<Matrix>,
+    },
+    /// @see [ColorSource::new_sweep_gradient](crate::ColorSource::new_sweep_gradient)
+    #[allow(missing_docs)]
+    SweepGradient {
+        center: Point,
+        start: f32,
+        end: f32,
+        colors: Vec<Color>,
+        stops: Vec<f32>,
+        tile_mode: TileMode,
+        transformation: Option<Matrix>,
+    },
+    /// @see [ColorSource::new_image](crate::ColorSource::new_image)
+    #[allow(missing_docs)]
+    Image {
+        image: Arc<RecordedTexture>,
+        horizontal_tile_mode: TileMode,
+        vertical_tile_mode: TileMode,
+        sampling: TextureSampling,
+        transformation: Option<Matrix>,
+    },
+    /// @see [Context::new_color_source_from_fragment_program](crate::Context::new_color_source_from_fragment_program)
+    ///
+    /// The program itself is opaque, so only its inputs are recorded.
+    #[allow(missing_docs)]
+    FragmentProgram {
+        samplers: Vec<Arc<RecordedTexture>>,
+        uniform_data: Vec<u8>,
+    },
+}
+/// The arguments an [ImageFilter](crate::ImageFilter) was created with.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RecordedImageFilter {
+    /// @see [ImageFilter::new_blur](crate::ImageFilter::new_blur)
+    #[allow(missing_docs)]
+    Blur {
+        x_sigma: f32,
+        y_sigma: f32,
+        tile_mode: TileMode,
+    },
+    /// @see [ImageFilter::new_dilate](crate::ImageFilter::new_dilate)
+    #[allow(missing_docs)]
+    Dilate { x_radius: f32, y_radius: f32 },
+    /// @see [ImageFilter::new_erode](crate::ImageFilter::new_erode)
+    #[allow(missing_docs)]
+    Erode { x_radius: f32, y_radius: f32 },
+    /// @see [ImageFilter::new_matrix](crate::ImageFilter::new_matrix)
+    #[allow(missing_docs)]
+    Matrix {
+        matrix: Matrix,
+        sampling: TextureSampling,
+    },
+    /// @see [ImageFilter::new_compose](crate::ImageFilter::new_compose)
+    #[allow(missing_docs)]
+    Compose {
+        outer: Arc<RecordedImageFilter>,
+        inner: Arc<RecordedImageFilter>,
+    },
+    /// @see [Context::new_image_filter_from_fragment_program](crate::Context::new_image_filter_from_fragment_program)
+    ///
+    /// The program itself is opaque, so only its inputs are recorded.
+    #[allow(missing_docs)]
+    FragmentProgram {
+        samplers: Vec<Arc<RecordedTexture>>,
+        uniform_data: Vec<u8>,
+    },
+}
+/// The arguments a [MaskFilter](crate::MaskFilter) was created with.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RecordedMaskFilter {
+    /// @see [MaskFilter::new_blur](crate::MaskFilter::new_blur)
+    #[allow(missing_docs)]
+    Blur { style: BlurStyle, sigma: f32 },
+}
+/// What is known about a [Texture](crate::Texture) on the rust side.
+///
+/// Textures live on the GPU, so usually only their size is recorded. Textures created
+/// with [Context::create_texture_from_recording](crate::Context::create_texture_from_recording)
+/// also keep their pixels around.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RecordedTexture {
+    /// The width in pixels.
+    pub width: u32,
+    /// The height in pixels.
+    pub height: u32,
+    /// The contents, contiguously laid out as RGBA8888.
+    pub pixels: Option<Arc<[u8]>>,
+}
+impl RecordedTexture {
+    /// Create a texture recording with its pixels.
+    ///
+    /// - width     width of texture
+    /// - height    height of texture
+    /// - pixels    texture bytes. contiguously laid out as RGBA8888
+    pub fn new_rgba8(
+        width: u32,
+        height: u32,
+        pixels: impl Into<Arc<[u8]>>,
+    ) -> Result<Self, &'static str> {
+        if width == 0 || height == 0 {
+            return Err("width and height must be greater than zero");
+        }
+        let pixels = pixels.into();
+        if pixels.len() != width as usize * height as usize * 4 {
+            return Err("provided buffer size does not match expected size");
+        }
+        Ok(Self {
+            width,
+            height,
+            pixels: Some(pixels),
+        })
+    }
+}
+/// The color of a gradient at `t`, where `0` is the first stop and `1` the last.
+///
+/// `None` where [TileMode::Decal] leaves the gradient transparent.
+/// Stops default to being evenly spaced, like in impeller.
+pub(crate) fn sample_gradient(
+    colors: &[Color],
+    stops: &[f32],
+    tile_mode: TileMode,
+    t: f32,
+) -> Option<Color> {
+    let t = match tile_mode {
+        TileMode::Clamp => t.clamp(0.0, 1.0),
+        TileMode::Repeat => t.rem_euclid(1.0),
+        TileMode::Mirror => 1.0 - (t.rem_euclid(2.0) - 1.0).abs(),
+        TileMode::Decal if !(0.0..=1.0).contains(&t) => return None,
+        TileMode::Decal => t,
+    };
+    let (first, last) = (colors.first()?, colors.last()?);
+    let stop = |i: usize| match stops.get(i) {
+        Some(stop) => *stop,
+        None => i as f32 / (colors.len() - 1).max(1) as f32,
+    };
+    if t <= stop(0) {
+        return Some(*first);
+    }
+    for i in 1..colors.len() {
+        let (start, end) = (stop(i - 1), stop(i));
+        if t <= end {
+            let f = if end > start {
+                (t - start) / (end - start)
+            } else {
+                1.0
+            };
+            let (a, b) = (colors[i - 1], colors[i]);
+            let mix = |a: f32, b: f32| a + (b - a) * f;
+            return Some(Color {
+                red: mix(a.red, b.red),
+                green: mix(a.green, b.green),
+                blue: mix(a.blue, b.blue),
+                alpha: mix(a.alpha, b.alpha),
+                color_space: a.color_space,
+            });
+        }
+    }
+    Some(*last)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn gradient_sampling() {
+        let red = Color::new_srgb(1.0, 0.0, 0.0);
+        let blue = Color::new_srgb(0.0, 0.0, 1.0);
+        let colors = [red, blue];
+        let sample = |tile_mode, t| sample_gradient(&colors, &[], tile_mode, t);
+        assert_eq!(sample(TileMode::Clamp, -1.0), Some(red));
+        assert_eq!(sample(TileMode::Clamp, 0.5).unwrap().blue, 0.5);
+        assert_eq!(sample(TileMode::Repeat, 1.25).unwrap().blue, 0.25);
+        assert_eq!(sample(TileMode::Mirror, 1.25).unwrap().blue, 0.75);
+        assert_eq!(sample(TileMode::Decal, 1.25), None);
+        // a hard stop in the middle
+        let hard = sample_gradient(
+            &[red, red, blue, blue],
+            &[0.0, 0.5, 0.5, 1.0],
+            TileMode::Clamp,
+            0.6,
+        );
+        assert_eq!(hard, Some(blue));
+    }
+}
@@ -0,0 +1,241 @@
+//! Blend mode math on premultiplied colors.
+//!
+//! <https://www.w3.org/TR/compositing-1/>
+use crate::{BlendMode, Color};
+
+/// Premultiplied red, green, blue and alpha.
+pub(crate) type Rgba = [f32; 4];
+
+/// Blend a premultiplied source color onto a premultiplied destination color.
+pub(crate) fn blend(mode: BlendMode, s: Rgba, d: Rgba) -> Rgba {
+    let (sa, da) = (s[3], d[3]);
+    let porter_duff = |fs: f32, fd: f32| std::array::from_fn(|i| s[i] * fs + d[i] * fd);
+    match mode {
+        BlendMode::Clear => [0.0; 4],
+        BlendMode::Source => s,
+        BlendMode::Destination => d,
+        BlendMode::SourceOver => porter_duff(1.0, 1.0 - sa),
+        BlendMode::DestinationOver => porter_duff(1.0 - da, 1.0),
+        BlendMode::SourceIn => porter_duff(da, 0.0),
+        BlendMode::DestinationIn => porter_duff(0.0, sa),
+        BlendMode::SourceOut => porter_duff(1.0 - da, 0.0),
+        BlendMode::DestinationOut => porter_duff(0.0, 1.0 - sa),
+        BlendMode::SourceATop => porter_duff(da, 1.0 - sa),
+        BlendMode::DestinationATop => porter_duff(1.0 - da, sa),
+        BlendMode::Xor => porter_duff(1.0 - da, 1.0 - sa),
+        BlendMode::Plus => std::array::from_fn(|i| (s[i] + d[i]).min(1.0)),
+        BlendMode::Modulate => std::array::from_fn(|i| s[i] * d[i]),
+        BlendMode::Screen => std::array::from_fn(|i| s[i] + d[i] - s[i] * d[i]),
+        BlendMode::Hue | BlendMode::Saturation | BlendMode::Color | BlendMode::Luminosity => {
+            let unpremultiply = |c: Rgba| {
+                if c[3] > 0.0 {
+                    [c[0] / c[3], c[1] / c[3], c[2] / c[3]]
+                } else {
+                    [0.0; 3]
+                }
+            };
+            let (cs, cb) = (unpremultiply(s), unpremultiply(d));
+            let mixed = match mode {
+                BlendMode::Hue => set_lum(set_sat(cs, sat(cb)), lum(cb)),
+                BlendMode::Saturation => set_lum(set_sat(cb, sat(cs)), lum(cb)),
+                BlendMode::Color => set_lum(cs, lum(cb)),
+                _ => set_lum(cb, lum(cs)),
+            };
+            let mut out: Rgba = std::array::from_fn(|i| {
+                if i < 3 {
+                    (1.0 - da) * s[i] + (1.0 - sa) * d[i] + sa * da * mixed[i]
+                } else {
+                    0.0
+                }
+            });
+            out[3] = sa + da - sa * da;
+            out
+        }
+        mode => {
+            // separable blend modes work on unpremultiplied channels
+            let f: fn(f32, f32) -> f32 = match mode {
+                BlendMode::Multiply => |cs, cb| cs * cb,
+                BlendMode::Overlay => |cs, cb| hard_light(cb, cs),
+                BlendMode::Darken => f32::min,
+                BlendMode::Lighten => f32::max,
+                BlendMode::ColorDodge => |cs, cb| {
+                    if cb == 0.0 {
+                        0.0
+                    } else if cs >= 1.0 {
+                        1.0
+                    } else {
+                        (cb / (1.0 - cs)).min(1.0)
+                    }
+                },
+                BlendMode::ColorBurn => |cs, cb| {
+                    if cb >= 1.0 {
+                        1.0
+                    } else if cs <= 0.0 {
+                        0.0
+                    } else {
+                        1.0 - ((1.0 - cb) / cs).min(1.0)
+                    }
+                },
+                BlendMode::HardLight => hard_light,
+                BlendMode::SoftLight => |cs, cb| {
+                    if cs <= 0.5 {
+                        cb - (1.0 - 2.0 * cs) * cb * (1.0 - cb)
+                    } else {
+                        let d = if cb <= 0.25 {
+                            ((16.0 * cb - 12.0) * cb + 4.0) * cb
+                        } else {
+                            cb.sqrt()
+                        };
+                        cb + (2.0 * cs - 1.0) * (d - cb)
+                    }
+                },
+                BlendMode::Difference => |cs, cb| (cs - cb).abs(),
+                _ => |cs, cb| cs + cb - 2.0 * cs * cb,
+            };
+            let mut out = [0.0; 4];
+            for i in 0..3 {
+                let cs = if sa > 0.0 { s[i] / sa } else { 0.0 };
+                let cb = if da > 0.0 { d[i] / da } else { 0.0 };
+                out[i] = (1.0 - da) * s[i] + (1.0 - sa) * d[i] + sa * da * f(cs, cb);
+            }
+            out[3] = sa + da - sa * da;
+            out
+        }
+    }
+}
+impl BlendMode {
+    /// Blend `src` onto `dst` on the CPU, like drawing `src` with this blend mode onto a
+    /// surface of the color `dst`. Both colors are not premultiplied, and neither is the result.
+    ///
+    /// Colors in different color spaces are converted to
+    /// [extended sRGB](crate::ColorSpace::ExtendedSRGB) first.
+    ///
+    /// <https://www.w3.org/TR/compositing-1/>
+    ///
+    /// ```rust
+    /// # use impellers::*;
+    /// let red = Color::new_srgb(1.0, 0.0, 0.0);
+    /// let half_blue = Color::new_srgba(0.0, 0.0, 1.0, 0.5);
+    /// assert_eq!(BlendMode::SourceOver.apply(half_blue, red), Color::new_srgb(0.5, 0.0, 0.5));
+    /// assert_eq!(BlendMode::Multiply.apply(red, Color::new_srgb(0.5, 0.5, 0.5)), Color::new_srgb(0.5, 0.0, 0.0));
+    /// ```
+    pub fn apply(self, src: Color, dst: Color) -> Color {
+        let (src, dst) = match src.color_space == dst.color_space {
+            true => (src, dst),
+            false => (src.to_extended_srgb(), dst.to_extended_srgb()),
+        };
+        let rgba = |c: Color| {
+            let c = c.premultiplied();
+            [c.red, c.green, c.blue, c.alpha]
+        };
+        let [red, green, blue, alpha] = blend(self, rgba(src), rgba(dst));
+        Color {
+            red,
+            green,
+            blue,
+            alpha,
+            color_space: src.color_space,
+        }
+        .unpremultiplied()
+    }
+}
+
+fn hard_light(cs: f32, cb: f32) -> f32 {
+    if cs <= 0.5 {
+        cb * 2.0 * cs
+    } else {
+        let cs = 2.0 * cs - 1.0;
+        cb + cs - cb * cs
+    }
+}
+fn lum(c: [f32; 3]) -> f32 {
+    0.3 * c[0] + 0.59 * c[1] + 0.11 * c[2]
+}
+fn set_lum(c: [f32; 3], l: f32) -> [f32; 3] {
+    let d = l - lum(c);
+    let c = c.map(|v| v + d);
+    let l = lum(c);
+    let n = c[0].min(c[1]).min(c[2]);
+    let x = c[0].max(c[1]).max(c[2]);
+    c.map(|v| {
+        let mut v = v;
+        if n < 0.0 {
+            v = l + (v - l) * l / (l - n);
+        }
+        if x > 1.0 {
+            v = l + (v - l) * (1.0 - l) / (x - l);
+        }
+        v
+    })
+}
+fn sat(c: [f32; 3]) -> f32 {
+    c[0].max(c[1]).max(c[2]) - c[0].min(c[1]).min(c[2])
+}
+fn set_sat(c: [f32; 3], s: f32) -> [f32; 3] {
+    let max = c[0].max(c[1]).max(c[2]);
+    let min = c[0].min(c[1]).min(c[2]);
+    if max <= min {
+        return [0.0; 3];
+    }
+    c.map(|v| (v - min) * s / (max - min))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ColorSpace;
+
+    #[test]
+    fn blend_modes() {
+        let red = [1.0, 0.0, 0.0, 1.0];
+        let half_blue = [0.0, 0.0, 0.5, 0.5];
+        assert_eq!(
+            blend(BlendMode::SourceOver, half_blue, red),
+            [0.5, 0.0, 0.5, 1.0]
+        );
+        assert_eq!(blend(BlendMode::DestinationOver, half_blue, red), red);
+        assert_eq!(blend(BlendMode::SourceIn, half_blue, [0.0; 4]), [0.0; 4]);
+        assert_eq!(blend(BlendMode::Xor, red, red), [0.0; 4]);
+        assert_eq!(blend(BlendMode::Plus, red, red), red);
+        let gray = [0.5, 0.5, 0.5, 1.0];
+        assert_eq!(blend(BlendMode::Multiply, gray, red), [0.5, 0.0, 0.0, 1.0]);
+        assert_eq!(blend(BlendMode::Screen, gray, red), [1.0, 0.5, 0.5, 1.0]);
+        assert_eq!(
+            blend(BlendMode::Difference, gray, red),
+            [0.5, 0.5, 0.5, 1.0]
+        );
+        // blending onto nothing is just the source
+        assert_eq!(blend(BlendMode::Overlay, half_blue, [0.0; 4]), half_blue);
+        // luminosity of gray on red keeps the hue of red
+        let [r, g, b, a] = blend(BlendMode::Luminosity, gray, red);
+        assert!(r > g && g == b && a == 1.0);
+        assert!((lum([r, g, b]) - 0.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn apply() {
+        let red = Color::new_srgb(1.0, 0.0, 0.0);
+        let half_blue = Color::new_srgba(0.0, 0.0, 1.0, 0.5);
+        assert_eq!(BlendMode::Clear.apply(red, red), Color::TRANSPARENT);
+        assert_eq!(BlendMode::Destination.apply(half_blue, red), red);
+        assert_eq!(BlendMode::Source.apply(half_blue, red), half_blue);
+        assert_eq!(
+            BlendMode::SourceOver.apply(half_blue, Color::TRANSPARENT),
+            half_blue
+        );
+        assert_eq!(
+            BlendMode::DestinationOut.apply(half_blue, red),
+            red.with_alpha(0.5)
+        );
+        let screen = BlendMode::Screen.apply(half_blue, red);
+        assert_eq!(screen, Color::new_srgb(1.0, 0.0, 0.5));
+        let p3 = Color {
+            color_space: ColorSpace::DisplayP3,
+            ..red
+        };
+        assert_eq!(BlendMode::Source.apply(p3, p3), p3);
+        let mixed = BlendMode::Source.apply(p3, red);
+        assert_eq!(mixed.color_space, ColorSpace::ExtendedSRGB);
+        assert!(mixed.red > 1.0);
+    }
+}
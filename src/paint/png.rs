@@ -0,0 +1,119 @@
+//! A minimal png encoder, so recorded pixels can be looked at without extra dependencies.
+//!
+//! The image data is stored without compression.
+//! <https://www.w3.org/TR/png-3/>
+use crate::RecordedTexture;
+
+impl RecordedTexture {
+    /// Encode the pixels as a png file.
+    ///
+    /// Returns `None` if the pixels were not recorded.
+    pub fn to_png(&self) -> Option<Vec<u8>> {
+        let pixels = self.pixels.as_ref()?;
+        Some(encode_rgba8(self.width, self.height, pixels))
+    }
+}
+
+/// Encode tightly packed RGBA8888 pixels as a png file.
+pub(crate) fn encode_rgba8(width: u32, height: u32, pixels: &[u8]) -> Vec<u8> {
+    debug_assert_eq!(pixels.len(), width as usize * height as usize * 4);
+    let mut out = b"\x89PNG\r\n\x1a\n".to_vec();
+
+    let mut header = vec![];
+    header.extend_from_slice(&width.to_be_bytes());
+    header.extend_from_slice(&height.to_be_bytes());
+    // 8 bits per channel, RGBA, deflate, adaptive filtering, no interlacing
+    header.extend_from_slice(&[8, 6, 0, 0, 0]);
+    chunk(&mut out, b"IHDR", &header);
+
+    // every scanline starts with its filter type, which is always "none" here.
+    let mut scanlines = Vec::with_capacity(pixels.len() + height as usize);
+    for row in pixels.chunks(width as usize * 4) {
+        scanlines.push(0);
+        scanlines.extend_from_slice(row);
+    }
+    chunk(&mut out, b"IDAT", &zlib_stored(&scanlines));
+    chunk(&mut out, b"IEND", &[]);
+    out
+}
+
+fn chunk(out: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = out.len();
+    out.extend_from_slice(kind);
+    out.extend_from_slice(data);
+    let crc = crc32(&out[start..]);
+    out.extend_from_slice(&crc.to_be_bytes());
+}
+
+/// A zlib stream made of uncompressed deflate blocks.
+///
+/// <https://www.rfc-editor.org/rfc/rfc1950> <https://www.rfc-editor.org/rfc/rfc1951#section-3.2.4>
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    let mut out = vec![0x78, 0x01];
+    let mut blocks = data.chunks(u16::MAX as usize).peekable();
+    if blocks.peek().is_none() {
+        out.extend_from_slice(&[1, 0, 0, 0xff, 0xff]);
+    }
+    while let Some(block) = blocks.next() {
+        out.push(blocks.peek().is_none() as u8);
+        let len = block.len() as u16;
+        out.extend_from_slice(&len.to_le_bytes());
+        out.extend_from_slice(&(!len).to_le_bytes());
+        out.extend_from_slice(block);
+    }
+    out.extend_from_slice(&adler32(data).to_be_bytes());
+    out
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for byte in data {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for byte in data {
+        a = (a + *byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    (b << 16) | a
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn png_layout() {
+        let texture =
+            RecordedTexture::new_rgba8(2, 1, vec![255, 0, 0, 255, 0, 0, 255, 128]).unwrap();
+        let png = texture.to_png().unwrap();
+        assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+        assert_eq!(&png[12..16], b"IHDR");
+        assert_eq!(&png[16..24], &[0, 0, 0, 2, 0, 0, 0, 1]);
+        // the well known crc of an empty IEND chunk
+        assert_eq!(&png[png.len() - 8..], b"IEND\xae\x42\x60\x82");
+        assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+        assert_eq!(adler32(b"Wikipedia"), 0x11e6_0398);
+        // zlib header, one final stored block of a filter byte and two pixels, then the checksum
+        let idat = &png[33 + 8..];
+        assert_eq!(&idat[..7], &[0x78, 0x01, 1, 9, 0, 0xf6, 0xff]);
+        assert_eq!(&idat[7..16], &[0, 255, 0, 0, 255, 0, 0, 255, 128]);
+        let no_pixels = RecordedTexture {
+            pixels: None,
+            ..texture
+        };
+        assert_eq!(no_pixels.to_png(), None);
+    }
+}
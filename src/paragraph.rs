@@ -0,0 +1,134 @@
+//! Rust-side mirrors of [ParagraphStyle](crate::ParagraphStyle),
+//! [ParagraphBuilder](crate::ParagraphBuilder) and [Paragraph](crate::Paragraph).
+//!
+//! Text layout happens inside impeller, so a [RecordedParagraph] keeps the styled
+//! text it was built from, along with the metrics that were measured at build time.
+use std::sync::Arc;
+
+use crate::{
+    Color, FontStyle, FontWeight, RecordedPaint, TextAlignment, TextDecorationStyle,
+    TextDecorationType, TextDirection,
+};
+
+/// The setters that were called on a [ParagraphStyle](crate::ParagraphStyle).
+///
+/// Fields that are `None` were never set, so impeller uses its defaults for them.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RecordedParagraphStyle {
+    /// @see [ParagraphStyle::set_foreground](crate::ParagraphStyle::set_foreground)
+    pub foreground: Option<RecordedPaint>,
+    /// @see [ParagraphStyle::set_background](crate::ParagraphStyle::set_background)
+    pub background: Option<RecordedPaint>,
+    /// @see [ParagraphStyle::set_font_weight](crate::ParagraphStyle::set_font_weight)
+    pub font_weight: Option<FontWeight>,
+    /// @see [ParagraphStyle::set_font_style](crate::ParagraphStyle::set_font_style)
+    pub font_style: Option<FontStyle>,
+    /// @see [ParagraphStyle::set_font_family](crate::ParagraphStyle::set_font_family)
+    pub font_family: Option<String>,
+    /// @see [ParagraphStyle::set_font_size](crate::ParagraphStyle::set_font_size)
+    pub font_size: Option<f32>,
+    /// @see [ParagraphStyle::set_height](crate::ParagraphStyle::set_height)
+    pub height: Option<f32>,
+    /// @see [ParagraphStyle::set_text_alignment](crate::ParagraphStyle::set_text_alignment)
+    pub text_alignment: Option<TextAlignment>,
+    /// @see [ParagraphStyle::set_text_direction](crate::ParagraphStyle::set_text_direction)
+    pub text_direction: Option<TextDirection>,
+    /// @see [ParagraphStyle::set_text_decoration](crate::ParagraphStyle::set_text_decoration)
+    pub text_decoration: Option<RecordedTextDecoration>,
+    /// @see [ParagraphStyle::set_max_lines](crate::ParagraphStyle::set_max_lines)
+    pub max_lines: Option<u32>,
+    /// @see [ParagraphStyle::set_locale](crate::ParagraphStyle::set_locale)
+    pub locale: Option<String>,
+    /// @see [ParagraphStyle::set_ellipsis](crate::ParagraphStyle::set_ellipsis)
+    pub ellipsis: Option<String>,
+}
+/// The arguments of [ParagraphStyle::set_text_decoration](crate::ParagraphStyle::set_text_decoration).
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[allow(missing_docs)]
+pub struct RecordedTextDecoration {
+    pub types: TextDecorationType,
+    pub color: Color,
+    pub style: TextDecorationStyle,
+    pub thickness_multiplier: f32,
+}
+/// A call on a [ParagraphBuilder](crate::ParagraphBuilder).
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParagraphItem {
+    /// @see [ParagraphBuilder::push_style](crate::ParagraphBuilder::push_style)
+    PushStyle(Arc<RecordedParagraphStyle>),
+    /// @see [ParagraphBuilder::pop_style](crate::ParagraphBuilder::pop_style)
+    PopStyle,
+    /// @see [ParagraphBuilder::add_text](crate::ParagraphBuilder::add_text)
+    Text(String),
+}
+/// The styled text of a [Paragraph](crate::Paragraph) and its layout metrics.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RecordedParagraph {
+    /// The calls made on the paragraph builder, in order.
+    pub items: Vec<ParagraphItem>,
+    /// The width passed to [ParagraphBuilder::build](crate::ParagraphBuilder::build).
+    pub width: f32,
+    /// @see [Paragraph::get_height](crate::Paragraph::get_height)
+    pub height: f32,
+    /// @see [Paragraph::get_longest_line_width](crate::Paragraph::get_longest_line_width)
+    pub longest_line_width: f32,
+    /// @see [Paragraph::get_alphabetic_baseline](crate::Paragraph::get_alphabetic_baseline)
+    pub alphabetic_baseline: f32,
+}
+impl RecordedParagraph {
+    /// All the text of the paragraph, without any styling.
+    pub fn text(&self) -> String {
+        self.items
+            .iter()
+            .filter_map(|item| match item {
+                ParagraphItem::Text(text) => Some(text.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+    /// The runs of text along with the style that is on top of the style stack
+    /// when they were added. `None` means the default style.
+    pub fn runs(&self) -> impl Iterator<Item = (Option<&RecordedParagraphStyle>, &str)> {
+        let mut stack = vec![];
+        self.items.iter().filter_map(move |item| match item {
+            ParagraphItem::PushStyle(style) => {
+                stack.push(style.as_ref());
+                None
+            }
+            ParagraphItem::PopStyle => {
+                stack.pop();
+                None
+            }
+            ParagraphItem::Text(text) => Some((stack.last().copied(), text.as_str())),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn text_and_runs() {
+        let big = RecordedParagraphStyle {
+            font_size: Some(24.0),
+            ..Default::default()
+        };
+        let paragraph = RecordedParagraph {
+            items: vec![
+                ParagraphItem::Text("Hello, ".into()),
+                ParagraphItem::PushStyle(Arc::new(big.clone())),
+                ParagraphItem::Text("Big".into()),
+                ParagraphItem::PopStyle,
+                ParagraphItem::Text(" world!".into()),
+            ],
+            ..Default::default()
+        };
+        assert_eq!(paragraph.text(), "Hello, Big world!");
+        let runs: Vec<_> = paragraph.runs().collect();
+        assert_eq!(
+            runs,
+            [(None, "Hello, "), (Some(&big), "Big"), (None, " world!")]
+        );
+    }
+}
@@ -0,0 +1,523 @@
+//! Rust-side mirror of the path commands that [PathBuilder](crate::PathBuilder) forwards to impeller.
+//!
+//! Impeller paths are opaque, so every [Path](crate::Path) also carries a [RecordedPath]
+//! that can be iterated and inspected without a GPU context.
+use crate::{FillType, Point, Rect, RoundingRadii};
+
+mod bounds;
+mod dash;
+pub(crate) mod flatten;
+mod hit;
+mod measure;
+mod ops;
+mod stroke;
+mod svg;
+mod transform;
+pub use flatten::Polyline;
+pub use measure::{ContourMeasure, PathMeasure};
+pub use ops::PathOp;
+pub use svg::ParseError;
+
+/// The magic number used to approximate a quarter circle with a cubic bézier curve.
+///
+/// <https://github.com/flutter/engine/blob/main/impeller/geometry/path_builder.h>
+pub(crate) const ARC_APPROXIMATION_MAGIC: f32 = 0.551_915_05;
+
+/// The verbs of a [RecordedPath].
+///
+/// Each verb consumes [Self::point_count] points from [RecordedPath::points].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum PathVerb {
+    /// Starts a new contour.
+    Move,
+    /// A straight line.
+    Line,
+    /// A quadratic bézier curve.
+    Quad,
+    /// A cubic bézier curve.
+    Cubic,
+    /// Closes the current contour with a line back to its starting point.
+    Close,
+}
+impl PathVerb {
+    /// The number of points consumed by this verb.
+    pub const fn point_count(self) -> usize {
+        match self {
+            PathVerb::Move | PathVerb::Line => 1,
+            PathVerb::Quad => 2,
+            PathVerb::Cubic => 3,
+            PathVerb::Close => 0,
+        }
+    }
+}
+/// A single path command along with its points.
+///
+/// The start point of a segment is the end point of the previous segment.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum PathSegment {
+    /// Start a new contour at the point.
+    MoveTo(Point),
+    /// A line to the point.
+    LineTo(Point),
+    /// A quadratic curve with a control point and an end point.
+    QuadTo(Point, Point),
+    /// A cubic curve with two control points and an end point.
+    CubicTo(Point, Point, Point),
+    /// Close the current contour.
+    Close,
+}
+impl PathSegment {
+    /// The verb of this segment.
+    pub fn verb(&self) -> PathVerb {
+        match self {
+            PathSegment::MoveTo(_) => PathVerb::Move,
+            PathSegment::LineTo(_) => PathVerb::Line,
+            PathSegment::QuadTo(..) => PathVerb::Quad,
+            PathSegment::CubicTo(..) => PathVerb::Cubic,
+            PathSegment::Close => PathVerb::Close,
+        }
+    }
+}
+
+/// A list of path verbs and their points, along with the fill type.
+///
+/// This is what [PathBuilder](crate::PathBuilder) records on the rust side
+/// while forwarding the same calls to impeller. Shapes like rects, ovals and
+/// arcs are recorded as the lines and curves that approximate them.
+///
+/// As this is plain data, you can also build one yourself and
+/// create a [Path](crate::Path) from it with [Self::to_path].
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordedPath {
+    verbs: Vec<PathVerb>,
+    points: Vec<Point>,
+    fill_type: FillType,
+}
+impl Default for RecordedPath {
+    fn default() -> Self {
+        Self {
+            verbs: vec![],
+            points: vec![],
+            fill_type: FillType::NonZero,
+        }
+    }
+}
+impl RecordedPath {
+    /// An empty path with the given fill type.
+    pub fn new(fill_type: FillType) -> Self {
+        Self {
+            fill_type,
+            ..Default::default()
+        }
+    }
+    /// The fill type used to decide the inside of this path.
+    pub fn fill_type(&self) -> FillType {
+        self.fill_type
+    }
+    /// Set the fill type.
+    pub fn set_fill_type(&mut self, fill_type: FillType) -> &mut Self {
+        self.fill_type = fill_type;
+        self
+    }
+    /// The recorded verbs.
+    pub fn verbs(&self) -> &[PathVerb] {
+        &self.verbs
+    }
+    /// The points of all the verbs, in order.
+    pub fn points(&self) -> &[Point] {
+        &self.points
+    }
+    /// True if no verbs have been recorded.
+    pub fn is_empty(&self) -> bool {
+        self.verbs.is_empty()
+    }
+    /// Remove all verbs and points. The fill type is left alone.
+    pub fn clear(&mut self) {
+        self.verbs.clear();
+        self.points.clear();
+    }
+    /// Iterate over the segments of the path.
+    pub fn iter(&self) -> PathIter<'_> {
+        PathIter {
+            verbs: self.verbs.iter(),
+            points: &self.points,
+        }
+    }
+    /// The end point of the last segment.
+    ///
+    /// After a close, this is the start of the closed contour.
+    pub fn current_point(&self) -> Option<Point> {
+        match self.verbs.last()? {
+            PathVerb::Close => self.last_move_to(),
+            _ => self.points.last().copied(),
+        }
+    }
+    fn last_move_to(&self) -> Option<Point> {
+        let mut index = self.points.len();
+        for verb in self.verbs.iter().rev() {
+            index -= verb.point_count();
+            if *verb == PathVerb::Move {
+                return Some(self.points[index]);
+            }
+        }
+        None
+    }
+    /// Lines/curves need a contour to be part of. Like skia, we start one
+    /// at the last contour's start point (or origin) when there isn't one.
+    fn inject_move_to_if_needed(&mut self) {
+        match self.verbs.last() {
+            None => self.move_to(Point::zero()),
+            Some(PathVerb::Close) => {
+                let start = self.last_move_to().unwrap_or_default();
+                self.move_to(start)
+            }
+            Some(_) => self,
+        };
+    }
+    /// Append a segment.
+    pub fn push(&mut self, segment: PathSegment) -> &mut Self {
+        match segment {
+            PathSegment::MoveTo(p) => self.move_to(p),
+            PathSegment::LineTo(p) => self.line_to(p),
+            PathSegment::QuadTo(c, p) => self.quadratic_curve_to(c, p),
+            PathSegment::CubicTo(c1, c2, p) => self.cubic_curve_to(c1, c2, p),
+            PathSegment::Close => self.close(),
+        }
+    }
+    /// Start a new contour at the location.
+    pub fn move_to(&mut self, location: Point) -> &mut Self {
+        self.verbs.push(PathVerb::Move);
+        self.points.push(location);
+        self
+    }
+    /// Add a line from the current point to the location.
+    pub fn line_to(&mut self, location: Point) -> &mut Self {
+        self.inject_move_to_if_needed();
+        self.verbs.push(PathVerb::Line);
+        self.points.push(location);
+        self
+    }
+    /// Add a quadratic curve from the current point to the end point.
+    pub fn quadratic_curve_to(&mut self, control_point: Point, end_point: Point) -> &mut Self {
+        self.inject_move_to_if_needed();
+        self.verbs.push(PathVerb::Quad);
+        self.points.extend([control_point, end_point]);
+        self
+    }
+    /// Add a cubic curve from the current point to the end point.
+    pub fn cubic_curve_to(
+        &mut self,
+        control_point_1: Point,
+        control_point_2: Point,
+        end_point: Point,
+    ) -> &mut Self {
+        self.inject_move_to_if_needed();
+        self.verbs.push(PathVerb::Cubic);
+        self.points
+            .extend([control_point_1, control_point_2, end_point]);
+        self
+    }
+    /// Close the current contour.
+    ///
+    /// Does nothing if there's no open contour.
+    pub fn close(&mut self) -> &mut Self {
+        if !matches!(self.verbs.last(), None | Some(PathVerb::Close)) {
+            self.verbs.push(PathVerb::Close);
+        }
+        self
+    }
+    /// Add a closed contour for the rect, going clockwise from the top left corner.
+    pub fn add_rect(&mut self, rect: &Rect) -> &mut Self {
+        self.move_to(rect.min())
+            .line_to(Point::new(rect.max_x(), rect.min_y()))
+            .line_to(rect.max())
+            .line_to(Point::new(rect.min_x(), rect.max_y()))
+            .close()
+    }
+    /// Add an arc of the oval as a new contour.
+    ///
+    /// Angles are in degrees and go clockwise starting from the positive x axis.
+    pub fn add_arc(
+        &mut self,
+        oval_bounds: &Rect,
+        start_angle_degrees: f32,
+        end_angle_degrees: f32,
+    ) -> &mut Self {
+        let center = oval_bounds.center();
+        let radii = oval_bounds.size.to_vector() / 2.0;
+        let start = start_angle_degrees.to_radians();
+        let sweep = (end_angle_degrees - start_angle_degrees).to_radians();
+        self.move_to(center + euclid::vec2(radii.x * start.cos(), radii.y * start.sin()));
+        append_arc(self, center, radii, 0.0, start, sweep);
+        self
+    }
+    /// Add a closed contour for the oval, going clockwise from the right most point.
+    pub fn add_oval(&mut self, oval_bounds: &Rect) -> &mut Self {
+        self.add_arc(oval_bounds, 0.0, 360.0).close()
+    }
+    /// Add a closed contour for the rounded rect, going clockwise from the top left corner.
+    ///
+    /// Radii that are too large are scaled down to fit the rect.
+    pub fn add_rounded_rect(&mut self, rect: &Rect, rounding_radii: &RoundingRadii) -> &mut Self {
+        let radii = scale_radii_to_fit(rounding_radii, rect);
+        let (l, t, r, b) = (rect.min_x(), rect.min_y(), rect.max_x(), rect.max_y());
+        let corner = |path: &mut Self, start: Point, corner: Point, end: Point| {
+            if start != end {
+                path.cubic_curve_to(
+                    start.lerp(corner, ARC_APPROXIMATION_MAGIC),
+                    end.lerp(corner, ARC_APPROXIMATION_MAGIC),
+                    end,
+                );
+            }
+        };
+        let tl = radii.top_left;
+        let tr = radii.top_right;
+        let br = radii.bottom_right;
+        let bl = radii.bottom_left;
+        self.move_to(Point::new(l + tl.x, t))
+            .line_to(Point::new(r - tr.x, t));
+        corner(
+            self,
+            Point::new(r - tr.x, t),
+            Point::new(r, t),
+            Point::new(r, t + tr.y),
+        );
+        self.line_to(Point::new(r, b - br.y));
+        corner(
+            self,
+            Point::new(r, b - br.y),
+            Point::new(r, b),
+            Point::new(r - br.x, b),
+        );
+        self.line_to(Point::new(l + bl.x, b));
+        corner(
+            self,
+            Point::new(l + bl.x, b),
+            Point::new(l, b),
+            Point::new(l, b - bl.y),
+        );
+        self.line_to(Point::new(l, t + tl.y));
+        corner(
+            self,
+            Point::new(l, t + tl.y),
+            Point::new(l, t),
+            Point::new(l + tl.x, t),
+        );
+        self.close()
+    }
+}
+/// Clamps negative radii to zero and scales all the radii down uniformly, so
+/// that adjacent corners don't overlap along any side of the rect.
+pub(crate) fn scale_radii_to_fit(radii: &RoundingRadii, rect: &Rect) -> RoundingRadii {
+    let clamp = |p: Point| Point::new(p.x.max(0.0), p.y.max(0.0));
+    let mut radii = RoundingRadii {
+        top_left: clamp(radii.top_left),
+        top_right: clamp(radii.top_right),
+        bottom_left: clamp(radii.bottom_left),
+        bottom_right: clamp(radii.bottom_right),
+    };
+    let fit = |side: f32, a: f32, b: f32| {
+        if a + b > side && a + b > 0.0 {
+            side.max(0.0) / (a + b)
+        } else {
+            1.0
+        }
+    };
+    let (w, h) = (rect.width(), rect.height());
+    let scale = fit(w, radii.top_left.x, radii.top_right.x)
+        .min(fit(w, radii.bottom_left.x, radii.bottom_right.x))
+        .min(fit(h, radii.top_left.y, radii.bottom_left.y))
+        .min(fit(h, radii.top_right.y, radii.bottom_right.y));
+    if scale < 1.0 {
+        for corner in [
+            &mut radii.top_left,
+            &mut radii.top_right,
+            &mut radii.bottom_left,
+            &mut radii.bottom_right,
+        ] {
+            *corner = Point::new(corner.x * scale, corner.y * scale);
+        }
+    }
+    radii
+}
+/// Appends cubic curves for an elliptical arc, starting from the current point.
+///
+/// The ellipse is rotated by `x_axis_rotation` radians around its center and the
+/// arc is split into pieces of at most 90 degrees.
+pub(crate) fn append_arc(
+    path: &mut RecordedPath,
+    center: Point,
+    radii: euclid::default::Vector2D<f32>,
+    x_axis_rotation: f32,
+    start_radians: f32,
+    sweep_radians: f32,
+) {
+    use std::f32::consts::FRAC_PI_2;
+    let sweep = sweep_radians.clamp(-std::f32::consts::TAU, std::f32::consts::TAU);
+    let pieces = (sweep.abs() / FRAC_PI_2).ceil().max(1.0) as usize;
+    let step = sweep / pieces as f32;
+    let k = 4.0 / 3.0 * (step / 4.0).tan();
+    let (sin_rot, cos_rot) = x_axis_rotation.sin_cos();
+    let rotate = |v: euclid::default::Vector2D<f32>| {
+        euclid::vec2(v.x * cos_rot - v.y * sin_rot, v.x * sin_rot + v.y * cos_rot)
+    };
+    let point_at =
+        |angle: f32| center + rotate(euclid::vec2(radii.x * angle.cos(), radii.y * angle.sin()));
+    let tangent_at = |angle: f32| {
+        rotate(euclid::vec2(
+            -radii.x * angle.sin() * k,
+            radii.y * angle.cos() * k,
+        ))
+    };
+    for piece in 0..pieces {
+        let a0 = start_radians + step * piece as f32;
+        let a1 = a0 + step;
+        path.cubic_curve_to(
+            point_at(a0) + tangent_at(a0),
+            point_at(a1) - tangent_at(a1),
+            point_at(a1),
+        );
+    }
+}
+
+/// Iterator over the segments of a path.
+///
+/// @see [RecordedPath::iter] and [Path::iter](crate::Path::iter)
+#[derive(Debug, Clone)]
+pub struct PathIter<'a> {
+    verbs: std::slice::Iter<'a, PathVerb>,
+    points: &'a [Point],
+}
+impl Iterator for PathIter<'_> {
+    type Item = PathSegment;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let verb = *self.verbs.next()?;
+        let (p, rest) = self.points.split_at(verb.point_count());
+        self.points = rest;
+        Some(match verb {
+            PathVerb::Move => PathSegment::MoveTo(p[0]),
+            PathVerb::Line => PathSegment::LineTo(p[0]),
+            PathVerb::Quad => PathSegment::QuadTo(p[0], p[1]),
+            PathVerb::Cubic => PathSegment::CubicTo(p[0], p[1], p[2]),
+            PathVerb::Close => PathSegment::Close,
+        })
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.verbs.size_hint()
+    }
+}
+impl ExactSizeIterator for PathIter<'_> {}
+impl<'a> IntoIterator for &'a RecordedPath {
+    type Item = PathSegment;
+    type IntoIter = PathIter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+impl FromIterator<PathSegment> for RecordedPath {
+    fn from_iter<T: IntoIterator<Item = PathSegment>>(iter: T) -> Self {
+        let mut path = Self::default();
+        path.extend(iter);
+        path
+    }
+}
+impl Extend<PathSegment> for RecordedPath {
+    fn extend<T: IntoIterator<Item = PathSegment>>(&mut self, iter: T) {
+        for segment in iter {
+            self.push(segment);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn approx_eq(a: Point, b: Point) -> bool {
+        (a - b).length() < 1e-3
+    }
+
+    #[test]
+    fn rect_is_recorded_as_closed_lines() {
+        let mut path = RecordedPath::new(FillType::Odd);
+        path.add_rect(&Rect::new(Point::new(1.0, 2.0), euclid::size2(3.0, 4.0)));
+        assert_eq!(path.fill_type(), FillType::Odd);
+        assert_eq!(
+            path.iter().collect::<Vec<_>>(),
+            vec![
+                PathSegment::MoveTo(Point::new(1.0, 2.0)),
+                PathSegment::LineTo(Point::new(4.0, 2.0)),
+                PathSegment::LineTo(Point::new(4.0, 6.0)),
+                PathSegment::LineTo(Point::new(1.0, 6.0)),
+                PathSegment::Close,
+            ]
+        );
+        assert_eq!(path.current_point(), Some(Point::new(1.0, 2.0)));
+    }
+
+    #[test]
+    fn line_without_move_starts_a_contour() {
+        let mut path = RecordedPath::default();
+        path.line_to(Point::new(5.0, 5.0));
+        path.move_to(Point::new(1.0, 1.0))
+            .line_to(Point::new(2.0, 1.0))
+            .close()
+            .line_to(Point::new(3.0, 3.0));
+        assert_eq!(
+            path.verbs(),
+            &[
+                PathVerb::Move,
+                PathVerb::Line,
+                PathVerb::Move,
+                PathVerb::Line,
+                PathVerb::Close,
+                PathVerb::Move,
+                PathVerb::Line
+            ]
+        );
+        assert_eq!(path.points()[0], Point::zero());
+        assert_eq!(path.points()[4], Point::new(1.0, 1.0));
+    }
+
+    #[test]
+    fn arcs_and_ovals_end_on_the_ellipse() {
+        let bounds = Rect::new(Point::new(0.0, 0.0), euclid::size2(20.0, 10.0));
+        let mut path = RecordedPath::default();
+        path.add_arc(&bounds, 0.0, 90.0);
+        assert!(approx_eq(path.points()[0], Point::new(20.0, 5.0)));
+        assert!(approx_eq(
+            path.current_point().unwrap(),
+            Point::new(10.0, 10.0)
+        ));
+        assert_eq!(path.verbs(), &[PathVerb::Move, PathVerb::Cubic]);
+
+        let mut oval = RecordedPath::default();
+        oval.add_oval(&bounds);
+        assert_eq!(oval.verbs().len(), 6);
+        let segments: Vec<_> = oval.iter().collect();
+        let PathSegment::CubicTo(_, _, bottom) = segments[1] else {
+            panic!("expected a cubic");
+        };
+        assert!(approx_eq(bottom, Point::new(10.0, 10.0)));
+    }
+
+    #[test]
+    fn rounded_rect_corners() {
+        let rect = Rect::new(Point::new(0.0, 0.0), euclid::size2(10.0, 10.0));
+        let radii = RoundingRadii {
+            top_left: Point::new(2.0, 2.0),
+            top_right: Point::new(2.0, 2.0),
+            bottom_left: Point::zero(),
+            bottom_right: Point::new(2.0, 2.0),
+        };
+        let mut path = RecordedPath::default();
+        path.add_rounded_rect(&rect, &radii);
+        let verbs = path.verbs();
+        assert_eq!(verbs.first(), Some(&PathVerb::Move));
+        assert_eq!(verbs.last(), Some(&PathVerb::Close));
+        assert_eq!(verbs.iter().filter(|v| **v == PathVerb::Cubic).count(), 3);
+        assert_eq!(path.points()[0], Point::new(2.0, 0.0));
+    }
+}
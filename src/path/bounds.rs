@@ -0,0 +1,127 @@
+//! Exact bounds of paths, using the extrema of their curves.
+use super::flatten::{eval_cubic, eval_quad};
+use super::{PathSegment, RecordedPath};
+use crate::{Point, Rect};
+
+/// The roots of `a * t^2 + b * t + c` strictly between 0 and 1.
+fn unit_roots(a: f32, b: f32, c: f32) -> impl Iterator<Item = f32> {
+    let roots = if a.abs() < 1e-12 {
+        if b.abs() < 1e-12 {
+            [None, None]
+        } else {
+            [Some(-c / b), None]
+        }
+    } else {
+        let discriminant = b * b - 4.0 * a * c;
+        if discriminant < 0.0 {
+            [None, None]
+        } else {
+            let sqrt = discriminant.sqrt();
+            [Some((-b + sqrt) / (2.0 * a)), Some((-b - sqrt) / (2.0 * a))]
+        }
+    };
+    roots.into_iter().flatten().filter(|t| *t > 0.0 && *t < 1.0)
+}
+
+impl RecordedPath {
+    /// The smallest rect containing the path.
+    ///
+    /// Unlike [Path::get_bounds](crate::Path::get_bounds), this finds the extrema
+    /// of the curves instead of including their control points, and skips moves
+    /// that don't start any segments. Empty paths have zero bounds.
+    pub fn tight_bounds(&self) -> Rect {
+        let mut points = vec![];
+        let mut current = Point::zero();
+        let mut pending_move = None;
+        for segment in self {
+            if let Some(start) = pending_move.take() {
+                if !matches!(segment, PathSegment::MoveTo(_) | PathSegment::Close) {
+                    points.push(start);
+                }
+            }
+            match segment {
+                PathSegment::MoveTo(p) => pending_move = Some(p),
+                PathSegment::LineTo(p) => points.push(p),
+                PathSegment::QuadTo(c, p) => {
+                    points.push(p);
+                    let [p0, c, p1] = [current, c, p].map(Point::to_vector);
+                    // the derivative is 2 * ((c - p0) + t * (p0 - 2c + p1))
+                    let (a, b) = (p0 - c, p0 - c * 2.0 + p1);
+                    for (a, b) in [(a.x, b.x), (a.y, b.y)] {
+                        for t in unit_roots(0.0, b, -a) {
+                            points.push(eval_quad(current, c.to_point(), p, t));
+                        }
+                    }
+                }
+                PathSegment::CubicTo(c1, c2, p) => {
+                    points.push(p);
+                    let [p0, c1, c2, p1] = [current, c1, c2, p].map(Point::to_vector);
+                    // the derivative is 3 * (a * t^2 + b * t + c)
+                    let a = p1 - p0 + (c1 - c2) * 3.0;
+                    let b = (p0 - c1 * 2.0 + c2) * 2.0;
+                    let c = c1 - p0;
+                    for (a, b, c) in [(a.x, b.x, c.x), (a.y, b.y, c.y)] {
+                        for t in unit_roots(a, b, c) {
+                            points.push(eval_cubic(current, c1.to_point(), c2.to_point(), p, t));
+                        }
+                    }
+                }
+                PathSegment::Close => {}
+            }
+            current = match segment {
+                PathSegment::MoveTo(p)
+                | PathSegment::LineTo(p)
+                | PathSegment::QuadTo(_, p)
+                | PathSegment::CubicTo(_, _, p) => p,
+                PathSegment::Close => current,
+            };
+        }
+        Rect::from_points(points)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Size;
+
+    #[test]
+    fn curve_extrema() {
+        let mut path = RecordedPath::default();
+        // the peak of the quad is at y = 50, halfway to its control point
+        path.move_to(Point::new(0.0, 0.0))
+            .quadratic_curve_to(Point::new(50.0, 100.0), Point::new(100.0, 0.0));
+        assert_eq!(
+            path.tight_bounds(),
+            Rect::new(Point::new(0.0, 0.0), Size::new(100.0, 50.0))
+        );
+        // a symmetric cubic reaches 3/4 of the way to its control points
+        let mut path = RecordedPath::default();
+        path.move_to(Point::new(0.0, 0.0)).cubic_curve_to(
+            Point::new(0.0, -40.0),
+            Point::new(100.0, -40.0),
+            Point::new(100.0, 0.0),
+        );
+        let bounds = path.tight_bounds();
+        assert!((bounds.min_y() + 30.0).abs() < 1e-4);
+        assert_eq!(bounds.max_y(), 0.0);
+        assert_eq!(bounds.width(), 100.0);
+    }
+
+    #[test]
+    fn circles_and_lone_moves() {
+        let mut path = RecordedPath::default();
+        path.move_to(Point::new(-100.0, -100.0))
+            .add_oval(&Rect::new(Point::new(10.0, 10.0), Size::new(80.0, 80.0)))
+            .move_to(Point::new(500.0, 500.0));
+        let bounds = path.tight_bounds();
+        let expected = Rect::new(Point::new(10.0, 10.0), Size::new(80.0, 80.0));
+        for (a, b) in [
+            (bounds.min(), expected.min()),
+            (bounds.max(), expected.max()),
+        ] {
+            assert!((a - b).length() < 1e-3, "{bounds:?}");
+        }
+        assert_eq!(RecordedPath::default().tight_bounds(), Rect::zero());
+    }
+}
@@ -0,0 +1,176 @@
+//! Splitting paths into dashes, like svg's `stroke-dasharray`.
+use super::{PathMeasure, RecordedPath};
+
+/// Like skia, give up on patterns that would create an absurd number of dashes.
+const MAX_DASHES: f32 = 1_000_000.0;
+
+impl RecordedPath {
+    /// Split the contours of this path into dashes.
+    ///
+    /// - intervals: Alternating lengths of dashes and gaps, starting with a dash.
+    ///   Like `stroke-dasharray`, odd length lists are repeated to make them even.
+    /// - phase: How far into the intervals the pattern starts, like `stroke-dashoffset`.
+    ///
+    /// The pattern restarts at every contour. On closed contours, a dash that goes over
+    /// the start point is kept as one piece. Dashes of zero length become a move and a
+    /// line to the same point, so they show up as dots when stroked with round or square caps.
+    ///
+    /// If the intervals are empty, have a negative or non finite length or add up to zero,
+    /// the path is returned unchanged (as svg draws a solid line in that case).
+    pub fn dash(&self, intervals: &[f32], phase: f32) -> RecordedPath {
+        let intervals: Vec<f32> = if intervals.len() % 2 == 1 {
+            intervals.iter().chain(intervals).copied().collect()
+        } else {
+            intervals.to_vec()
+        };
+        let total: f32 = intervals.iter().sum();
+        if intervals.is_empty()
+            || intervals.iter().any(|i| *i < 0.0 || !i.is_finite())
+            || total <= 0.0
+            || !total.is_finite()
+        {
+            return self.clone();
+        }
+        let measure = PathMeasure::new(self, false);
+        if measure.length() / total * intervals.len() as f32 > MAX_DASHES {
+            return self.clone();
+        }
+        // where the pattern starts
+        let mut start_index = 0;
+        let mut start_remaining = intervals[0];
+        let mut phase = if phase.is_finite() {
+            phase.rem_euclid(total)
+        } else {
+            0.0
+        };
+        while phase > 0.0 && phase >= start_remaining {
+            phase -= start_remaining;
+            start_index = (start_index + 1) % intervals.len();
+            start_remaining = intervals[start_index];
+        }
+        start_remaining -= phase;
+
+        let mut path = RecordedPath::new(self.fill_type());
+        for contour in measure.contours() {
+            let length = contour.length();
+            let mut dashes = vec![];
+            let mut index = start_index;
+            let mut remaining = start_remaining;
+            let mut distance = 0.0;
+            while distance < length {
+                let end = (distance + remaining).min(length);
+                if index % 2 == 0 {
+                    dashes.push((distance, end));
+                }
+                distance += remaining;
+                index = (index + 1) % intervals.len();
+                remaining = intervals[index];
+            }
+            let mut dashes = dashes.as_slice();
+            if contour.is_closed() && dashes.len() > 1 {
+                let (first, last) = (dashes[0], dashes[dashes.len() - 1]);
+                if first.0 == 0.0 && first.1 > 0.0 && last.1 >= length && last.0 < length {
+                    // join the dash that ends at the start point with the one starting there
+                    contour.extract_path(last.0, length, true, &mut path);
+                    contour.extract_path(0.0, first.1, false, &mut path);
+                    dashes = &dashes[1..dashes.len() - 1];
+                }
+            }
+            for &(start, end) in dashes {
+                if start == end {
+                    let (p, _) = contour.pos_tan(start);
+                    path.move_to(p).line_to(p);
+                } else {
+                    contour.extract_path(start, end, true, &mut path);
+                }
+            }
+        }
+        path
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{PathSegment, Point, Rect};
+
+    fn dashes(path: &RecordedPath) -> Vec<(Point, Point)> {
+        let mut dashes = vec![];
+        for segment in path {
+            match segment {
+                PathSegment::MoveTo(p) => dashes.push((p, p)),
+                PathSegment::LineTo(p) => dashes.last_mut().unwrap().1 = p,
+                _ => panic!("{segment:?}"),
+            }
+        }
+        dashes
+    }
+    fn line() -> RecordedPath {
+        let mut path = RecordedPath::default();
+        path.move_to(Point::new(0.0, 0.0))
+            .line_to(Point::new(100.0, 0.0));
+        path
+    }
+    /// The rounded x coordinates of the dashes on a horizontal line.
+    fn x(dashes: &[(Point, Point)]) -> Vec<(f32, f32)> {
+        dashes
+            .iter()
+            .map(|(a, b)| (a.x.round(), b.x.round()))
+            .collect()
+    }
+
+    #[test]
+    fn intervals_and_phase() {
+        let dashed = dashes(&line().dash(&[30.0, 10.0], 0.0));
+        assert_eq!(x(&dashed), [(0.0, 30.0), (40.0, 70.0), (80.0, 100.0)]);
+        let dashed = dashes(&line().dash(&[30.0, 10.0], 35.0));
+        assert_eq!(x(&dashed), [(5.0, 35.0), (45.0, 75.0), (85.0, 100.0)]);
+        // negative phases wrap around
+        let dashed = dashes(&line().dash(&[30.0, 10.0], -10.0));
+        assert_eq!(x(&dashed), [(10.0, 40.0), (50.0, 80.0), (90.0, 100.0)]);
+        // odd lists repeat, so 20 is a dash, then a gap, then a dash again
+        let dashed = dashes(&line().dash(&[20.0, 10.0, 40.0], 0.0));
+        assert_eq!(x(&dashed), [(0.0, 20.0), (30.0, 70.0), (90.0, 100.0)]);
+        // zero length dashes make dots
+        let dashed = dashes(&line().dash(&[0.0, 50.0], 0.0));
+        assert_eq!(x(&dashed), [(0.0, 0.0), (50.0, 50.0)]);
+        // invalid patterns leave the path alone
+        assert_eq!(line().dash(&[], 0.0), line());
+        assert_eq!(line().dash(&[10.0, -1.0], 0.0), line());
+        assert_eq!(line().dash(&[0.0, 0.0], 0.0), line());
+    }
+
+    #[test]
+    fn closed_contours_join_at_the_start() {
+        let mut square = RecordedPath::default();
+        square.add_rect(&Rect::new(Point::zero(), euclid::size2(40.0, 40.0)));
+        // the perimeter is 160, so the dash at the end continues into the first one
+        let dashed = square.dash(&[20.0, 20.0], 10.0);
+        let moves = dashed
+            .iter()
+            .filter(|s| matches!(s, PathSegment::MoveTo(_)))
+            .count();
+        assert_eq!(moves, 4);
+        let first: Vec<_> = dashed.iter().take(3).collect();
+        assert_eq!(
+            first,
+            [
+                PathSegment::MoveTo(Point::new(0.0, 10.0)),
+                PathSegment::LineTo(Point::new(0.0, 0.0)),
+                PathSegment::LineTo(Point::new(10.0, 0.0)),
+            ]
+        );
+    }
+
+    #[test]
+    fn curves_stay_curves() {
+        let mut oval = RecordedPath::default();
+        oval.add_oval(&Rect::new(Point::zero(), euclid::size2(100.0, 100.0)));
+        let dashed = oval.dash(&[10.0, 5.0], 0.0);
+        assert!(dashed.iter().any(|s| matches!(s, PathSegment::CubicTo(..))));
+        let measure = PathMeasure::new(&dashed, false);
+        // about two thirds of the circumference is left
+        let expected = PathMeasure::new(&oval, false).length() * 2.0 / 3.0;
+        assert!((measure.length() - expected).abs() < 10.0);
+    }
+}
@@ -0,0 +1,187 @@
+//! Approximating path curves with straight lines.
+use super::{PathSegment, RecordedPath};
+use crate::Point;
+
+/// The tolerance used by operations that flatten paths internally.
+///
+/// Paths are usually in logical pixels, so this is well below what anyone can see.
+pub(crate) const DEFAULT_TOLERANCE: f32 = 0.05;
+
+/// A contour of a path with its curves replaced by lines.
+///
+/// @see [RecordedPath::flatten]
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Polyline {
+    /// The points of the lines. Closed polylines don't repeat their first point at the end.
+    pub points: Vec<Point>,
+    /// Whether the contour was closed, connecting the last point back to the first.
+    pub closed: bool,
+}
+
+pub(crate) fn eval_quad(p0: Point, p1: Point, p2: Point, t: f32) -> Point {
+    let mt = 1.0 - t;
+    (p0.to_vector() * (mt * mt) + p1.to_vector() * (2.0 * mt * t) + p2.to_vector() * (t * t))
+        .to_point()
+}
+
+pub(crate) fn eval_cubic(p0: Point, p1: Point, p2: Point, p3: Point, t: f32) -> Point {
+    let mt = 1.0 - t;
+    (p0.to_vector() * (mt * mt * mt)
+        + p1.to_vector() * (3.0 * mt * mt * t)
+        + p2.to_vector() * (3.0 * mt * t * t)
+        + p3.to_vector() * (t * t * t))
+        .to_point()
+}
+
+/// The number of lines needed to stay within tolerance of a curve, using Wang's formula.
+///
+/// `degree_factor` is `degree * (degree - 1) / 8` and `max_second_difference` is the
+/// largest `|p[i] - 2 * p[i + 1] + p[i + 2]|` of the control points.
+fn subdivisions(degree_factor: f32, max_second_difference: f32, tolerance: f32) -> usize {
+    let n = (degree_factor * max_second_difference / tolerance)
+        .sqrt()
+        .ceil();
+    if n.is_finite() {
+        (n as usize).clamp(1, 1000)
+    } else {
+        1
+    }
+}
+
+pub(crate) fn quad_subdivisions(p0: Point, p1: Point, p2: Point, tolerance: f32) -> usize {
+    let dd = (p0.to_vector() - p1.to_vector() * 2.0 + p2.to_vector()).length();
+    subdivisions(0.25, dd, tolerance)
+}
+
+pub(crate) fn cubic_subdivisions(
+    p0: Point,
+    p1: Point,
+    p2: Point,
+    p3: Point,
+    tolerance: f32,
+) -> usize {
+    let dd1 = (p0.to_vector() - p1.to_vector() * 2.0 + p2.to_vector()).length();
+    let dd2 = (p1.to_vector() - p2.to_vector() * 2.0 + p3.to_vector()).length();
+    subdivisions(0.75, dd1.max(dd2), tolerance)
+}
+
+/// Flatten all the contours of a path.
+///
+/// Contours with just a move (no segments) are skipped.
+pub(crate) fn flatten(path: &RecordedPath, tolerance: f32) -> Vec<Polyline> {
+    let tolerance = tolerance.max(1e-4);
+    let mut polylines = vec![];
+    let mut current = Polyline::default();
+    let finish = |current: &mut Polyline, polylines: &mut Vec<Polyline>| {
+        let polyline = std::mem::take(current);
+        if polyline.points.len() > 1 {
+            polylines.push(polyline);
+        }
+    };
+    for segment in path {
+        let last = current.points.last().copied().unwrap_or_default();
+        match segment {
+            PathSegment::MoveTo(p) => {
+                finish(&mut current, &mut polylines);
+                current.points.push(p);
+            }
+            PathSegment::LineTo(p) => current.points.push(p),
+            PathSegment::QuadTo(c, p) => {
+                let n = quad_subdivisions(last, c, p, tolerance);
+                current
+                    .points
+                    .extend((1..n).map(|i| eval_quad(last, c, p, i as f32 / n as f32)));
+                current.points.push(p);
+            }
+            PathSegment::CubicTo(c1, c2, p) => {
+                let n = cubic_subdivisions(last, c1, c2, p, tolerance);
+                current
+                    .points
+                    .extend((1..n).map(|i| eval_cubic(last, c1, c2, p, i as f32 / n as f32)));
+                current.points.push(p);
+            }
+            PathSegment::Close => {
+                current.closed = true;
+                // contours that already end at their start (like ovals, or an explicit
+                // line back) would repeat it. the float error of arcs is scaled by the
+                // coordinates, and two points are kept so that dots still stroke.
+                let points = &mut current.points;
+                if let [first, .., _, last] = points[..] {
+                    let scale = first.x.abs().max(first.y.abs()).max(1.0);
+                    if (last - first).length() <= scale * 1e-5 {
+                        points.pop();
+                    }
+                }
+                finish(&mut current, &mut polylines);
+            }
+        }
+    }
+    finish(&mut current, &mut polylines);
+    polylines
+}
+
+impl RecordedPath {
+    /// Approximate the curves of this path with straight lines.
+    ///
+    /// - tolerance: The furthest the lines may be from the curves. It's clamped to
+    ///   at least `0.0001`, and each curve is split into at most 1000 lines.
+    ///
+    /// Every contour with at least one segment becomes a [Polyline].
+    pub fn flatten(&self, tolerance: f32) -> impl Iterator<Item = Polyline> {
+        flatten(self, tolerance).into_iter()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn curves_stay_within_tolerance() {
+        let mut path = RecordedPath::default();
+        path.add_oval(&crate::Rect::new(
+            Point::zero(),
+            euclid::size2(100.0, 100.0),
+        ));
+        let polylines = flatten(&path, 0.1);
+        assert_eq!(polylines.len(), 1);
+        assert!(polylines[0].closed);
+        let center = Point::new(50.0, 50.0);
+        for (a, b) in polylines[0].points.iter().zip(&polylines[0].points[1..]) {
+            // the midpoint of each chord is the furthest point from the circle
+            let mid = a.lerp(*b, 0.5);
+            assert!(50.0 - (mid - center).length() < 0.1 + 1e-3);
+        }
+    }
+
+    #[test]
+    fn closed_contours_do_not_repeat_their_start() {
+        let mut path = RecordedPath::default();
+        path.add_oval(&crate::Rect::new(
+            Point::new(1000.0, 1000.0),
+            euclid::size2(100.0, 100.0),
+        ));
+        path.move_to(Point::new(0.0, 0.0))
+            .line_to(Point::new(10.0, 0.0))
+            .line_to(Point::new(0.0, 10.0))
+            .line_to(Point::new(0.0, 0.0))
+            .close();
+        // a dot keeps its two points
+        path.move_to(Point::new(5.0, 5.0))
+            .line_to(Point::new(5.0, 5.0))
+            .close();
+        // open contours are left alone
+        path.move_to(Point::new(0.0, 0.0))
+            .line_to(Point::new(10.0, 0.0))
+            .line_to(Point::new(0.0, 0.0));
+        let polylines = flatten(&path, 0.1);
+        assert_eq!(polylines.len(), 4);
+        for polyline in &polylines[..2] {
+            let (first, last) = (polyline.points[0], *polyline.points.last().unwrap());
+            assert!((last - first).length() > 1.0, "{polyline:?}");
+        }
+        assert_eq!(polylines[1].points.len(), 3);
+        assert_eq!(polylines[2].points.len(), 2);
+        assert_eq!(polylines[3].points.len(), 3);
+    }
+}
@@ -0,0 +1,146 @@
+//! Hit testing the fill and stroke of paths.
+use super::flatten::{flatten, DEFAULT_TOLERANCE};
+use super::RecordedPath;
+use euclid::default::Point2D;
+
+use crate::{FillType, Point};
+
+/// The winding number of the point around closed contours given as their edges,
+/// counting clockwise (on screen) contours as positive.
+pub(super) fn winding(
+    edges: impl IntoIterator<Item = (Point2D<f64>, Point2D<f64>)>,
+    point: Point2D<f64>,
+) -> i32 {
+    let mut winding = 0;
+    for (a, b) in edges {
+        let side = (b - a).cross(point - a);
+        if a.y <= point.y {
+            if b.y > point.y && side > 0.0 {
+                winding += 1;
+            }
+        } else if b.y <= point.y && side < 0.0 {
+            winding -= 1;
+        }
+    }
+    winding
+}
+/// Whether a winding number is inside the fill with the given [FillType].
+pub(super) fn is_inside(winding: i32, fill_type: FillType) -> bool {
+    match fill_type {
+        FillType::NonZero => winding != 0,
+        FillType::Odd => winding % 2 != 0,
+    }
+}
+
+impl RecordedPath {
+    /// The winding number of the point: how many times the contours wind around it,
+    /// counting clockwise (on screen) contours as positive.
+    ///
+    /// Open contours are implicitly closed, like when filling them.
+    pub fn winding_number(&self, point: Point) -> i32 {
+        let polylines = flatten(self, DEFAULT_TOLERANCE);
+        let edges = polylines.iter().flat_map(|polyline| {
+            let points = &polyline.points;
+            (0..points.len()).map(|i| (points[i].cast(), points[(i + 1) % points.len()].cast()))
+        });
+        winding(edges, point.cast())
+    }
+    /// Whether the point is inside the fill of the path, according to its [FillType].
+    pub fn contains(&self, point: Point) -> bool {
+        is_inside(self.winding_number(point), self.fill_type())
+    }
+    /// The distance from the point to the closest segment of the path.
+    ///
+    /// Closed contours include their closing line, while open contours don't.
+    /// Returns [f32::INFINITY] for paths without any segments.
+    pub fn distance_to_stroke(&self, point: Point) -> f32 {
+        let mut distance = f32::INFINITY;
+        for polyline in flatten(self, DEFAULT_TOLERANCE) {
+            let points = &polyline.points;
+            let count = if polyline.closed {
+                points.len()
+            } else {
+                points.len() - 1
+            };
+            for i in 0..count {
+                let (a, b) = (points[i], points[(i + 1) % points.len()]);
+                let ab = b - a;
+                let t = if ab.square_length() > 0.0 {
+                    ((point - a).dot(ab) / ab.square_length()).clamp(0.0, 1.0)
+                } else {
+                    0.0
+                };
+                distance = distance.min((a + ab * t - point).length());
+            }
+        }
+        distance
+    }
+    /// Whether the point is on the stroke of the path with the given width.
+    ///
+    /// Caps and joins are treated as round, which is close enough for picking
+    /// with a pointer. For the exact shape, use [RecordedPath::stroke_to_path]
+    /// and [RecordedPath::contains].
+    pub fn hit_stroke(&self, point: Point, stroke_width: f32) -> bool {
+        self.distance_to_stroke(point) <= stroke_width / 2.0
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::fixtures::rect;
+
+    #[test]
+    fn fill_types() {
+        // two nested rects in the same direction
+        let mut path = RecordedPath::default();
+        path.add_rect(&rect(0.0, 0.0, 100.0, 100.0))
+            .add_rect(&rect(25.0, 25.0, 50.0, 50.0));
+        assert_eq!(path.winding_number(Point::new(50.0, 50.0)), 2);
+        assert!(path.contains(Point::new(50.0, 50.0)));
+        assert!(path.contains(Point::new(10.0, 10.0)));
+        assert!(!path.contains(Point::new(150.0, 50.0)));
+        path.set_fill_type(FillType::Odd);
+        assert!(!path.contains(Point::new(50.0, 50.0)));
+        assert!(path.contains(Point::new(10.0, 10.0)));
+    }
+
+    #[test]
+    fn curves_and_open_contours() {
+        let mut oval = RecordedPath::default();
+        oval.add_oval(&rect(0.0, 0.0, 100.0, 100.0));
+        assert!(oval.contains(Point::new(50.0, 50.0)));
+        assert!(oval.contains(Point::new(50.0, 1.0)));
+        // inside the bounds, but outside the circle
+        assert!(!oval.contains(Point::new(5.0, 5.0)));
+        // open contours are filled as if they were closed
+        let mut triangle = RecordedPath::default();
+        triangle
+            .move_to(Point::new(0.0, 0.0))
+            .line_to(Point::new(100.0, 0.0))
+            .line_to(Point::new(0.0, 100.0));
+        assert!(triangle.contains(Point::new(10.0, 10.0)));
+        assert!(!triangle.contains(Point::new(60.0, 60.0)));
+    }
+
+    #[test]
+    fn strokes() {
+        let mut open = RecordedPath::default();
+        open.move_to(Point::new(0.0, 0.0))
+            .line_to(Point::new(100.0, 0.0))
+            .line_to(Point::new(100.0, 100.0));
+        assert_eq!(open.distance_to_stroke(Point::new(50.0, 3.0)), 3.0);
+        assert!(open.hit_stroke(Point::new(50.0, 3.0), 6.0));
+        assert!(!open.hit_stroke(Point::new(50.0, 3.0), 5.0));
+        // round around the ends
+        assert!(open.hit_stroke(Point::new(-2.0, -2.0), 6.0));
+        // the closing line is only part of the stroke once closed
+        assert!(!open.hit_stroke(Point::new(50.0, 50.0), 2.0));
+        open.close();
+        assert!(open.hit_stroke(Point::new(50.0, 50.0), 2.0));
+        assert_eq!(
+            RecordedPath::default().distance_to_stroke(Point::zero()),
+            f32::INFINITY
+        );
+    }
+}
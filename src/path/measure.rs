@@ -0,0 +1,381 @@
+//! Measuring the length of paths and finding points along them.
+//!
+//! Like skia's `SkContourMeasure`, each curve is split into short pieces to build a
+//! table from distance to curve parameter. Points and sub-paths are computed on the
+//! original curves, so they stay exact between the pieces.
+use super::flatten::{cubic_subdivisions, eval_cubic, eval_quad, quad_subdivisions};
+use super::{PathSegment, RecordedPath};
+use crate::{Point, Vector};
+
+/// Much finer than what's needed for drawing, so that lengths are accurate.
+const TOLERANCE: f32 = 0.005;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Segment {
+    Line(Point, Point),
+    Quad(Point, Point, Point),
+    Cubic(Point, Point, Point, Point),
+}
+impl Segment {
+    fn eval(&self, t: f32) -> Point {
+        match *self {
+            Segment::Line(p0, p1) => p0.lerp(p1, t),
+            Segment::Quad(p0, c, p1) => eval_quad(p0, c, p1, t),
+            Segment::Cubic(p0, c1, c2, p1) => eval_cubic(p0, c1, c2, p1, t),
+        }
+    }
+    fn derivative(&self, t: f32) -> Vector {
+        let mt = 1.0 - t;
+        match *self {
+            Segment::Line(p0, p1) => p1 - p0,
+            Segment::Quad(p0, c, p1) => ((c - p0) * mt + (p1 - c) * t) * 2.0,
+            Segment::Cubic(p0, c1, c2, p1) => {
+                ((c1 - p0) * (mt * mt) + (c2 - c1) * (2.0 * mt * t) + (p1 - c2) * (t * t)) * 3.0
+            }
+        }
+    }
+    fn subdivisions(&self) -> usize {
+        match *self {
+            Segment::Line(..) => 1,
+            Segment::Quad(p0, c, p1) => quad_subdivisions(p0, c, p1, TOLERANCE),
+            Segment::Cubic(p0, c1, c2, p1) => cubic_subdivisions(p0, c1, c2, p1, TOLERANCE),
+        }
+    }
+    /// Append the part of the segment between `t0` and `t1`, assuming the path
+    /// is already at the point at `t0`.
+    ///
+    /// The control points of the part are the blossoms (polar forms) of the curve.
+    fn append_part(&self, t0: f32, t1: f32, path: &mut RecordedPath) {
+        if t0 >= t1 {
+            return;
+        }
+        match *self {
+            Segment::Line(..) => {
+                path.line_to(self.eval(t1));
+            }
+            Segment::Quad(p0, c, p1) => {
+                let blossom = |u: f32, v: f32| {
+                    (p0.to_vector() * ((1.0 - u) * (1.0 - v))
+                        + c.to_vector() * ((1.0 - u) * v + u * (1.0 - v))
+                        + p1.to_vector() * (u * v))
+                        .to_point()
+                };
+                path.quadratic_curve_to(blossom(t0, t1), blossom(t1, t1));
+            }
+            Segment::Cubic(p0, c1, c2, p1) => {
+                let blossom = |u: f32, v: f32, w: f32| {
+                    let (mu, mv, mw) = (1.0 - u, 1.0 - v, 1.0 - w);
+                    (p0.to_vector() * (mu * mv * mw)
+                        + c1.to_vector() * (u * mv * mw + mu * v * mw + mu * mv * w)
+                        + c2.to_vector() * (u * v * mw + u * mv * w + mu * v * w)
+                        + p1.to_vector() * (u * v * w))
+                        .to_point()
+                };
+                path.cubic_curve_to(
+                    blossom(t0, t0, t1),
+                    blossom(t0, t1, t1),
+                    blossom(t1, t1, t1),
+                );
+            }
+        }
+    }
+}
+
+/// The end of a short piece of a segment.
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct Piece {
+    /// The distance from the start of the contour.
+    distance: f32,
+    segment: usize,
+    t: f32,
+}
+
+/// The length of a single contour and the points along it.
+///
+/// Created by [PathMeasure]. Contours with a length of zero are skipped.
+///
+/// <https://api.flutter.dev/flutter/dart-ui/PathMetric-class.html>
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContourMeasure {
+    segments: Vec<Segment>,
+    pieces: Vec<Piece>,
+    closed: bool,
+}
+impl ContourMeasure {
+    fn new(segments: Vec<Segment>, closed: bool) -> Self {
+        let mut pieces = vec![];
+        let mut distance = 0.0;
+        for (index, segment) in segments.iter().enumerate() {
+            let n = segment.subdivisions();
+            let mut previous = segment.eval(0.0);
+            for i in 1..=n {
+                let t = i as f32 / n as f32;
+                let p = segment.eval(t);
+                let length = (p - previous).length();
+                if length > 0.0 {
+                    distance += length;
+                    pieces.push(Piece {
+                        distance,
+                        segment: index,
+                        t,
+                    });
+                }
+                previous = p;
+            }
+        }
+        Self {
+            segments,
+            pieces,
+            closed,
+        }
+    }
+    /// The length of the contour.
+    pub fn length(&self) -> f32 {
+        self.pieces.last().map(|p| p.distance).unwrap_or_default()
+    }
+    /// Whether the contour is closed (or was forced closed by [PathMeasure::new]).
+    pub fn is_closed(&self) -> bool {
+        self.closed
+    }
+    /// The segment and curve parameter at the distance, which is clamped to the contour.
+    fn locate(&self, distance: f32) -> (usize, f32) {
+        let distance = distance.clamp(0.0, self.length());
+        let index = self
+            .pieces
+            .partition_point(|p| p.distance < distance)
+            .min(self.pieces.len() - 1);
+        let piece = self.pieces[index];
+        let (start_distance, start_t) = match index.checked_sub(1).map(|i| self.pieces[i]) {
+            Some(previous) if previous.segment == piece.segment => (previous.distance, previous.t),
+            Some(previous) => (previous.distance, 0.0),
+            None => (0.0, 0.0),
+        };
+        let ratio = (distance - start_distance) / (piece.distance - start_distance);
+        (
+            piece.segment,
+            start_t + (piece.t - start_t) * ratio.clamp(0.0, 1.0),
+        )
+    }
+    /// The position and unit tangent at the distance along the contour.
+    ///
+    /// The distance is clamped to `0.0..=length`.
+    ///
+    /// <https://api.flutter.dev/flutter/dart-ui/PathMetric/getTangentForOffset.html>
+    pub fn pos_tan(&self, distance: f32) -> (Point, Vector) {
+        let (index, t) = self.locate(distance);
+        let segment = &self.segments[index];
+        let mut tangent = segment.derivative(t);
+        if tangent.square_length() < 1e-12 {
+            // curves with control points on their end points have no derivative there
+            tangent = segment.eval((t + 1e-3).min(1.0)) - segment.eval((t - 1e-3).max(0.0));
+        }
+        (segment.eval(t), tangent.normalize())
+    }
+    /// The part of the contour between two distances.
+    ///
+    /// - start_with_move_to: Whether the part starts a new contour. Otherwise, it continues
+    ///   from the current point of the `path`.
+    ///
+    /// The distances are clamped to the contour, and nothing is added if `start >= end`.
+    /// Curves stay curves.
+    ///
+    /// <https://api.flutter.dev/flutter/dart-ui/PathMetric/extractPath.html>
+    pub fn extract_path(
+        &self,
+        start: f32,
+        end: f32,
+        start_with_move_to: bool,
+        path: &mut RecordedPath,
+    ) {
+        let start = start.max(0.0);
+        let end = end.min(self.length());
+        if start >= end {
+            return;
+        }
+        let (first, t0) = self.locate(start);
+        let (last, t1) = self.locate(end);
+        if start_with_move_to {
+            path.move_to(self.segments[first].eval(t0));
+        }
+        if first == last {
+            self.segments[first].append_part(t0, t1, path);
+            return;
+        }
+        self.segments[first].append_part(t0, 1.0, path);
+        for segment in &self.segments[first + 1..last] {
+            segment.append_part(0.0, 1.0, path);
+        }
+        self.segments[last].append_part(0.0, t1, path);
+    }
+}
+
+/// Measures the contours of a path.
+///
+/// ```rust
+/// # use impellers::*;
+/// let mut path = RecordedPath::default();
+/// path.add_rect(&Rect::new(Point::zero(), Size::new(10.0, 20.0)));
+/// let measure = PathMeasure::new(&path, false);
+/// assert_eq!(measure.length(), 60.0);
+/// let (position, tangent) = measure.contours()[0].pos_tan(15.0);
+/// assert_eq!(position, Point::new(10.0, 5.0));
+/// assert_eq!(tangent, Vector::new(0.0, 1.0));
+/// ```
+///
+/// <https://api.flutter.dev/flutter/dart-ui/PathMetrics-class.html>
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct PathMeasure {
+    contours: Vec<ContourMeasure>,
+}
+impl PathMeasure {
+    /// Measure all the contours of the path.
+    ///
+    /// - force_closed: Measure open contours as if they were closed.
+    pub fn new(path: &RecordedPath, force_closed: bool) -> Self {
+        let mut contours = vec![];
+        let mut segments = vec![];
+        let mut start = Point::zero();
+        let mut last = Point::zero();
+        let mut finish = |segments: &mut Vec<Segment>, last: Point, start: Point, closed| {
+            let closed = closed || force_closed;
+            if closed && last != start {
+                segments.push(Segment::Line(last, start));
+            }
+            let contour = ContourMeasure::new(std::mem::take(segments), closed);
+            if contour.length() > 0.0 {
+                contours.push(contour);
+            }
+        };
+        for segment in path {
+            match segment {
+                PathSegment::MoveTo(p) => {
+                    if !segments.is_empty() {
+                        finish(&mut segments, last, start, false);
+                    }
+                    start = p;
+                    last = p;
+                }
+                PathSegment::LineTo(p) => {
+                    segments.push(Segment::Line(last, p));
+                    last = p;
+                }
+                PathSegment::QuadTo(c, p) => {
+                    segments.push(Segment::Quad(last, c, p));
+                    last = p;
+                }
+                PathSegment::CubicTo(c1, c2, p) => {
+                    segments.push(Segment::Cubic(last, c1, c2, p));
+                    last = p;
+                }
+                PathSegment::Close => {
+                    finish(&mut segments, last, start, true);
+                    last = start;
+                }
+            }
+        }
+        if !segments.is_empty() {
+            finish(&mut segments, last, start, false);
+        }
+        Self { contours }
+    }
+    /// The measured contours, in the order of the path.
+    pub fn contours(&self) -> &[ContourMeasure] {
+        &self.contours
+    }
+    /// The total length of all contours.
+    pub fn length(&self) -> f32 {
+        self.contours.iter().map(ContourMeasure::length).sum()
+    }
+    /// The part of the path between two distances, measured along all of its
+    /// contours one after another.
+    ///
+    /// This is handy for animating the "drawing" of a path with many strokes.
+    /// Every contour in the range starts with a move.
+    pub fn extract_path(&self, start: f32, end: f32) -> RecordedPath {
+        let mut path = RecordedPath::default();
+        let mut offset = 0.0;
+        for contour in &self.contours {
+            let length = contour.length();
+            if start < offset + length && end > offset {
+                contour.extract_path(start - offset, end - offset, true, &mut path);
+            }
+            offset += length;
+        }
+        path
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Rect;
+
+    fn assert_near(a: Point, b: Point) {
+        assert!((a - b).length() < 1e-3, "{a:?} != {b:?}");
+    }
+
+    #[test]
+    fn lines_and_closing() {
+        let mut path = RecordedPath::default();
+        path.move_to(Point::new(0.0, 0.0))
+            .line_to(Point::new(30.0, 0.0))
+            .line_to(Point::new(30.0, 40.0));
+        let open = PathMeasure::new(&path, false);
+        assert_eq!(open.length(), 70.0);
+        assert!(!open.contours()[0].is_closed());
+        let closed = PathMeasure::new(&path, true);
+        assert_eq!(closed.length(), 120.0);
+        let (p, tangent) = closed.contours()[0].pos_tan(95.0);
+        assert_near(p, Point::new(15.0, 20.0));
+        assert_near(tangent.to_point(), Point::new(-0.6, -0.8));
+        // clamped to the ends
+        assert_eq!(open.contours()[0].pos_tan(-5.0).0, Point::new(0.0, 0.0));
+        assert_eq!(open.contours()[0].pos_tan(500.0).0, Point::new(30.0, 40.0));
+        // lone moves and zero length contours are skipped
+        path.move_to(Point::new(5.0, 5.0))
+            .move_to(Point::new(6.0, 6.0))
+            .line_to(Point::new(6.0, 6.0));
+        assert_eq!(PathMeasure::new(&path, false).contours().len(), 1);
+    }
+
+    #[test]
+    fn curves() {
+        let mut circle = RecordedPath::default();
+        circle.add_oval(&Rect::new(Point::zero(), euclid::size2(100.0, 100.0)));
+        let measure = PathMeasure::new(&circle, false);
+        let contour = &measure.contours()[0];
+        assert!(contour.is_closed());
+        let circumference = std::f32::consts::PI * 100.0;
+        assert!((contour.length() - circumference).abs() < 0.05);
+        // a quarter of the way around, starting from the right going clockwise on screen
+        let (p, tangent) = contour.pos_tan(contour.length() / 4.0);
+        assert!((p - Point::new(50.0, 100.0)).length() < 0.05);
+        assert!((tangent - Vector::new(-1.0, 0.0)).length() < 1e-3);
+    }
+
+    #[test]
+    fn extracting() {
+        let mut path = RecordedPath::default();
+        path.move_to(Point::new(0.0, 0.0))
+            .quadratic_curve_to(Point::new(50.0, 100.0), Point::new(100.0, 0.0))
+            .move_to(Point::new(0.0, 200.0))
+            .line_to(Point::new(100.0, 200.0));
+        let measure = PathMeasure::new(&path, false);
+        let first = &measure.contours()[0];
+        let part = measure.extract_path(first.length() * 0.25, first.length() + 50.0);
+        let segments: Vec<_> = part.iter().collect();
+        assert_eq!(segments.len(), 4);
+        let PathSegment::MoveTo(start) = segments[0] else {
+            panic!("{segments:?}")
+        };
+        assert_near(start, first.pos_tan(first.length() * 0.25).0);
+        // the curve stays a curve, ending where the original ends
+        assert!(matches!(segments[1], PathSegment::QuadTo(_, p) if p == Point::new(100.0, 0.0)));
+        assert_eq!(segments[2], PathSegment::MoveTo(Point::new(0.0, 200.0)));
+        assert_eq!(segments[3], PathSegment::LineTo(Point::new(50.0, 200.0)));
+        // the middle part of the curve matches the original curve
+        let middle = PathMeasure::new(&part, false).contours()[0].clone();
+        assert!((middle.length() - first.length() * 0.75).abs() < 0.05);
+        assert!(measure.extract_path(10.0, 10.0).is_empty());
+    }
+}
@@ -0,0 +1,336 @@
+//! Boolean operations on paths.
+//!
+//! Both paths are flattened, their edges are split at every intersection and
+//! each piece is kept if the result is inside on exactly one side of it.
+//! The kept pieces are oriented to have the inside on the same side and chained
+//! back into closed contours.
+use std::collections::{HashMap, HashSet};
+
+use super::flatten::{flatten, DEFAULT_TOLERANCE};
+use super::hit::{is_inside, winding};
+use super::RecordedPath;
+use crate::FillType;
+
+/// The boolean operation to combine two paths with.
+///
+/// @see [RecordedPath::op] and [Path::op](crate::Path::op)
+///
+/// <https://api.flutter.dev/flutter/dart-ui/PathOperation.html>
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum PathOp {
+    /// The area inside either path.
+    Union,
+    /// The area inside both paths.
+    Intersect,
+    /// The area inside the first path, but not inside the second.
+    Difference,
+    /// The area inside exactly one of the paths.
+    Xor,
+}
+impl PathOp {
+    fn apply(self, a: bool, b: bool) -> bool {
+        match self {
+            PathOp::Union => a || b,
+            PathOp::Intersect => a && b,
+            PathOp::Difference => a && !b,
+            PathOp::Xor => a != b,
+        }
+    }
+}
+
+type P = euclid::default::Point2D<f64>;
+
+#[derive(Debug, Copy, Clone)]
+struct Edge {
+    a: P,
+    b: P,
+    /// 0 for the first path and 1 for the second.
+    owner: usize,
+}
+
+fn cross(a: euclid::default::Vector2D<f64>, b: euclid::default::Vector2D<f64>) -> f64 {
+    a.x * b.y - a.y * b.x
+}
+
+fn edges_of(path: &RecordedPath, owner: usize) -> Vec<Edge> {
+    let mut edges = vec![];
+    // fills implicitly close every contour
+    for polyline in flatten(path, DEFAULT_TOLERANCE) {
+        // tiny edges (like the float error between the end and start of an oval)
+        // are too short to tell their sides apart, so merge them into their neighbours.
+        let mut points: Vec<P> = vec![];
+        for p in polyline.points {
+            let p: P = p.cast();
+            if points.last().is_none_or(|last| (*last - p).length() > 1e-4) {
+                points.push(p);
+            }
+        }
+        while points.len() > 1 && (points[0] - points[points.len() - 1]).length() <= 1e-4 {
+            points.pop();
+        }
+        for (i, a) in points.iter().enumerate() {
+            let b = points[(i + 1) % points.len()];
+            if *a != b {
+                edges.push(Edge { a: *a, b, owner });
+            }
+        }
+    }
+    edges
+}
+
+/// Winding number of the point with respect to the edges of one of the paths.
+fn winding_of(edges: &[Edge], owner: usize, p: P) -> i32 {
+    let edges = edges.iter().filter(|e| e.owner == owner);
+    winding(edges.map(|edge| (edge.a, edge.b)), p)
+}
+
+/// Records the points where the edges cross (or overlap) each other.
+///
+/// The exact same point is recorded for both edges, so that the split edges
+/// can be chained together again.
+fn split_points(edges: &[Edge]) -> Vec<Vec<P>> {
+    const EPSILON: f64 = 1e-9;
+    let mut splits = vec![vec![]; edges.len()];
+    for i in 0..edges.len() {
+        let Edge { a, b, .. } = edges[i];
+        let r = b - a;
+        for j in (i + 1)..edges.len() {
+            let Edge { a: c, b: d, .. } = edges[j];
+            if a.x.max(b.x) < c.x.min(d.x)
+                || c.x.max(d.x) < a.x.min(b.x)
+                || a.y.max(b.y) < c.y.min(d.y)
+                || c.y.max(d.y) < a.y.min(b.y)
+            {
+                continue;
+            }
+            let s = d - c;
+            let denominator = cross(r, s);
+            let scale = r.length() * s.length();
+            if denominator.abs() <= EPSILON * scale {
+                // parallel. if collinear, split each edge at the other's end points
+                if cross(c - a, r).abs() > EPSILON * r.length() * (c - a).length().max(1.0) {
+                    continue;
+                }
+                let on_ab = |p: P| {
+                    let t = (p - a).dot(r) / r.square_length();
+                    t > EPSILON && t < 1.0 - EPSILON
+                };
+                let on_cd = |p: P| {
+                    let t = (p - c).dot(s) / s.square_length();
+                    t > EPSILON && t < 1.0 - EPSILON
+                };
+                for p in [c, d] {
+                    if on_ab(p) {
+                        splits[i].push(p);
+                    }
+                }
+                for p in [a, b] {
+                    if on_cd(p) {
+                        splits[j].push(p);
+                    }
+                }
+                continue;
+            }
+            let t = cross(c - a, s) / denominator;
+            let u = cross(c - a, r) / denominator;
+            if !(-EPSILON..=1.0 + EPSILON).contains(&t) || !(-EPSILON..=1.0 + EPSILON).contains(&u)
+            {
+                continue;
+            }
+            let t_is_end = t <= EPSILON || t >= 1.0 - EPSILON;
+            let u_is_end = u <= EPSILON || u >= 1.0 - EPSILON;
+            // prefer existing vertices over computed points
+            let point = if t <= EPSILON {
+                a
+            } else if t >= 1.0 - EPSILON {
+                b
+            } else if u <= EPSILON {
+                c
+            } else if u >= 1.0 - EPSILON {
+                d
+            } else {
+                a + r * t
+            };
+            if !t_is_end {
+                splits[i].push(point);
+            }
+            if !u_is_end {
+                splits[j].push(point);
+            }
+        }
+    }
+    splits
+}
+
+fn key(p: P) -> (u64, u64) {
+    (p.x.to_bits(), p.y.to_bits())
+}
+
+impl RecordedPath {
+    /// Combine this path with another using a boolean operation.
+    ///
+    /// The [FillType]s of both paths decide what's inside them.
+    /// Curves are flattened, so the result only contains straight lines and
+    /// is made of closed contours using [FillType::NonZero].
+    pub fn op(&self, other: &RecordedPath, op: PathOp) -> RecordedPath {
+        let mut edges = edges_of(self, 0);
+        edges.extend(edges_of(other, 1));
+        let splits = split_points(&edges);
+        let mut pieces = vec![];
+        for (edge, mut points) in edges.iter().zip(splits) {
+            let direction = edge.b - edge.a;
+            points.sort_by(|p, q| {
+                (*p - edge.a)
+                    .dot(direction)
+                    .total_cmp(&(*q - edge.a).dot(direction))
+            });
+            points.insert(0, edge.a);
+            points.push(edge.b);
+            points.dedup();
+            pieces.extend(points.windows(2).map(|w| (w[0], w[1])));
+        }
+        let fill_types = [self.fill_type(), other.fill_type()];
+        let inside = |p: P| {
+            op.apply(
+                is_inside(winding_of(&edges, 0, p), fill_types[0]),
+                is_inside(winding_of(&edges, 1, p), fill_types[1]),
+            )
+        };
+        let mut kept: Vec<(P, P)> = vec![];
+        let mut seen = HashSet::new();
+        for (a, b) in pieces {
+            let direction = b - a;
+            let length = direction.length();
+            if length == 0.0 {
+                continue;
+            }
+            let normal = euclid::vec2(-direction.y, direction.x) / length;
+            let offset = (length * 0.25).min(1e-3);
+            let mid = a.lerp(b, 0.5);
+            let left = inside(mid + normal * offset);
+            let right = inside(mid - normal * offset);
+            if left == right {
+                continue;
+            }
+            // keep the inside on the left
+            let (a, b) = if left { (a, b) } else { (b, a) };
+            if seen.insert((key(a), key(b))) {
+                kept.push((a, b));
+            }
+        }
+        chain(&kept)
+    }
+}
+
+/// Join edges that share end points into closed contours.
+fn chain(edges: &[(P, P)]) -> RecordedPath {
+    let mut outgoing: HashMap<(u64, u64), Vec<usize>> = HashMap::new();
+    for (i, (a, _)) in edges.iter().enumerate() {
+        outgoing.entry(key(*a)).or_default().push(i);
+    }
+    let mut used = vec![false; edges.len()];
+    let mut path = RecordedPath::new(FillType::NonZero);
+    for first in 0..edges.len() {
+        if used[first] {
+            continue;
+        }
+        used[first] = true;
+        let start = edges[first].0;
+        path.move_to(start.cast());
+        let mut end = edges[first].1;
+        while key(end) != key(start) {
+            path.line_to(end.cast());
+            let next = outgoing
+                .get(&key(end))
+                .and_then(|candidates| candidates.iter().copied().find(|i| !used[*i]));
+            let Some(next) = next else {
+                break;
+            };
+            used[next] = true;
+            end = edges[next].1;
+        }
+        path.close();
+    }
+    path
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::fixtures;
+
+    fn rect(x: f32, y: f32, w: f32, h: f32) -> RecordedPath {
+        let mut path = RecordedPath::default();
+        path.add_rect(&fixtures::rect(x, y, w, h));
+        path
+    }
+    /// Area of the fill, assuming the contours don't overlap each other in the wrong way.
+    fn area(path: &RecordedPath) -> f32 {
+        flatten(path, DEFAULT_TOLERANCE)
+            .iter()
+            .map(|polyline| {
+                let p = &polyline.points;
+                (0..p.len())
+                    .map(|i| {
+                        let (a, b) = (p[i], p[(i + 1) % p.len()]);
+                        a.x * b.y - b.x * a.y
+                    })
+                    .sum::<f32>()
+                    / 2.0
+            })
+            .sum::<f32>()
+            .abs()
+    }
+    fn contains(path: &RecordedPath, x: f32, y: f32) -> bool {
+        let edges = edges_of(path, 0);
+        is_inside(
+            winding_of(&edges, 0, P::new(x as f64, y as f64)),
+            path.fill_type(),
+        )
+    }
+
+    #[test]
+    fn overlapping_rects() {
+        let a = rect(0.0, 0.0, 10.0, 10.0);
+        let b = rect(5.0, 5.0, 10.0, 10.0);
+        assert!((area(&a.op(&b, PathOp::Union)) - 175.0).abs() < 1e-3);
+        assert!((area(&a.op(&b, PathOp::Intersect)) - 25.0).abs() < 1e-3);
+        assert!((area(&a.op(&b, PathOp::Difference)) - 75.0).abs() < 1e-3);
+        let xor = a.op(&b, PathOp::Xor);
+        assert!(contains(&xor, 1.0, 1.0));
+        assert!(contains(&xor, 14.0, 14.0));
+        assert!(!contains(&xor, 7.0, 7.0));
+        assert!(!contains(&xor, 20.0, 20.0));
+    }
+
+    #[test]
+    fn shared_edges_and_holes() {
+        let a = rect(0.0, 0.0, 10.0, 10.0);
+        let b = rect(10.0, 0.0, 10.0, 10.0);
+        let union = a.op(&b, PathOp::Union);
+        assert!((area(&union) - 200.0).abs() < 1e-3);
+        assert!(contains(&union, 10.0, 5.0));
+        // cutting a hole
+        let hole = rect(2.0, 2.0, 2.0, 2.0);
+        let cut = a.op(&hole, PathOp::Difference);
+        assert!(!contains(&cut, 3.0, 3.0));
+        assert!(contains(&cut, 5.0, 5.0));
+        assert!(a
+            .op(&rect(50.0, 50.0, 1.0, 1.0), PathOp::Intersect)
+            .is_empty());
+    }
+
+    #[test]
+    fn fill_type_is_respected() {
+        // two nested rects in the same direction, so only even-odd makes a hole
+        let mut nested = rect(0.0, 0.0, 10.0, 10.0);
+        nested.add_rect(&fixtures::rect(2.0, 2.0, 6.0, 6.0));
+        let everything = rect(-1.0, -1.0, 20.0, 20.0);
+        let non_zero = everything.op(&nested, PathOp::Intersect);
+        assert!(contains(&non_zero, 5.0, 5.0));
+        nested.set_fill_type(FillType::Odd);
+        let odd = everything.op(&nested, PathOp::Intersect);
+        assert!(!contains(&odd, 5.0, 5.0));
+        assert!(contains(&odd, 1.0, 1.0));
+    }
+}
@@ -0,0 +1,278 @@
+//! Converting the stroke of a path into a path that can be filled.
+use super::flatten::{flatten, DEFAULT_TOLERANCE};
+use super::{append_arc, RecordedPath};
+use crate::{FillType, Point, StrokeCap, StrokeJoin, Vector};
+
+fn cross(a: Vector, b: Vector) -> f32 {
+    a.x * b.y - a.y * b.x
+}
+/// The normal on the "left" of a direction. With y pointing down, that's
+/// clockwise from the direction on screen.
+fn normal(direction: Vector) -> Vector {
+    euclid::vec2(-direction.y, direction.x)
+}
+
+struct Stroker {
+    half_width: f32,
+    cap: StrokeCap,
+    join: StrokeJoin,
+    miter_limit: f32,
+    out: RecordedPath,
+}
+impl Stroker {
+    /// Arc around the center, from `center + from * half_width` sweeping by `sweep` radians.
+    fn arc(&mut self, center: Point, from: Vector, sweep: f32) {
+        let start = from.y.atan2(from.x);
+        append_arc(
+            &mut self.out,
+            center,
+            euclid::vec2(self.half_width, self.half_width),
+            0.0,
+            start,
+            sweep,
+        );
+    }
+    /// Emits the join between two segments on their left side.
+    ///
+    /// The cursor must already be at the end of the incoming segment's offset.
+    fn join(&mut self, pivot: Point, incoming: Vector, outgoing: Vector) {
+        let n0 = normal(incoming);
+        let n1 = normal(outgoing);
+        let hw = self.half_width;
+        let turn = cross(incoming, outgoing);
+        let straight = turn.abs() < 1e-6 && incoming.dot(outgoing) > 0.0;
+        if straight {
+            return;
+        }
+        if turn > 0.0 {
+            // the left side is the inside of the turn. going through the pivot keeps
+            // the outline simple, the overlap is covered by the rest of the stroke.
+            self.out.line_to(pivot);
+            self.out.line_to(pivot + n1 * hw);
+            return;
+        }
+        match self.join {
+            StrokeJoin::Miter => {
+                let mid = (n0 + n1) / 2.0;
+                // |mid| is the cosine of half the angle between the normals
+                let cos_half = mid.length();
+                if cos_half > 1e-6 && 1.0 / cos_half <= self.miter_limit {
+                    self.out.line_to(pivot + mid * (hw / (cos_half * cos_half)));
+                }
+            }
+            StrokeJoin::Round => {
+                let sweep = if turn.abs() < 1e-6 {
+                    // a full reversal goes around the tip
+                    -std::f32::consts::PI
+                } else {
+                    cross(n0, n1).atan2(n0.dot(n1))
+                };
+                self.arc(pivot, n0, sweep);
+            }
+            StrokeJoin::Bevel => {}
+        }
+        self.out.line_to(pivot + n1 * hw);
+    }
+    /// Emits a cap at the end of a segment, going from its left side to its right side.
+    fn cap(&mut self, end: Point, direction: Vector) {
+        let n = normal(direction);
+        let hw = self.half_width;
+        match self.cap {
+            StrokeCap::Butt => {}
+            StrokeCap::Square => {
+                self.out.line_to(end + (n + direction) * hw);
+                self.out.line_to(end + (direction - n) * hw);
+            }
+            StrokeCap::Round => self.arc(end, n, -std::f32::consts::PI),
+        }
+        self.out.line_to(end - n * hw);
+    }
+    /// Emits the left offset of the points, with joins between the segments.
+    ///
+    /// For closed contours, this starts a new contour and also joins the last segment
+    /// with the first. Open contours continue from the cursor, which must already be
+    /// at the offset of the first point.
+    fn side(&mut self, points: &[Point], closed: bool) {
+        let hw = self.half_width;
+        let direction = |i: usize| (points[(i + 1) % points.len()] - points[i]).normalize();
+        let segments = if closed {
+            points.len()
+        } else {
+            points.len() - 1
+        };
+        for i in 0..segments {
+            let d = direction(i);
+            let n = normal(d);
+            if i == 0 {
+                if closed {
+                    self.out
+                        .move_to(points[0] + normal(direction(segments - 1)) * hw);
+                    self.join(points[0], direction(segments - 1), d);
+                }
+            } else {
+                self.join(points[i], direction(i - 1), d);
+            }
+            self.out.line_to(points[(i + 1) % points.len()] + n * hw);
+        }
+    }
+    fn dot(&mut self, center: Point) {
+        let hw = self.half_width;
+        match self.cap {
+            StrokeCap::Butt => {}
+            StrokeCap::Round => {
+                self.out.move_to(center + euclid::vec2(hw, 0.0));
+                self.arc(center, euclid::vec2(1.0, 0.0), std::f32::consts::TAU);
+                self.out.close();
+            }
+            StrokeCap::Square => {
+                self.out.add_rect(&euclid::Rect::new(
+                    center - euclid::vec2(hw, hw),
+                    euclid::size2(hw * 2.0, hw * 2.0),
+                ));
+            }
+        }
+    }
+    fn contour(&mut self, mut points: Vec<Point>, closed: bool) {
+        points.dedup_by(|a, b| (*a - *b).length() < 1e-5);
+        if closed && points.len() > 1 && (points[0] - points[points.len() - 1]).length() < 1e-5 {
+            points.pop();
+        }
+        if points.len() < 2 {
+            if let Some(p) = points.first() {
+                self.dot(*p);
+            }
+            return;
+        }
+        if closed {
+            self.side(&points, true);
+            self.out.close();
+            points.reverse();
+            self.side(&points, true);
+            self.out.close();
+            return;
+        }
+        let last = points.len() - 1;
+        self.out
+            .move_to(points[0] + normal(points[1] - points[0]).normalize() * self.half_width);
+        self.side(&points, false);
+        self.cap(points[last], (points[last] - points[last - 1]).normalize());
+        points.reverse();
+        self.side(&points, false);
+        self.cap(points[last], (points[last] - points[last - 1]).normalize());
+        self.out.close();
+    }
+}
+
+impl RecordedPath {
+    /// The outline of the stroke of this path, as a path that can be filled.
+    ///
+    /// - width: The stroke width. Zero or negative widths produce an empty path.
+    /// - cap: How the ends of open contours look.
+    /// - join: How the corners between segments look.
+    /// - miter_limit: The longest a miter join can be (as a multiple of the stroke width),
+    ///   before it's drawn as a bevel instead. Impeller's default is `4.0`.
+    ///
+    /// Curves are flattened before stroking, while round joins and caps use curves.
+    /// The result uses [FillType::NonZero], as the outline overlaps itself at sharp corners.
+    pub fn stroke_to_path(
+        &self,
+        width: f32,
+        cap: StrokeCap,
+        join: StrokeJoin,
+        miter_limit: f32,
+    ) -> RecordedPath {
+        let mut stroker = Stroker {
+            half_width: width / 2.0,
+            cap,
+            join,
+            miter_limit,
+            out: RecordedPath::new(FillType::NonZero),
+        };
+        if width.is_nan() || width <= 0.0 {
+            return stroker.out;
+        }
+        // flatten skips lone moves, but a move and a line to the same point still draw a dot.
+        for polyline in flatten(self, DEFAULT_TOLERANCE.min(width / 4.0)) {
+            stroker.contour(polyline.points, polyline.closed);
+        }
+        stroker.out
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{PathOp, Rect};
+    use std::f32::consts::PI;
+
+    /// Area of the fill. The outline overlaps itself, so the union with
+    /// nothing is used to get simple contours first.
+    fn area(path: &RecordedPath) -> f32 {
+        let simple = path.op(&RecordedPath::default(), PathOp::Union);
+        flatten(&simple, DEFAULT_TOLERANCE)
+            .iter()
+            .map(|polyline| {
+                let p = &polyline.points;
+                (0..p.len())
+                    .map(|i| {
+                        let (a, b) = (p[i], p[(i + 1) % p.len()]);
+                        a.x * b.y - b.x * a.y
+                    })
+                    .sum::<f32>()
+                    / 2.0
+            })
+            .sum::<f32>()
+            .abs()
+    }
+    fn assert_area(path: &RecordedPath, expected: f32) {
+        let area = area(path);
+        assert!(
+            (area - expected).abs() < expected * 0.005,
+            "{area} != {expected}"
+        );
+    }
+
+    fn line() -> RecordedPath {
+        let mut path = RecordedPath::default();
+        path.move_to(Point::new(0.0, 0.0))
+            .line_to(Point::new(100.0, 0.0));
+        path
+    }
+
+    #[test]
+    fn caps() {
+        let stroke = |cap| line().stroke_to_path(20.0, cap, StrokeJoin::Miter, 4.0);
+        assert_area(&stroke(StrokeCap::Butt), 2000.0);
+        assert_area(&stroke(StrokeCap::Square), 2400.0);
+        assert_area(&stroke(StrokeCap::Round), 2000.0 + 100.0 * PI);
+        let butt = stroke(StrokeCap::Butt);
+        assert_eq!(butt.fill_type(), FillType::NonZero);
+        assert_eq!(butt.points()[0], Point::new(0.0, 10.0));
+    }
+
+    #[test]
+    fn joins_of_a_closed_square() {
+        let mut square = RecordedPath::default();
+        square.add_rect(&Rect::new(Point::zero(), euclid::size2(100.0, 100.0)));
+        let stroke = |join, limit| square.stroke_to_path(20.0, StrokeCap::Butt, join, limit);
+        assert_area(&stroke(StrokeJoin::Miter, 4.0), 8000.0);
+        // right angle miters are sqrt(2) long, so this limit turns them into bevels
+        assert_area(&stroke(StrokeJoin::Miter, 1.4), 7800.0);
+        assert_area(&stroke(StrokeJoin::Bevel, 4.0), 7800.0);
+        assert_area(&stroke(StrokeJoin::Round, 4.0), 7600.0 + 100.0 * PI);
+    }
+
+    #[test]
+    fn dots_and_empty_strokes() {
+        let mut dot = RecordedPath::default();
+        dot.move_to(Point::new(5.0, 5.0))
+            .line_to(Point::new(5.0, 5.0));
+        let stroke = |cap| dot.stroke_to_path(20.0, cap, StrokeJoin::Miter, 4.0);
+        assert!(stroke(StrokeCap::Butt).is_empty());
+        assert_area(&stroke(StrokeCap::Square), 400.0);
+        assert_area(&stroke(StrokeCap::Round), 100.0 * PI);
+        assert!(line()
+            .stroke_to_path(0.0, StrokeCap::Round, StrokeJoin::Round, 4.0)
+            .is_empty());
+    }
+}
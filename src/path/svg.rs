@@ -0,0 +1,494 @@
+//! Parsing and writing the path data (`d` attribute) of svg `<path>` elements.
+//!
+//! <https://www.w3.org/TR/SVG2/paths.html#PathData>
+use std::fmt::Write;
+
+use super::{append_arc, PathSegment, RecordedPath};
+use crate::Point;
+
+/// An error encountered while parsing text like svg path data.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct ParseError {
+    /// Byte offset into the input where the error was found.
+    pub position: usize,
+    /// What went wrong.
+    pub message: &'static str,
+}
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} at byte {}", self.message, self.position)
+    }
+}
+impl std::error::Error for ParseError {}
+
+struct Parser<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+impl Parser<'_> {
+    fn error(&self, message: &'static str) -> ParseError {
+        ParseError {
+            position: self.pos,
+            message,
+        }
+    }
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.pos).copied()
+    }
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(|b| b.is_ascii_whitespace()) {
+            self.pos += 1;
+        }
+    }
+    /// Skips whitespace with at most one comma in it.
+    fn skip_separator(&mut self) {
+        self.skip_whitespace();
+        if self.peek() == Some(b',') {
+            self.pos += 1;
+            self.skip_whitespace();
+        }
+    }
+    fn at_number(&self) -> bool {
+        self.peek()
+            .is_some_and(|b| b.is_ascii_digit() || matches!(b, b'+' | b'-' | b'.'))
+    }
+    fn number(&mut self) -> Result<f32, ParseError> {
+        self.skip_separator();
+        let start = self.pos;
+        if matches!(self.peek(), Some(b'+' | b'-')) {
+            self.pos += 1;
+        }
+        let mut digits = 0;
+        while self.peek().is_some_and(|b| b.is_ascii_digit()) {
+            self.pos += 1;
+            digits += 1;
+        }
+        if self.peek() == Some(b'.') {
+            self.pos += 1;
+            while self.peek().is_some_and(|b| b.is_ascii_digit()) {
+                self.pos += 1;
+                digits += 1;
+            }
+        }
+        if digits == 0 {
+            self.pos = start;
+            return Err(self.error("expected a number"));
+        }
+        if matches!(self.peek(), Some(b'e' | b'E')) {
+            let mantissa_end = self.pos;
+            self.pos += 1;
+            if matches!(self.peek(), Some(b'+' | b'-')) {
+                self.pos += 1;
+            }
+            if self.peek().is_some_and(|b| b.is_ascii_digit()) {
+                while self.peek().is_some_and(|b| b.is_ascii_digit()) {
+                    self.pos += 1;
+                }
+            } else {
+                // not an exponent. eg: the `e` could be the start of garbage
+                self.pos = mantissa_end;
+            }
+        }
+        // only ascii was consumed, so this is always valid utf-8
+        std::str::from_utf8(&self.bytes[start..self.pos])
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .ok_or(ParseError {
+                position: start,
+                message: "invalid number",
+            })
+    }
+    fn point(&mut self) -> Result<Point, ParseError> {
+        Ok(Point::new(self.number()?, self.number()?))
+    }
+    /// Arc flags are a single `0` or `1` and don't need to be separated from what follows.
+    fn flag(&mut self) -> Result<bool, ParseError> {
+        self.skip_separator();
+        let flag = match self.peek() {
+            Some(b'0') => false,
+            Some(b'1') => true,
+            _ => return Err(self.error("expected an arc flag (0 or 1)")),
+        };
+        self.pos += 1;
+        Ok(flag)
+    }
+}
+
+impl RecordedPath {
+    /// Parse svg path data (the `d` attribute of a `<path>` element).
+    ///
+    /// All the commands (`M`, `L`, `H`, `V`, `C`, `S`, `Q`, `T`, `A` and `Z`) are
+    /// supported in both absolute (uppercase) and relative (lowercase) forms.
+    /// Elliptical arcs are converted to cubic curves.
+    ///
+    /// The fill type is [FillType::NonZero](crate::FillType::NonZero), as svg specifies the fill rule
+    /// separately from the path data.
+    pub fn from_svg_path_data(data: &str) -> Result<Self, ParseError> {
+        let mut parser = Parser {
+            bytes: data.as_bytes(),
+            pos: 0,
+        };
+        let mut path = RecordedPath::default();
+        let mut command: Option<u8> = None;
+        let mut current = Point::zero();
+        let mut contour_start = Point::zero();
+        // The second control point of the previous segment, used by the smooth curve commands.
+        let mut last_cubic_control: Option<Point> = None;
+        let mut last_quad_control: Option<Point> = None;
+        loop {
+            parser.skip_whitespace();
+            let Some(next) = parser.peek() else {
+                break;
+            };
+            let cmd = if next.is_ascii_alphabetic() {
+                parser.pos += 1;
+                next
+            } else if parser.at_number() || next == b',' {
+                // numbers without a command letter repeat the previous command.
+                // moves are repeated as lines.
+                match command {
+                    Some(b'M') => b'L',
+                    Some(b'm') => b'l',
+                    Some(c) if !c.eq_ignore_ascii_case(&b'z') => c,
+                    _ => return Err(parser.error("expected a command")),
+                }
+            } else {
+                return Err(parser.error("unexpected character"));
+            };
+            if command.is_none() && !cmd.eq_ignore_ascii_case(&b'm') {
+                return Err(parser.error("path data must start with a move command"));
+            }
+            command = Some(cmd);
+            let relative = cmd.is_ascii_lowercase();
+            let origin = if relative { current } else { Point::zero() };
+            let abs = |p: Point| origin + p.to_vector();
+            let mut cubic_control = None;
+            let mut quad_control = None;
+            match cmd.to_ascii_uppercase() {
+                b'M' => {
+                    current = abs(parser.point()?);
+                    contour_start = current;
+                    path.move_to(current);
+                }
+                b'L' => {
+                    current = abs(parser.point()?);
+                    path.line_to(current);
+                }
+                b'H' => {
+                    let x = parser.number()?;
+                    current = Point::new(if relative { current.x + x } else { x }, current.y);
+                    path.line_to(current);
+                }
+                b'V' => {
+                    let y = parser.number()?;
+                    current = Point::new(current.x, if relative { current.y + y } else { y });
+                    path.line_to(current);
+                }
+                b'C' => {
+                    let c1 = abs(parser.point()?);
+                    let c2 = abs(parser.point()?);
+                    current = abs(parser.point()?);
+                    path.cubic_curve_to(c1, c2, current);
+                    cubic_control = Some(c2);
+                }
+                b'S' => {
+                    let c1 = last_cubic_control.map_or(current, |c| current + (current - c));
+                    let c2 = abs(parser.point()?);
+                    current = abs(parser.point()?);
+                    path.cubic_curve_to(c1, c2, current);
+                    cubic_control = Some(c2);
+                }
+                b'Q' => {
+                    let c = abs(parser.point()?);
+                    current = abs(parser.point()?);
+                    path.quadratic_curve_to(c, current);
+                    quad_control = Some(c);
+                }
+                b'T' => {
+                    let c = last_quad_control.map_or(current, |c| current + (current - c));
+                    current = abs(parser.point()?);
+                    path.quadratic_curve_to(c, current);
+                    quad_control = Some(c);
+                }
+                b'A' => {
+                    let rx = parser.number()?;
+                    let ry = parser.number()?;
+                    let x_axis_rotation = parser.number()?;
+                    let large_arc = parser.flag()?;
+                    let sweep = parser.flag()?;
+                    let end = abs(parser.point()?);
+                    arc_to(
+                        &mut path,
+                        current,
+                        rx,
+                        ry,
+                        x_axis_rotation,
+                        large_arc,
+                        sweep,
+                        end,
+                    );
+                    current = end;
+                }
+                b'Z' => {
+                    path.close();
+                    current = contour_start;
+                }
+                _ => {
+                    parser.pos -= 1;
+                    return Err(parser.error("unknown command"));
+                }
+            }
+            last_cubic_control = cubic_control;
+            last_quad_control = quad_control;
+        }
+        Ok(path)
+    }
+    /// Write the path as svg path data (the `d` attribute of a `<path>` element).
+    ///
+    /// Only absolute `M`, `L`, `Q`, `C` and `Z` commands are used.
+    /// The fill type is not part of path data, use the `fill-rule` attribute for that.
+    ///
+    /// Svg has no syntax for NaN or infinity, so segments with non-finite points are left
+    /// out, and so is the whole contour when its start point is not finite.
+    pub fn to_svg_path_data(&self) -> String {
+        let mut out = String::new();
+        // whether the current contour started at a non-finite point
+        let mut skip_contour = false;
+        for segment in self {
+            let points: &[Point] = match &segment {
+                PathSegment::MoveTo(p) | PathSegment::LineTo(p) => &[*p],
+                PathSegment::QuadTo(c, p) => &[*c, *p],
+                PathSegment::CubicTo(c1, c2, p) => &[*c1, *c2, *p],
+                PathSegment::Close => &[],
+            };
+            let finite = points.iter().all(|p| p.x.is_finite() && p.y.is_finite());
+            if let PathSegment::MoveTo(_) = segment {
+                skip_contour = !finite;
+            }
+            if skip_contour || !finite {
+                continue;
+            }
+            if !out.is_empty() {
+                out.push(' ');
+            }
+            // writing to a string never fails
+            let _ = match segment {
+                PathSegment::MoveTo(p) => write!(out, "M{} {}", n(p.x), n(p.y)),
+                PathSegment::LineTo(p) => write!(out, "L{} {}", n(p.x), n(p.y)),
+                PathSegment::QuadTo(c, p) => {
+                    write!(out, "Q{} {} {} {}", n(c.x), n(c.y), n(p.x), n(p.y))
+                }
+                PathSegment::CubicTo(c1, c2, p) => write!(
+                    out,
+                    "C{} {} {} {} {} {}",
+                    n(c1.x),
+                    n(c1.y),
+                    n(c2.x),
+                    n(c2.y),
+                    n(p.x),
+                    n(p.y)
+                ),
+                PathSegment::Close => write!(out, "Z"),
+            };
+        }
+        out
+    }
+}
+/// Avoids writing `-0`.
+fn n(v: f32) -> f32 {
+    if v == 0.0 {
+        0.0
+    } else {
+        v
+    }
+}
+
+/// Converts an svg endpoint arc to curves.
+///
+/// <https://www.w3.org/TR/SVG2/implnote.html#ArcImplementationNotes>
+#[allow(clippy::too_many_arguments)]
+fn arc_to(
+    path: &mut RecordedPath,
+    from: Point,
+    rx: f32,
+    ry: f32,
+    x_axis_rotation_degrees: f32,
+    large_arc: bool,
+    sweep: bool,
+    to: Point,
+) {
+    if from == to {
+        return;
+    }
+    let (mut rx, mut ry) = (rx.abs(), ry.abs());
+    if rx == 0.0 || ry == 0.0 {
+        path.line_to(to);
+        return;
+    }
+    let phi = x_axis_rotation_degrees.to_radians();
+    let (sin_phi, cos_phi) = phi.sin_cos();
+    let half = (from - to) / 2.0;
+    let x1 = cos_phi * half.x + sin_phi * half.y;
+    let y1 = -sin_phi * half.x + cos_phi * half.y;
+    // scale up radii that are too small to reach the end point
+    let lambda = (x1 * x1) / (rx * rx) + (y1 * y1) / (ry * ry);
+    if lambda > 1.0 {
+        rx *= lambda.sqrt();
+        ry *= lambda.sqrt();
+    }
+    let numerator = rx * rx * ry * ry - rx * rx * y1 * y1 - ry * ry * x1 * x1;
+    let denominator = rx * rx * y1 * y1 + ry * ry * x1 * x1;
+    let mut coefficient = (numerator / denominator).max(0.0).sqrt();
+    if large_arc == sweep {
+        coefficient = -coefficient;
+    }
+    let cx1 = coefficient * rx * y1 / ry;
+    let cy1 = -coefficient * ry * x1 / rx;
+    let mid = from.lerp(to, 0.5);
+    let center = Point::new(
+        cos_phi * cx1 - sin_phi * cy1 + mid.x,
+        sin_phi * cx1 + cos_phi * cy1 + mid.y,
+    );
+    let start_vector: euclid::default::Vector2D<f32> =
+        euclid::vec2((x1 - cx1) / rx, (y1 - cy1) / ry);
+    let end_vector: euclid::default::Vector2D<f32> =
+        euclid::vec2((-x1 - cx1) / rx, (-y1 - cy1) / ry);
+    let start_angle = start_vector.y.atan2(start_vector.x);
+    let mut sweep_angle = end_vector.y.atan2(end_vector.x) - start_angle;
+    if sweep && sweep_angle < 0.0 {
+        sweep_angle += std::f32::consts::TAU;
+    } else if !sweep && sweep_angle > 0.0 {
+        sweep_angle -= std::f32::consts::TAU;
+    }
+    append_arc(
+        path,
+        center,
+        euclid::vec2(rx, ry),
+        phi,
+        start_angle,
+        sweep_angle,
+    );
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::path::PathVerb;
+
+    fn approx_eq(a: Point, b: Point) -> bool {
+        (a - b).length() < 1e-3
+    }
+
+    #[test]
+    fn absolute_and_relative_commands() {
+        let path =
+            RecordedPath::from_svg_path_data("M10,10 h 5 v5 H10 z m 1 1 l 2 0 L20 20").unwrap();
+        assert_eq!(
+            path.iter().collect::<Vec<_>>(),
+            vec![
+                PathSegment::MoveTo(Point::new(10.0, 10.0)),
+                PathSegment::LineTo(Point::new(15.0, 10.0)),
+                PathSegment::LineTo(Point::new(15.0, 15.0)),
+                PathSegment::LineTo(Point::new(10.0, 15.0)),
+                PathSegment::Close,
+                PathSegment::MoveTo(Point::new(11.0, 11.0)),
+                PathSegment::LineTo(Point::new(13.0, 11.0)),
+                PathSegment::LineTo(Point::new(20.0, 20.0)),
+            ]
+        );
+    }
+
+    #[test]
+    fn implicit_commands_and_compact_numbers() {
+        // extra pairs after a move are lines, numbers can run into each other
+        let path = RecordedPath::from_svg_path_data("m0 0 10-5.5.5 1e1").unwrap();
+        assert_eq!(
+            path.iter().collect::<Vec<_>>(),
+            vec![
+                PathSegment::MoveTo(Point::new(0.0, 0.0)),
+                PathSegment::LineTo(Point::new(10.0, -5.5)),
+                PathSegment::LineTo(Point::new(10.5, 4.5)),
+            ]
+        );
+    }
+
+    #[test]
+    fn smooth_curves_reflect_control_points() {
+        let path = RecordedPath::from_svg_path_data(
+            "M0 0 C0 10 10 10 10 0 S 20 -10 20 0 Q25 5 30 0 T40 0",
+        )
+        .unwrap();
+        let segments: Vec<_> = path.iter().collect();
+        assert_eq!(
+            segments[2],
+            PathSegment::CubicTo(
+                Point::new(10.0, -10.0),
+                Point::new(20.0, -10.0),
+                Point::new(20.0, 0.0)
+            )
+        );
+        assert_eq!(
+            segments[4],
+            PathSegment::QuadTo(Point::new(35.0, -5.0), Point::new(40.0, 0.0))
+        );
+    }
+
+    #[test]
+    fn arcs_become_cubics() {
+        // half circle of radius 10 from (0, 0) to (20, 0)
+        let path = RecordedPath::from_svg_path_data("M0 0 A10 10 0 0 1 20 0").unwrap();
+        assert_eq!(
+            path.verbs(),
+            &[PathVerb::Move, PathVerb::Cubic, PathVerb::Cubic]
+        );
+        let points = path.points();
+        // sweep flag 1 goes clockwise on screen, passing through the top
+        assert!(approx_eq(points[3], Point::new(10.0, -10.0)));
+        assert!(approx_eq(*points.last().unwrap(), Point::new(20.0, 0.0)));
+        // flags don't need separators
+        let compact = RecordedPath::from_svg_path_data("M0 0a10 10 0 0120 0").unwrap();
+        assert_eq!(compact, path);
+        // zero radius is a line
+        let line = RecordedPath::from_svg_path_data("M0 0 A0 5 0 0 0 5 5").unwrap();
+        assert_eq!(line.verbs(), &[PathVerb::Move, PathVerb::Line]);
+    }
+
+    #[test]
+    fn round_trip() {
+        let data = "M1 2 L3 4 Q5 6 7 8 C9 10 11 12 13.5 -14 Z";
+        let path = RecordedPath::from_svg_path_data(data).unwrap();
+        assert_eq!(path.to_svg_path_data(), data);
+    }
+
+    #[test]
+    fn non_finite() {
+        let mut path = RecordedPath::default();
+        path.move_to(Point::new(0.0, 0.0))
+            .line_to(Point::new(f32::NAN, 1.0))
+            .line_to(Point::new(1.0, 1.0))
+            .close()
+            .move_to(Point::new(f32::INFINITY, 0.0))
+            .line_to(Point::new(2.0, 2.0))
+            .close()
+            .move_to(Point::new(3.0, 3.0))
+            .line_to(Point::new(4.0, f32::NEG_INFINITY));
+        assert_eq!(path.to_svg_path_data(), "M0 0 L1 1 Z M3 3");
+    }
+
+    #[test]
+    fn errors() {
+        let err = |d: &str| RecordedPath::from_svg_path_data(d).unwrap_err();
+        assert_eq!(
+            err("L 1 2").message,
+            "path data must start with a move command"
+        );
+        assert_eq!(err("M 1").position, 3);
+        assert_eq!(err("M 1 2 X").message, "unknown command");
+        assert_eq!(err("M 1 2 Z 3").message, "expected a command");
+        assert_eq!(
+            err("M 0 0 A 1 1 0 2 0 1 1").message,
+            "expected an arc flag (0 or 1)"
+        );
+        assert!(RecordedPath::from_svg_path_data("").unwrap().is_empty());
+    }
+}
@@ -0,0 +1,194 @@
+//! Transforming, appending and reversing paths.
+use super::flatten::{flatten, DEFAULT_TOLERANCE};
+use super::{PathSegment, RecordedPath};
+use crate::{Matrix, Point};
+
+impl RecordedPath {
+    /// Append the contours of another path, optionally transformed.
+    ///
+    /// The fill type of this path is kept.
+    pub fn add_path(&mut self, other: &RecordedPath, transform: Option<&Matrix>) -> &mut Self {
+        match transform {
+            Some(transform) => self.extend(&other.transformed(transform)),
+            None => self.extend(other),
+        }
+        self
+    }
+    /// This path with every point transformed by the matrix.
+    ///
+    /// Affine transforms map curves to curves exactly. With perspective, curves are
+    /// flattened to lines first, and points that end up behind the viewer are dropped.
+    pub fn transformed(&self, transform: &Matrix) -> RecordedPath {
+        let mut path = RecordedPath::new(self.fill_type());
+        if transform.m14 == 0.0 && transform.m24 == 0.0 && transform.m44 != 0.0 {
+            let map = |p: Point| {
+                let h = transform.transform_point2d_homogeneous(p);
+                Point::new(h.x / h.w, h.y / h.w)
+            };
+            path.extend(self.iter().map(|segment| match segment {
+                PathSegment::MoveTo(p) => PathSegment::MoveTo(map(p)),
+                PathSegment::LineTo(p) => PathSegment::LineTo(map(p)),
+                PathSegment::QuadTo(c, p) => PathSegment::QuadTo(map(c), map(p)),
+                PathSegment::CubicTo(c1, c2, p) => PathSegment::CubicTo(map(c1), map(c2), map(p)),
+                PathSegment::Close => PathSegment::Close,
+            }));
+            return path;
+        }
+        for polyline in flatten(self, DEFAULT_TOLERANCE) {
+            let mut points = polyline
+                .points
+                .iter()
+                .filter_map(|p| transform.transform_point2d(*p));
+            let Some(first) = points.next() else {
+                continue;
+            };
+            path.move_to(first);
+            for p in points {
+                path.line_to(p);
+            }
+            if polyline.closed {
+                path.close();
+            }
+        }
+        path
+    }
+    /// This path with the direction of every contour reversed.
+    ///
+    /// Each contour starts where it used to end, and closed contours stay closed.
+    /// The order of the contours and the fill type are kept.
+    pub fn reversed(&self) -> RecordedPath {
+        let mut path = RecordedPath::new(self.fill_type());
+        // (segment, the point it starts from)
+        let mut contour: Vec<(PathSegment, Point)> = vec![];
+        let mut current = Point::zero();
+        let mut finish = |contour: &mut Vec<(PathSegment, Point)>, end: Point, closed: bool| {
+            path.move_to(end);
+            for (segment, from) in contour.drain(..).rev() {
+                path.push(match segment {
+                    PathSegment::LineTo(_) => PathSegment::LineTo(from),
+                    PathSegment::QuadTo(c, _) => PathSegment::QuadTo(c, from),
+                    PathSegment::CubicTo(c1, c2, _) => PathSegment::CubicTo(c2, c1, from),
+                    PathSegment::MoveTo(_) | PathSegment::Close => unreachable!(),
+                });
+            }
+            if closed {
+                path.close();
+            }
+        };
+        let mut open = false;
+        for segment in self {
+            match segment {
+                PathSegment::MoveTo(p) => {
+                    if open {
+                        finish(&mut contour, current, false);
+                    }
+                    open = true;
+                    current = p;
+                }
+                PathSegment::Close => {
+                    finish(&mut contour, current, true);
+                    open = false;
+                }
+                PathSegment::LineTo(p)
+                | PathSegment::QuadTo(_, p)
+                | PathSegment::CubicTo(_, _, p) => {
+                    contour.push((segment, current));
+                    current = p;
+                }
+            }
+        }
+        if open {
+            finish(&mut contour, current, false);
+        }
+        path
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{Rect, Size};
+
+    fn curvy() -> RecordedPath {
+        let mut path = RecordedPath::default();
+        path.move_to(Point::new(0.0, 0.0))
+            .line_to(Point::new(10.0, 0.0))
+            .quadratic_curve_to(Point::new(20.0, 0.0), Point::new(20.0, 10.0))
+            .cubic_curve_to(
+                Point::new(20.0, 20.0),
+                Point::new(10.0, 30.0),
+                Point::new(0.0, 30.0),
+            )
+            .close()
+            .move_to(Point::new(50.0, 50.0))
+            .line_to(Point::new(60.0, 60.0));
+        path
+    }
+
+    #[test]
+    fn affine_transforms() {
+        let mut rect = RecordedPath::default();
+        rect.add_rect(&Rect::new(Point::new(1.0, 2.0), Size::new(3.0, 4.0)));
+        let moved = rect.transformed(
+            &Matrix::scale(2.0, 2.0, 1.0).then_translate(euclid::vec3(10.0, 20.0, 0.0)),
+        );
+        assert_eq!(
+            moved.points(),
+            [
+                Point::new(12.0, 24.0),
+                Point::new(18.0, 24.0),
+                Point::new(18.0, 32.0),
+                Point::new(12.0, 32.0),
+            ]
+        );
+        assert_eq!(moved.verbs(), rect.verbs());
+        let mut composed = RecordedPath::default();
+        composed
+            .add_path(&rect, None)
+            .add_path(&rect, Some(&Matrix::translation(100.0, 0.0, 0.0)));
+        assert_eq!(composed.points().len(), 8);
+        assert_eq!(composed.points()[4], Point::new(101.0, 2.0));
+    }
+
+    #[test]
+    fn perspective_flattens() {
+        let mut perspective = Matrix::identity();
+        perspective.m14 = 0.001;
+        let projected = curvy().transformed(&perspective);
+        assert!(projected
+            .iter()
+            .all(|s| !matches!(s, PathSegment::QuadTo(..) | PathSegment::CubicTo(..))));
+        // w = 1 + 0.001 * x
+        let end = *projected.points().last().unwrap();
+        assert!((end - Point::new(60.0 / 1.06, 60.0 / 1.06)).length() < 1e-4);
+    }
+
+    #[test]
+    fn reversing() {
+        let path = curvy();
+        let reversed = path.reversed();
+        let segments: Vec<_> = reversed.iter().collect();
+        assert_eq!(
+            segments,
+            [
+                PathSegment::MoveTo(Point::new(0.0, 30.0)),
+                PathSegment::CubicTo(
+                    Point::new(10.0, 30.0),
+                    Point::new(20.0, 20.0),
+                    Point::new(20.0, 10.0)
+                ),
+                PathSegment::QuadTo(Point::new(20.0, 0.0), Point::new(10.0, 0.0)),
+                PathSegment::LineTo(Point::new(0.0, 0.0)),
+                PathSegment::Close,
+                PathSegment::MoveTo(Point::new(60.0, 60.0)),
+                PathSegment::LineTo(Point::new(50.0, 50.0)),
+            ]
+        );
+        assert_eq!(reversed.reversed(), path);
+        // the winding flips
+        let mut rect = RecordedPath::default();
+        rect.add_rect(&Rect::new(Point::zero(), Size::new(10.0, 10.0)));
+        assert_eq!(rect.winding_number(Point::new(5.0, 5.0)), 1);
+        assert_eq!(rect.reversed().winding_number(Point::new(5.0, 5.0)), -1);
+    }
+}